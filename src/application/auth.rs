@@ -1,3 +1,7 @@
+use crate::domain::AccountRole;
+use crate::domain::LoadAccount;
+use crate::domain::LoadAccountError;
+
 use thiserror::Error;
 use uuid::Uuid;
 
@@ -11,13 +15,33 @@ pub enum AuthError {
 }
 
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, serde::Serialize, serde::Deserialize)]
 pub enum AuthRole {
     Member,
     Moderator,
     Administrator,
 }
 
+impl From<AccountRole> for AuthRole {
+    fn from(role: AccountRole) -> Self {
+        match role {
+            AccountRole::Member => Self::Member,
+            AccountRole::Moderator => Self::Moderator,
+            AccountRole::Administrator => Self::Administrator,
+        }
+    }
+}
+
+impl From<AuthRole> for AccountRole {
+    fn from(role: AuthRole) -> Self {
+        match role {
+            AuthRole::Member => Self::Member,
+            AuthRole::Moderator => Self::Moderator,
+            AuthRole::Administrator => Self::Administrator,
+        }
+    }
+}
+
 
 #[derive(Debug)]
 pub enum AuthInfo {
@@ -29,6 +53,20 @@ pub enum AuthInfo {
 }
 
 impl AuthInfo {
+    // Tokens carry the role of their account at login. Services granting rights by role check the
+    // current role instead, so that promotions and demotions take effect at once. Tokens of accounts
+    // that no longer exist are not trusted at all.
+    pub fn with_current_role(&self, accounts: &impl LoadAccount) -> Result<Self, LoadAccountError> {
+        let Self::Authenticated { account_id, .. } = self else {
+            return Ok(Self::Unauthenticated);
+        };
+        match accounts.load_account(*account_id) {
+            Ok(account) => Ok(Self::Authenticated { account_id: *account_id, role: account.role().into() }),
+            Err(LoadAccountError::AccountNotFound) => Ok(Self::Unauthenticated),
+            Err(error) => Err(error),
+        }
+    }
+
     pub fn ensure_authenticated(&self) -> Result<Uuid, AuthError> {
         match self {
             Self::Unauthenticated => Err(AuthError::AuthenticationRequired),
@@ -64,8 +102,34 @@ impl AuthInfo {
 
 #[cfg(test)]
 mod tests {
+    use crate::domain::Account;
+    use crate::domain::Username;
+    use crate::domain::unix_time;
+
     use super::*;
 
+    struct DummyAccounts {
+        account: Account,
+        load_error: Option<LoadAccountError>,
+    }
+
+    impl LoadAccount for DummyAccounts {
+        fn load_account(&self, account_id: Uuid) -> Result<Account, LoadAccountError> {
+            if let Some(error) = self.load_error {
+                return Err(error);
+            }
+            (account_id == self.account.id()).then(|| self.account.clone()).ok_or(LoadAccountError::AccountNotFound)
+        }
+
+        fn load_account_by_username(&self, _username: &Username) -> Result<Account, LoadAccountError> {
+            Err(LoadAccountError::AccountNotFound)
+        }
+
+        fn load_accounts_with_role(&self, _role: AccountRole) -> Result<Vec<Account>, LoadAccountError> {
+            Ok(vec![])
+        }
+    }
+
     #[test]
     fn ensure_authenticated() {
         let auth_info = AuthInfo::Unauthenticated;
@@ -104,4 +168,19 @@ mod tests {
         let auth_info = AuthInfo::Authenticated { account_id, role: AuthRole::Administrator };
         assert!(matches!(auth_info.ensure_administrator(), Ok(id) if id == account_id));
     }
+
+    #[test]
+    fn current_role_replaces_role_of_token() {
+        let mut accounts = DummyAccounts { account: Account::register(Username::new("daniel").unwrap(), "correct horse").unwrap(), load_error: None };
+        accounts.account.promote(None, unix_time()).unwrap();
+        let account_id = accounts.account.id();
+        let auth_info = AuthInfo::Authenticated { account_id, role: AuthRole::Administrator };
+        let current = auth_info.with_current_role(&accounts).unwrap();
+        assert!(matches!(current, AuthInfo::Authenticated { account_id: id, role: AuthRole::Moderator } if id == account_id));
+        let unknown = AuthInfo::Authenticated { account_id: Uuid::new_v4(), role: AuthRole::Administrator };
+        assert!(matches!(unknown.with_current_role(&accounts).unwrap(), AuthInfo::Unauthenticated));
+        assert!(matches!(AuthInfo::Unauthenticated.with_current_role(&accounts).unwrap(), AuthInfo::Unauthenticated));
+        accounts.load_error = Some(LoadAccountError::DatabaseReadingError);
+        assert!(matches!(auth_info.with_current_role(&accounts), Err(LoadAccountError::DatabaseReadingError)));
+    }
 }
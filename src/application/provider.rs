@@ -1,45 +1,201 @@
 use crate::application::*;
 
+use crate::domain::AccessAccounts;
+use crate::domain::AccessBans;
 use crate::domain::AccessTableMessageBroadcast;
 use crate::domain::AccessTournaments;
+use crate::domain::BlockedWords;
+use crate::domain::EventFollowers;
+use crate::domain::LoadEventsError;
+use crate::domain::PendingEvents;
+use crate::domain::TournamentProjection;
+
+use log::info;
 
 
 #[derive(Debug)]
-pub struct ServiceProvider<Repository: AccessTournaments, Broadcast: AccessTableMessageBroadcast> {
+pub struct ServiceProvider<Repository: AccessTournaments, Broadcast: AccessTableMessageBroadcast, Accounts: AccessAccounts + AccessBans> {
     repository: Repository,
     broadcast: Broadcast,
+    followers: EventFollowers,
+    accounts: Accounts,
+    blocked_words: BlockedWords,
 }
 
-impl<Repository: AccessTournaments, Broadcast: AccessTableMessageBroadcast> ServiceProvider<Repository, Broadcast> {
-    pub fn new(repository: Repository, broadcast: Broadcast) -> Self {
-        Self { repository, broadcast }
+impl<Repository: AccessTournaments, Broadcast: AccessTableMessageBroadcast, Accounts: AccessAccounts + AccessBans> ServiceProvider<Repository, Broadcast, Accounts> {
+    // Events left unprocessed when the server stopped are processed first, which is the only time
+    // every stored tournament is checked. The projection is then rebuilt from the stored events, so
+    // it is never out of date on startup.
+    pub fn new(mut repository: Repository, broadcast: Broadcast, accounts: Accounts) -> Result<Self, LoadEventsError> {
+        let mut pending = PendingEvents::all(&repository)?;
+        let pending_stream_count = pending.process(&mut repository);
+        if pending_stream_count > 0 {
+            info!("processed pending events of {} stream(s)", pending_stream_count);
+        }
+        let followers = EventFollowers { projection: TournamentProjection::rebuild(&repository)?, pending };
+        Ok(Self { repository, broadcast, followers, accounts, blocked_words: BlockedWords::default() })
+    }
+
+    // Applies to nicknames registered from now on
+    pub fn with_blocked_words(self, blocked_words: BlockedWords) -> Self {
+        Self { blocked_words, ..self }
     }
 }
 
 
-impl<Repository: AccessTournaments, Broadcast: AccessTableMessageBroadcast> FindTournaments for ServiceProvider<Repository, Broadcast> {
+impl<Repository: AccessTournaments, Broadcast: AccessTableMessageBroadcast, Accounts: AccessAccounts + AccessBans> FindTournaments for ServiceProvider<Repository, Broadcast, Accounts> {
     fn find_tournaments(&self, request: FindTournamentsRequest, auth_info: &AuthInfo) -> Result<FindTournamentsResponse, FindTournamentsError> {
-        find_tournaments(request, auth_info, &self.repository)
+        find_tournaments(request, auth_info, &self.followers.projection)
+    }
+}
+
+
+impl<Repository: AccessTournaments, Broadcast: AccessTableMessageBroadcast, Accounts: AccessAccounts + AccessBans> GetTournament for ServiceProvider<Repository, Broadcast, Accounts> {
+    fn get_tournament(&self, request: GetTournamentRequest, auth_info: &AuthInfo) -> Result<GetTournamentResponse, GetTournamentError> {
+        get_tournament(request, auth_info, &self.repository)
     }
 }
 
 
-impl<Repository: AccessTournaments, Broadcast: AccessTableMessageBroadcast> CreateTournament for ServiceProvider<Repository, Broadcast> {
+impl<Repository: AccessTournaments, Broadcast: AccessTableMessageBroadcast, Accounts: AccessAccounts + AccessBans> CreateTournament for ServiceProvider<Repository, Broadcast, Accounts> {
     fn create_tournament(&mut self, request: CreateTournamentRequest, auth_info: &AuthInfo) -> Result<CreateTournamentResponse, CreateTournamentError> {
-        create_tournament(request, auth_info, &mut self.repository)
+        create_tournament(request, auth_info, &mut self.repository, &mut self.broadcast, &mut self.followers)
     }
 }
 
 
-impl<Repository: AccessTournaments, Broadcast: AccessTableMessageBroadcast> JoinTournament for ServiceProvider<Repository, Broadcast> {
+impl<Repository: AccessTournaments, Broadcast: AccessTableMessageBroadcast, Accounts: AccessAccounts + AccessBans> JoinTournament for ServiceProvider<Repository, Broadcast, Accounts> {
     fn join_tournament(&mut self, request: JoinTournamentRequest, auth_info: &AuthInfo) -> Result<JoinTournamentResponse, JoinTournamentError> {
-        join_tournament(request, auth_info, &mut self.repository, &self.broadcast)
+        join_tournament(request, auth_info, &mut self.repository, &mut self.broadcast, &mut self.followers, &self.accounts, &self.blocked_words)
     }
 }
 
 
-impl<Repository: AccessTournaments, Broadcast: AccessTableMessageBroadcast> ObserveTable for ServiceProvider<Repository, Broadcast> {
+impl<Repository: AccessTournaments, Broadcast: AccessTableMessageBroadcast, Accounts: AccessAccounts + AccessBans> ObserveTable for ServiceProvider<Repository, Broadcast, Accounts> {
     fn observe_table(&mut self, request: ObserveTableRequest, auth_info: &AuthInfo) -> Result<ObserveTableResponse, ObserveTableError> {
-        observe_table(request, auth_info, &self.repository, &mut self.broadcast)
+        observe_table(request, auth_info, &self.repository, &mut self.broadcast, &self.accounts)
+    }
+}
+
+
+impl<Repository: AccessTournaments, Broadcast: AccessTableMessageBroadcast, Accounts: AccessAccounts + AccessBans> ObserveTournament for ServiceProvider<Repository, Broadcast, Accounts> {
+    fn observe_tournament(&mut self, request: ObserveTournamentRequest, auth_info: &AuthInfo) -> Result<ObserveTournamentResponse, ObserveTournamentError> {
+        observe_tournament(request, auth_info, &self.repository, &mut self.broadcast)
+    }
+}
+
+
+impl<Repository: AccessTournaments, Broadcast: AccessTableMessageBroadcast, Accounts: AccessAccounts + AccessBans> ObserveLobby for ServiceProvider<Repository, Broadcast, Accounts> {
+    fn observe_lobby(&mut self, request: ObserveLobbyRequest, auth_info: &AuthInfo) -> Result<ObserveLobbyResponse, ObserveLobbyError> {
+        observe_lobby(request, auth_info, &mut self.broadcast)
+    }
+}
+
+
+impl<Repository: AccessTournaments, Broadcast: AccessTableMessageBroadcast, Accounts: AccessAccounts + AccessBans> GetBroadcastStatistics for ServiceProvider<Repository, Broadcast, Accounts> {
+    fn get_broadcast_statistics(&self, request: GetBroadcastStatisticsRequest, auth_info: &AuthInfo) -> Result<GetBroadcastStatisticsResponse, GetBroadcastStatisticsError> {
+        get_broadcast_statistics(request, auth_info, &self.broadcast, &self.accounts)
+    }
+}
+
+
+impl<Repository: AccessTournaments, Broadcast: AccessTableMessageBroadcast, Accounts: AccessAccounts + AccessBans> RelayMessages for ServiceProvider<Repository, Broadcast, Accounts> {
+    fn relay_messages(&mut self, request: RelayMessagesRequest) -> Result<RelayMessagesResponse, RelayMessagesError> {
+        relay_messages(request, &mut self.repository, &mut self.broadcast, &mut self.followers.pending)
+    }
+}
+
+
+impl<Repository: AccessTournaments, Broadcast: AccessTableMessageBroadcast, Accounts: AccessAccounts + AccessBans> RebuildProjections for ServiceProvider<Repository, Broadcast, Accounts> {
+    fn rebuild_projections(&mut self, request: RebuildProjectionsRequest, auth_info: &AuthInfo) -> Result<RebuildProjectionsResponse, RebuildProjectionsError> {
+        rebuild_projections(request, auth_info, &self.repository, &mut self.followers.projection, &self.accounts)
+    }
+}
+
+
+impl<Repository: AccessTournaments, Broadcast: AccessTableMessageBroadcast, Accounts: AccessAccounts + AccessBans> GetPlayerHistory for ServiceProvider<Repository, Broadcast, Accounts> {
+    fn get_player_history(&self, request: GetPlayerHistoryRequest, auth_info: &AuthInfo) -> Result<GetPlayerHistoryResponse, GetPlayerHistoryError> {
+        get_player_history(request, auth_info, &self.followers.projection)
+    }
+}
+
+
+impl<Repository: AccessTournaments, Broadcast: AccessTableMessageBroadcast, Accounts: AccessAccounts + AccessBans> RegisterAccount for ServiceProvider<Repository, Broadcast, Accounts> {
+    fn register_account(&mut self, request: RegisterAccountRequest, auth_info: &AuthInfo) -> Result<RegisterAccountResponse, RegisterAccountError> {
+        register_account(request, auth_info, &mut self.accounts)
+    }
+}
+
+
+impl<Repository: AccessTournaments, Broadcast: AccessTableMessageBroadcast, Accounts: AccessAccounts + AccessBans> Login for ServiceProvider<Repository, Broadcast, Accounts> {
+    fn login(&self, request: LoginRequest, auth_info: &AuthInfo) -> Result<PendingLogin, LoginError> {
+        login(request, auth_info, &self.accounts)
+    }
+}
+
+
+impl<Repository: AccessTournaments, Broadcast: AccessTableMessageBroadcast, Accounts: AccessAccounts + AccessBans> ChangePassword for ServiceProvider<Repository, Broadcast, Accounts> {
+    fn change_password(&self, request: ChangePasswordRequest, auth_info: &AuthInfo) -> Result<PendingPasswordChange, ChangePasswordError> {
+        change_password(request, auth_info, &self.accounts)
+    }
+
+    fn complete_password_change(&mut self, password_change: PasswordChange, auth_info: &AuthInfo) -> Result<ChangePasswordResponse, ChangePasswordError> {
+        complete_password_change(password_change, auth_info, &mut self.accounts)
+    }
+}
+
+
+impl<Repository: AccessTournaments, Broadcast: AccessTableMessageBroadcast, Accounts: AccessAccounts + AccessBans> ChangeNickname for ServiceProvider<Repository, Broadcast, Accounts> {
+    fn change_nickname(&mut self, request: ChangeNicknameRequest, auth_info: &AuthInfo) -> Result<ChangeNicknameResponse, ChangeNicknameError> {
+        change_nickname(request, auth_info, &mut self.accounts, &self.blocked_words)
+    }
+}
+
+
+impl<Repository: AccessTournaments, Broadcast: AccessTableMessageBroadcast, Accounts: AccessAccounts + AccessBans> PromoteAccount for ServiceProvider<Repository, Broadcast, Accounts> {
+    fn promote_account(&mut self, request: PromoteAccountRequest, auth_info: &AuthInfo) -> Result<PromoteAccountResponse, PromoteAccountError> {
+        promote_account(request, auth_info, &mut self.accounts)
+    }
+}
+
+
+impl<Repository: AccessTournaments, Broadcast: AccessTableMessageBroadcast, Accounts: AccessAccounts + AccessBans> DemoteAccount for ServiceProvider<Repository, Broadcast, Accounts> {
+    fn demote_account(&mut self, request: DemoteAccountRequest, auth_info: &AuthInfo) -> Result<DemoteAccountResponse, DemoteAccountError> {
+        demote_account(request, auth_info, &mut self.accounts)
+    }
+}
+
+
+impl<Repository: AccessTournaments, Broadcast: AccessTableMessageBroadcast, Accounts: AccessAccounts + AccessBans> ListAccounts for ServiceProvider<Repository, Broadcast, Accounts> {
+    fn list_accounts(&self, request: ListAccountsRequest, auth_info: &AuthInfo) -> Result<ListAccountsResponse, ListAccountsError> {
+        list_accounts(request, auth_info, &self.accounts)
+    }
+}
+
+
+impl<Repository: AccessTournaments, Broadcast: AccessTableMessageBroadcast, Accounts: AccessAccounts + AccessBans> GetRoleChanges for ServiceProvider<Repository, Broadcast, Accounts> {
+    fn get_role_changes(&self, request: GetRoleChangesRequest, auth_info: &AuthInfo) -> Result<GetRoleChangesResponse, GetRoleChangesError> {
+        get_role_changes(request, auth_info, &self.accounts)
+    }
+}
+
+
+impl<Repository: AccessTournaments, Broadcast: AccessTableMessageBroadcast, Accounts: AccessAccounts + AccessBans> BootstrapAdministrator for ServiceProvider<Repository, Broadcast, Accounts> {
+    fn bootstrap_administrator(&mut self, request: BootstrapAdministratorRequest) -> Result<BootstrapAdministratorResponse, BootstrapAdministratorError> {
+        bootstrap_administrator(request, &mut self.accounts)
+    }
+}
+
+
+impl<Repository: AccessTournaments, Broadcast: AccessTableMessageBroadcast, Accounts: AccessAccounts + AccessBans> BanAccount for ServiceProvider<Repository, Broadcast, Accounts> {
+    fn ban_account(&mut self, request: BanAccountRequest, auth_info: &AuthInfo) -> Result<BanAccountResponse, BanAccountError> {
+        ban_account(request, auth_info, &mut self.repository, &mut self.broadcast, &mut self.followers, &mut self.accounts)
+    }
+}
+
+
+impl<Repository: AccessTournaments, Broadcast: AccessTableMessageBroadcast, Accounts: AccessAccounts + AccessBans> LiftBan for ServiceProvider<Repository, Broadcast, Accounts> {
+    fn lift_ban(&mut self, request: LiftBanRequest, auth_info: &AuthInfo) -> Result<LiftBanResponse, LiftBanError> {
+        lift_ban(request, auth_info, &mut self.accounts)
     }
 }
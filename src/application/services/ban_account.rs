@@ -0,0 +1,293 @@
+use crate::application::AuthError;
+use crate::application::AuthInfo;
+
+use crate::domain::AccessEventStreams;
+use crate::domain::AccessOutbox;
+use crate::domain::AccessTables;
+use crate::domain::Ban;
+use crate::domain::BanError;
+use crate::domain::BanScope;
+use crate::domain::EventFollowers;
+use crate::domain::LoadAccount;
+use crate::domain::LoadAccountError;
+use crate::domain::LoadTournament;
+use crate::domain::LoadTournamentError;
+use crate::domain::PublishTournamentMessages;
+use crate::domain::SaveBan;
+use crate::domain::SaveBanError;
+use crate::domain::SaveTournament;
+use crate::domain::SaveTournamentAndPublishMessagesError;
+use crate::domain::SaveTournamentError;
+use crate::domain::TournamentError;
+use crate::domain::save_tournament_and_publish_messages;
+use crate::domain::unix_time;
+
+use thiserror::Error;
+use uuid::Uuid;
+
+use std::time::Duration;
+
+
+#[derive(Debug, Error)]
+pub enum BanAccountError {
+    #[error(transparent)]
+    AuthError(#[from] AuthError),
+    #[error(transparent)]
+    LoadAccountError(#[from] LoadAccountError),
+    #[error(transparent)]
+    BanError(#[from] BanError),
+    #[error(transparent)]
+    LoadTournamentError(#[from] LoadTournamentError),
+    #[error(transparent)]
+    TournamentError(#[from] TournamentError),
+    #[error(transparent)]
+    SaveTournamentAndPublishMessagesError(#[from] SaveTournamentAndPublishMessagesError),
+    #[error(transparent)]
+    SaveBanError(#[from] SaveBanError),
+}
+
+
+#[derive(Debug)]
+pub struct BanAccountRequest {
+    pub account_id: Uuid,
+    pub tournament_id: Option<Uuid>,    // None for a ban from the whole platform
+    pub duration: Option<Duration>,     // None for a ban without time limit
+    pub reason: String,
+}
+
+
+#[derive(Debug)]
+pub struct BanAccountResponse {
+    pub ban_id: Uuid,
+    pub expires_at: Option<u64>,
+}
+
+
+pub trait BanAccount {
+    fn ban_account(&mut self, request: BanAccountRequest, auth_info: &AuthInfo) -> Result<BanAccountResponse, BanAccountError>;
+}
+
+
+// Keeps the account from joining and observing, and removes it from the tournaments within the
+// scope of the ban, after which the process manager frees its seat. For a ban from the platform
+// these are the tournaments the account is still part of according to its history. The account
+// leaves before the ban is saved, so that a failure leaves no ban behind that a retry would repeat.
+pub(in crate::application) fn ban_account<Repository, Publisher, Accounts>(
+    request: BanAccountRequest,
+    auth_info: &AuthInfo,
+    repository: &mut Repository,
+    publisher: &mut Publisher,
+    followers: &mut EventFollowers,
+    accounts: &mut Accounts,
+) -> Result<BanAccountResponse, BanAccountError>
+where
+    Repository: LoadTournament + SaveTournament + AccessTables + AccessEventStreams + AccessOutbox,
+    Publisher: PublishTournamentMessages,
+    Accounts: LoadAccount + SaveBan,
+{
+    let moderator_id = auth_info.with_current_role(accounts)?.ensure_moderator()?;
+    let account = accounts.load_account(request.account_id)?;
+    let scope = match request.tournament_id {
+        Some(tournament_id) => BanScope::Tournament { tournament_id },
+        None => BanScope::Platform,
+    };
+    let ban = Ban::issue(&account, scope, moderator_id, request.reason, request.duration, unix_time())?;
+    let tournament_ids = match ban.scope {
+        BanScope::Tournament { tournament_id } => vec![tournament_id],
+        BanScope::Platform => followers.projection.player_history(account.id()).into_iter()
+            .filter(|entry| entry.rank.is_none())
+            .map(|entry| entry.tournament_id)
+            .collect(),
+    };
+    for tournament_id in tournament_ids {
+        leave_tournament(tournament_id, account.id(), repository, publisher, followers)?;
+    }
+    let response = BanAccountResponse { ban_id: ban.id, expires_at: ban.expires_at };
+    accounts.save_ban(ban)?;
+    Ok(response)
+}
+
+
+// Players who are not part of the tournament (any more) have nothing to leave. Like joins, leaves
+// are tried again on the fresh state when someone else saved the tournament in the meantime.
+fn leave_tournament<Repository, Publisher>(
+    tournament_id: Uuid,
+    account_id: Uuid,
+    repository: &mut Repository,
+    publisher: &mut Publisher,
+    followers: &mut EventFollowers,
+) -> Result<(), BanAccountError>
+where
+    Repository: LoadTournament + SaveTournament + AccessTables + AccessEventStreams + AccessOutbox,
+    Publisher: PublishTournamentMessages,
+{
+    const MAX_ATTEMPTS: usize = 3;
+    let mut attempt = 1;
+    loop {
+        let mut tournament = repository.load_tournament(tournament_id)?;
+        if !tournament.has_player(account_id) || tournament.is_finished() {
+            return Ok(());
+        }
+        tournament.leave(account_id)?;
+        match save_tournament_and_publish_messages(tournament, repository, publisher, followers) {
+            Ok(()) => return Ok(()),
+            Err(SaveTournamentAndPublishMessagesError::SaveTournamentError(SaveTournamentError::TournamentOutdated)) if attempt < MAX_ATTEMPTS => attempt += 1,
+            Err(error) => return Err(error.into()),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::application::AuthRole;
+    use crate::domain::Account;
+    use crate::domain::AccountRole;
+    use crate::domain::LoadTable;
+    use crate::domain::Nickname;
+    use crate::domain::TableMessageBroadcast;
+    use crate::domain::Tournament;
+    use crate::domain::TournamentSpecification;
+    use crate::domain::Username;
+    use crate::domain::save_tournament_and_publish_messages;
+    use crate::infrastructure::InMemoryTournamentRepository;
+
+    use super::*;
+
+    struct DummyAccounts {
+        account: Account,
+        moderator: Account,
+        bans: Vec<Ban>,
+    }
+
+    impl DummyAccounts {
+        fn new() -> Self {
+            let account = Account::register(Username::new("daniel").unwrap(), "correct horse").unwrap();
+            let mut moderator = Account::register(Username::new("james").unwrap(), "correct horse").unwrap();
+            moderator.promote(None, unix_time()).unwrap();
+            Self { account, moderator, bans: vec![] }
+        }
+    }
+
+    impl LoadAccount for DummyAccounts {
+        fn load_account(&self, account_id: Uuid) -> Result<Account, LoadAccountError> {
+            [&self.account, &self.moderator].into_iter()
+                .find(|account| account.id() == account_id)
+                .cloned()
+                .ok_or(LoadAccountError::AccountNotFound)
+        }
+
+        fn load_account_by_username(&self, username: &Username) -> Result<Account, LoadAccountError> {
+            (username == self.account.username()).then(|| self.account.clone()).ok_or(LoadAccountError::AccountNotFound)
+        }
+
+        fn load_accounts_with_role(&self, role: AccountRole) -> Result<Vec<Account>, LoadAccountError> {
+            Ok((self.account.role() == role).then(|| self.account.clone()).into_iter().collect())
+        }
+    }
+
+    impl SaveBan for DummyAccounts {
+        fn save_ban(&mut self, ban: Ban) -> Result<(), SaveBanError> {
+            self.bans.push(ban);
+            Ok(())
+        }
+    }
+
+    fn request(account_id: Uuid, tournament_id: Option<Uuid>) -> BanAccountRequest {
+        BanAccountRequest { account_id, tournament_id, duration: Some(Duration::from_secs(3600)), reason: "spam".into() }
+    }
+
+    fn ban_account_in(request: BanAccountRequest, auth_info: &AuthInfo, accounts: &mut DummyAccounts) -> Result<BanAccountResponse, BanAccountError> {
+        ban_account(request, auth_info, &mut InMemoryTournamentRepository::new(), &mut TableMessageBroadcast::new(), &mut EventFollowers::new(), accounts)
+    }
+
+    fn joined_tournament(account_id: Uuid, repository: &mut InMemoryTournamentRepository, followers: &mut EventFollowers) -> Uuid {
+        let mut tournament = Tournament::new(&TournamentSpecification::new(1, 3).unwrap());
+        let tournament_id = tournament.id();
+        _ = tournament.join(account_id, Nickname::new("Daniel").unwrap());
+        save_tournament_and_publish_messages(tournament, repository, &mut TableMessageBroadcast::new(), followers).unwrap();
+        tournament_id
+    }
+
+    // Whether the tournament and its table have the player
+    fn seating(account_id: Uuid, tournament_id: Uuid, repository: &InMemoryTournamentRepository) -> (bool, bool) {
+        (repository.load_tournament(tournament_id).unwrap().has_player(account_id), repository.load_table(tournament_id, 0).unwrap().has_player(account_id))
+    }
+
+    #[test]
+    fn ban_account_without_being_moderator() {
+        let mut accounts = DummyAccounts::new();
+        let auth_info = AuthInfo::Authenticated { account_id: accounts.account.id(), role: AuthRole::Member };
+        let result = ban_account_in(request(accounts.moderator.id(), None), &auth_info, &mut accounts);
+        assert!(matches!(result, Err(BanAccountError::AuthError(AuthError::PermissionDenied { .. }))));
+        assert_eq!(accounts.bans, vec![]);
+    }
+
+    #[test]
+    fn ban_account_with_outdated_token() {
+        let mut accounts = DummyAccounts::new();
+        let auth_info = AuthInfo::Authenticated { account_id: accounts.moderator.id(), role: AuthRole::Moderator };
+        accounts.moderator.demote(None, unix_time()).unwrap();
+        let result = ban_account_in(request(accounts.account.id(), None), &auth_info, &mut accounts);
+        assert!(matches!(result, Err(BanAccountError::AuthError(AuthError::PermissionDenied { found: AuthRole::Member, .. }))));
+        assert_eq!(accounts.bans, vec![]);
+    }
+
+    #[test]
+    fn ban_account_with_moderator_account() {
+        let mut accounts = DummyAccounts::new();
+        accounts.account.promote(None, unix_time()).unwrap();
+        let auth_info = AuthInfo::Authenticated { account_id: accounts.moderator.id(), role: AuthRole::Moderator };
+        let result = ban_account_in(request(accounts.account.id(), None), &auth_info, &mut accounts);
+        assert!(matches!(result, Err(BanAccountError::BanError(BanError::AccountProtected))));
+    }
+
+    #[test]
+    fn ban_account_without_any_error() {
+        let mut accounts = DummyAccounts::new();
+        let moderator_id = accounts.moderator.id();
+        let mut repository = InMemoryTournamentRepository::new();
+        let mut followers = EventFollowers::new();
+        let tournament_id = joined_tournament(Uuid::new_v4(), &mut repository, &mut followers);
+        let auth_info = AuthInfo::Authenticated { account_id: moderator_id, role: AuthRole::Moderator };
+        let request = request(accounts.account.id(), Some(tournament_id));
+        let response = ban_account(request, &auth_info, &mut repository, &mut TableMessageBroadcast::new(), &mut followers, &mut accounts).unwrap();
+        assert_eq!(accounts.bans.len(), 1);
+        let ban = &accounts.bans[0];
+        assert_eq!((ban.id, ban.expires_at), (response.ban_id, response.expires_at));
+        assert_eq!(ban.expires_at, Some(ban.banned_at + 3600));
+        assert_eq!(ban.scope, BanScope::Tournament { tournament_id });
+        assert_eq!(ban.banned_by, moderator_id);
+    }
+
+    #[test]
+    fn banned_players_leave_the_tournaments_within_the_scope() {
+        let mut accounts = DummyAccounts::new();
+        let account_id = accounts.account.id();
+        let mut repository = InMemoryTournamentRepository::new();
+        let mut followers = EventFollowers::new();
+        let tournament_ids = [
+            joined_tournament(account_id, &mut repository, &mut followers),
+            joined_tournament(account_id, &mut repository, &mut followers),
+        ];
+        let auth_info = AuthInfo::Authenticated { account_id: accounts.moderator.id(), role: AuthRole::Moderator };
+
+        ban_account(request(account_id, Some(tournament_ids[0])), &auth_info, &mut repository, &mut TableMessageBroadcast::new(), &mut followers, &mut accounts).unwrap();
+        assert_eq!(seating(account_id, tournament_ids[0], &repository), (false, false));
+        assert_eq!(seating(account_id, tournament_ids[1], &repository), (true, true));
+
+        ban_account(request(account_id, None), &auth_info, &mut repository, &mut TableMessageBroadcast::new(), &mut followers, &mut accounts).unwrap();
+        assert_eq!(seating(account_id, tournament_ids[1], &repository), (false, false));
+        assert_eq!(followers.projection.player_history(account_id), vec![]);
+        assert_eq!(accounts.bans.len(), 2);
+    }
+
+    #[test]
+    fn ban_account_from_unknown_tournament() {
+        let mut accounts = DummyAccounts::new();
+        let auth_info = AuthInfo::Authenticated { account_id: accounts.moderator.id(), role: AuthRole::Moderator };
+        let result = ban_account_in(request(accounts.account.id(), Some(Uuid::new_v4())), &auth_info, &mut accounts);
+        assert!(matches!(result, Err(BanAccountError::LoadTournamentError(LoadTournamentError::TournamentNotFound))));
+        assert_eq!(accounts.bans, vec![]);
+    }
+}
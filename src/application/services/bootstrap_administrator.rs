@@ -0,0 +1,154 @@
+use crate::domain::AccessAccounts;
+use crate::domain::Account;
+use crate::domain::AccountError;
+use crate::domain::AccountRole;
+use crate::domain::LoadAccountError;
+use crate::domain::SaveAccountError;
+use crate::domain::Username;
+use crate::domain::unix_time;
+
+use thiserror::Error;
+use uuid::Uuid;
+
+
+#[derive(Debug, Error)]
+pub enum BootstrapAdministratorError {
+    #[error(transparent)]
+    AccountError(#[from] AccountError),
+    #[error(transparent)]
+    LoadAccountError(#[from] LoadAccountError),
+    #[error(transparent)]
+    SaveAccountError(#[from] SaveAccountError),
+}
+
+
+#[derive(Debug)]
+pub struct BootstrapAdministratorRequest {
+    pub username: String,
+    pub password: String,
+}
+
+
+#[derive(Debug)]
+pub struct BootstrapAdministratorResponse {
+    pub account_id: Option<Uuid>,   // None if there already was an administrator
+}
+
+
+// Run by the server itself on startup, with the credentials from its configuration
+pub trait BootstrapAdministrator {
+    fn bootstrap_administrator(&mut self, request: BootstrapAdministratorRequest) -> Result<BootstrapAdministratorResponse, BootstrapAdministratorError>;
+}
+
+
+// Makes the configured account the first administrator, registering it if needed. An existing account
+// is only promoted with the configured password, so registering the name first grants nothing.
+pub(in crate::application) fn bootstrap_administrator<Accounts: AccessAccounts>(
+    request: BootstrapAdministratorRequest,
+    accounts: &mut Accounts,
+) -> Result<BootstrapAdministratorResponse, BootstrapAdministratorError> {
+    if !accounts.load_accounts_with_role(AccountRole::Administrator)?.is_empty() {
+        return Ok(BootstrapAdministratorResponse { account_id: None });
+    }
+    let username = Username::new(request.username)?;
+    let mut account = match accounts.load_account_by_username(&username) {
+        Err(LoadAccountError::AccountNotFound) => Account::register(username, &request.password)?,
+        result => result?,
+    };
+    account.verify_password(&request.password)?;
+    while account.role() != AccountRole::Administrator {
+        account.promote(None, unix_time())?;
+    }
+    let account_id = account.id();
+    accounts.save_account(account)?;
+    Ok(BootstrapAdministratorResponse { account_id: Some(account_id) })
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::domain::LoadAccount;
+    use crate::domain::LoadRoleChanges;
+    use crate::domain::LoadRoleChangesError;
+    use crate::domain::RoleChange;
+    use crate::domain::SaveAccount;
+
+    use super::*;
+
+    struct DummyAccounts {
+        accounts: Vec<Account>,
+        role_changes: Vec<RoleChange>,
+    }
+
+    impl DummyAccounts {
+        fn new(accounts: Vec<Account>) -> Self {
+            Self { accounts, role_changes: vec![] }
+        }
+    }
+
+    impl LoadAccount for DummyAccounts {
+        fn load_account(&self, account_id: Uuid) -> Result<Account, LoadAccountError> {
+            self.accounts.iter().find(|account| account.id() == account_id).cloned().ok_or(LoadAccountError::AccountNotFound)
+        }
+
+        fn load_account_by_username(&self, username: &Username) -> Result<Account, LoadAccountError> {
+            self.accounts.iter().find(|account| account.username() == username).cloned().ok_or(LoadAccountError::AccountNotFound)
+        }
+
+        fn load_accounts_with_role(&self, role: AccountRole) -> Result<Vec<Account>, LoadAccountError> {
+            Ok(self.accounts.iter().filter(|account| account.role() == role).cloned().collect())
+        }
+    }
+
+    impl SaveAccount for DummyAccounts {
+        fn save_account(&mut self, mut account: Account) -> Result<(), SaveAccountError> {
+            self.role_changes.extend(account.collect_role_changes());
+            self.accounts.retain(|other| other.id() != account.id());
+            self.accounts.push(account);
+            Ok(())
+        }
+    }
+
+    impl LoadRoleChanges for DummyAccounts {
+        fn load_role_changes(&self, account_id: Uuid) -> Result<Vec<RoleChange>, LoadRoleChangesError> {
+            Ok(self.role_changes.iter().filter(|change| change.account_id == account_id).cloned().collect())
+        }
+    }
+
+    fn request(password: &str) -> BootstrapAdministratorRequest {
+        BootstrapAdministratorRequest { username: "Daniel".into(), password: password.into() }
+    }
+
+    #[test]
+    fn bootstrap_administrator_with_new_account() {
+        let mut accounts = DummyAccounts::new(vec![]);
+        let response = bootstrap_administrator(request("correct horse"), &mut accounts).unwrap();
+        let account = accounts.load_account(response.account_id.unwrap()).unwrap();
+        assert_eq!(account.username().as_str(), "daniel");
+        assert_eq!(account.role(), AccountRole::Administrator);
+        assert!(accounts.role_changes.iter().all(|change| change.changed_by.is_none()));
+        assert_eq!(accounts.role_changes.len(), 2);
+    }
+
+    #[test]
+    fn bootstrap_administrator_with_existing_account() {
+        let account = Account::register(Username::new("daniel").unwrap(), "correct horse").unwrap();
+        let mut accounts = DummyAccounts::new(vec![account.clone()]);
+        let result = bootstrap_administrator(request("wrong horse"), &mut accounts);
+        assert!(matches!(result, Err(BootstrapAdministratorError::AccountError(AccountError::InvalidCredentials))));
+        let response = bootstrap_administrator(request("correct horse"), &mut accounts).unwrap();
+        assert_eq!(response.account_id, Some(account.id()));
+        assert_eq!(accounts.load_account(account.id()).unwrap().role(), AccountRole::Administrator);
+    }
+
+    #[test]
+    fn bootstrap_administrator_with_existing_administrator() {
+        let mut administrator = Account::register(Username::new("james").unwrap(), "correct horse").unwrap();
+        administrator.promote(None, unix_time()).unwrap();
+        administrator.promote(None, unix_time()).unwrap();
+        let mut accounts = DummyAccounts::new(vec![administrator]);
+        let response = bootstrap_administrator(request("correct horse"), &mut accounts).unwrap();
+        assert_eq!(response.account_id, None);
+        assert_eq!(accounts.accounts.len(), 1);
+    }
+}
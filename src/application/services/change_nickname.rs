@@ -0,0 +1,154 @@
+use crate::application::AuthError;
+use crate::application::AuthInfo;
+
+use crate::domain::AccessAccounts;
+use crate::domain::AccountError;
+use crate::domain::BlockedWords;
+use crate::domain::LoadAccountError;
+use crate::domain::Nickname;
+use crate::domain::NicknameError;
+use crate::domain::SaveAccountError;
+use crate::domain::unix_time;
+
+use thiserror::Error;
+
+
+#[derive(Debug, Error)]
+pub enum ChangeNicknameError {
+    #[error(transparent)]
+    AuthError(#[from] AuthError),
+    #[error(transparent)]
+    NicknameError(#[from] NicknameError),
+    #[error(transparent)]
+    LoadAccountError(#[from] LoadAccountError),
+    #[error(transparent)]
+    AccountError(#[from] AccountError),
+    #[error(transparent)]
+    SaveAccountError(#[from] SaveAccountError),
+}
+
+
+#[derive(Debug)]
+pub struct ChangeNicknameRequest {
+    pub nickname: String,
+}
+
+
+#[derive(Debug)]
+pub struct ChangeNicknameResponse {
+}
+
+
+pub trait ChangeNickname {
+    fn change_nickname(&mut self, request: ChangeNicknameRequest, auth_info: &AuthInfo) -> Result<ChangeNicknameResponse, ChangeNicknameError>;
+}
+
+
+// Tournaments joined before the change keep the old nickname
+pub(in crate::application) fn change_nickname<Accounts: AccessAccounts>(
+    request: ChangeNicknameRequest,
+    auth_info: &AuthInfo,
+    accounts: &mut Accounts,
+    blocked_words: &BlockedWords,
+) -> Result<ChangeNicknameResponse, ChangeNicknameError> {
+    let account_id = auth_info.ensure_authenticated()?;
+    let nickname = Nickname::new(request.nickname)?;
+    blocked_words.check(&nickname)?;
+    let mut account = accounts.load_account(account_id)?;
+    account.change_nickname(nickname, unix_time())?;
+    accounts.save_account(account)?;
+    Ok(ChangeNicknameResponse {})
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::application::AuthRole;
+    use crate::domain::Account;
+    use crate::domain::AccountRole;
+    use crate::domain::LoadAccount;
+    use crate::domain::LoadRoleChanges;
+    use crate::domain::LoadRoleChangesError;
+    use crate::domain::RoleChange;
+    use crate::domain::SaveAccount;
+    use crate::domain::Username;
+
+    use uuid::Uuid;
+
+    use super::*;
+
+    struct DummyAccounts {
+        account: Account,
+    }
+
+    impl DummyAccounts {
+        fn new() -> Self {
+            Self { account: Account::register(Username::new("daniel").unwrap(), "correct horse").unwrap() }
+        }
+    }
+
+    impl LoadAccount for DummyAccounts {
+        fn load_account(&self, account_id: Uuid) -> Result<Account, LoadAccountError> {
+            (account_id == self.account.id()).then(|| self.account.clone()).ok_or(LoadAccountError::AccountNotFound)
+        }
+
+        fn load_account_by_username(&self, username: &Username) -> Result<Account, LoadAccountError> {
+            (username == self.account.username()).then(|| self.account.clone()).ok_or(LoadAccountError::AccountNotFound)
+        }
+
+        fn load_accounts_with_role(&self, role: AccountRole) -> Result<Vec<Account>, LoadAccountError> {
+            Ok((self.account.role() == role).then(|| self.account.clone()).into_iter().collect())
+        }
+    }
+
+    impl SaveAccount for DummyAccounts {
+        fn save_account(&mut self, account: Account) -> Result<(), SaveAccountError> {
+            self.account = account;
+            Ok(())
+        }
+    }
+
+    impl LoadRoleChanges for DummyAccounts {
+        fn load_role_changes(&self, _account_id: Uuid) -> Result<Vec<RoleChange>, LoadRoleChangesError> {
+            Ok(vec![])
+        }
+    }
+
+    fn request(nickname: &str) -> ChangeNicknameRequest {
+        ChangeNicknameRequest { nickname: nickname.into() }
+    }
+
+    #[test]
+    fn change_nickname_without_being_authenticated() {
+        let mut accounts = DummyAccounts::new();
+        let result = change_nickname(request("Daniel"), &AuthInfo::Unauthenticated, &mut accounts, &BlockedWords::default());
+        assert!(matches!(result, Err(ChangeNicknameError::AuthError(AuthError::AuthenticationRequired))));
+    }
+
+    #[test]
+    fn change_nickname_to_blocked_word() {
+        let mut accounts = DummyAccounts::new();
+        let auth_info = AuthInfo::Authenticated { account_id: accounts.account.id(), role: AuthRole::Member };
+        let result = change_nickname(request("TheAdmin"), &auth_info, &mut accounts, &BlockedWords::new(["admin"]));
+        assert!(matches!(result, Err(ChangeNicknameError::NicknameError(NicknameError::BlockedWord))));
+        assert_eq!(accounts.account.nickname(), None);
+    }
+
+    #[test]
+    fn change_nickname_during_cooldown() {
+        let mut accounts = DummyAccounts::new();
+        let auth_info = AuthInfo::Authenticated { account_id: accounts.account.id(), role: AuthRole::Member };
+        change_nickname(request("Daniel"), &auth_info, &mut accounts, &BlockedWords::default()).unwrap();
+        let result = change_nickname(request("Denyo"), &auth_info, &mut accounts, &BlockedWords::default());
+        assert!(matches!(result, Err(ChangeNicknameError::AccountError(AccountError::NicknameChangeTooSoon { .. }))));
+        assert_eq!(accounts.account.nickname(), Some(&Nickname::new("Daniel").unwrap()));
+    }
+
+    #[test]
+    fn change_nickname_without_any_error() {
+        let mut accounts = DummyAccounts::new();
+        let auth_info = AuthInfo::Authenticated { account_id: accounts.account.id(), role: AuthRole::Member };
+        change_nickname(request("Daniel"), &auth_info, &mut accounts, &BlockedWords::default()).unwrap();
+        assert_eq!(accounts.account.nickname(), Some(&Nickname::new("Daniel").unwrap()));
+    }
+}
@@ -0,0 +1,202 @@
+use crate::application::AuthError;
+use crate::application::AuthInfo;
+
+use crate::domain::AccessAccounts;
+use crate::domain::AccountError;
+use crate::domain::HashedPassword;
+use crate::domain::LoadAccountError;
+use crate::domain::SaveAccountError;
+
+use thiserror::Error;
+use uuid::Uuid;
+
+
+#[derive(Debug, Error)]
+pub enum ChangePasswordError {
+    #[error(transparent)]
+    AuthError(#[from] AuthError),
+    #[error(transparent)]
+    LoadAccountError(#[from] LoadAccountError),
+    #[error(transparent)]
+    AccountError(#[from] AccountError),
+    #[error(transparent)]
+    SaveAccountError(#[from] SaveAccountError),
+}
+
+
+#[derive(Debug)]
+pub struct ChangePasswordRequest {
+    pub current_password: String,
+    pub new_password: String,
+}
+
+
+#[derive(Debug)]
+pub struct ChangePasswordResponse {
+}
+
+
+// Verifying the current password and hashing the new one are done apart from the service, see
+// PendingPasswordChange
+pub trait ChangePassword {
+    fn change_password(&self, request: ChangePasswordRequest, auth_info: &AuthInfo) -> Result<PendingPasswordChange, ChangePasswordError>;
+    fn complete_password_change(&mut self, password_change: PasswordChange, auth_info: &AuthInfo) -> Result<ChangePasswordResponse, ChangePasswordError>;
+}
+
+
+// A password change whose passwords still have to be verified and hashed, which is slow on purpose
+// and therefore done without holding on to the service
+#[derive(Debug)]
+pub struct PendingPasswordChange {
+    account_id: Uuid,
+    hashed_password: HashedPassword,
+    current_password: String,
+    new_password: String,
+}
+
+impl PendingPasswordChange {
+    pub fn hash(self) -> Result<PasswordChange, ChangePasswordError> {
+        self.hashed_password.verify(&self.current_password)?;
+        let new_password = HashedPassword::new(&self.new_password)?;
+        Ok(PasswordChange { account_id: self.account_id, verified: self.hashed_password, new_password })
+    }
+}
+
+
+// Only completed if the password has not changed since it was verified
+#[derive(Debug)]
+pub struct PasswordChange {
+    account_id: Uuid,
+    verified: HashedPassword,
+    new_password: HashedPassword,
+}
+
+
+pub(in crate::application) fn change_password<Accounts: AccessAccounts>(
+    request: ChangePasswordRequest,
+    auth_info: &AuthInfo,
+    accounts: &Accounts,
+) -> Result<PendingPasswordChange, ChangePasswordError> {
+    let account_id = auth_info.ensure_authenticated()?;
+    let account = accounts.load_account(account_id)?;
+    Ok(PendingPasswordChange {
+        account_id,
+        hashed_password: account.hashed_password(),
+        current_password: request.current_password,
+        new_password: request.new_password,
+    })
+}
+
+
+// Tokens issued before the change stay valid until they expire
+pub(in crate::application) fn complete_password_change<Accounts: AccessAccounts>(
+    password_change: PasswordChange,
+    auth_info: &AuthInfo,
+    accounts: &mut Accounts,
+) -> Result<ChangePasswordResponse, ChangePasswordError> {
+    let account_id = auth_info.ensure_authenticated()?;
+    if account_id != password_change.account_id {
+        return Err(AccountError::InvalidCredentials.into());
+    }
+    let mut account = accounts.load_account(account_id)?;
+    account.change_password(&password_change.verified, password_change.new_password)?;
+    accounts.save_account(account)?;
+    Ok(ChangePasswordResponse {})
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::application::AuthRole;
+    use crate::domain::Account;
+    use crate::domain::AccountRole;
+    use crate::domain::LoadAccount;
+    use crate::domain::LoadRoleChanges;
+    use crate::domain::LoadRoleChangesError;
+    use crate::domain::RoleChange;
+    use crate::domain::SaveAccount;
+    use crate::domain::Username;
+
+    use super::*;
+
+    struct DummyAccounts {
+        account: Account,
+    }
+
+    impl DummyAccounts {
+        fn new() -> Self {
+            Self { account: Account::register(Username::new("daniel").unwrap(), "correct horse").unwrap() }
+        }
+    }
+
+    impl LoadAccount for DummyAccounts {
+        fn load_account(&self, account_id: Uuid) -> Result<Account, LoadAccountError> {
+            (account_id == self.account.id()).then(|| self.account.clone()).ok_or(LoadAccountError::AccountNotFound)
+        }
+
+        fn load_account_by_username(&self, username: &Username) -> Result<Account, LoadAccountError> {
+            (username == self.account.username()).then(|| self.account.clone()).ok_or(LoadAccountError::AccountNotFound)
+        }
+
+        fn load_accounts_with_role(&self, role: AccountRole) -> Result<Vec<Account>, LoadAccountError> {
+            Ok((self.account.role() == role).then(|| self.account.clone()).into_iter().collect())
+        }
+    }
+
+    impl SaveAccount for DummyAccounts {
+        fn save_account(&mut self, account: Account) -> Result<(), SaveAccountError> {
+            self.account = account;
+            Ok(())
+        }
+    }
+
+    impl LoadRoleChanges for DummyAccounts {
+        fn load_role_changes(&self, _account_id: Uuid) -> Result<Vec<RoleChange>, LoadRoleChangesError> {
+            Ok(vec![])
+        }
+    }
+
+    fn request(current_password: &str, new_password: &str) -> ChangePasswordRequest {
+        ChangePasswordRequest { current_password: current_password.into(), new_password: new_password.into() }
+    }
+
+    fn change(request: ChangePasswordRequest, auth_info: &AuthInfo, accounts: &mut DummyAccounts) -> Result<ChangePasswordResponse, ChangePasswordError> {
+        let password_change = change_password(request, auth_info, accounts)?.hash()?;
+        complete_password_change(password_change, auth_info, accounts)
+    }
+
+    #[test]
+    fn change_password_without_being_authenticated() {
+        let mut accounts = DummyAccounts::new();
+        let result = change(request("correct horse", "battery staple"), &AuthInfo::Unauthenticated, &mut accounts);
+        assert!(matches!(result, Err(ChangePasswordError::AuthError(AuthError::AuthenticationRequired))));
+    }
+
+    #[test]
+    fn change_password_with_wrong_password() {
+        let mut accounts = DummyAccounts::new();
+        let auth_info = AuthInfo::Authenticated { account_id: accounts.account.id(), role: AuthRole::Member };
+        let result = change(request("wrong horse", "battery staple"), &auth_info, &mut accounts);
+        assert!(matches!(result, Err(ChangePasswordError::AccountError(AccountError::InvalidCredentials))));
+        assert!(accounts.account.verify_password("correct horse").is_ok());
+    }
+
+    #[test]
+    fn change_password_changed_in_the_meantime() {
+        let mut accounts = DummyAccounts::new();
+        let auth_info = AuthInfo::Authenticated { account_id: accounts.account.id(), role: AuthRole::Member };
+        let password_change = change_password(request("correct horse", "battery staple"), &auth_info, &accounts).unwrap().hash().unwrap();
+        change(request("correct horse", "horse battery"), &auth_info, &mut accounts).unwrap();
+        let result = complete_password_change(password_change, &auth_info, &mut accounts);
+        assert!(matches!(result, Err(ChangePasswordError::AccountError(AccountError::InvalidCredentials))));
+        assert!(accounts.account.verify_password("horse battery").is_ok());
+    }
+
+    #[test]
+    fn change_password_without_any_error() {
+        let mut accounts = DummyAccounts::new();
+        let auth_info = AuthInfo::Authenticated { account_id: accounts.account.id(), role: AuthRole::Member };
+        change(request("correct horse", "battery staple"), &auth_info, &mut accounts).unwrap();
+        assert!(accounts.account.verify_password("battery staple").is_ok());
+    }
+}
@@ -1,11 +1,19 @@
 use crate::application::AuthError;
 use crate::application::AuthInfo;
 
+use crate::domain::AccessEventStreams;
+use crate::domain::AccessOutbox;
+use crate::domain::AccessTables;
+use crate::domain::EventFollowers;
+use crate::domain::LoadTournament;
+use crate::domain::ObservationPolicy;
+use crate::domain::PublishTournamentMessages;
 use crate::domain::SaveTournament;
-use crate::domain::SaveTournamentError;
+use crate::domain::SaveTournamentAndPublishMessagesError;
 use crate::domain::Tournament;
 use crate::domain::TournamentSpecification;
 use crate::domain::TournamentSpecificationError;
+use crate::domain::save_tournament_and_publish_messages;
 
 use thiserror::Error;
 use uuid::Uuid;
@@ -16,7 +24,7 @@ pub enum CreateTournamentError {
     #[error(transparent)]
     TournamentSpecificationError(#[from] TournamentSpecificationError),
     #[error(transparent)]
-    SaveTournamentError(#[from] SaveTournamentError),
+    SaveTournamentAndPublishMessagesError(#[from] SaveTournamentAndPublishMessagesError),
     #[error(transparent)]
     AuthError(#[from] AuthError),
 }
@@ -26,6 +34,7 @@ pub enum CreateTournamentError {
 pub struct CreateTournamentRequest {
     pub table_count: u8,
     pub table_seat_count: u8,
+    pub observation_policy: ObservationPolicy,
 }
 
 
@@ -40,62 +49,185 @@ pub trait CreateTournament {
 }
 
 
-pub(in crate::application) fn create_tournament<Repository: SaveTournament>(request: CreateTournamentRequest, auth_info: &AuthInfo, repository: &mut Repository) -> Result<CreateTournamentResponse, CreateTournamentError> {
+pub(in crate::application) fn create_tournament<Repository: LoadTournament + SaveTournament + AccessTables + AccessEventStreams + AccessOutbox, Publisher: PublishTournamentMessages>(
+    request: CreateTournamentRequest,
+    auth_info: &AuthInfo,
+    repository: &mut Repository,
+    publisher: &mut Publisher,
+    followers: &mut EventFollowers,
+) -> Result<CreateTournamentResponse, CreateTournamentError> {
     auth_info.ensure_authenticated()?;
-    let tournament_spec = TournamentSpecification::new(request.table_count, request.table_seat_count)?;
+    let tournament_spec = TournamentSpecification::new(request.table_count, request.table_seat_count)?
+        .with_observation_policy(request.observation_policy)?;
     let tournament = Tournament::new(&tournament_spec);
     let tournament_id = tournament.id();
     let response = CreateTournamentResponse { tournament_id };
-    repository.save_tournament(tournament)?;
+    save_tournament_and_publish_messages(tournament, repository, publisher, followers)?;
     Ok(response)
 }
 
 
 #[cfg(test)]
 mod tests {
+    use std::collections::HashMap;
+
     use crate::application::auth::AuthRole;
+    use crate::domain::EventStream;
+    use crate::domain::LoadCheckpoint;
+    use crate::domain::LoadCheckpointError;
+    use crate::domain::LoadEvents;
+    use crate::domain::LoadEventsError;
+    use crate::domain::LoadOutbox;
+    use crate::domain::LoadOutboxError;
+    use crate::domain::LoadTable;
+    use crate::domain::LoadTableError;
+    use crate::domain::MarkDelivered;
+    use crate::domain::MarkDeliveredError;
+    use crate::domain::ObservationAccess;
+    use crate::domain::OutboxEntry;
+    use crate::domain::LoadTournamentError;
+    use crate::domain::QueryTournaments;
+    use crate::domain::SaveCheckpoint;
+    use crate::domain::SaveCheckpointError;
+    use crate::domain::SaveTable;
+    use crate::domain::SaveTableError;
+    use crate::domain::SaveTournamentError;
+    use crate::domain::Table;
+    use crate::domain::TableEvent;
+    use crate::domain::TournamentEvent;
+    use crate::domain::TournamentMessage;
+    use crate::domain::TournamentQuery;
 
     use super::*;
 
+    use std::time::Duration;
+
     struct DummyRepository {
         save_error: Option<SaveTournamentError>,
         tournament: Option<Tournament>,
+        tournament_events: Vec<TournamentEvent>,
+        tables: HashMap<(Uuid, usize), Table>,
+        checkpoints: HashMap<EventStream, usize>,
+        outbox: Vec<OutboxEntry>,
     }
 
     impl DummyRepository {
         fn new_with_successful_save() -> Self {
-            Self { save_error: None, tournament: None }
+            Self { save_error: None, tournament: None, tournament_events: vec![], tables: HashMap::new(), checkpoints: HashMap::new(), outbox: vec![] }
         }
 
         fn new_with_error_on_save(error: SaveTournamentError) -> Self {
-            Self { save_error: Some(error), tournament: None }
+            Self { save_error: Some(error), tournament: None, tournament_events: vec![], tables: HashMap::new(), checkpoints: HashMap::new(), outbox: vec![] }
         }
 
         fn tournament(&self) -> Option<&Tournament> {
             return self.tournament.as_ref()
         }
+
+        fn add_to_outbox(&mut self, messages: Vec<TournamentMessage>) {
+            for message in messages {
+                let position = self.outbox.last().map_or(1, |entry| entry.position + 1);
+                self.outbox.push(OutboxEntry { position, message });
+            }
+        }
     }
 
     impl SaveTournament for DummyRepository {
-        fn save_tournament(&mut self, tournament: Tournament) -> Result<(), SaveTournamentError> {
+        fn save_tournament(&mut self, mut tournament: Tournament) -> Result<(), SaveTournamentError> {
             if matches!(self.save_error, Some(SaveTournamentError::DatabaseWritingError)) {
                 Err(SaveTournamentError::DatabaseWritingError)
             } else if matches!(self.save_error, Some(SaveTournamentError::TournamentOutdated)) {
                 Err(SaveTournamentError::TournamentOutdated)
             } else {
+                self.tournament_events.extend(tournament.new_events());
+                self.add_to_outbox(tournament.collect_messages());
                 self.tournament = Some(tournament);
                 Ok(())
             }
         }
     }
 
+    impl LoadTournament for DummyRepository {
+        fn load_tournament(&self, _tournament_id: Uuid) -> Result<Tournament, LoadTournamentError> {
+            self.tournament.clone().ok_or(LoadTournamentError::TournamentNotFound)
+        }
+    }
+
+    impl LoadTable for DummyRepository {
+        fn load_table(&self, tournament_id: Uuid, table_number: usize) -> Result<Table, LoadTableError> {
+            self.tables.get(&(tournament_id, table_number)).cloned().ok_or(LoadTableError::TableNotFound)
+        }
+    }
+
+    impl SaveTable for DummyRepository {
+        fn save_table(&mut self, mut table: Table) -> Result<(), SaveTableError> {
+            self.add_to_outbox(table.collect_tournament_messages());
+            self.tables.insert((table.tournament_id(), table.table_number()), table);
+            Ok(())
+        }
+    }
+
+    impl LoadEvents for DummyRepository {
+        fn load_tournament_ids(&self) -> Result<Vec<Uuid>, LoadEventsError> {
+            Ok(vec![])
+        }
+
+        fn load_tournament_events(&self, _tournament_id: Uuid, from_version: usize) -> Result<Vec<TournamentEvent>, LoadEventsError> {
+            Ok(self.tournament_events.iter().skip(from_version).cloned().collect())
+        }
+
+        fn load_table_events(&self, _tournament_id: Uuid, _table_number: usize, _from_version: usize) -> Result<Vec<TableEvent>, LoadEventsError> {
+            Ok(vec![])
+        }
+    }
+
+    impl LoadCheckpoint for DummyRepository {
+        fn load_checkpoint(&self, stream: EventStream) -> Result<usize, LoadCheckpointError> {
+            Ok(self.checkpoints.get(&stream).copied().unwrap_or(0))
+        }
+    }
+
+    impl SaveCheckpoint for DummyRepository {
+        fn save_checkpoint(&mut self, stream: EventStream, checkpoint: usize) -> Result<(), SaveCheckpointError> {
+            self.checkpoints.insert(stream, checkpoint);
+            Ok(())
+        }
+    }
+
+    impl LoadOutbox for DummyRepository {
+        fn load_pending_messages(&self) -> Result<Vec<OutboxEntry>, LoadOutboxError> {
+            Ok(self.outbox.clone())
+        }
+    }
+
+    impl MarkDelivered for DummyRepository {
+        fn mark_delivered(&mut self, position: u64) -> Result<(), MarkDeliveredError> {
+            self.outbox.retain(|entry| entry.position > position);
+            Ok(())
+        }
+    }
+
+
+    struct DummyPublisher;
+
+    impl PublishTournamentMessages for DummyPublisher {
+        fn close_tournament_channels(&mut self, _tournament_id: Uuid) {
+        }
+
+        fn publish_outbox_entries(&mut self, _entries: Vec<OutboxEntry>) {
+        }
+
+        fn sweep_channels(&mut self) {
+        }
+    }
+
 
     #[test]
     fn create_tournament_without_being_authenticated() {
         let mut repository = DummyRepository::new_with_successful_save();
-        let request = CreateTournamentRequest { table_count: 1, table_seat_count: 5 };
+        let request = CreateTournamentRequest { table_count: 1, table_seat_count: 5, observation_policy: ObservationPolicy::default() };
         let auth_info = AuthInfo::Unauthenticated;
-        let result = create_tournament(request, &auth_info, &mut repository);
+        let result = create_tournament(request, &auth_info, &mut repository, &mut DummyPublisher, &mut EventFollowers::new());
         assert!(matches!(result, Err(CreateTournamentError::AuthError(AuthError::AuthenticationRequired))));
         assert_eq!(repository.tournament(), None);
     }
@@ -103,29 +235,47 @@ mod tests {
     #[test]
     fn create_tournament_with_invalid_parameters() {
         let mut repository = DummyRepository::new_with_successful_save();
-        let request = CreateTournamentRequest { table_count: 0, table_seat_count: 5 };
+        let request = CreateTournamentRequest { table_count: 0, table_seat_count: 5, observation_policy: ObservationPolicy::default() };
         let auth_info = AuthInfo::Authenticated { account_id: Uuid::new_v4(), role: AuthRole::Member };
-        let result = create_tournament(request, &auth_info, &mut repository);
+        let result = create_tournament(request, &auth_info, &mut repository, &mut DummyPublisher, &mut EventFollowers::new());
         assert!(matches!(result, Err(CreateTournamentError::TournamentSpecificationError(_))));
         assert_eq!(repository.tournament(), None);
     }
 
+    #[test]
+    fn create_tournament_with_too_long_spectator_delay() {
+        let mut repository = DummyRepository::new_with_successful_save();
+        let observation_policy = ObservationPolicy::new(ObservationAccess::Public, ObservationPolicy::MAX_SPECTATOR_DELAY + Duration::from_secs(1));
+        let request = CreateTournamentRequest { table_count: 1, table_seat_count: 5, observation_policy };
+        let auth_info = AuthInfo::Authenticated { account_id: Uuid::new_v4(), role: AuthRole::Member };
+        let result = create_tournament(request, &auth_info, &mut repository, &mut DummyPublisher, &mut EventFollowers::new());
+        assert!(matches!(result, Err(CreateTournamentError::TournamentSpecificationError(TournamentSpecificationError::SpectatorDelayTooLong { .. }))));
+        assert_eq!(repository.tournament(), None);
+    }
+
     #[test]
     fn create_tournament_with_repository_error() {
         let mut repository = DummyRepository::new_with_error_on_save(SaveTournamentError::DatabaseWritingError);
-        let request = CreateTournamentRequest { table_count: 50, table_seat_count: 5 };
+        let request = CreateTournamentRequest { table_count: 50, table_seat_count: 5, observation_policy: ObservationPolicy::default() };
         let auth_info = AuthInfo::Authenticated { account_id: Uuid::new_v4(), role: AuthRole::Member };
-        let result = create_tournament(request, &auth_info, &mut repository);
-        assert!(matches!(result, Err(CreateTournamentError::SaveTournamentError(SaveTournamentError::DatabaseWritingError))));
+        let result = create_tournament(request, &auth_info, &mut repository, &mut DummyPublisher, &mut EventFollowers::new());
+        assert!(matches!(result, Err(CreateTournamentError::SaveTournamentAndPublishMessagesError(
+            SaveTournamentAndPublishMessagesError::SaveTournamentError(SaveTournamentError::DatabaseWritingError)
+        ))));
+        assert!(repository.tables.is_empty());
     }
 
     #[test]
     fn create_tournament_without_any_error() {
         let mut repository = DummyRepository::new_with_successful_save();
-        let request = CreateTournamentRequest { table_count: 50, table_seat_count: 5 };
+        let request = CreateTournamentRequest { table_count: 50, table_seat_count: 5, observation_policy: ObservationPolicy::default() };
         let auth_info = AuthInfo::Authenticated { account_id: Uuid::new_v4(), role: AuthRole::Member };
-        let result = create_tournament(request, &auth_info, &mut repository);
+        let mut followers = EventFollowers::new();
+        let result = create_tournament(request, &auth_info, &mut repository, &mut DummyPublisher, &mut followers);
         let tournament = repository.tournament().unwrap();
         assert!(result.is_ok_and(|response| response.tournament_id == tournament.id()));
+        assert_eq!(repository.tables.len(), 50);
+        let page = followers.projection.query_tournaments(&TournamentQuery::default());
+        assert_eq!(page.tournaments[0].id(), tournament.id());
     }
 }
@@ -2,8 +2,10 @@ use crate::application::AuthError;
 use crate::application::AuthInfo;
 
 use crate::domain::QueryTournaments;
-use crate::domain::QueryTournamentsError;
-use crate::domain::Tournament;
+use crate::domain::TournamentCursor;
+use crate::domain::TournamentQuery;
+use crate::domain::TournamentSortKey;
+use crate::domain::TournamentStage as DomainTournamentStage;
 
 use thiserror::Error;
 use uuid::Uuid;
@@ -13,17 +15,36 @@ use uuid::Uuid;
 pub enum FindTournamentsError {
     #[error(transparent)]
     AuthError(#[from] AuthError),
-    #[error(transparent)]
-    QueryTournamentsError(#[from] QueryTournamentsError),
 }
 
 
-#[derive(Debug)]
+const DEFAULT_PAGE_SIZE: usize = 20;
+const MAX_PAGE_SIZE: usize = 100;
+
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TournamentStageFilter {
+    WaitingForPlayers,
+    Running,
+    Finished,
+}
+
+
+#[derive(Debug, Default)]
 pub struct FindTournamentsRequest {
+    pub stage: Option<TournamentStageFilter>,
+    pub table_seat_count: Option<u8>,
+    pub joined_only: bool,              // Only tournaments the requesting account has joined
+    pub with_free_seats: bool,
+    pub sort_key: TournamentSortKey,
+    pub descending: bool,
+    pub cursor: Option<TournamentCursor>,
+    pub limit: Option<usize>,
 }
 
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, serde::Serialize)]
+#[serde(tag = "name", content = "table_number")]
 pub enum TournamentStage {
     WaitingForPlayers(Option<usize>), // Not yet started, player might have joined table numer (usize)
     Running(Option<usize>),           // Running, player might be playing on table number (usize)
@@ -31,7 +52,7 @@ pub enum TournamentStage {
 }
 
 
-#[derive(Debug)]
+#[derive(Debug, serde::Serialize)]
 pub struct TournamentInfo {
     pub tournament_id: Uuid,
     pub table_count: usize,
@@ -43,7 +64,9 @@ pub struct TournamentInfo {
 
 #[derive(Debug)]
 pub struct FindTournamentsResponse {
-    pub infos: Vec<TournamentInfo>
+    pub infos: Vec<TournamentInfo>,
+    pub total_count: usize,
+    pub next_cursor: Option<TournamentCursor>,
 }
 
 
@@ -52,41 +75,162 @@ pub trait FindTournaments {
 }
 
 
-pub(in crate::application) fn find_tournaments<Repository: QueryTournaments>(
+// Queries the lobby projection rather than the tournament aggregates
+pub(in crate::application) fn find_tournaments<Projection: QueryTournaments>(
     request: FindTournamentsRequest,
     auth_info: &AuthInfo,
-    repository: &Repository,
+    projection: &Projection,
 ) -> Result<FindTournamentsResponse, FindTournamentsError> {
     let account_id = auth_info.ensure_authenticated()?;
 
-    let tournaments = repository.query_tournaments()?;
+    let query = build_query(request, account_id);
+    let page = projection.query_tournaments(&query);
 
-    let infos = tournaments.iter().map(|tournament| {
+    let infos = page.tournaments.iter().map(|summary| {
         TournamentInfo {
-            tournament_id: tournament.id(),
-            table_count: tournament.table_count(),
-            table_seat_count: tournament.table_seat_count(),
-            player_count: tournament.player_count(),
-            stage: get_tournament_stage(tournament, account_id)
+            tournament_id: summary.id(),
+            table_count: summary.table_count(),
+            table_seat_count: summary.table_seat_count(),
+            player_count: summary.player_count(),
+            stage: get_tournament_stage(summary.stage(), summary.players_table_number(account_id))
         }
     }).collect();
 
-    Ok(FindTournamentsResponse { infos })
+    Ok(FindTournamentsResponse { infos, total_count: page.total_count, next_cursor: page.next_cursor })
+}
+
+
+fn build_query(request: FindTournamentsRequest, account_id: Uuid) -> TournamentQuery {
+    let stages = match request.stage {
+        None => vec![],
+        Some(TournamentStageFilter::WaitingForPlayers) => vec![DomainTournamentStage::WaitingForPlayers],
+        Some(TournamentStageFilter::Running) => vec![DomainTournamentStage::ReadyToStart, DomainTournamentStage::Running],
+        Some(TournamentStageFilter::Finished) => vec![DomainTournamentStage::Finished],
+    };
+    TournamentQuery {
+        stages,
+        table_seat_count: request.table_seat_count,
+        joined_by: request.joined_only.then_some(account_id),
+        with_free_seats: request.with_free_seats,
+        sort_key: request.sort_key,
+        descending: request.descending,
+        cursor: request.cursor,
+        limit: request.limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE),
+    }
 }
 
 
-fn get_tournament_stage(tournament: &Tournament, account_id: Uuid) -> TournamentStage {
-    let table_number = tournament.players_table_number(account_id);
-    if tournament.is_waiting_for_players() {
-        TournamentStage::WaitingForPlayers(table_number)
-    } else if tournament.is_finished() {
-        TournamentStage::Finished
-    } else {
-        TournamentStage::Running(table_number)
+pub(in crate::application) fn get_tournament_stage(stage: DomainTournamentStage, table_number: Option<usize>) -> TournamentStage {
+    match stage {
+        DomainTournamentStage::WaitingForPlayers => TournamentStage::WaitingForPlayers(table_number),
+        DomainTournamentStage::Finished => TournamentStage::Finished,
+        _ => TournamentStage::Running(table_number),
     }
 }
 
 
 #[cfg(test)]
 mod tests {
+    use std::cell::Cell;
+
+    use crate::application::AuthRole;
+    use crate::domain::Nickname;
+    use crate::domain::Tournament;
+    use crate::domain::TournamentPage;
+    use crate::domain::TournamentProjection;
+    use crate::domain::TournamentSpecification;
+
+    use super::*;
+
+    struct DummyProjection {
+        projection: TournamentProjection,
+        query: Cell<Option<TournamentQuery>>,
+    }
+
+    impl DummyProjection {
+        fn new(tournaments: Vec<Tournament>) -> Self {
+            let mut projection = TournamentProjection::new();
+            for tournament in tournaments {
+                projection.project(tournament.id(), tournament.new_events()).unwrap();
+            }
+            Self { projection, query: Cell::new(None) }
+        }
+    }
+
+    impl QueryTournaments for DummyProjection {
+        fn query_tournaments(&self, query: &TournamentQuery) -> TournamentPage {
+            self.query.set(Some(query.clone()));
+            self.projection.query_tournaments(query)
+        }
+    }
+
+    #[test]
+    fn find_tournaments_without_being_authenticated() {
+        let projection = DummyProjection::new(vec![]);
+        let result = find_tournaments(FindTournamentsRequest::default(), &AuthInfo::Unauthenticated, &projection);
+        assert!(matches!(result, Err(FindTournamentsError::AuthError(AuthError::AuthenticationRequired))));
+    }
+
+    #[test]
+    fn find_tournaments_with_joined_tournament() {
+        let account_id = Uuid::new_v4();
+        let spec = TournamentSpecification::new(2, 3).unwrap();
+        let mut joined_tournament = Tournament::new(&spec);
+        _ = joined_tournament.join(Uuid::new_v4(), Nickname::new("James").unwrap());
+        _ = joined_tournament.join(account_id, Nickname::new("Daniel").unwrap());
+        let other_tournament = Tournament::new(&spec);
+        let projection = DummyProjection::new(vec![joined_tournament.clone(), other_tournament.clone()]);
+        let auth_info = AuthInfo::Authenticated { account_id, role: AuthRole::Member };
+        let request = FindTournamentsRequest { descending: true, ..FindTournamentsRequest::default() };
+        let response = find_tournaments(request, &auth_info, &projection).unwrap();
+        assert_eq!(response.infos.len(), 2);
+        assert_eq!(response.total_count, 2);
+        let info = &response.infos[0];
+        assert_eq!(info.tournament_id, joined_tournament.id());
+        assert_eq!(info.table_count, 2);
+        assert_eq!(info.table_seat_count, 3);
+        assert_eq!(info.player_count, 2);
+        assert_eq!(info.stage, TournamentStage::WaitingForPlayers(Some(0)));
+        assert_eq!(response.infos[1].stage, TournamentStage::WaitingForPlayers(None));
+    }
+
+    #[test]
+    fn find_tournaments_builds_query_from_request() {
+        let account_id = Uuid::new_v4();
+        let projection = DummyProjection::new(vec![]);
+        let auth_info = AuthInfo::Authenticated { account_id, role: AuthRole::Member };
+        let request = FindTournamentsRequest {
+            stage: Some(TournamentStageFilter::Running),
+            joined_only: true,
+            limit: Some(1000),
+            ..FindTournamentsRequest::default()
+        };
+        let response = find_tournaments(request, &auth_info, &projection).unwrap();
+        assert_eq!(response.total_count, 0);
+        assert_eq!(response.next_cursor, None);
+        let query = projection.query.take().unwrap();
+        assert_eq!(query.stages, vec![DomainTournamentStage::ReadyToStart, DomainTournamentStage::Running]);
+        assert_eq!(query.joined_by, Some(account_id));
+        assert_eq!(query.limit, MAX_PAGE_SIZE);
+    }
+
+    #[test]
+    fn tournament_info_json_schema() {
+        let tournament_id = Uuid::new_v4();
+        let info = TournamentInfo {
+            tournament_id,
+            table_count: 2,
+            table_seat_count: 6,
+            player_count: 7,
+            stage: TournamentStage::Running(Some(1)),
+        };
+        let expected = format!(
+            r#"{{"tournament_id":"{}","table_count":2,"table_seat_count":6,"player_count":7,"stage":{{"name":"Running","table_number":1}}}}"#,
+            tournament_id
+        );
+        assert_eq!(serde_json::to_string(&info).unwrap(), expected);
+        let stage = TournamentStage::WaitingForPlayers(None);
+        assert_eq!(serde_json::to_string(&stage).unwrap(), r#"{"name":"WaitingForPlayers","table_number":null}"#);
+        assert_eq!(serde_json::to_string(&TournamentStage::Finished).unwrap(), r#"{"name":"Finished"}"#);
+    }
 }
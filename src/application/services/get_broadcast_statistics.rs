@@ -0,0 +1,120 @@
+use crate::application::AuthError;
+use crate::application::AuthInfo;
+
+use crate::domain::LoadAccount;
+use crate::domain::LoadAccountError;
+use crate::domain::MonitorTableMessageBroadcast;
+
+use thiserror::Error;
+
+
+#[derive(Debug, Error)]
+pub enum GetBroadcastStatisticsError {
+    #[error(transparent)]
+    AuthError(#[from] AuthError),
+    #[error(transparent)]
+    LoadAccountError(#[from] LoadAccountError),
+}
+
+
+#[derive(Debug)]
+pub struct GetBroadcastStatisticsRequest {
+}
+
+
+#[derive(Debug)]
+pub struct GetBroadcastStatisticsResponse {
+    pub channel_count: usize,
+    pub subscriber_count: usize,
+}
+
+
+pub trait GetBroadcastStatistics {
+    fn get_broadcast_statistics(&self, request: GetBroadcastStatisticsRequest, auth_info: &AuthInfo) -> Result<GetBroadcastStatisticsResponse, GetBroadcastStatisticsError>;
+}
+
+
+pub(in crate::application) fn get_broadcast_statistics<Broadcast: MonitorTableMessageBroadcast, Accounts: LoadAccount>(
+    _request: GetBroadcastStatisticsRequest,
+    auth_info: &AuthInfo,
+    broadcast: &Broadcast,
+    accounts: &Accounts,
+) -> Result<GetBroadcastStatisticsResponse, GetBroadcastStatisticsError> {
+    auth_info.with_current_role(accounts)?.ensure_moderator()?;
+    Ok(GetBroadcastStatisticsResponse {
+        channel_count: broadcast.channel_count(),
+        subscriber_count: broadcast.subscriber_count(),
+    })
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::domain::Account;
+    use crate::domain::AccountRole;
+    use crate::domain::Username;
+
+    use uuid::Uuid;
+
+    use super::*;
+
+    struct DummyBroadcast;
+
+    impl MonitorTableMessageBroadcast for DummyBroadcast {
+        fn channel_count(&self) -> usize {
+            3
+        }
+
+        fn subscriber_count(&self) -> usize {
+            7
+        }
+    }
+
+    struct DummyAccounts {
+        roles: Vec<(Uuid, AccountRole)>,
+    }
+
+    impl DummyAccounts {
+        // Registers an account with the role and returns a token for it
+        fn authenticate(&mut self, role: AccountRole) -> AuthInfo {
+            let account_id = Uuid::new_v4();
+            self.roles.push((account_id, role));
+            AuthInfo::Authenticated { account_id, role: role.into() }
+        }
+    }
+
+    impl LoadAccount for DummyAccounts {
+        fn load_account(&self, account_id: Uuid) -> Result<Account, LoadAccountError> {
+            let (_, role) = self.roles.iter().find(|(id, _)| *id == account_id).ok_or(LoadAccountError::AccountNotFound)?;
+            Ok(Account::restore(account_id, Username::new("daniel").unwrap(), String::new(), *role, None, None))
+        }
+
+        fn load_account_by_username(&self, _username: &Username) -> Result<Account, LoadAccountError> {
+            Err(LoadAccountError::AccountNotFound)
+        }
+
+        fn load_accounts_with_role(&self, _role: AccountRole) -> Result<Vec<Account>, LoadAccountError> {
+            Ok(vec![])
+        }
+    }
+
+    #[test]
+    fn get_broadcast_statistics_as_member() {
+        let request = GetBroadcastStatisticsRequest {};
+        let mut accounts = DummyAccounts { roles: vec![] };
+        let auth_info = accounts.authenticate(AccountRole::Moderator);
+        accounts.roles[0].1 = AccountRole::Member;
+        let result = get_broadcast_statistics(request, &auth_info, &DummyBroadcast, &accounts);
+        assert!(matches!(result, Err(GetBroadcastStatisticsError::AuthError(AuthError::PermissionDenied { .. }))));
+    }
+
+    #[test]
+    fn get_broadcast_statistics_as_moderator() {
+        let request = GetBroadcastStatisticsRequest {};
+        let mut accounts = DummyAccounts { roles: vec![] };
+        let auth_info = accounts.authenticate(AccountRole::Moderator);
+        let response = get_broadcast_statistics(request, &auth_info, &DummyBroadcast, &accounts).unwrap();
+        assert_eq!(response.channel_count, 3);
+        assert_eq!(response.subscriber_count, 7);
+    }
+}
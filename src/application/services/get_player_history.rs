@@ -0,0 +1,92 @@
+use crate::application::AuthError;
+use crate::application::AuthInfo;
+
+use crate::domain::TournamentProjection;
+
+use thiserror::Error;
+use uuid::Uuid;
+
+
+#[derive(Debug, Error)]
+pub enum GetPlayerHistoryError {
+    #[error(transparent)]
+    AuthError(#[from] AuthError),
+}
+
+
+#[derive(Debug)]
+pub struct GetPlayerHistoryRequest {
+    pub account_id: Uuid,
+}
+
+
+#[derive(Debug, PartialEq, serde::Serialize)]
+pub struct PlayerTournamentInfo {
+    pub tournament_id: Uuid,
+    pub nickname: String,
+    pub rank: Option<usize>,    // None as long as the player is still in the tournament
+}
+
+
+#[derive(Debug, serde::Serialize)]
+pub struct GetPlayerHistoryResponse {
+    pub tournaments: Vec<PlayerTournamentInfo>,
+}
+
+
+pub trait GetPlayerHistory {
+    fn get_player_history(&self, request: GetPlayerHistoryRequest, auth_info: &AuthInfo) -> Result<GetPlayerHistoryResponse, GetPlayerHistoryError>;
+}
+
+
+pub(in crate::application) fn get_player_history(
+    request: GetPlayerHistoryRequest,
+    auth_info: &AuthInfo,
+    projection: &TournamentProjection,
+) -> Result<GetPlayerHistoryResponse, GetPlayerHistoryError> {
+    auth_info.ensure_authenticated()?;
+    let tournaments = projection.player_history(request.account_id).into_iter().map(|entry| {
+        PlayerTournamentInfo {
+            tournament_id: entry.tournament_id,
+            nickname: entry.nickname.into(),
+            rank: entry.rank,
+        }
+    }).collect();
+    Ok(GetPlayerHistoryResponse { tournaments })
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::application::AuthRole;
+    use crate::domain::Nickname;
+    use crate::domain::Tournament;
+    use crate::domain::TournamentSpecification;
+
+    use super::*;
+
+    #[test]
+    fn get_player_history_without_being_authenticated() {
+        let request = GetPlayerHistoryRequest { account_id: Uuid::new_v4() };
+        let result = get_player_history(request, &AuthInfo::Unauthenticated, &TournamentProjection::new());
+        assert!(matches!(result, Err(GetPlayerHistoryError::AuthError(AuthError::AuthenticationRequired))));
+    }
+
+    #[test]
+    fn get_player_history_of_other_player() {
+        let account_id = Uuid::new_v4();
+        let spec = TournamentSpecification::new(1, 3).unwrap();
+        let mut tournament = Tournament::new(&spec);
+        _ = tournament.join(account_id, Nickname::new("Daniel").unwrap());
+        let mut projection = TournamentProjection::new();
+        projection.project(tournament.id(), tournament.new_events()).unwrap();
+        let request = GetPlayerHistoryRequest { account_id };
+        let auth_info = AuthInfo::Authenticated { account_id: Uuid::new_v4(), role: AuthRole::Member };
+        let response = get_player_history(request, &auth_info, &projection).unwrap();
+        assert_eq!(response.tournaments, vec![PlayerTournamentInfo {
+            tournament_id: tournament.id(),
+            nickname: "Daniel".into(),
+            rank: None,
+        }]);
+    }
+}
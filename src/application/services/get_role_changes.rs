@@ -0,0 +1,143 @@
+use crate::application::AuthError;
+use crate::application::AuthInfo;
+use crate::application::AuthRole;
+
+use crate::domain::LoadAccount;
+use crate::domain::LoadAccountError;
+use crate::domain::LoadRoleChanges;
+use crate::domain::LoadRoleChangesError;
+
+use thiserror::Error;
+use uuid::Uuid;
+
+
+#[derive(Debug, Error)]
+pub enum GetRoleChangesError {
+    #[error(transparent)]
+    AuthError(#[from] AuthError),
+    #[error(transparent)]
+    LoadAccountError(#[from] LoadAccountError),
+    #[error(transparent)]
+    LoadRoleChangesError(#[from] LoadRoleChangesError),
+}
+
+
+#[derive(Debug)]
+pub struct GetRoleChangesRequest {
+    pub account_id: Uuid,
+}
+
+
+#[derive(Debug, serde::Serialize)]
+pub struct RoleChangeInfo {
+    pub changed_by: Option<Uuid>,   // None for the administrator bootstrapped from the configuration
+    pub old_role: AuthRole,
+    pub new_role: AuthRole,
+    pub changed_at: u64,
+}
+
+
+#[derive(Debug, serde::Serialize)]
+pub struct GetRoleChangesResponse {
+    pub changes: Vec<RoleChangeInfo>,
+}
+
+
+pub trait GetRoleChanges {
+    fn get_role_changes(&self, request: GetRoleChangesRequest, auth_info: &AuthInfo) -> Result<GetRoleChangesResponse, GetRoleChangesError>;
+}
+
+
+pub(in crate::application) fn get_role_changes<Accounts: LoadAccount + LoadRoleChanges>(
+    request: GetRoleChangesRequest,
+    auth_info: &AuthInfo,
+    accounts: &Accounts,
+) -> Result<GetRoleChangesResponse, GetRoleChangesError> {
+    auth_info.with_current_role(accounts)?.ensure_administrator()?;
+    let account = accounts.load_account(request.account_id)?;
+    let changes = accounts.load_role_changes(account.id())?.into_iter().map(|change| {
+        RoleChangeInfo {
+            changed_by: change.changed_by,
+            old_role: change.old_role.into(),
+            new_role: change.new_role.into(),
+            changed_at: change.changed_at,
+        }
+    }).collect();
+    Ok(GetRoleChangesResponse { changes })
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::domain::Account;
+    use crate::domain::AccountRole;
+    use crate::domain::RoleChange;
+    use crate::domain::Username;
+    use crate::domain::unix_time;
+
+    use super::*;
+
+    struct DummyAccounts {
+        account: Account,
+        role_changes: Vec<RoleChange>,
+    }
+
+    impl DummyAccounts {
+        fn new() -> Self {
+            let mut account = Account::register(Username::new("daniel").unwrap(), "correct horse").unwrap();
+            account.promote(None, unix_time()).unwrap();
+            account.promote(Some(Uuid::new_v4()), unix_time()).unwrap();
+            let role_changes = account.collect_role_changes();
+            Self { account, role_changes }
+        }
+    }
+
+    impl LoadAccount for DummyAccounts {
+        fn load_account(&self, account_id: Uuid) -> Result<Account, LoadAccountError> {
+            (account_id == self.account.id()).then(|| self.account.clone()).ok_or(LoadAccountError::AccountNotFound)
+        }
+
+        fn load_account_by_username(&self, username: &Username) -> Result<Account, LoadAccountError> {
+            (username == self.account.username()).then(|| self.account.clone()).ok_or(LoadAccountError::AccountNotFound)
+        }
+
+        fn load_accounts_with_role(&self, role: AccountRole) -> Result<Vec<Account>, LoadAccountError> {
+            Ok((self.account.role() == role).then(|| self.account.clone()).into_iter().collect())
+        }
+    }
+
+    impl LoadRoleChanges for DummyAccounts {
+        fn load_role_changes(&self, account_id: Uuid) -> Result<Vec<RoleChange>, LoadRoleChangesError> {
+            Ok(self.role_changes.iter().filter(|change| change.account_id == account_id).cloned().collect())
+        }
+    }
+
+    #[test]
+    fn get_role_changes_without_being_administrator() {
+        let accounts = DummyAccounts::new();
+        let request = GetRoleChangesRequest { account_id: accounts.account.id() };
+        let result = get_role_changes(request, &AuthInfo::Unauthenticated, &accounts);
+        assert!(matches!(result, Err(GetRoleChangesError::AuthError(AuthError::AuthenticationRequired))));
+        let auth_info = AuthInfo::Authenticated { account_id: Uuid::new_v4(), role: AuthRole::Administrator };
+        let result = get_role_changes(GetRoleChangesRequest { account_id: accounts.account.id() }, &auth_info, &accounts);
+        assert!(matches!(result, Err(GetRoleChangesError::AuthError(AuthError::AuthenticationRequired))));
+    }
+
+    #[test]
+    fn get_role_changes_with_unknown_account() {
+        let accounts = DummyAccounts::new();
+        let auth_info = AuthInfo::Authenticated { account_id: accounts.account.id(), role: AuthRole::Administrator };
+        let result = get_role_changes(GetRoleChangesRequest { account_id: Uuid::new_v4() }, &auth_info, &accounts);
+        assert!(matches!(result, Err(GetRoleChangesError::LoadAccountError(LoadAccountError::AccountNotFound))));
+    }
+
+    #[test]
+    fn get_role_changes_without_any_error() {
+        let accounts = DummyAccounts::new();
+        let auth_info = AuthInfo::Authenticated { account_id: accounts.account.id(), role: AuthRole::Administrator };
+        let response = get_role_changes(GetRoleChangesRequest { account_id: accounts.account.id() }, &auth_info, &accounts).unwrap();
+        assert_eq!(response.changes.len(), 2);
+        assert_eq!(response.changes[0].changed_by, None);
+        assert!(matches!((response.changes[1].old_role, response.changes[1].new_role), (AuthRole::Moderator, AuthRole::Administrator)));
+    }
+}
@@ -0,0 +1,200 @@
+use crate::application::AuthError;
+use crate::application::AuthInfo;
+use crate::application::TournamentStage;
+
+use crate::domain::LoadTable;
+use crate::domain::LoadTableError;
+use crate::domain::LoadTournament;
+use crate::domain::LoadTournamentError;
+
+use super::find_tournaments::get_tournament_stage;
+
+use thiserror::Error;
+use uuid::Uuid;
+
+use std::cmp::Reverse;
+
+
+#[derive(Debug, Error)]
+pub enum GetTournamentError {
+    #[error(transparent)]
+    AuthError(#[from] AuthError),
+    #[error(transparent)]
+    LoadTournamentError(#[from] LoadTournamentError),
+    #[error(transparent)]
+    LoadTableError(#[from] LoadTableError),
+}
+
+
+#[derive(Debug)]
+pub struct GetTournamentRequest {
+    pub tournament_id: Uuid,
+}
+
+
+#[derive(Debug, PartialEq, serde::Serialize)]
+pub struct SeatInfo {
+    pub position: usize,
+    pub nickname: String,
+    pub stack: u32,
+}
+
+
+#[derive(Debug, PartialEq, serde::Serialize)]
+pub struct TableInfo {
+    pub table_number: usize,
+    pub players: Vec<SeatInfo>,
+}
+
+
+#[derive(Debug, PartialEq, serde::Serialize)]
+pub struct Standing {
+    pub rank: usize,
+    pub nickname: String,
+    pub stack: u32,
+}
+
+
+#[derive(Debug, serde::Serialize)]
+pub struct GetTournamentResponse {
+    pub tournament_id: Uuid,
+    pub table_count: usize,
+    pub table_seat_count: u8,
+    pub player_count: usize,
+    pub stage: TournamentStage,
+    pub level: Option<u32>,         // Current blind level, None if not yet started
+    pub tables: Vec<TableInfo>,
+    pub standings: Vec<Standing>,   // Remaining players ordered by stack
+}
+
+
+pub trait GetTournament {
+    fn get_tournament(&self, request: GetTournamentRequest, auth_info: &AuthInfo) -> Result<GetTournamentResponse, GetTournamentError>;
+}
+
+
+pub(in crate::application) fn get_tournament<Repository: LoadTournament + LoadTable>(
+    request: GetTournamentRequest,
+    auth_info: &AuthInfo,
+    repository: &Repository,
+) -> Result<GetTournamentResponse, GetTournamentError> {
+    let account_id = auth_info.ensure_authenticated()?;
+    let tournament = repository.load_tournament(request.tournament_id)?;
+
+    let mut tables = vec![];
+    for table_number in 0..tournament.table_count() {
+        let table_state = repository.load_table(tournament.id(), table_number)?.state();
+        let players = table_state.seats.into_iter().enumerate().filter_map(|(position, seat)| {
+            seat.map(|seat| SeatInfo { position, nickname: seat.nickname.to_string(), stack: seat.stack })
+        }).collect();
+        tables.push(TableInfo { table_number, players });
+    }
+
+    let mut standings: Vec<(String, u32)> = tables.iter()
+        .flat_map(|table| table.players.iter().map(|player| (player.nickname.clone(), player.stack)))
+        .collect();
+    standings.sort_by_key(|(_, stack)| Reverse(*stack));
+    let standings = standings.into_iter().enumerate()
+        .map(|(index, (nickname, stack))| Standing { rank: index + 1, nickname, stack })
+        .collect();
+
+    Ok(GetTournamentResponse {
+        tournament_id: tournament.id(),
+        table_count: tournament.table_count(),
+        table_seat_count: tournament.table_seat_count(),
+        player_count: tournament.player_count(),
+        stage: get_tournament_stage(tournament.stage(), tournament.players_table_number(account_id)),
+        level: tournament.current_level(),
+        tables,
+        standings,
+    })
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::application::AuthRole;
+    use crate::domain::Nickname;
+    use crate::domain::Table;
+    use crate::domain::Tournament;
+    use crate::domain::TournamentSpecification;
+
+    use super::*;
+
+    struct DummyRepository {
+        tournament: Tournament,
+        tables: HashMap<(Uuid, usize), Table>,
+    }
+
+    impl DummyRepository {
+        fn new(spec: &TournamentSpecification, players: &[(Uuid, &str)]) -> Self {
+            let mut tournament = Tournament::new(spec);
+            let mut tables: HashMap<_, _> = (0..tournament.table_count()).map(|table_number| {
+                ((tournament.id(), table_number), Table::new(tournament.id(), table_number, &spec.table_spec()))
+            }).collect();
+            for (account_id, nickname) in players {
+                let table_number = tournament.join(*account_id, Nickname::new(*nickname).unwrap()).unwrap();
+                let table = tables.get_mut(&(tournament.id(), table_number)).unwrap();
+                table.sit_down(*account_id, Nickname::new(*nickname).unwrap(), 1500).unwrap();
+            }
+            Self { tournament, tables }
+        }
+    }
+
+    impl LoadTournament for DummyRepository {
+        fn load_tournament(&self, tournament_id: Uuid) -> Result<Tournament, LoadTournamentError> {
+            if self.tournament.id() == tournament_id {
+                Ok(self.tournament.clone())
+            } else {
+                Err(LoadTournamentError::TournamentNotFound)
+            }
+        }
+    }
+
+    impl LoadTable for DummyRepository {
+        fn load_table(&self, tournament_id: Uuid, table_number: usize) -> Result<Table, LoadTableError> {
+            self.tables.get(&(tournament_id, table_number)).cloned().ok_or(LoadTableError::TableNotFound)
+        }
+    }
+
+    #[test]
+    fn get_tournament_without_being_authenticated() {
+        let spec = TournamentSpecification::new(2, 2).unwrap();
+        let repository = DummyRepository::new(&spec, &[]);
+        let request = GetTournamentRequest { tournament_id: repository.tournament.id() };
+        let result = get_tournament(request, &AuthInfo::Unauthenticated, &repository);
+        assert!(matches!(result, Err(GetTournamentError::AuthError(AuthError::AuthenticationRequired))));
+    }
+
+    #[test]
+    fn get_unknown_tournament() {
+        let spec = TournamentSpecification::new(2, 2).unwrap();
+        let repository = DummyRepository::new(&spec, &[]);
+        let request = GetTournamentRequest { tournament_id: Uuid::new_v4() };
+        let auth_info = AuthInfo::Authenticated { account_id: Uuid::new_v4(), role: AuthRole::Member };
+        let result = get_tournament(request, &auth_info, &repository);
+        assert!(matches!(result, Err(GetTournamentError::LoadTournamentError(LoadTournamentError::TournamentNotFound))));
+    }
+
+    #[test]
+    fn get_tournament_with_players() {
+        let account_id = Uuid::new_v4();
+        let spec = TournamentSpecification::new(2, 2).unwrap();
+        let repository = DummyRepository::new(&spec, &[(Uuid::new_v4(), "James"), (Uuid::new_v4(), "Patricia"), (account_id, "Daniel")]);
+        let request = GetTournamentRequest { tournament_id: repository.tournament.id() };
+        let auth_info = AuthInfo::Authenticated { account_id, role: AuthRole::Member };
+        let response = get_tournament(request, &auth_info, &repository).unwrap();
+        assert_eq!(response.player_count, 3);
+        assert_eq!(response.stage, TournamentStage::WaitingForPlayers(Some(1)));
+        assert_eq!(response.level, None);
+        assert_eq!(response.tables.len(), 2);
+        assert_eq!(response.tables[1], TableInfo {
+            table_number: 1,
+            players: vec![SeatInfo { position: 0, nickname: "Daniel".into(), stack: 1500 }],
+        });
+        assert_eq!(response.standings.len(), 3);
+        assert_eq!(response.standings[2].rank, 3);
+    }
+}
@@ -1,6 +1,17 @@
 use crate::application::AuthError;
 use crate::application::AuthInfo;
 
+use crate::domain::AccessEventStreams;
+use crate::domain::AccessOutbox;
+use crate::domain::AccessTables;
+use crate::domain::AccountError;
+use crate::domain::BanError;
+use crate::domain::BlockedWords;
+use crate::domain::EventFollowers;
+use crate::domain::LoadAccount;
+use crate::domain::LoadAccountError;
+use crate::domain::LoadBanError;
+use crate::domain::LoadBans;
 use crate::domain::LoadTournament;
 use crate::domain::LoadTournamentError;
 use crate::domain::Nickname;
@@ -8,8 +19,11 @@ use crate::domain::NicknameError;
 use crate::domain::PublishTournamentMessages;
 use crate::domain::SaveTournament;
 use crate::domain::SaveTournamentError;
+use crate::domain::SaveTournamentAndPublishMessagesError;
 use crate::domain::TournamentError;
+use crate::domain::ensure_not_banned;
 use crate::domain::save_tournament_and_publish_messages;
+use crate::domain::unix_time;
 
 use thiserror::Error;
 use uuid::Uuid;
@@ -22,18 +36,26 @@ pub enum JoinTournamentError {
     #[error(transparent)]
     LoadTournamentError(#[from] LoadTournamentError),
     #[error(transparent)]
-    SaveTournamentError(#[from] SaveTournamentError),
+    SaveTournamentAndPublishMessagesError(#[from] SaveTournamentAndPublishMessagesError),
     #[error(transparent)]
     TournamentError(#[from] TournamentError),
     #[error(transparent)]
     AuthError(#[from] AuthError),
+    #[error(transparent)]
+    LoadBanError(#[from] LoadBanError),
+    #[error(transparent)]
+    BanError(#[from] BanError),
+    #[error(transparent)]
+    LoadAccountError(#[from] LoadAccountError),
+    #[error(transparent)]
+    AccountError(#[from] AccountError),
 }
 
 
 #[derive(Debug)]
 pub struct JoinTournamentRequest {
     pub tournament_id: Uuid,
-    pub nickname: String,
+    pub nickname: Option<String>,       // Must match the registered nickname, if given
 }
 
 
@@ -48,58 +70,124 @@ pub trait JoinTournament {
 }
 
 
-pub(in crate::application) fn join_tournament<Repository: LoadTournament + SaveTournament, Publisher: PublishTournamentMessages>(
+pub(in crate::application) fn join_tournament<Repository: LoadTournament + SaveTournament + AccessTables + AccessEventStreams + AccessOutbox, Publisher: PublishTournamentMessages, Accounts: LoadAccount + LoadBans>(
     request: JoinTournamentRequest,
     auth_info: &AuthInfo,
     repository: &mut Repository,
-    publisher: &Publisher,
+    publisher: &mut Publisher,
+    followers: &mut EventFollowers,
+    accounts: &Accounts,
+    blocked_words: &BlockedWords,
 ) -> Result<JoinTournamentResponse, JoinTournamentError> {
+    const MAX_ATTEMPTS: usize = 3;
     let account_id = auth_info.ensure_authenticated()?;
-    let nickname = Nickname::new(request.nickname)?;
-    let mut tournament = repository.load_tournament(request.tournament_id)?;
-    let table_number = tournament.join(account_id, nickname)?;
-    save_tournament_and_publish_messages(tournament, repository, publisher)?;
-    Ok(JoinTournamentResponse { table_number })
+    ensure_not_banned(&accounts.load_bans(account_id)?, Some(request.tournament_id), unix_time())?;
+    let nickname = request.nickname.map(Nickname::new).transpose()?;
+    let nickname = accounts.load_account(account_id)?.nickname_for_join(nickname.as_ref())?;
+    // Words may have been blocked since the nickname was registered
+    blocked_words.check(&nickname)?;
+    let mut attempt = 1;
+    loop {
+        let mut tournament = repository.load_tournament(request.tournament_id)?;
+        let table_number = tournament.join(account_id, nickname.clone())?;
+        match save_tournament_and_publish_messages(tournament, repository, publisher, followers) {
+            Ok(()) => return Ok(JoinTournamentResponse { table_number }),
+            // Someone else saved the tournament in the meantime, so join the fresh state again
+            Err(SaveTournamentAndPublishMessagesError::SaveTournamentError(SaveTournamentError::TournamentOutdated)) if attempt < MAX_ATTEMPTS => attempt += 1,
+            Err(error) => return Err(error.into()),
+        }
+    }
 }
 
 
 #[cfg(test)]
 mod tests {
     use std::cell::Cell;
+    use std::collections::HashMap;
 
     use crate::application::AuthRole;
+    use crate::domain::Account;
+    use crate::domain::AccountRole;
+    use crate::domain::Ban;
+    use crate::domain::BanScope;
+    use crate::domain::EventStream;
+    use crate::domain::LoadCheckpoint;
+    use crate::domain::LoadCheckpointError;
+    use crate::domain::LoadEvents;
+    use crate::domain::LoadEventsError;
+    use crate::domain::LoadOutbox;
+    use crate::domain::LoadOutboxError;
+    use crate::domain::LoadTable;
+    use crate::domain::LoadTableError;
+    use crate::domain::MarkDelivered;
+    use crate::domain::MarkDeliveredError;
+    use crate::domain::OutboxEntry;
+    use crate::domain::SaveCheckpoint;
+    use crate::domain::SaveCheckpointError;
+    use crate::domain::SaveTable;
+    use crate::domain::SaveTableError;
+    use crate::domain::Table;
+    use crate::domain::TableEvent;
+    use crate::domain::TournamentEvent;
     use crate::domain::TournamentMessage;
+    use crate::domain::TournamentMessageType;
     use crate::domain::Tournament;
     use crate::domain::TournamentSpecification;
+    use crate::domain::Username;
 
     use super::*;
 
     struct DummyRepository {
         load_error: Option<LoadTournamentError>,
         save_error: Option<SaveTournamentError>,
+        outdated_save_count: usize,
         tournament: Option<Tournament>,
+        tournament_events: Vec<TournamentEvent>,
+        tables: HashMap<(Uuid, usize), Table>,
+        checkpoints: HashMap<EventStream, usize>,
+        outbox: Vec<OutboxEntry>,
     }
 
     impl DummyRepository {
         fn new_with_error_on_load(load_error: LoadTournamentError) -> Self {
-            Self { load_error: Some(load_error), save_error: None, tournament: None }
+            Self { load_error: Some(load_error), save_error: None, outdated_save_count: 0, tournament: None, tournament_events: vec![], tables: HashMap::new(), checkpoints: HashMap::new(), outbox: vec![] }
         }
 
         fn new_with_error_on_save(save_error: SaveTournamentError, tournament: Tournament) -> Self {
-            Self { load_error: None, save_error: Some(save_error), tournament: Some(tournament) }
+            let tables = tables_of(&tournament);
+            Self { load_error: None, save_error: Some(save_error), outdated_save_count: 0, tournament: Some(tournament), tournament_events: vec![], tables, checkpoints: HashMap::new(), outbox: vec![] }
         }
 
         fn new_without_tournament() -> Self {
-            Self { load_error: None, save_error: None, tournament: None }
+            Self { load_error: None, save_error: None, outdated_save_count: 0, tournament: None, tournament_events: vec![], tables: HashMap::new(), checkpoints: HashMap::new(), outbox: vec![] }
         }
 
         fn new_with_tournament(tournament: Tournament) -> Self {
-            Self { load_error: None, save_error: None, tournament: Some(tournament) }
+            let tables = tables_of(&tournament);
+            Self { load_error: None, save_error: None, outdated_save_count: 0, tournament: Some(tournament), tournament_events: vec![], tables, checkpoints: HashMap::new(), outbox: vec![] }
+        }
+
+        fn new_with_outdated_saves(outdated_save_count: usize, tournament: Tournament) -> Self {
+            let tables = tables_of(&tournament);
+            Self { load_error: None, save_error: None, outdated_save_count, tournament: Some(tournament), tournament_events: vec![], tables, checkpoints: HashMap::new(), outbox: vec![] }
         }
 
         fn tournament(&self) -> Option<&Tournament> {
             return self.tournament.as_ref()
         }
+
+        fn add_to_outbox(&mut self, messages: Vec<TournamentMessage>) {
+            for message in messages {
+                let position = self.outbox.last().map_or(1, |entry| entry.position + 1);
+                self.outbox.push(OutboxEntry { position, message });
+            }
+        }
+    }
+
+    fn tables_of(tournament: &Tournament) -> HashMap<(Uuid, usize), Table> {
+        (0..tournament.table_count()).map(|table_number| {
+            ((tournament.id(), table_number), Table::new(tournament.id(), table_number, &tournament.spec().table_spec()))
+        }).collect()
     }
 
     impl LoadTournament for DummyRepository {
@@ -119,16 +207,130 @@ mod tests {
     }
 
     impl SaveTournament for DummyRepository {
-        fn save_tournament(&mut self, tournament: Tournament) -> Result<(), SaveTournamentError> {
+        fn save_tournament(&mut self, mut tournament: Tournament) -> Result<(), SaveTournamentError> {
             if let Some(error) = self.save_error {
                 Err(error)
+            } else if self.outdated_save_count > 0 {
+                self.outdated_save_count -= 1;
+                Err(SaveTournamentError::TournamentOutdated)
             } else {
+                self.tournament_events.extend(tournament.new_events());
+                self.add_to_outbox(tournament.collect_messages());
                 self.tournament = Some(tournament);
                 Ok(())
             }
         }
     }
 
+    impl LoadTable for DummyRepository {
+        fn load_table(&self, tournament_id: Uuid, table_number: usize) -> Result<Table, LoadTableError> {
+            self.tables.get(&(tournament_id, table_number)).cloned().ok_or(LoadTableError::TableNotFound)
+        }
+    }
+
+    impl SaveTable for DummyRepository {
+        fn save_table(&mut self, mut table: Table) -> Result<(), SaveTableError> {
+            self.add_to_outbox(table.collect_tournament_messages());
+            self.tables.insert((table.tournament_id(), table.table_number()), table);
+            Ok(())
+        }
+    }
+
+    impl LoadEvents for DummyRepository {
+        fn load_tournament_ids(&self) -> Result<Vec<Uuid>, LoadEventsError> {
+            Ok(vec![])
+        }
+
+        fn load_tournament_events(&self, _tournament_id: Uuid, from_version: usize) -> Result<Vec<TournamentEvent>, LoadEventsError> {
+            Ok(self.tournament_events.iter().skip(from_version).cloned().collect())
+        }
+
+        fn load_table_events(&self, _tournament_id: Uuid, _table_number: usize, _from_version: usize) -> Result<Vec<TableEvent>, LoadEventsError> {
+            Ok(vec![])
+        }
+    }
+
+    impl LoadCheckpoint for DummyRepository {
+        fn load_checkpoint(&self, stream: EventStream) -> Result<usize, LoadCheckpointError> {
+            Ok(self.checkpoints.get(&stream).copied().unwrap_or(0))
+        }
+    }
+
+    impl SaveCheckpoint for DummyRepository {
+        fn save_checkpoint(&mut self, stream: EventStream, checkpoint: usize) -> Result<(), SaveCheckpointError> {
+            self.checkpoints.insert(stream, checkpoint);
+            Ok(())
+        }
+    }
+
+    impl LoadOutbox for DummyRepository {
+        fn load_pending_messages(&self) -> Result<Vec<OutboxEntry>, LoadOutboxError> {
+            Ok(self.outbox.clone())
+        }
+    }
+
+    impl MarkDelivered for DummyRepository {
+        fn mark_delivered(&mut self, position: u64) -> Result<(), MarkDeliveredError> {
+            self.outbox.retain(|entry| entry.position > position);
+            Ok(())
+        }
+    }
+
+
+    struct DummyAccounts {
+        accounts: Vec<Account>,
+        bans: Vec<Ban>,
+    }
+
+    impl DummyAccounts {
+        // The first account is the one joining, it has registered a nickname
+        fn new() -> Self {
+            let mut accounts = Self::without_nickname();
+            accounts.accounts[0].change_nickname(Nickname::new("Daniel").unwrap(), unix_time()).unwrap();
+            accounts
+        }
+
+        fn without_nickname() -> Self {
+            Self { accounts: vec![Account::register(Username::new("daniel").unwrap(), "correct horse").unwrap()], bans: vec![] }
+        }
+
+        fn account(&self) -> &Account {
+            &self.accounts[0]
+        }
+
+        fn auth_info(&self) -> AuthInfo {
+            AuthInfo::Authenticated { account_id: self.account().id(), role: AuthRole::Member }
+        }
+    }
+
+    impl LoadAccount for DummyAccounts {
+        fn load_account(&self, account_id: Uuid) -> Result<Account, LoadAccountError> {
+            self.accounts.iter().find(|account| account.id() == account_id).cloned().ok_or(LoadAccountError::AccountNotFound)
+        }
+
+        fn load_account_by_username(&self, username: &Username) -> Result<Account, LoadAccountError> {
+            self.accounts.iter().find(|account| account.username() == username).cloned().ok_or(LoadAccountError::AccountNotFound)
+        }
+
+        fn load_accounts_with_role(&self, role: AccountRole) -> Result<Vec<Account>, LoadAccountError> {
+            Ok(self.accounts.iter().filter(|account| account.role() == role).cloned().collect())
+        }
+    }
+
+    impl LoadBans for DummyAccounts {
+        fn load_ban(&self, ban_id: Uuid) -> Result<Ban, LoadBanError> {
+            self.bans.iter().find(|ban| ban.id == ban_id).cloned().ok_or(LoadBanError::BanNotFound)
+        }
+
+        fn load_bans(&self, account_id: Uuid) -> Result<Vec<Ban>, LoadBanError> {
+            Ok(self.bans.iter().filter(|ban| ban.account_id == account_id).cloned().collect())
+        }
+
+        fn load_tournament_bans(&self, tournament_id: Uuid) -> Result<Vec<Ban>, LoadBanError> {
+            Ok(self.bans.iter().filter(|ban| ban.scope == BanScope::Tournament { tournament_id }).cloned().collect())
+        }
+    }
+
 
     struct DummyPublisher {
         messages: Cell<Vec<TournamentMessage>>
@@ -145,19 +347,26 @@ mod tests {
     }
 
     impl PublishTournamentMessages for DummyPublisher {
-        fn publish_tournament_messages(&self, messages: Vec<TournamentMessage>) {
-            self.messages.replace(messages);
+        fn close_tournament_channels(&mut self, _tournament_id: Uuid) {
+        }
+
+        fn publish_outbox_entries(&mut self, entries: Vec<OutboxEntry>) {
+            self.messages.replace(entries.into_iter().map(|entry| entry.message).collect());
+        }
+
+        fn sweep_channels(&mut self) {
         }
     }
 
 
     #[test]
     fn join_tournament_without_being_authenticated() {
+        let accounts = DummyAccounts::new();
         let mut repository = DummyRepository::new_without_tournament();
-        let publisher = DummyPublisher::new();
-        let request = JoinTournamentRequest { tournament_id: Uuid::new_v4(), nickname: "Daniel".into() };
+        let mut publisher = DummyPublisher::new();
+        let request = JoinTournamentRequest { tournament_id: Uuid::new_v4(), nickname: Some("Daniel".into()) };
         let auth_info = AuthInfo::Unauthenticated;
-        let result = join_tournament(request, &auth_info, &mut repository, &publisher);
+        let result = join_tournament(request, &auth_info, &mut repository, &mut publisher, &mut EventFollowers::new(), &accounts, &BlockedWords::default());
         assert!(matches!(result, Err(JoinTournamentError::AuthError(AuthError::AuthenticationRequired))));
         assert_eq!(publisher.consume(), vec![]);
         assert_eq!(repository.tournament(), None);
@@ -165,69 +374,176 @@ mod tests {
 
     #[test]
     fn join_tournament_with_invalid_parameters() {
+        let accounts = DummyAccounts::new();
         let mut repository = DummyRepository::new_without_tournament();
-        let publisher = DummyPublisher::new();
-        let request = JoinTournamentRequest { tournament_id: Uuid::new_v4(), nickname: "".into() };
-        let auth_info = AuthInfo::Authenticated { account_id: Uuid::new_v4(), role: AuthRole::Member };
-        let result = join_tournament(request, &auth_info, &mut repository, &publisher);
+        let mut publisher = DummyPublisher::new();
+        let request = JoinTournamentRequest { tournament_id: Uuid::new_v4(), nickname: Some("".into()) };
+        let auth_info = accounts.auth_info();
+        let result = join_tournament(request, &auth_info, &mut repository, &mut publisher, &mut EventFollowers::new(), &accounts, &BlockedWords::default());
         assert!(matches!(result, Err(JoinTournamentError::NicknameError(_))));
         assert_eq!(publisher.consume(), vec![]);
         assert_eq!(repository.tournament(), None);
     }
 
+    #[test]
+    fn join_tournament_while_banned() {
+        let mut accounts = DummyAccounts::new();
+        let spec = TournamentSpecification::new(1, 2).unwrap();
+        let tournament = Tournament::new(&spec);
+        let tournament_id = tournament.id();
+        let mut repository = DummyRepository::new_with_tournament(tournament);
+        let mut publisher = DummyPublisher::new();
+        let exclusion = Ban::issue(accounts.account(), BanScope::Tournament { tournament_id }, Uuid::new_v4(), "collusion".into(), None, unix_time()).unwrap();
+        accounts.bans.push(Ban::issue(accounts.account(), BanScope::Tournament { tournament_id: Uuid::new_v4() }, Uuid::new_v4(), "spam".into(), None, unix_time()).unwrap());
+        let auth_info = accounts.auth_info();
+        let request = JoinTournamentRequest { tournament_id, nickname: Some("Daniel".into()) };
+        assert!(join_tournament(request, &auth_info, &mut repository, &mut publisher, &mut EventFollowers::new(), &accounts, &BlockedWords::default()).is_ok());
+        accounts.bans.push(exclusion);
+        let request = JoinTournamentRequest { tournament_id, nickname: Some("Daniel".into()) };
+        let result = join_tournament(request, &auth_info, &mut repository, &mut publisher, &mut EventFollowers::new(), &accounts, &BlockedWords::default());
+        assert!(matches!(result, Err(JoinTournamentError::BanError(BanError::AccountExcluded))));
+    }
+
     #[test]
     fn join_tournament_with_repository_error_on_load() {
+        let accounts = DummyAccounts::new();
         let mut repository = DummyRepository::new_with_error_on_load(LoadTournamentError::DatabaseReadingError);
-        let publisher = DummyPublisher::new();
-        let request = JoinTournamentRequest { tournament_id: Uuid::new_v4(), nickname: "Daniel".into() };
-        let auth_info = AuthInfo::Authenticated { account_id: Uuid::new_v4(), role: AuthRole::Member };
-        let result = join_tournament(request, &auth_info, &mut repository, &publisher);
+        let mut publisher = DummyPublisher::new();
+        let request = JoinTournamentRequest { tournament_id: Uuid::new_v4(), nickname: Some("Daniel".into()) };
+        let auth_info = accounts.auth_info();
+        let result = join_tournament(request, &auth_info, &mut repository, &mut publisher, &mut EventFollowers::new(), &accounts, &BlockedWords::default());
         assert!(matches!(result, Err(JoinTournamentError::LoadTournamentError(LoadTournamentError::DatabaseReadingError))));
         assert_eq!(publisher.consume(), vec![]);
     }
 
     #[test]
     fn join_tournament_with_repository_error_on_save() {
+        let accounts = DummyAccounts::new();
         let spec = TournamentSpecification::new(1, 2).unwrap();
         let tournament = Tournament::new(&spec);
         let tournament_id = tournament.id();
         let mut repository = DummyRepository::new_with_error_on_save(SaveTournamentError::DatabaseWritingError, tournament);
-        let publisher = DummyPublisher::new();
-        let request = JoinTournamentRequest { tournament_id, nickname: "Daniel".into() };
-        let auth_info = AuthInfo::Authenticated { account_id: Uuid::new_v4(), role: AuthRole::Member };
-        let result = join_tournament(request, &auth_info, &mut repository, &publisher);
-        assert!(matches!(result, Err(JoinTournamentError::SaveTournamentError(SaveTournamentError::DatabaseWritingError))));
+        let mut publisher = DummyPublisher::new();
+        let request = JoinTournamentRequest { tournament_id, nickname: Some("Daniel".into()) };
+        let auth_info = accounts.auth_info();
+        let result = join_tournament(request, &auth_info, &mut repository, &mut publisher, &mut EventFollowers::new(), &accounts, &BlockedWords::default());
+        assert!(matches!(result, Err(JoinTournamentError::SaveTournamentAndPublishMessagesError(SaveTournamentAndPublishMessagesError::SaveTournamentError(SaveTournamentError::DatabaseWritingError)))));
         assert_eq!(publisher.consume(), vec![]);
     }
 
     #[test]
     fn join_tournament_with_tournament_error() {
+        let accounts = DummyAccounts::new();
         let spec = TournamentSpecification::new(1, 2).unwrap();
         let mut tournament = Tournament::new(&spec);
         _ = tournament.join(Uuid::new_v4(), Nickname::new("James").unwrap());
         _ = tournament.join(Uuid::new_v4(), Nickname::new("Patricia").unwrap());
         let tournament_id = tournament.id();
         let mut repository = DummyRepository::new_with_tournament(tournament);
-        let publisher = DummyPublisher::new();
-        let request = JoinTournamentRequest { tournament_id, nickname: "Daniel".into() };
-        let auth_info = AuthInfo::Authenticated { account_id: Uuid::new_v4(), role: AuthRole::Member };
-        let result = join_tournament(request, &auth_info, &mut repository, &publisher);
+        let mut publisher = DummyPublisher::new();
+        let request = JoinTournamentRequest { tournament_id, nickname: Some("Daniel".into()) };
+        let auth_info = accounts.auth_info();
+        let result = join_tournament(request, &auth_info, &mut repository, &mut publisher, &mut EventFollowers::new(), &accounts, &BlockedWords::default());
         assert!(matches!(result, Err(JoinTournamentError::TournamentError(_))));
         assert_eq!(publisher.consume(), vec![]);
     }
 
     #[test]
     fn join_tournament_without_any_error() {
+        let accounts = DummyAccounts::new();
         let spec = TournamentSpecification::new(1, 2).unwrap();
         let tournament = Tournament::new(&spec);
         let tournament_id = tournament.id();
         let mut repository = DummyRepository::new_with_tournament(tournament);
-        let publisher = DummyPublisher::new();
-        let request = JoinTournamentRequest { tournament_id, nickname: "Daniel".into() };
-        let auth_info = AuthInfo::Authenticated { account_id: Uuid::new_v4(), role: AuthRole::Member };
-        let result = join_tournament(request, &auth_info, &mut repository, &publisher);
+        let mut publisher = DummyPublisher::new();
+        let request = JoinTournamentRequest { tournament_id, nickname: Some("Daniel".into()) };
+        let auth_info = accounts.auth_info();
+        let result = join_tournament(request, &auth_info, &mut repository, &mut publisher, &mut EventFollowers::new(), &accounts, &BlockedWords::default());
         assert!(result.is_ok_and(|response| response.table_number == 0));
         let tournament_messages = publisher.consume();
-        assert_eq!(tournament_messages.len(), 1);
+        assert_eq!(tournament_messages.len(), 2);
+        assert!(matches!(tournament_messages[0].message_type, TournamentMessageType::RegistrationCountChanged { player_count: 1 }));
+        assert!(matches!(tournament_messages[1].message_type, TournamentMessageType::TableMessage { table_number: 0, .. }));
+    }
+
+    #[test]
+    fn join_tournament_retries_outdated_save() {
+        let accounts = DummyAccounts::new();
+        let spec = TournamentSpecification::new(1, 2).unwrap();
+        let tournament = Tournament::new(&spec);
+        let tournament_id = tournament.id();
+        let mut repository = DummyRepository::new_with_outdated_saves(2, tournament);
+        let mut publisher = DummyPublisher::new();
+        let request = JoinTournamentRequest { tournament_id, nickname: Some("Daniel".into()) };
+        let auth_info = accounts.auth_info();
+        let result = join_tournament(request, &auth_info, &mut repository, &mut publisher, &mut EventFollowers::new(), &accounts, &BlockedWords::default());
+        assert!(result.is_ok_and(|response| response.table_number == 0));
+        assert_eq!(repository.tournament().unwrap().player_count(), 1);
+        assert_eq!(publisher.consume().len(), 2);
+    }
+
+    #[test]
+    fn join_tournament_gives_up_on_repeatedly_outdated_save() {
+        let accounts = DummyAccounts::new();
+        let spec = TournamentSpecification::new(1, 2).unwrap();
+        let tournament = Tournament::new(&spec);
+        let tournament_id = tournament.id();
+        let mut repository = DummyRepository::new_with_outdated_saves(3, tournament);
+        let mut publisher = DummyPublisher::new();
+        let request = JoinTournamentRequest { tournament_id, nickname: Some("Daniel".into()) };
+        let auth_info = accounts.auth_info();
+        let result = join_tournament(request, &auth_info, &mut repository, &mut publisher, &mut EventFollowers::new(), &accounts, &BlockedWords::default());
+        assert!(matches!(result, Err(JoinTournamentError::SaveTournamentAndPublishMessagesError(SaveTournamentAndPublishMessagesError::SaveTournamentError(SaveTournamentError::TournamentOutdated)))));
+        assert_eq!(publisher.consume(), vec![]);
+    }
+
+    #[test]
+    fn join_tournament_requires_registered_nickname() {
+        let accounts = DummyAccounts::without_nickname();
+        let spec = TournamentSpecification::new(1, 2).unwrap();
+        let tournament = Tournament::new(&spec);
+        let tournament_id = tournament.id();
+        let mut repository = DummyRepository::new_with_tournament(tournament);
+        let mut publisher = DummyPublisher::new();
+        let auth_info = accounts.auth_info();
+        for nickname in [None, Some("Daniel".into())] {
+            let request = JoinTournamentRequest { tournament_id, nickname };
+            let result = join_tournament(request, &auth_info, &mut repository, &mut publisher, &mut EventFollowers::new(), &accounts, &BlockedWords::default());
+            assert!(matches!(result, Err(JoinTournamentError::AccountError(AccountError::NicknameNotRegistered))));
+        }
+        assert_eq!(repository.tournament().unwrap().player_count(), 0);
+    }
+
+    #[test]
+    fn join_tournament_with_nickname_blocked_since_registration() {
+        let accounts = DummyAccounts::new();
+        let spec = TournamentSpecification::new(1, 2).unwrap();
+        let tournament = Tournament::new(&spec);
+        let tournament_id = tournament.id();
+        let mut repository = DummyRepository::new_with_tournament(tournament);
+        let mut publisher = DummyPublisher::new();
+        let auth_info = accounts.auth_info();
+        let request = JoinTournamentRequest { tournament_id, nickname: None };
+        let result = join_tournament(request, &auth_info, &mut repository, &mut publisher, &mut EventFollowers::new(), &accounts, &BlockedWords::new(["dan"]));
+        assert!(matches!(result, Err(JoinTournamentError::NicknameError(NicknameError::BlockedWord))));
+        assert_eq!(repository.tournament().unwrap().player_count(), 0);
+    }
+
+    #[test]
+    fn join_tournament_uses_registered_nickname() {
+        let mut accounts = DummyAccounts::without_nickname();
+        accounts.accounts[0].change_nickname(Nickname::new("Denyo").unwrap(), unix_time()).unwrap();
+        let spec = TournamentSpecification::new(1, 2).unwrap();
+        let tournament = Tournament::new(&spec);
+        let tournament_id = tournament.id();
+        let mut repository = DummyRepository::new_with_tournament(tournament);
+        let mut publisher = DummyPublisher::new();
+        let auth_info = accounts.auth_info();
+        let request = JoinTournamentRequest { tournament_id, nickname: Some("Daniel".into()) };
+        let result = join_tournament(request, &auth_info, &mut repository, &mut publisher, &mut EventFollowers::new(), &accounts, &BlockedWords::default());
+        assert!(matches!(result, Err(JoinTournamentError::AccountError(AccountError::NicknameMismatch))));
+        let request = JoinTournamentRequest { tournament_id, nickname: None };
+        join_tournament(request, &auth_info, &mut repository, &mut publisher, &mut EventFollowers::new(), &accounts, &BlockedWords::default()).unwrap();
+        assert_eq!(repository.tournament_events[1], TournamentEvent::PlayerJoined { account_id: accounts.account().id(), nickname: Nickname::new("Denyo").unwrap() });
     }
 }
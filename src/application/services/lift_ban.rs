@@ -0,0 +1,157 @@
+use crate::application::AuthError;
+use crate::application::AuthInfo;
+
+use crate::domain::AccessBans;
+use crate::domain::BanError;
+use crate::domain::LoadAccount;
+use crate::domain::LoadAccountError;
+use crate::domain::LoadBanError;
+use crate::domain::SaveBanError;
+use crate::domain::unix_time;
+
+use thiserror::Error;
+use uuid::Uuid;
+
+
+#[derive(Debug, Error)]
+pub enum LiftBanError {
+    #[error(transparent)]
+    AuthError(#[from] AuthError),
+    #[error(transparent)]
+    LoadAccountError(#[from] LoadAccountError),
+    #[error(transparent)]
+    LoadBanError(#[from] LoadBanError),
+    #[error(transparent)]
+    BanError(#[from] BanError),
+    #[error(transparent)]
+    SaveBanError(#[from] SaveBanError),
+}
+
+
+#[derive(Debug)]
+pub struct LiftBanRequest {
+    pub ban_id: Uuid,
+}
+
+
+#[derive(Debug)]
+pub struct LiftBanResponse {
+}
+
+
+pub trait LiftBan {
+    fn lift_ban(&mut self, request: LiftBanRequest, auth_info: &AuthInfo) -> Result<LiftBanResponse, LiftBanError>;
+}
+
+
+pub(in crate::application) fn lift_ban<Bans: LoadAccount + AccessBans>(
+    request: LiftBanRequest,
+    auth_info: &AuthInfo,
+    bans: &mut Bans,
+) -> Result<LiftBanResponse, LiftBanError> {
+    let moderator_id = auth_info.with_current_role(bans)?.ensure_moderator()?;
+    let mut ban = bans.load_ban(request.ban_id)?;
+    ban.lift(moderator_id, unix_time())?;
+    bans.save_ban(ban)?;
+    Ok(LiftBanResponse {})
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::application::AuthRole;
+    use crate::domain::Account;
+    use crate::domain::AccountRole;
+    use crate::domain::Ban;
+    use crate::domain::BanScope;
+    use crate::domain::LoadBans;
+    use crate::domain::SaveBan;
+    use crate::domain::Username;
+
+    use super::*;
+
+    struct DummyBans {
+        moderator: Account,
+        ban: Ban,
+    }
+
+    impl DummyBans {
+        fn new() -> Self {
+            let account = Account::register(Username::new("daniel").unwrap(), "correct horse").unwrap();
+            let mut moderator = Account::register(Username::new("james").unwrap(), "correct horse").unwrap();
+            moderator.promote(None, unix_time()).unwrap();
+            Self { moderator, ban: Ban::issue(&account, BanScope::Platform, Uuid::new_v4(), "spam".into(), None, unix_time()).unwrap() }
+        }
+
+        fn auth_info(&self) -> AuthInfo {
+            AuthInfo::Authenticated { account_id: self.moderator.id(), role: AuthRole::Moderator }
+        }
+    }
+
+    impl LoadAccount for DummyBans {
+        fn load_account(&self, account_id: Uuid) -> Result<Account, LoadAccountError> {
+            (account_id == self.moderator.id()).then(|| self.moderator.clone()).ok_or(LoadAccountError::AccountNotFound)
+        }
+
+        fn load_account_by_username(&self, username: &Username) -> Result<Account, LoadAccountError> {
+            (username == self.moderator.username()).then(|| self.moderator.clone()).ok_or(LoadAccountError::AccountNotFound)
+        }
+
+        fn load_accounts_with_role(&self, role: AccountRole) -> Result<Vec<Account>, LoadAccountError> {
+            Ok((self.moderator.role() == role).then(|| self.moderator.clone()).into_iter().collect())
+        }
+    }
+
+    impl LoadBans for DummyBans {
+        fn load_ban(&self, ban_id: Uuid) -> Result<Ban, LoadBanError> {
+            (ban_id == self.ban.id).then(|| self.ban.clone()).ok_or(LoadBanError::BanNotFound)
+        }
+
+        fn load_bans(&self, account_id: Uuid) -> Result<Vec<Ban>, LoadBanError> {
+            Ok((account_id == self.ban.account_id).then(|| self.ban.clone()).into_iter().collect())
+        }
+
+        fn load_tournament_bans(&self, tournament_id: Uuid) -> Result<Vec<Ban>, LoadBanError> {
+            Ok((self.ban.scope == BanScope::Tournament { tournament_id }).then(|| self.ban.clone()).into_iter().collect())
+        }
+    }
+
+    impl SaveBan for DummyBans {
+        fn save_ban(&mut self, ban: Ban) -> Result<(), SaveBanError> {
+            self.ban = ban;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn lift_ban_without_being_moderator() {
+        let mut bans = DummyBans::new();
+        let request = LiftBanRequest { ban_id: bans.ban.id };
+        let result = lift_ban(request, &AuthInfo::Unauthenticated, &mut bans);
+        assert!(matches!(result, Err(LiftBanError::AuthError(AuthError::AuthenticationRequired))));
+        let auth_info = bans.auth_info();
+        bans.moderator.demote(None, unix_time()).unwrap();
+        let result = lift_ban(LiftBanRequest { ban_id: bans.ban.id }, &auth_info, &mut bans);
+        assert!(matches!(result, Err(LiftBanError::AuthError(AuthError::PermissionDenied { .. }))));
+        assert_eq!(bans.ban.expires_at, None);
+    }
+
+    #[test]
+    fn lift_ban_with_unknown_ban() {
+        let mut bans = DummyBans::new();
+        let auth_info = bans.auth_info();
+        let result = lift_ban(LiftBanRequest { ban_id: Uuid::new_v4() }, &auth_info, &mut bans);
+        assert!(matches!(result, Err(LiftBanError::LoadBanError(LoadBanError::BanNotFound))));
+    }
+
+    #[test]
+    fn lift_ban_without_any_error() {
+        let mut bans = DummyBans::new();
+        let moderator_id = bans.moderator.id();
+        let auth_info = bans.auth_info();
+        lift_ban(LiftBanRequest { ban_id: bans.ban.id }, &auth_info, &mut bans).unwrap();
+        assert_eq!(bans.ban.lifted_by, Some(moderator_id));
+        let result = lift_ban(LiftBanRequest { ban_id: bans.ban.id }, &auth_info, &mut bans);
+        assert!(matches!(result, Err(LiftBanError::BanError(BanError::BanNotInForce))));
+    }
+}
@@ -0,0 +1,128 @@
+use crate::application::AuthError;
+use crate::application::AuthInfo;
+use crate::application::AuthRole;
+
+use crate::domain::LoadAccount;
+use crate::domain::LoadAccountError;
+
+use thiserror::Error;
+use uuid::Uuid;
+
+
+#[derive(Debug, Error)]
+pub enum ListAccountsError {
+    #[error(transparent)]
+    AuthError(#[from] AuthError),
+    #[error(transparent)]
+    LoadAccountError(#[from] LoadAccountError),
+}
+
+
+#[derive(Debug)]
+pub struct ListAccountsRequest {
+    pub role: AuthRole,
+}
+
+
+#[derive(Debug, serde::Serialize)]
+pub struct AccountInfo {
+    pub account_id: Uuid,
+    pub username: String,
+    pub role: AuthRole,
+}
+
+
+#[derive(Debug, serde::Serialize)]
+pub struct ListAccountsResponse {
+    pub accounts: Vec<AccountInfo>,
+}
+
+
+pub trait ListAccounts {
+    fn list_accounts(&self, request: ListAccountsRequest, auth_info: &AuthInfo) -> Result<ListAccountsResponse, ListAccountsError>;
+}
+
+
+pub(in crate::application) fn list_accounts<Accounts: LoadAccount>(
+    request: ListAccountsRequest,
+    auth_info: &AuthInfo,
+    accounts: &Accounts,
+) -> Result<ListAccountsResponse, ListAccountsError> {
+    auth_info.with_current_role(accounts)?.ensure_administrator()?;
+    let accounts = accounts.load_accounts_with_role(request.role.into())?.into_iter().map(|account| {
+        AccountInfo {
+            account_id: account.id(),
+            username: account.username().to_string(),
+            role: account.role().into(),
+        }
+    }).collect();
+    Ok(ListAccountsResponse { accounts })
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::domain::Account;
+    use crate::domain::AccountRole;
+    use crate::domain::Username;
+    use crate::domain::unix_time;
+
+    use super::*;
+
+    struct DummyAccounts {
+        accounts: Vec<Account>,
+    }
+
+    impl LoadAccount for DummyAccounts {
+        fn load_account(&self, account_id: Uuid) -> Result<Account, LoadAccountError> {
+            self.accounts.iter().find(|account| account.id() == account_id).cloned().ok_or(LoadAccountError::AccountNotFound)
+        }
+
+        fn load_account_by_username(&self, username: &Username) -> Result<Account, LoadAccountError> {
+            self.accounts.iter().find(|account| account.username() == username).cloned().ok_or(LoadAccountError::AccountNotFound)
+        }
+
+        fn load_accounts_with_role(&self, role: AccountRole) -> Result<Vec<Account>, LoadAccountError> {
+            Ok(self.accounts.iter().filter(|account| account.role() == role).cloned().collect())
+        }
+    }
+
+    fn accounts() -> DummyAccounts {
+        let member = Account::register(Username::new("daniel").unwrap(), "correct horse").unwrap();
+        let mut moderator = Account::register(Username::new("james").unwrap(), "correct horse").unwrap();
+        moderator.promote(None, unix_time()).unwrap();
+        let mut administrator = Account::register(Username::new("patricia").unwrap(), "correct horse").unwrap();
+        administrator.promote(None, unix_time()).unwrap();
+        administrator.promote(None, unix_time()).unwrap();
+        DummyAccounts { accounts: vec![member, moderator, administrator] }
+    }
+
+    fn authenticate(accounts: &DummyAccounts, username: &str, role: AuthRole) -> AuthInfo {
+        let account = accounts.load_account_by_username(&Username::new(username).unwrap()).unwrap();
+        AuthInfo::Authenticated { account_id: account.id(), role }
+    }
+
+    #[test]
+    fn list_accounts_without_being_administrator() {
+        let accounts = accounts();
+        let auth_info = authenticate(&accounts, "james", AuthRole::Moderator);
+        let result = list_accounts(ListAccountsRequest { role: AuthRole::Moderator }, &auth_info, &accounts);
+        assert!(matches!(result, Err(ListAccountsError::AuthError(AuthError::PermissionDenied { .. }))));
+        let auth_info = authenticate(&accounts, "james", AuthRole::Administrator);
+        let result = list_accounts(ListAccountsRequest { role: AuthRole::Moderator }, &auth_info, &accounts);
+        assert!(matches!(result, Err(ListAccountsError::AuthError(AuthError::PermissionDenied { found: AuthRole::Moderator, .. }))));
+    }
+
+    #[test]
+    fn list_accounts_without_any_error() {
+        let accounts = accounts();
+        let auth_info = authenticate(&accounts, "patricia", AuthRole::Administrator);
+        let response = list_accounts(ListAccountsRequest { role: AuthRole::Moderator }, &auth_info, &accounts).unwrap();
+        assert_eq!(response.accounts.len(), 1);
+        assert_eq!(response.accounts[0].username, "james");
+        assert!(matches!(response.accounts[0].role, AuthRole::Moderator));
+        let response = list_accounts(ListAccountsRequest { role: AuthRole::Member }, &auth_info, &accounts).unwrap();
+        assert_eq!(response.accounts.len(), 1);
+        assert_eq!(response.accounts[0].username, "daniel");
+    }
+}
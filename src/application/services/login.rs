@@ -0,0 +1,146 @@
+use crate::application::AuthInfo;
+use crate::application::AuthRole;
+
+use crate::domain::AccountError;
+use crate::domain::HashedPassword;
+use crate::domain::LoadAccount;
+use crate::domain::LoadAccountError;
+use crate::domain::Username;
+
+use thiserror::Error;
+use uuid::Uuid;
+
+
+#[derive(Debug, Error)]
+pub enum LoginError {
+    #[error(transparent)]
+    AccountError(#[from] AccountError),
+    #[error(transparent)]
+    LoadAccountError(#[from] LoadAccountError),
+}
+
+
+#[derive(Debug)]
+pub struct LoginRequest {
+    pub username: String,
+    pub password: String,
+}
+
+
+#[derive(Debug)]
+pub struct LoginResponse {
+    pub account_id: Uuid,
+    pub role: AuthRole,
+}
+
+
+// The password is verified apart from the service, see PendingLogin
+pub trait Login {
+    fn login(&self, request: LoginRequest, auth_info: &AuthInfo) -> Result<PendingLogin, LoginError>;
+}
+
+
+// A login whose password still has to be verified, which is slow on purpose and therefore done
+// without holding on to the service. Unknown accounts are verified against a dummy hash, so that
+// they take as long as existing ones.
+#[derive(Debug)]
+pub struct PendingLogin {
+    account: Option<LoginResponse>,
+    hashed_password: HashedPassword,
+    password: String,
+}
+
+impl PendingLogin {
+    pub fn verify(self) -> Result<LoginResponse, LoginError> {
+        self.hashed_password.verify(&self.password)?;
+        Ok(self.account.ok_or(AccountError::InvalidCredentials)?)
+    }
+}
+
+
+// Unknown and malformed usernames fail like wrong passwords, so as not to reveal which accounts exist
+pub(in crate::application) fn login<Accounts: LoadAccount>(
+    request: LoginRequest,
+    _auth_info: &AuthInfo,
+    accounts: &Accounts,
+) -> Result<PendingLogin, LoginError> {
+    let username = Username::new(request.username).map_err(|_| AccountError::InvalidCredentials)?;
+    let pending_login = match accounts.load_account_by_username(&username) {
+        Err(LoadAccountError::AccountNotFound) => PendingLogin {
+            account: None,
+            hashed_password: HashedPassword::dummy(),
+            password: request.password,
+        },
+        result => {
+            let account = result?;
+            PendingLogin {
+                account: Some(LoginResponse { account_id: account.id(), role: account.role().into() }),
+                hashed_password: account.hashed_password(),
+                password: request.password,
+            }
+        },
+    };
+    Ok(pending_login)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::domain::Account;
+    use crate::domain::AccountRole;
+
+    use super::*;
+
+    struct DummyAccounts {
+        account: Account,
+    }
+
+    impl DummyAccounts {
+        fn new() -> Self {
+            Self { account: Account::register(Username::new("daniel").unwrap(), "correct horse").unwrap() }
+        }
+    }
+
+    impl LoadAccount for DummyAccounts {
+        fn load_account(&self, account_id: Uuid) -> Result<Account, LoadAccountError> {
+            (account_id == self.account.id()).then(|| self.account.clone()).ok_or(LoadAccountError::AccountNotFound)
+        }
+
+        fn load_account_by_username(&self, username: &Username) -> Result<Account, LoadAccountError> {
+            (username == self.account.username()).then(|| self.account.clone()).ok_or(LoadAccountError::AccountNotFound)
+        }
+
+        fn load_accounts_with_role(&self, role: AccountRole) -> Result<Vec<Account>, LoadAccountError> {
+            Ok((self.account.role() == role).then(|| self.account.clone()).into_iter().collect())
+        }
+    }
+
+    fn request(username: &str, password: &str) -> LoginRequest {
+        LoginRequest { username: username.into(), password: password.into() }
+    }
+
+    #[test]
+    fn login_with_invalid_credentials() {
+        let accounts = DummyAccounts::new();
+        for request in [request("daniel", "wrong horse"), request("james", "correct horse"), request("-", "correct horse")] {
+            let result = login(request, &AuthInfo::Unauthenticated, &accounts).and_then(PendingLogin::verify);
+            assert!(matches!(result, Err(LoginError::AccountError(AccountError::InvalidCredentials))));
+        }
+    }
+
+    #[test]
+    fn login_without_any_error() {
+        let accounts = DummyAccounts::new();
+        let response = login(request("Daniel", "correct horse"), &AuthInfo::Unauthenticated, &accounts).unwrap().verify().unwrap();
+        assert_eq!(response.account_id, accounts.account.id());
+        assert!(matches!(response.role, AuthRole::Member));
+    }
+
+    #[test]
+    fn login_to_unknown_account_verifies_the_password() {
+        let accounts = DummyAccounts::new();
+        let pending_login = login(request("james", "correct horse"), &AuthInfo::Unauthenticated, &accounts).unwrap();
+        assert_eq!(pending_login.hashed_password, HashedPassword::dummy());
+        assert!(matches!(pending_login.verify(), Err(LoginError::AccountError(AccountError::InvalidCredentials))));
+    }
+}
@@ -1,13 +1,49 @@
+mod ban_account;
+mod bootstrap_administrator;
+mod change_nickname;
+mod change_password;
 mod create_tournament;
+mod demote_account;
 mod find_tournaments;
+mod get_broadcast_statistics;
+mod get_player_history;
+mod get_role_changes;
+mod get_tournament;
 mod join_tournament;
+mod lift_ban;
+mod list_accounts;
+mod login;
+mod observe_lobby;
 mod observe_table;
+mod observe_tournament;
+mod promote_account;
+mod rebuild_projections;
+mod register_account;
+mod relay_messages;
 
+pub use ban_account::*;
+pub use bootstrap_administrator::*;
+pub use change_nickname::*;
+pub use change_password::*;
 pub use create_tournament::*;
+pub use demote_account::*;
 pub use find_tournaments::*;
+pub use get_broadcast_statistics::*;
+pub use get_player_history::*;
+pub use get_role_changes::*;
+pub use get_tournament::*;
 pub use join_tournament::*;
+pub use lift_ban::*;
+pub use list_accounts::*;
+pub use login::*;
+pub use observe_lobby::*;
 pub use observe_table::*;
+pub use observe_tournament::*;
+pub use promote_account::*;
+pub use rebuild_projections::*;
+pub use register_account::*;
+pub use relay_messages::*;
 
 
-pub trait ProvideServices: FindTournaments + CreateTournament + JoinTournament + ObserveTable {}
-impl<T: FindTournaments + CreateTournament + JoinTournament + ObserveTable> ProvideServices for T {}
+pub trait ProvideServices: FindTournaments + GetTournament + CreateTournament + JoinTournament + ObserveTable + ObserveTournament + ObserveLobby + GetBroadcastStatistics + RelayMessages + RebuildProjections + GetPlayerHistory + RegisterAccount + Login + ChangePassword + ChangeNickname + PromoteAccount + DemoteAccount + ListAccounts + GetRoleChanges + BootstrapAdministrator + BanAccount + LiftBan {}
+impl<T: FindTournaments + GetTournament + CreateTournament + JoinTournament + ObserveTable + ObserveTournament + ObserveLobby + GetBroadcastStatistics + RelayMessages + RebuildProjections + GetPlayerHistory + RegisterAccount + Login + ChangePassword + ChangeNickname + PromoteAccount + DemoteAccount + ListAccounts + GetRoleChanges + BootstrapAdministrator + BanAccount + LiftBan> ProvideServices for T {}
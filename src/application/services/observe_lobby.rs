@@ -0,0 +1,81 @@
+use crate::application::AuthError;
+use crate::application::AuthInfo;
+use crate::domain::SequencedTournamentMessage;
+use crate::domain::SubscribeTournamentMessages;
+use crate::domain::MessageReceiver;
+use crate::domain::TournamentMessageReceiver;
+
+use thiserror::Error;
+
+
+#[derive(Debug, Error)]
+pub enum ObserveLobbyError {
+    #[error(transparent)]
+    AuthError(#[from] AuthError),
+}
+
+
+#[derive(Debug)]
+pub struct ObserveLobbyRequest {
+    pub last_seen_sequence: Option<u64>,
+}
+
+
+#[derive(Debug)]
+pub struct ObserveLobbyResponse {
+    pub receiver: TournamentMessageReceiver,
+    pub last_sequence: u64,                                        // Sequence of the last message published before
+    pub missed_messages: Option<Vec<SequencedTournamentMessage>>,  // None if the observer has to query the tournaments anew
+}
+
+
+pub trait ObserveLobby {
+    fn observe_lobby(
+        &mut self,
+        request: ObserveLobbyRequest,
+        auth_info: &AuthInfo
+    ) -> Result<ObserveLobbyResponse, ObserveLobbyError>;
+}
+
+
+pub(in crate::application) fn observe_lobby<Broadcast: SubscribeTournamentMessages>(
+    request: ObserveLobbyRequest,
+    auth_info: &AuthInfo,
+    broadcast: &mut Broadcast,
+) -> Result<ObserveLobbyResponse, ObserveLobbyError> {
+    auth_info.ensure_authenticated()?;
+    let subscription = broadcast.subscribe_lobby_messages(request.last_seen_sequence);
+    Ok(ObserveLobbyResponse {
+        receiver: MessageReceiver::new(subscription.receiver),
+        last_sequence: subscription.last_sequence,
+        missed_messages: subscription.missed_messages,
+    })
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::application::AuthRole;
+    use crate::domain::TableMessageBroadcast;
+
+    use uuid::Uuid;
+
+    use super::*;
+
+    #[test]
+    fn observe_lobby_without_being_authenticated() {
+        let mut broadcast = TableMessageBroadcast::new();
+        let request = ObserveLobbyRequest { last_seen_sequence: None };
+        let result = observe_lobby(request, &AuthInfo::Unauthenticated, &mut broadcast);
+        assert!(matches!(result, Err(ObserveLobbyError::AuthError(AuthError::AuthenticationRequired))));
+    }
+
+    #[test]
+    fn observe_lobby_without_any_error() {
+        let mut broadcast = TableMessageBroadcast::new();
+        let request = ObserveLobbyRequest { last_seen_sequence: Some(0) };
+        let auth_info = AuthInfo::Authenticated { account_id: Uuid::new_v4(), role: AuthRole::Member };
+        let response = observe_lobby(request, &auth_info, &mut broadcast).unwrap();
+        assert_eq!(response.missed_messages, Some(vec![]));
+    }
+}
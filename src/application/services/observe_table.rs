@@ -1,15 +1,33 @@
 use crate::application::AuthError;
 use crate::application::AuthInfo;
+use crate::domain::BanError;
+use crate::domain::LoadAccount;
+use crate::domain::LoadAccountError;
+use crate::domain::LoadBanError;
+use crate::domain::LoadBans;
+use crate::domain::LoadTable;
+use crate::domain::LoadTableError;
 use crate::domain::LoadTournament;
 use crate::domain::LoadTournamentError;
+use crate::domain::MessageReceiver;
+use crate::domain::ObservationAccess;
+use crate::domain::ObservationError;
+use crate::domain::SequencedTableMessage;
 use crate::domain::SubscribeTableMessages;
 use crate::domain::TableMessageReceiver;
 use crate::domain::TableState;
+use crate::domain::Tournament;
 use crate::domain::TournamentError;
+use crate::domain::ensure_not_banned;
+use crate::domain::has_exclusions_in_force;
+use crate::domain::unix_time;
 
 use thiserror::Error;
 use uuid::Uuid;
 
+use std::time::Duration;
+use std::time::Instant;
+
 
 #[derive(Debug, Error)]
 pub enum ObserveTableError {
@@ -18,7 +36,17 @@ pub enum ObserveTableError {
     #[error(transparent)]
     LoadTournamentError(#[from] LoadTournamentError),
     #[error(transparent)]
-    TournamentError(#[from] TournamentError)
+    LoadTableError(#[from] LoadTableError),
+    #[error(transparent)]
+    TournamentError(#[from] TournamentError),
+    #[error(transparent)]
+    LoadAccountError(#[from] LoadAccountError),
+    #[error(transparent)]
+    LoadBanError(#[from] LoadBanError),
+    #[error(transparent)]
+    BanError(#[from] BanError),
+    #[error(transparent)]
+    ObservationError(#[from] ObservationError),
 }
 
 
@@ -26,13 +54,16 @@ pub enum ObserveTableError {
 pub struct ObserveTableRequest {
     pub tournament_id: Uuid,
     pub table_number: usize,
+    pub last_seen_sequence: Option<u64>,
 }
 
 
 #[derive(Debug)]
 pub struct ObserveTableResponse {
-    pub receiver: TableMessageReceiver,
-    pub table_state: TableState,
+    pub receiver: TableMessageReceiver,                       // Holds messages back by the spectator delay
+    pub table_state: Option<TableState>,                      // As of the delay for delayed observers, None if not needed
+    pub table_sequence: u64,                                  // Sequence of the last message reflected up front
+    pub missed_messages: Option<Vec<SequencedTableMessage>>,  // None if the observer needs the full table state
 }
 
 
@@ -45,24 +76,361 @@ pub trait ObserveTable {
 }
 
 
-pub(in crate::application) fn observe_table<Repository: LoadTournament, Broadcast: SubscribeTableMessages>(
+pub(in crate::application) fn observe_table<Repository: LoadTournament + LoadTable, Broadcast: SubscribeTableMessages, Accounts: LoadAccount + LoadBans>(
     request: ObserveTableRequest,
-    _auth_info: &AuthInfo,
+    auth_info: &AuthInfo,
     repository: &Repository,
     broadcast: &mut Broadcast,
+    accounts: &Accounts,
 ) -> Result<ObserveTableResponse, ObserveTableError> {
-    // TODO: Think about whether public / unauthenticated observation should
-    //       also be handled here. We do not want to duplicate service code.
-    //       An authenticated request whose author sits at the table could
-    //       receive private messages.
-    // _ = auth_info.ensure_authenticated()?;
+    let auth_info = &auth_info.with_current_role(accounts)?;
+    // Anonymous observers cannot be told apart from excluded accounts, so tournaments excluding
+    // anyone may only be observed by authenticated ones
+    match auth_info.ensure_authenticated() {
+        Ok(account_id) => ensure_not_banned(&accounts.load_bans(account_id)?, Some(request.tournament_id), unix_time())?,
+        Err(error) if has_exclusions_in_force(&accounts.load_tournament_bans(request.tournament_id)?, unix_time()) => return Err(error.into()),
+        Err(_) => {},
+    }
     let tournament = repository.load_tournament(request.tournament_id)?;
-    let table_state = tournament.table_state(request.table_number)?;
-    let receiver = broadcast.subscribe_table_messages(request.tournament_id, request.table_number);
-    Ok(ObserveTableResponse { receiver, table_state })
+    tournament.ensure_table_exists(request.table_number)?;
+    let spectator_delay = ensure_may_observe(&tournament, request.table_number, auth_info)?;
+    let table_state = repository.load_table(request.tournament_id, request.table_number)?.state();
+    let subscription = broadcast.subscribe_table_messages(request.tournament_id, request.table_number, request.last_seen_sequence, &table_state);
+    if spectator_delay.is_zero() {
+        return Ok(ObserveTableResponse {
+            receiver: MessageReceiver::new(subscription.receiver),
+            table_state: Some(table_state),
+            table_sequence: subscription.last_sequence,
+            missed_messages: subscription.missed_messages,
+        });
+    }
+    let now = Instant::now();
+    if let Some(missed_messages) = subscription.missed_messages {
+        let (receiver, missed_messages) = MessageReceiver::delayed(subscription.receiver, Some(missed_messages), spectator_delay, now);
+        let last_seen_sequence = request.last_seen_sequence.unwrap_or_default();
+        let table_sequence = missed_messages.as_ref().and_then(|messages| messages.last()).map_or(last_seen_sequence, |message| message.sequence);
+        return Ok(ObserveTableResponse { receiver, table_state: None, table_sequence, missed_messages });
+    }
+    // Delayed observers who cannot catch up from the log start from the table as it was before the
+    // delay, which is unknown for a while after the broadcast started keeping its state
+    let (table_sequence, delayed_state) = now.checked_sub(spectator_delay)
+        .and_then(|before| broadcast.table_state_at(request.tournament_id, request.table_number, before))
+        .ok_or(ObservationError::DelayedStateUnavailable)?;
+    let subscription = broadcast.subscribe_table_messages(request.tournament_id, request.table_number, Some(table_sequence), &table_state);
+    let (receiver, _) = MessageReceiver::delayed(subscription.receiver, subscription.missed_messages, spectator_delay, now);
+    Ok(ObserveTableResponse {
+        receiver,
+        table_state: Some(delayed_state),
+        table_sequence,
+        missed_messages: None,
+    })
+}
+
+
+// Players always observe their own table without delay. Moderators may observe any table, but like
+// all other spectators only with the delay, since they might be playing at another table.
+fn ensure_may_observe(tournament: &Tournament, table_number: usize, auth_info: &AuthInfo) -> Result<Duration, ObserveTableError> {
+    let policy = tournament.observation_policy();
+    let account_id = auth_info.ensure_authenticated().ok();
+    let table_number_of_observer = account_id.and_then(|account_id| tournament.players_table_number(account_id));
+    if table_number_of_observer == Some(table_number) {
+        return Ok(Duration::ZERO);
+    }
+    if auth_info.ensure_moderator().is_err() {
+        match policy.access {
+            ObservationAccess::Public => {},
+            ObservationAccess::MembersOnly => _ = auth_info.ensure_authenticated()?,
+            ObservationAccess::ParticipantsOnly => {
+                auth_info.ensure_authenticated()?;
+                if table_number_of_observer.is_none() {
+                    return Err(ObservationError::NotParticipating.into());
+                }
+            },
+            ObservationAccess::Disabled => return Err(ObservationError::ObservationDisabled.into()),
+        }
+    }
+    Ok(policy.spectator_delay)
 }
 
 
 #[cfg(test)]
 mod tests {
+    use std::collections::HashMap;
+
+    use crate::application::AuthRole;
+    use crate::domain::Account;
+    use crate::domain::AccountRole;
+    use crate::domain::Ban;
+    use crate::domain::BanScope;
+    use crate::domain::Nickname;
+    use crate::domain::ObservationPolicy;
+    use crate::domain::OutboxEntry;
+    use crate::domain::PublishTournamentMessages;
+    use crate::domain::Table;
+    use crate::domain::TableMessage;
+    use crate::domain::TableMessageBroadcast;
+    use crate::domain::Tournament;
+    use crate::domain::TournamentMessage;
+    use crate::domain::TournamentMessageType;
+    use crate::domain::TournamentSpecification;
+    use crate::domain::Username;
+
+    use super::*;
+
+    struct DummyRepository {
+        tournament: Tournament,
+        tables: HashMap<(Uuid, usize), Table>,
+    }
+
+    impl LoadTournament for DummyRepository {
+        fn load_tournament(&self, tournament_id: Uuid) -> Result<Tournament, LoadTournamentError> {
+            if self.tournament.id() == tournament_id {
+                Ok(self.tournament.clone())
+            } else {
+                Err(LoadTournamentError::TournamentNotFound)
+            }
+        }
+    }
+
+    impl LoadTable for DummyRepository {
+        fn load_table(&self, tournament_id: Uuid, table_number: usize) -> Result<Table, LoadTableError> {
+            self.tables.get(&(tournament_id, table_number)).cloned().ok_or(LoadTableError::TableNotFound)
+        }
+    }
+
+    // Every account exists, only those listed are moderators
+    struct DummyAccounts {
+        moderator_ids: Vec<Uuid>,
+        bans: Vec<Ban>,
+    }
+
+    impl DummyAccounts {
+        fn new() -> Self {
+            Self { moderator_ids: vec![], bans: vec![] }
+        }
+    }
+
+    impl LoadAccount for DummyAccounts {
+        fn load_account(&self, account_id: Uuid) -> Result<Account, LoadAccountError> {
+            let role = if self.moderator_ids.contains(&account_id) { AccountRole::Moderator } else { AccountRole::Member };
+            Ok(Account::restore(account_id, Username::new("daniel").unwrap(), String::new(), role, None, None))
+        }
+
+        fn load_account_by_username(&self, _username: &Username) -> Result<Account, LoadAccountError> {
+            Err(LoadAccountError::AccountNotFound)
+        }
+
+        fn load_accounts_with_role(&self, _role: AccountRole) -> Result<Vec<Account>, LoadAccountError> {
+            Ok(vec![])
+        }
+    }
+
+    impl LoadBans for DummyAccounts {
+        fn load_ban(&self, ban_id: Uuid) -> Result<Ban, LoadBanError> {
+            self.bans.iter().find(|ban| ban.id == ban_id).cloned().ok_or(LoadBanError::BanNotFound)
+        }
+
+        fn load_bans(&self, account_id: Uuid) -> Result<Vec<Ban>, LoadBanError> {
+            Ok(self.bans.iter().filter(|ban| ban.account_id == account_id).cloned().collect())
+        }
+
+        fn load_tournament_bans(&self, tournament_id: Uuid) -> Result<Vec<Ban>, LoadBanError> {
+            Ok(self.bans.iter().filter(|ban| ban.scope == BanScope::Tournament { tournament_id }).cloned().collect())
+        }
+    }
+
+    fn setup_with_two_players() -> (DummyRepository, TableMessageBroadcast) {
+        let (repository, broadcast, _) = setup_with_observation_policy(ObservationPolicy::default());
+        (repository, broadcast)
+    }
+
+    // Returns the account ids of the players as well
+    fn setup_with_observation_policy(policy: ObservationPolicy) -> (DummyRepository, TableMessageBroadcast, Vec<Uuid>) {
+        let spec = TournamentSpecification::new(2, 3).unwrap().with_observation_policy(policy).unwrap();
+        let mut tournament = Tournament::new(&spec);
+        let mut table = Table::new(tournament.id(), 0, &spec.table_spec());
+        let mut broadcast = TableMessageBroadcast::new();
+        let mut account_ids = vec![];
+        for nickname in ["James", "Patricia"] {
+            let account_id = Uuid::new_v4();
+            _ = tournament.join(account_id, Nickname::new(nickname).unwrap());
+            _ = table.sit_down(account_id, Nickname::new(nickname).unwrap(), 1500);
+            account_ids.push(account_id);
+        }
+        broadcast.publish_outbox_entries((1..).zip(table.collect_messages()).map(|(position, message_type)| OutboxEntry {
+            position,
+            message: TournamentMessage {
+                tournament_id: tournament.id(),
+                message_type: TournamentMessageType::TableMessage { table_number: 0, message_type },
+            },
+        }).collect());
+        let tables = HashMap::from([((tournament.id(), 0), table), ((tournament.id(), 1), Table::new(tournament.id(), 1, &spec.table_spec()))]);
+        (DummyRepository { tournament, tables }, broadcast, account_ids)
+    }
+
+    fn observe(repository: &DummyRepository, broadcast: &mut TableMessageBroadcast, table_number: usize, auth_info: &AuthInfo) -> Result<ObserveTableResponse, ObserveTableError> {
+        observe_with_accounts(repository, broadcast, table_number, auth_info, &DummyAccounts::new())
+    }
+
+    fn observe_with_accounts(repository: &DummyRepository, broadcast: &mut TableMessageBroadcast, table_number: usize, auth_info: &AuthInfo, accounts: &DummyAccounts) -> Result<ObserveTableResponse, ObserveTableError> {
+        let request = ObserveTableRequest { tournament_id: repository.tournament.id(), table_number, last_seen_sequence: Some(0) };
+        observe_table(request, auth_info, repository, broadcast, accounts)
+    }
+
+    #[test]
+    fn observe_table_with_invalid_table_number() {
+        let (repository, mut broadcast) = setup_with_two_players();
+        let request = ObserveTableRequest { tournament_id: repository.tournament.id(), table_number: 2, last_seen_sequence: None };
+        let auth_info = AuthInfo::Authenticated { account_id: Uuid::new_v4(), role: AuthRole::Member };
+        let result = observe_table(request, &auth_info, &repository, &mut broadcast, &DummyAccounts::new());
+        assert!(matches!(result, Err(ObserveTableError::TournamentError(TournamentError::NotSuchTable))));
+    }
+
+    #[test]
+    fn observe_table_while_banned() {
+        let (repository, mut broadcast) = setup_with_two_players();
+        let account = Account::register(Username::new("daniel").unwrap(), "correct horse").unwrap();
+        let bans = DummyAccounts { moderator_ids: vec![], bans: vec![Ban::issue(&account, BanScope::Platform, Uuid::new_v4(), "spam".into(), None, unix_time()).unwrap()] };
+        let request = ObserveTableRequest { tournament_id: repository.tournament.id(), table_number: 0, last_seen_sequence: None };
+        let auth_info = AuthInfo::Authenticated { account_id: account.id(), role: AuthRole::Member };
+        let result = observe_table(request, &auth_info, &repository, &mut broadcast, &bans);
+        assert!(matches!(result, Err(ObserveTableError::BanError(BanError::AccountBanned))));
+    }
+
+    #[test]
+    fn observe_table_anonymously_while_excluded() {
+        let (repository, mut broadcast) = setup_with_two_players();
+        let account = Account::register(Username::new("daniel").unwrap(), "correct horse").unwrap();
+        let scope = BanScope::Tournament { tournament_id: repository.tournament.id() };
+        let mut accounts = DummyAccounts { moderator_ids: vec![], bans: vec![Ban::issue(&account, scope, Uuid::new_v4(), "collusion".into(), None, unix_time()).unwrap()] };
+        let result = observe_with_accounts(&repository, &mut broadcast, 0, &AuthInfo::Unauthenticated, &accounts);
+        assert!(matches!(result, Err(ObserveTableError::AuthError(AuthError::AuthenticationRequired))));
+        let auth_info = AuthInfo::Authenticated { account_id: account.id(), role: AuthRole::Member };
+        let result = observe_with_accounts(&repository, &mut broadcast, 0, &auth_info, &accounts);
+        assert!(matches!(result, Err(ObserveTableError::BanError(BanError::AccountExcluded))));
+        let auth_info = AuthInfo::Authenticated { account_id: Uuid::new_v4(), role: AuthRole::Member };
+        assert!(observe_with_accounts(&repository, &mut broadcast, 0, &auth_info, &accounts).is_ok());
+        accounts.bans[0].lift(Uuid::new_v4(), unix_time()).unwrap();
+        assert!(observe_with_accounts(&repository, &mut broadcast, 0, &AuthInfo::Unauthenticated, &accounts).is_ok());
+    }
+
+    #[test]
+    fn observe_table_from_scratch() {
+        let (repository, mut broadcast) = setup_with_two_players();
+        let request = ObserveTableRequest { tournament_id: repository.tournament.id(), table_number: 0, last_seen_sequence: None };
+        let auth_info = AuthInfo::Unauthenticated;
+        let response = observe_table(request, &auth_info, &repository, &mut broadcast, &DummyAccounts::new()).unwrap();
+        assert_eq!(response.table_sequence, 2);
+        assert_eq!(response.table_state.unwrap().seats.iter().flatten().count(), 2);
+        assert!(response.missed_messages.is_none());
+    }
+
+    #[test]
+    fn observe_table_resuming_from_sequence() {
+        let (repository, mut broadcast) = setup_with_two_players();
+        let request = ObserveTableRequest { tournament_id: repository.tournament.id(), table_number: 0, last_seen_sequence: Some(1) };
+        let auth_info = AuthInfo::Unauthenticated;
+        let response = observe_table(request, &auth_info, &repository, &mut broadcast, &DummyAccounts::new()).unwrap();
+        let missed_messages = response.missed_messages.unwrap();
+        assert_eq!(missed_messages.len(), 1);
+        assert_eq!(missed_messages[0].sequence, 2);
+    }
+
+    #[test]
+    fn observe_members_only_table() {
+        let (repository, mut broadcast, _) = setup_with_observation_policy(ObservationPolicy::new(ObservationAccess::MembersOnly, Duration::ZERO));
+        let result = observe(&repository, &mut broadcast, 0, &AuthInfo::Unauthenticated);
+        assert!(matches!(result, Err(ObserveTableError::AuthError(AuthError::AuthenticationRequired))));
+        let auth_info = AuthInfo::Authenticated { account_id: Uuid::new_v4(), role: AuthRole::Member };
+        assert!(observe(&repository, &mut broadcast, 0, &auth_info).is_ok());
+    }
+
+    #[test]
+    fn observe_participants_only_table() {
+        let (repository, mut broadcast, account_ids) = setup_with_observation_policy(ObservationPolicy::new(ObservationAccess::ParticipantsOnly, Duration::ZERO));
+        let auth_info = AuthInfo::Authenticated { account_id: Uuid::new_v4(), role: AuthRole::Member };
+        let result = observe(&repository, &mut broadcast, 0, &auth_info);
+        assert!(matches!(result, Err(ObserveTableError::ObservationError(ObservationError::NotParticipating))));
+        let auth_info = AuthInfo::Authenticated { account_id: account_ids[0], role: AuthRole::Member };
+        assert!(observe(&repository, &mut broadcast, 1, &auth_info).is_ok());
+    }
+
+    #[test]
+    fn observe_table_with_observation_disabled() {
+        let (repository, mut broadcast, account_ids) = setup_with_observation_policy(ObservationPolicy::new(ObservationAccess::Disabled, Duration::ZERO));
+        let auth_info = AuthInfo::Authenticated { account_id: account_ids[0], role: AuthRole::Member };
+        let result = observe(&repository, &mut broadcast, 1, &auth_info);
+        assert!(matches!(result, Err(ObserveTableError::ObservationError(ObservationError::ObservationDisabled))));
+        assert!(observe(&repository, &mut broadcast, 0, &auth_info).is_ok());
+        let moderator_id = Uuid::new_v4();
+        let auth_info = AuthInfo::Authenticated { account_id: moderator_id, role: AuthRole::Moderator };
+        let accounts = DummyAccounts { moderator_ids: vec![moderator_id], bans: vec![] };
+        assert!(observe_with_accounts(&repository, &mut broadcast, 1, &auth_info, &accounts).is_ok());
+        let result = observe(&repository, &mut broadcast, 1, &auth_info);
+        assert!(matches!(result, Err(ObserveTableError::ObservationError(ObservationError::ObservationDisabled))));
+    }
+
+    #[test]
+    fn spectators_do_not_receive_recent_messages_right_away() {
+        let (repository, mut broadcast, account_ids) = setup_with_observation_policy(ObservationPolicy::new(ObservationAccess::Public, Duration::from_secs(60)));
+        let response = observe(&repository, &mut broadcast, 0, &AuthInfo::Unauthenticated).unwrap();
+        assert_eq!(response.missed_messages, Some(vec![]));
+        let auth_info = AuthInfo::Authenticated { account_id: account_ids[0], role: AuthRole::Member };
+        let response = observe(&repository, &mut broadcast, 0, &auth_info).unwrap();
+        assert_eq!(response.missed_messages.unwrap().len(), 2);
+    }
+
+    #[test]
+    fn delayed_spectators_do_not_see_the_live_table() {
+        let (repository, mut broadcast, account_ids) = setup_with_observation_policy(ObservationPolicy::new(ObservationAccess::Public, Duration::from_secs(60)));
+        let request = ObserveTableRequest { tournament_id: repository.tournament.id(), table_number: 0, last_seen_sequence: None };
+        let result = observe_table(request, &AuthInfo::Unauthenticated, &repository, &mut broadcast, &DummyAccounts::new());
+        assert!(matches!(result, Err(ObserveTableError::ObservationError(ObservationError::DelayedStateUnavailable))));
+        let request = ObserveTableRequest { tournament_id: repository.tournament.id(), table_number: 0, last_seen_sequence: Some(1) };
+        let response = observe_table(request, &AuthInfo::Unauthenticated, &repository, &mut broadcast, &DummyAccounts::new()).unwrap();
+        assert!(response.table_state.is_none());
+        assert_eq!(response.table_sequence, 1);
+        let request = ObserveTableRequest { tournament_id: repository.tournament.id(), table_number: 0, last_seen_sequence: None };
+        let auth_info = AuthInfo::Authenticated { account_id: account_ids[0], role: AuthRole::Member };
+        let response = observe_table(request, &auth_info, &repository, &mut broadcast, &DummyAccounts::new()).unwrap();
+        assert!(response.table_state.is_some());
+        assert_eq!(response.table_sequence, 2);
+    }
+
+    #[test]
+    fn delayed_spectators_start_from_the_delayed_table_state() {
+        let spectator_delay = Duration::from_millis(20);
+        let (repository, mut broadcast, _) = setup_with_observation_policy(ObservationPolicy::new(ObservationAccess::Public, spectator_delay));
+        let tournament_id = repository.tournament.id();
+        let observe_from = |broadcast: &mut TableMessageBroadcast, last_seen_sequence| {
+            let request = ObserveTableRequest { tournament_id, table_number: 0, last_seen_sequence };
+            observe_table(request, &AuthInfo::Unauthenticated, &repository, broadcast, &DummyAccounts::new())
+        };
+        assert!(observe_from(&mut broadcast, None).is_err());
+        std::thread::sleep(spectator_delay);
+        let response = observe_from(&mut broadcast, None).unwrap();
+        assert_eq!((response.table_sequence, response.missed_messages), (2, None));
+        assert_eq!(response.table_state.unwrap().seats.iter().flatten().count(), 2);
+        // More messages than the log holds, so that resuming from the first ones is impossible
+        let message_count = 100;
+        // Positions follow the two messages published on setup
+        broadcast.publish_outbox_entries((0..message_count).map(|index| OutboxEntry {
+            position: 3 + index,
+            message: TournamentMessage {
+                tournament_id,
+                message_type: TournamentMessageType::TableMessage {
+                    table_number: 0,
+                    message_type: match index % 2 {
+                        0 => TableMessage::PlayerLeft { position: 1 },
+                        _ => TableMessage::PlayerSeated { nickname: Nickname::new("Mary").unwrap(), stack: 1500, position: 1 },
+                    },
+                },
+            },
+        }).collect());
+        std::thread::sleep(spectator_delay);
+        let response = observe_from(&mut broadcast, Some(1)).unwrap();
+        assert_eq!((response.table_sequence, response.missed_messages), (2 + message_count, None));
+        let table_state = response.table_state.unwrap();
+        assert_eq!(table_state.seats[1].as_ref().map(|seat| seat.nickname.to_string()), Some("Mary".to_string()));
+    }
 }
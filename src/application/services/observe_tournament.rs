@@ -0,0 +1,148 @@
+use crate::application::AuthError;
+use crate::application::AuthInfo;
+use crate::domain::LoadTournament;
+use crate::domain::LoadTournamentError;
+use crate::domain::SequencedTournamentMessage;
+use crate::domain::SubscribeTournamentMessages;
+use crate::domain::MessageReceiver;
+use crate::domain::TournamentMessageReceiver;
+
+use thiserror::Error;
+use uuid::Uuid;
+
+
+#[derive(Debug, Error)]
+pub enum ObserveTournamentError {
+    #[error(transparent)]
+    AuthError(#[from] AuthError),
+    #[error(transparent)]
+    LoadTournamentError(#[from] LoadTournamentError),
+}
+
+
+#[derive(Debug)]
+pub struct ObserveTournamentRequest {
+    pub tournament_id: Uuid,
+    pub last_seen_sequence: Option<u64>,
+}
+
+
+#[derive(Debug)]
+pub struct ObserveTournamentResponse {
+    pub receiver: TournamentMessageReceiver,
+    pub last_sequence: u64,                                        // Sequence of the last message published before
+    pub missed_messages: Option<Vec<SequencedTournamentMessage>>,  // None if the observer has to query the tournament anew
+}
+
+
+pub trait ObserveTournament {
+    fn observe_tournament(
+        &mut self,
+        request: ObserveTournamentRequest,
+        auth_info: &AuthInfo
+    ) -> Result<ObserveTournamentResponse, ObserveTournamentError>;
+}
+
+
+pub(in crate::application) fn observe_tournament<Repository: LoadTournament, Broadcast: SubscribeTournamentMessages>(
+    request: ObserveTournamentRequest,
+    auth_info: &AuthInfo,
+    repository: &Repository,
+    broadcast: &mut Broadcast,
+) -> Result<ObserveTournamentResponse, ObserveTournamentError> {
+    auth_info.ensure_authenticated()?;
+    _ = repository.load_tournament(request.tournament_id)?;
+    let subscription = broadcast.subscribe_tournament_messages(request.tournament_id, request.last_seen_sequence);
+    Ok(ObserveTournamentResponse {
+        receiver: MessageReceiver::new(subscription.receiver),
+        last_sequence: subscription.last_sequence,
+        missed_messages: subscription.missed_messages,
+    })
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::application::AuthRole;
+    use crate::domain::Nickname;
+    use crate::domain::OutboxEntry;
+    use crate::domain::PublishTournamentMessages;
+    use crate::domain::TableMessageBroadcast;
+    use crate::domain::Tournament;
+    use crate::domain::TournamentMessage;
+    use crate::domain::TournamentMessageType;
+    use crate::domain::TournamentSpecification;
+
+    use super::*;
+
+    struct DummyRepository {
+        tournament: Tournament,
+    }
+
+    impl LoadTournament for DummyRepository {
+        fn load_tournament(&self, tournament_id: Uuid) -> Result<Tournament, LoadTournamentError> {
+            if self.tournament.id() == tournament_id {
+                Ok(self.tournament.clone())
+            } else {
+                Err(LoadTournamentError::TournamentNotFound)
+            }
+        }
+    }
+
+    fn outbox_entries(messages: Vec<TournamentMessage>) -> Vec<OutboxEntry> {
+        (1..).zip(messages).map(|(position, message)| OutboxEntry { position, message }).collect()
+    }
+
+    #[test]
+    fn observe_tournament_without_being_authenticated() {
+        let spec = TournamentSpecification::new(1, 3).unwrap();
+        let repository = DummyRepository { tournament: Tournament::new(&spec) };
+        let mut broadcast = TableMessageBroadcast::new();
+        let request = ObserveTournamentRequest { tournament_id: repository.tournament.id(), last_seen_sequence: None };
+        let result = observe_tournament(request, &AuthInfo::Unauthenticated, &repository, &mut broadcast);
+        assert!(matches!(result, Err(ObserveTournamentError::AuthError(AuthError::AuthenticationRequired))));
+    }
+
+    #[test]
+    fn observe_unknown_tournament() {
+        let spec = TournamentSpecification::new(1, 3).unwrap();
+        let repository = DummyRepository { tournament: Tournament::new(&spec) };
+        let mut broadcast = TableMessageBroadcast::new();
+        let request = ObserveTournamentRequest { tournament_id: Uuid::new_v4(), last_seen_sequence: None };
+        let auth_info = AuthInfo::Authenticated { account_id: Uuid::new_v4(), role: AuthRole::Member };
+        let result = observe_tournament(request, &auth_info, &repository, &mut broadcast);
+        assert!(matches!(result, Err(ObserveTournamentError::LoadTournamentError(LoadTournamentError::TournamentNotFound))));
+    }
+
+    #[test]
+    fn observe_tournament_resuming_from_sequence() {
+        let spec = TournamentSpecification::new(1, 3).unwrap();
+        let mut tournament = Tournament::new(&spec);
+        let mut broadcast = TableMessageBroadcast::new();
+        _ = tournament.join(Uuid::new_v4(), Nickname::new("James").unwrap());
+        _ = tournament.join(Uuid::new_v4(), Nickname::new("Patricia").unwrap());
+        broadcast.publish_outbox_entries(outbox_entries(tournament.collect_messages()));
+        let repository = DummyRepository { tournament };
+        let request = ObserveTournamentRequest { tournament_id: repository.tournament.id(), last_seen_sequence: Some(1) };
+        let auth_info = AuthInfo::Authenticated { account_id: Uuid::new_v4(), role: AuthRole::Member };
+        let response = observe_tournament(request, &auth_info, &repository, &mut broadcast).unwrap();
+        let missed_messages = response.missed_messages.unwrap();
+        assert_eq!(missed_messages.len(), 1);
+        assert!(matches!(missed_messages[0].message.message_type, TournamentMessageType::RegistrationCountChanged { player_count: 2 }));
+    }
+
+    #[test]
+    fn observe_tournament_with_too_large_gap() {
+        let spec = TournamentSpecification::new(1, 3).unwrap();
+        let mut tournament = Tournament::new(&spec);
+        let mut broadcast = TableMessageBroadcast::new();
+        _ = tournament.join(Uuid::new_v4(), Nickname::new("James").unwrap());
+        broadcast.publish_outbox_entries(outbox_entries(tournament.collect_messages()));
+        let repository = DummyRepository { tournament };
+        let request = ObserveTournamentRequest { tournament_id: repository.tournament.id(), last_seen_sequence: Some(5) };
+        let auth_info = AuthInfo::Authenticated { account_id: Uuid::new_v4(), role: AuthRole::Member };
+        let response = observe_tournament(request, &auth_info, &repository, &mut broadcast).unwrap();
+        assert_eq!(response.missed_messages, None);
+        assert_eq!(response.last_sequence, 1);
+    }
+}
@@ -0,0 +1,171 @@
+use crate::application::AuthError;
+use crate::application::AuthInfo;
+use crate::application::AuthRole;
+
+use crate::domain::AccessAccounts;
+use crate::domain::AccountError;
+use crate::domain::LoadAccountError;
+use crate::domain::SaveAccountError;
+use crate::domain::unix_time;
+
+use thiserror::Error;
+use uuid::Uuid;
+
+
+#[derive(Debug, Error)]
+pub enum PromoteAccountError {
+    #[error(transparent)]
+    AuthError(#[from] AuthError),
+    #[error(transparent)]
+    LoadAccountError(#[from] LoadAccountError),
+    #[error(transparent)]
+    AccountError(#[from] AccountError),
+    #[error(transparent)]
+    SaveAccountError(#[from] SaveAccountError),
+}
+
+
+#[derive(Debug)]
+pub struct PromoteAccountRequest {
+    pub account_id: Uuid,
+}
+
+
+#[derive(Debug)]
+pub struct PromoteAccountResponse {
+    pub role: AuthRole,
+}
+
+
+pub trait PromoteAccount {
+    fn promote_account(&mut self, request: PromoteAccountRequest, auth_info: &AuthInfo) -> Result<PromoteAccountResponse, PromoteAccountError>;
+}
+
+
+// Raises the role by one step. Rights are checked against the current role, so the promoted account
+// gets its new rights at once.
+pub(in crate::application) fn promote_account<Accounts: AccessAccounts>(
+    request: PromoteAccountRequest,
+    auth_info: &AuthInfo,
+    accounts: &mut Accounts,
+) -> Result<PromoteAccountResponse, PromoteAccountError> {
+    let administrator_id = auth_info.with_current_role(accounts)?.ensure_administrator()?;
+    let mut account = accounts.load_account(request.account_id)?;
+    let role = account.promote(Some(administrator_id), unix_time())?;
+    accounts.save_account(account)?;
+    Ok(PromoteAccountResponse { role: role.into() })
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::domain::Account;
+    use crate::domain::AccountRole;
+    use crate::domain::LoadAccount;
+    use crate::domain::LoadRoleChanges;
+    use crate::domain::LoadRoleChangesError;
+    use crate::domain::RoleChange;
+    use crate::domain::SaveAccount;
+    use crate::domain::Username;
+
+    use super::*;
+
+    struct DummyAccounts {
+        account: Account,
+        administrator: Account,
+        role_changes: Vec<RoleChange>,
+    }
+
+    impl DummyAccounts {
+        fn new() -> Self {
+            let account = Account::register(Username::new("daniel").unwrap(), "correct horse").unwrap();
+            let mut administrator = Account::register(Username::new("james").unwrap(), "correct horse").unwrap();
+            administrator.promote(None, unix_time()).unwrap();
+            administrator.promote(None, unix_time()).unwrap();
+            administrator.collect_role_changes();
+            Self { account, administrator, role_changes: vec![] }
+        }
+
+        fn auth_info(account: &Account, role: AuthRole) -> AuthInfo {
+            AuthInfo::Authenticated { account_id: account.id(), role }
+        }
+    }
+
+    impl LoadAccount for DummyAccounts {
+        fn load_account(&self, account_id: Uuid) -> Result<Account, LoadAccountError> {
+            [&self.account, &self.administrator].into_iter()
+                .find(|account| account.id() == account_id)
+                .cloned()
+                .ok_or(LoadAccountError::AccountNotFound)
+        }
+
+        fn load_account_by_username(&self, username: &Username) -> Result<Account, LoadAccountError> {
+            (username == self.account.username()).then(|| self.account.clone()).ok_or(LoadAccountError::AccountNotFound)
+        }
+
+        fn load_accounts_with_role(&self, role: AccountRole) -> Result<Vec<Account>, LoadAccountError> {
+            Ok((self.account.role() == role).then(|| self.account.clone()).into_iter().collect())
+        }
+    }
+
+    impl SaveAccount for DummyAccounts {
+        fn save_account(&mut self, mut account: Account) -> Result<(), SaveAccountError> {
+            self.role_changes.extend(account.collect_role_changes());
+            if account.id() == self.administrator.id() {
+                self.administrator = account;
+            } else {
+                self.account = account;
+            }
+            Ok(())
+        }
+    }
+
+    impl LoadRoleChanges for DummyAccounts {
+        fn load_role_changes(&self, _account_id: Uuid) -> Result<Vec<RoleChange>, LoadRoleChangesError> {
+            Ok(self.role_changes.clone())
+        }
+    }
+
+    #[test]
+    fn promote_account_without_being_administrator() {
+        let mut accounts = DummyAccounts::new();
+        let request = PromoteAccountRequest { account_id: accounts.account.id() };
+        let auth_info = DummyAccounts::auth_info(&accounts.account, AuthRole::Moderator);
+        let result = promote_account(request, &auth_info, &mut accounts);
+        assert!(matches!(result, Err(PromoteAccountError::AuthError(AuthError::PermissionDenied { .. }))));
+        assert_eq!(accounts.account.role(), AccountRole::Member);
+    }
+
+    #[test]
+    fn promote_account_with_outdated_token() {
+        let mut accounts = DummyAccounts::new();
+        let auth_info = DummyAccounts::auth_info(&accounts.administrator, AuthRole::Administrator);
+        accounts.administrator.demote(None, unix_time()).unwrap();
+        let request = PromoteAccountRequest { account_id: accounts.account.id() };
+        let result = promote_account(request, &auth_info, &mut accounts);
+        assert!(matches!(result, Err(PromoteAccountError::AuthError(AuthError::PermissionDenied { found: AuthRole::Moderator, .. }))));
+        assert_eq!(accounts.account.role(), AccountRole::Member);
+    }
+
+    #[test]
+    fn promote_account_with_unknown_account() {
+        let mut accounts = DummyAccounts::new();
+        let request = PromoteAccountRequest { account_id: Uuid::new_v4() };
+        let auth_info = DummyAccounts::auth_info(&accounts.administrator, AuthRole::Administrator);
+        let result = promote_account(request, &auth_info, &mut accounts);
+        assert!(matches!(result, Err(PromoteAccountError::LoadAccountError(LoadAccountError::AccountNotFound))));
+    }
+
+    #[test]
+    fn promote_account_without_any_error() {
+        let mut accounts = DummyAccounts::new();
+        let administrator_id = accounts.administrator.id();
+        let auth_info = DummyAccounts::auth_info(&accounts.administrator, AuthRole::Administrator);
+        let request = PromoteAccountRequest { account_id: accounts.account.id() };
+        let response = promote_account(request, &auth_info, &mut accounts).unwrap();
+        assert!(matches!(response.role, AuthRole::Moderator));
+        assert_eq!(accounts.account.role(), AccountRole::Moderator);
+        assert_eq!(accounts.role_changes.len(), 1);
+        assert_eq!(accounts.role_changes[0].changed_by, Some(administrator_id));
+    }
+}
@@ -0,0 +1,166 @@
+use crate::application::AuthError;
+use crate::application::AuthInfo;
+
+use crate::domain::LoadAccount;
+use crate::domain::LoadAccountError;
+use crate::domain::LoadEvents;
+use crate::domain::LoadEventsError;
+use crate::domain::TournamentProjection;
+
+use thiserror::Error;
+
+
+#[derive(Debug, Error)]
+pub enum RebuildProjectionsError {
+    #[error(transparent)]
+    AuthError(#[from] AuthError),
+    #[error(transparent)]
+    LoadAccountError(#[from] LoadAccountError),
+    #[error(transparent)]
+    LoadEventsError(#[from] LoadEventsError),
+}
+
+
+#[derive(Debug)]
+pub struct RebuildProjectionsRequest {
+}
+
+
+#[derive(Debug)]
+pub struct RebuildProjectionsResponse {
+    pub tournament_count: usize,
+}
+
+
+pub trait RebuildProjections {
+    fn rebuild_projections(&mut self, request: RebuildProjectionsRequest, auth_info: &AuthInfo) -> Result<RebuildProjectionsResponse, RebuildProjectionsError>;
+}
+
+
+// Replays all stored events into fresh read models. The current ones are kept if replaying fails.
+pub(in crate::application) fn rebuild_projections<Repository: LoadEvents, Accounts: LoadAccount>(
+    _request: RebuildProjectionsRequest,
+    auth_info: &AuthInfo,
+    repository: &Repository,
+    projection: &mut TournamentProjection,
+    accounts: &Accounts,
+) -> Result<RebuildProjectionsResponse, RebuildProjectionsError> {
+    auth_info.with_current_role(accounts)?.ensure_administrator()?;
+    *projection = TournamentProjection::rebuild(repository)?;
+    Ok(RebuildProjectionsResponse { tournament_count: projection.tournament_count() })
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::domain::Account;
+    use crate::domain::AccountRole;
+    use crate::domain::Nickname;
+    use crate::domain::TableEvent;
+    use crate::domain::Tournament;
+    use crate::domain::TournamentEvent;
+    use crate::domain::TournamentSpecification;
+    use crate::domain::Username;
+
+    use uuid::Uuid;
+
+    use super::*;
+
+    struct DummyRepository {
+        tournament: Tournament,
+        read_error: Option<LoadEventsError>,
+    }
+
+    impl DummyRepository {
+        fn new() -> Self {
+            let spec = TournamentSpecification::new(1, 3).unwrap();
+            let mut tournament = Tournament::new(&spec);
+            _ = tournament.join(Uuid::new_v4(), Nickname::new("James").unwrap());
+            Self { tournament, read_error: None }
+        }
+    }
+
+    impl LoadEvents for DummyRepository {
+        fn load_tournament_ids(&self) -> Result<Vec<Uuid>, LoadEventsError> {
+            match self.read_error {
+                Some(error) => Err(error),
+                None => Ok(vec![self.tournament.id()]),
+            }
+        }
+
+        fn load_tournament_events(&self, _tournament_id: Uuid, from_version: usize) -> Result<Vec<TournamentEvent>, LoadEventsError> {
+            Ok(self.tournament.new_events().into_iter().skip(from_version).collect())
+        }
+
+        fn load_table_events(&self, _tournament_id: Uuid, _table_number: usize, _from_version: usize) -> Result<Vec<TableEvent>, LoadEventsError> {
+            Ok(vec![])
+        }
+    }
+
+    struct DummyAccounts {
+        roles: Vec<(Uuid, AccountRole)>,
+    }
+
+    impl DummyAccounts {
+        // Registers an account with the role and returns a token for it
+        fn authenticate(&mut self, role: AccountRole) -> AuthInfo {
+            let account_id = Uuid::new_v4();
+            self.roles.push((account_id, role));
+            AuthInfo::Authenticated { account_id, role: role.into() }
+        }
+    }
+
+    impl LoadAccount for DummyAccounts {
+        fn load_account(&self, account_id: Uuid) -> Result<Account, LoadAccountError> {
+            let (_, role) = self.roles.iter().find(|(id, _)| *id == account_id).ok_or(LoadAccountError::AccountNotFound)?;
+            Ok(Account::restore(account_id, Username::new("daniel").unwrap(), String::new(), *role, None, None))
+        }
+
+        fn load_account_by_username(&self, _username: &Username) -> Result<Account, LoadAccountError> {
+            Err(LoadAccountError::AccountNotFound)
+        }
+
+        fn load_accounts_with_role(&self, _role: AccountRole) -> Result<Vec<Account>, LoadAccountError> {
+            Ok(vec![])
+        }
+    }
+
+    #[test]
+    fn rebuild_projections_as_moderator() {
+        let repository = DummyRepository::new();
+        let mut projection = TournamentProjection::new();
+        let mut accounts = DummyAccounts { roles: vec![] };
+        let auth_info = accounts.authenticate(AccountRole::Moderator);
+        let result = rebuild_projections(RebuildProjectionsRequest {}, &auth_info, &repository, &mut projection, &accounts);
+        assert!(matches!(result, Err(RebuildProjectionsError::AuthError(AuthError::PermissionDenied { .. }))));
+        let auth_info = accounts.authenticate(AccountRole::Administrator);
+        accounts.roles[1].1 = AccountRole::Moderator;
+        let result = rebuild_projections(RebuildProjectionsRequest {}, &auth_info, &repository, &mut projection, &accounts);
+        assert!(matches!(result, Err(RebuildProjectionsError::AuthError(AuthError::PermissionDenied { .. }))));
+        assert_eq!(projection.tournament_count(), 0);
+    }
+
+    #[test]
+    fn rebuild_projections_with_repository_error() {
+        let mut repository = DummyRepository::new();
+        let mut projection = TournamentProjection::new();
+        let mut accounts = DummyAccounts { roles: vec![] };
+        let auth_info = accounts.authenticate(AccountRole::Administrator);
+        rebuild_projections(RebuildProjectionsRequest {}, &auth_info, &repository, &mut projection, &accounts).unwrap();
+        repository.read_error = Some(LoadEventsError::DatabaseReadingError);
+        let result = rebuild_projections(RebuildProjectionsRequest {}, &auth_info, &repository, &mut projection, &accounts);
+        assert!(matches!(result, Err(RebuildProjectionsError::LoadEventsError(LoadEventsError::DatabaseReadingError))));
+        assert_eq!(projection.tournament_count(), 1);
+    }
+
+    #[test]
+    fn rebuild_projections_as_administrator() {
+        let repository = DummyRepository::new();
+        let mut projection = TournamentProjection::new();
+        let mut accounts = DummyAccounts { roles: vec![] };
+        let auth_info = accounts.authenticate(AccountRole::Administrator);
+        let response = rebuild_projections(RebuildProjectionsRequest {}, &auth_info, &repository, &mut projection, &accounts).unwrap();
+        assert_eq!(response.tournament_count, 1);
+        assert_eq!(projection.tournament_count(), 1);
+    }
+}
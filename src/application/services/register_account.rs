@@ -0,0 +1,94 @@
+use crate::application::AuthInfo;
+
+use crate::domain::Account;
+use crate::domain::AccountError;
+use crate::domain::HashedPassword;
+use crate::domain::SaveAccount;
+use crate::domain::SaveAccountError;
+use crate::domain::Username;
+
+use thiserror::Error;
+use uuid::Uuid;
+
+
+#[derive(Debug, Error)]
+pub enum RegisterAccountError {
+    #[error(transparent)]
+    AccountError(#[from] AccountError),
+    #[error(transparent)]
+    SaveAccountError(#[from] SaveAccountError),
+}
+
+
+#[derive(Debug)]
+pub struct RegisterAccountRequest {
+    pub username: String,
+    pub password: HashedPassword,     // Hashed beforehand, as hashing is slow on purpose
+}
+
+
+#[derive(Debug)]
+pub struct RegisterAccountResponse {
+    pub account_id: Uuid,
+}
+
+
+pub trait RegisterAccount {
+    fn register_account(&mut self, request: RegisterAccountRequest, auth_info: &AuthInfo) -> Result<RegisterAccountResponse, RegisterAccountError>;
+}
+
+
+// Open to everyone, registering does not log in
+pub(in crate::application) fn register_account<Accounts: SaveAccount>(
+    request: RegisterAccountRequest,
+    _auth_info: &AuthInfo,
+    accounts: &mut Accounts,
+) -> Result<RegisterAccountResponse, RegisterAccountError> {
+    let username = Username::new(request.username)?;
+    let account = Account::register_hashed(username, request.password);
+    let account_id = account.id();
+    accounts.save_account(account)?;
+    Ok(RegisterAccountResponse { account_id })
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct DummyAccounts {
+        accounts: Vec<Account>,
+    }
+
+    impl SaveAccount for DummyAccounts {
+        fn save_account(&mut self, account: Account) -> Result<(), SaveAccountError> {
+            if self.accounts.iter().any(|other| other.username() == account.username()) {
+                return Err(SaveAccountError::UsernameTaken);
+            }
+            self.accounts.push(account);
+            Ok(())
+        }
+    }
+
+    fn request(username: &str, password: &str) -> RegisterAccountRequest {
+        RegisterAccountRequest { username: username.into(), password: HashedPassword::new(password).unwrap() }
+    }
+
+    #[test]
+    fn register_account_with_invalid_parameters() {
+        let mut accounts = DummyAccounts { accounts: vec![] };
+        let result = register_account(request("Daniel Craig", "correct horse"), &AuthInfo::Unauthenticated, &mut accounts);
+        assert!(matches!(result, Err(RegisterAccountError::AccountError(AccountError::UsernameCharactersInvalid))));
+        assert!(accounts.accounts.is_empty());
+    }
+
+    #[test]
+    fn register_account_with_taken_username() {
+        let mut accounts = DummyAccounts { accounts: vec![] };
+        let response = register_account(request("daniel", "correct horse"), &AuthInfo::Unauthenticated, &mut accounts).unwrap();
+        assert_eq!(accounts.accounts[0].id(), response.account_id);
+        let result = register_account(request("Daniel", "battery staple"), &AuthInfo::Unauthenticated, &mut accounts);
+        assert!(matches!(result, Err(RegisterAccountError::SaveAccountError(SaveAccountError::UsernameTaken))));
+        assert_eq!(accounts.accounts.len(), 1);
+    }
+}
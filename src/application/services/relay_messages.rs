@@ -0,0 +1,222 @@
+use crate::domain::AccessEventStreams;
+use crate::domain::AccessOutbox;
+use crate::domain::AccessTables;
+use crate::domain::LoadTournament;
+use crate::domain::PendingEvents;
+use crate::domain::PublishTournamentMessages;
+use crate::domain::RelayOutboxMessagesError;
+use crate::domain::SaveTournament;
+use crate::domain::relay_outbox_messages;
+
+use thiserror::Error;
+
+
+#[derive(Debug, Error)]
+pub enum RelayMessagesError {
+    #[error(transparent)]
+    RelayOutboxMessagesError(#[from] RelayOutboxMessagesError),
+}
+
+
+#[derive(Debug)]
+pub struct RelayMessagesRequest {
+}
+
+
+#[derive(Debug)]
+pub struct RelayMessagesResponse {
+    pub pending_stream_count: usize,
+    pub message_count: usize,
+}
+
+
+// Run by the server itself rather than on behalf of a user, so there is no one to authorize. Events
+// of tournaments left unprocessed by an earlier failure are processed first, so that their messages
+// go out too. Idle channels are swept on every run, whether there are messages or not.
+pub trait RelayMessages {
+    fn relay_messages(&mut self, request: RelayMessagesRequest) -> Result<RelayMessagesResponse, RelayMessagesError>;
+}
+
+
+pub(in crate::application) fn relay_messages<Repository, Publisher>(
+    _request: RelayMessagesRequest,
+    repository: &mut Repository,
+    publisher: &mut Publisher,
+    pending: &mut PendingEvents,
+) -> Result<RelayMessagesResponse, RelayMessagesError>
+where
+    Repository: LoadTournament + SaveTournament + AccessTables + AccessEventStreams + AccessOutbox,
+    Publisher: PublishTournamentMessages,
+{
+    let pending_stream_count = pending.process(repository);
+    let message_count = relay_outbox_messages(repository, publisher)?;
+    publisher.sweep_channels();
+    Ok(RelayMessagesResponse { pending_stream_count, message_count })
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::domain::EventStream;
+    use crate::domain::LoadCheckpoint;
+    use crate::domain::LoadCheckpointError;
+    use crate::domain::LoadEventsError;
+    use crate::domain::LoadEvents;
+    use crate::domain::LoadOutbox;
+    use crate::domain::LoadOutboxError;
+    use crate::domain::LoadTable;
+    use crate::domain::LoadTableError;
+    use crate::domain::LoadTournamentError;
+    use crate::domain::MarkDelivered;
+    use crate::domain::MarkDeliveredError;
+    use crate::domain::OutboxEntry;
+    use crate::domain::SaveCheckpoint;
+    use crate::domain::SaveCheckpointError;
+    use crate::domain::SaveTable;
+    use crate::domain::SaveTableError;
+    use crate::domain::SaveTournamentError;
+    use crate::domain::Table;
+    use crate::domain::TableEvent;
+    use crate::domain::Tournament;
+    use crate::domain::TournamentEvent;
+    use crate::domain::TournamentMessage;
+    use crate::domain::TournamentMessageType;
+
+    use uuid::Uuid;
+
+    use super::*;
+
+    struct DummyRepository {
+        outbox: Vec<OutboxEntry>,
+        mark_error: Option<MarkDeliveredError>,
+    }
+
+    impl DummyRepository {
+        fn new(message_types: Vec<TournamentMessageType>, tournament_id: Uuid) -> Self {
+            let outbox = (1..).zip(message_types).map(|(position, message_type)| OutboxEntry {
+                position,
+                message: TournamentMessage { tournament_id, message_type },
+            }).collect();
+            Self { outbox, mark_error: None }
+        }
+    }
+
+    // Holds no tournaments, so there are never pending events to process
+    impl LoadTournament for DummyRepository {
+        fn load_tournament(&self, _tournament_id: Uuid) -> Result<Tournament, LoadTournamentError> {
+            Err(LoadTournamentError::TournamentNotFound)
+        }
+    }
+
+    impl SaveTournament for DummyRepository {
+        fn save_tournament(&mut self, _tournament: Tournament) -> Result<(), SaveTournamentError> {
+            Err(SaveTournamentError::DatabaseWritingError)
+        }
+    }
+
+    impl LoadTable for DummyRepository {
+        fn load_table(&self, _tournament_id: Uuid, _table_number: usize) -> Result<Table, LoadTableError> {
+            Err(LoadTableError::TableNotFound)
+        }
+    }
+
+    impl SaveTable for DummyRepository {
+        fn save_table(&mut self, _table: Table) -> Result<(), SaveTableError> {
+            Err(SaveTableError::DatabaseWritingError)
+        }
+    }
+
+    impl LoadEvents for DummyRepository {
+        fn load_tournament_ids(&self) -> Result<Vec<Uuid>, LoadEventsError> {
+            Ok(vec![])
+        }
+
+        fn load_tournament_events(&self, _tournament_id: Uuid, _from_version: usize) -> Result<Vec<TournamentEvent>, LoadEventsError> {
+            Ok(vec![])
+        }
+
+        fn load_table_events(&self, _tournament_id: Uuid, _table_number: usize, _from_version: usize) -> Result<Vec<TableEvent>, LoadEventsError> {
+            Ok(vec![])
+        }
+    }
+
+    impl LoadCheckpoint for DummyRepository {
+        fn load_checkpoint(&self, _stream: EventStream) -> Result<usize, LoadCheckpointError> {
+            Ok(0)
+        }
+    }
+
+    impl SaveCheckpoint for DummyRepository {
+        fn save_checkpoint(&mut self, _stream: EventStream, _checkpoint: usize) -> Result<(), SaveCheckpointError> {
+            Ok(())
+        }
+    }
+
+    impl LoadOutbox for DummyRepository {
+        fn load_pending_messages(&self) -> Result<Vec<OutboxEntry>, LoadOutboxError> {
+            Ok(self.outbox.clone())
+        }
+    }
+
+    impl MarkDelivered for DummyRepository {
+        fn mark_delivered(&mut self, position: u64) -> Result<(), MarkDeliveredError> {
+            if let Some(error) = self.mark_error {
+                return Err(error);
+            }
+            self.outbox.retain(|entry| entry.position > position);
+            Ok(())
+        }
+    }
+
+
+    #[derive(Default)]
+    struct DummyPublisher {
+        positions: Vec<u64>,
+        closed_tournament_ids: Vec<Uuid>,
+        sweep_count: usize,
+    }
+
+    impl PublishTournamentMessages for DummyPublisher {
+        fn close_tournament_channels(&mut self, tournament_id: Uuid) {
+            self.closed_tournament_ids.push(tournament_id);
+        }
+
+        fn publish_outbox_entries(&mut self, entries: Vec<OutboxEntry>) {
+            self.positions.extend(entries.iter().map(|entry| entry.position));
+        }
+
+        fn sweep_channels(&mut self) {
+            self.sweep_count += 1;
+        }
+    }
+
+
+    #[test]
+    fn relay_messages_without_any_error() {
+        let tournament_id = Uuid::new_v4();
+        let mut repository = DummyRepository::new(vec![TournamentMessageType::TournamentStarted, TournamentMessageType::TournamentFinished], tournament_id);
+        let mut publisher = DummyPublisher::default();
+        let response = relay_messages(RelayMessagesRequest {}, &mut repository, &mut publisher, &mut PendingEvents::new()).unwrap();
+        assert_eq!(response.message_count, 2);
+        assert_eq!(publisher.positions, vec![1, 2]);
+        assert_eq!(publisher.closed_tournament_ids, vec![tournament_id]);
+        assert_eq!(repository.outbox, vec![]);
+        let response = relay_messages(RelayMessagesRequest {}, &mut repository, &mut publisher, &mut PendingEvents::new()).unwrap();
+        assert_eq!(response.message_count, 0);
+        assert_eq!(publisher.sweep_count, 2);
+    }
+
+    #[test]
+    fn relay_messages_with_repository_error_on_mark_delivered() {
+        let mut repository = DummyRepository::new(vec![TournamentMessageType::TournamentStarted], Uuid::new_v4());
+        repository.mark_error = Some(MarkDeliveredError::DatabaseWritingError);
+        let mut publisher = DummyPublisher::default();
+        let result = relay_messages(RelayMessagesRequest {}, &mut repository, &mut publisher, &mut PendingEvents::new());
+        assert!(matches!(result, Err(RelayMessagesError::RelayOutboxMessagesError(RelayOutboxMessagesError::MarkDeliveredError(_)))));
+        assert_eq!(repository.outbox.len(), 1);
+        repository.mark_error = None;
+        relay_messages(RelayMessagesRequest {}, &mut repository, &mut publisher, &mut PendingEvents::new()).unwrap();
+        assert_eq!(publisher.positions, vec![1, 1]);
+        assert_eq!(repository.outbox, vec![]);
+    }
+}
@@ -0,0 +1,382 @@
+use super::nickname::Nickname;
+
+use argon2::Argon2;
+use argon2::password_hash::PasswordHash;
+use argon2::password_hash::PasswordHasher;
+use argon2::password_hash::PasswordVerifier;
+use argon2::password_hash::SaltString;
+use thiserror::Error;
+use uuid::Uuid;
+
+use std::fmt::Display;
+use std::sync::LazyLock;
+use std::time::Duration;
+
+
+const USERNAME_LENGTH: std::ops::RangeInclusive<usize> = 3..=32;
+const PASSWORD_LENGTH: std::ops::RangeInclusive<usize> = 8..=128;
+const NICKNAME_COOLDOWN: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+
+
+#[derive(Debug, Error)]
+pub enum AccountError {
+    #[error("Usernames must have between 3 and 32 characters")]
+    UsernameLengthInvalid,
+    #[error("Usernames may only contain ASCII letters, digits, '-' and '_'")]
+    UsernameCharactersInvalid,
+    #[error("Passwords must have at least 8 characters")]
+    PasswordTooShort,
+    #[error("Passwords must not have more than 128 characters")]
+    PasswordTooLong,
+    #[error("Wrong username or password")]
+    InvalidCredentials,
+    #[error("Account already has the highest role")]
+    NoHigherRole,
+    #[error("Account already has the lowest role")]
+    NoLowerRole,
+    #[error("Accounts cannot change their own role")]
+    OwnRoleChange,
+    #[error("Account has no registered nickname")]
+    NicknameNotRegistered,
+    #[error("Nickname differs from the registered one")]
+    NicknameMismatch,
+    #[error("Nickname can be changed again in {retry_after_secs} seconds")]
+    NicknameChangeTooSoon { retry_after_secs: u64 },
+}
+
+
+// Login name of an account, stored in lowercase so that it is unique regardless of case
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Username {
+    value: String,
+}
+
+impl Username {
+    pub fn new(value: impl Into<String>) -> Result<Self, AccountError> {
+        let value = value.into().to_ascii_lowercase();
+        if !USERNAME_LENGTH.contains(&value.len()) {
+            Err(AccountError::UsernameLengthInvalid)
+        } else if !value.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+            Err(AccountError::UsernameCharactersInvalid)
+        } else {
+            Ok(Self { value })
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.value
+    }
+}
+
+impl Display for Username {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.value)
+    }
+}
+
+
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+pub enum AccountRole {
+    Member,
+    Moderator,
+    Administrator,
+}
+
+impl AccountRole {
+    fn higher(self) -> Option<Self> {
+        match self {
+            Self::Member => Some(Self::Moderator),
+            Self::Moderator => Some(Self::Administrator),
+            Self::Administrator => None,
+        }
+    }
+
+    fn lower(self) -> Option<Self> {
+        match self {
+            Self::Member => None,
+            Self::Moderator => Some(Self::Member),
+            Self::Administrator => Some(Self::Moderator),
+        }
+    }
+}
+
+
+// Argon2 hash of a password with its salt and parameters in PHC string format. Hashing and
+// verifying are slow on purpose, so both are done without holding on to the accounts.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HashedPassword {
+    value: String,
+}
+
+impl HashedPassword {
+    pub fn new(password: &str) -> Result<Self, AccountError> {
+        Ok(Self { value: hash_password(password)? })
+    }
+
+    // Stands in for the hash of unknown accounts, so that logging in to them takes as long as to
+    // existing ones and does not reveal which accounts exist
+    pub fn dummy() -> Self {
+        static DUMMY: LazyLock<HashedPassword> = LazyLock::new(|| HashedPassword::new("dummy password").unwrap());
+        DUMMY.clone()
+    }
+
+    // A stored hash that cannot be parsed never matches
+    pub fn verify(&self, password: &str) -> Result<(), AccountError> {
+        let hash = PasswordHash::new(&self.value).map_err(|_| AccountError::InvalidCredentials)?;
+        Argon2::default().verify_password(password.as_bytes(), &hash).map_err(|_| AccountError::InvalidCredentials)
+    }
+}
+
+
+// Audit record of a role change, changed_by is None for changes made by the server itself
+#[derive(Debug, Clone, PartialEq)]
+pub struct RoleChange {
+    pub account_id: Uuid,
+    pub changed_by: Option<Uuid>,
+    pub old_role: AccountRole,
+    pub new_role: AccountRole,
+    pub changed_at: u64,        // Seconds since the Unix epoch
+}
+
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Account {
+    id: Uuid,
+    username: Username,
+    password_hash: String,      // Argon2 hash with its salt and parameters in PHC string format
+    role: AccountRole,
+    role_changes: Vec<RoleChange>,      // Not saved yet
+    nickname: Option<Nickname>,         // Unique regardless of case, registered on the first join at the latest
+    nickname_changed_at: Option<u64>,   // Seconds since the Unix epoch
+}
+
+impl Account {
+    pub fn register(username: Username, password: &str) -> Result<Self, AccountError> {
+        Ok(Self::register_hashed(username, HashedPassword::new(password)?))
+    }
+
+    pub fn register_hashed(username: Username, password: HashedPassword) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            username,
+            password_hash: password.value,
+            role: AccountRole::Member,
+            role_changes: vec![],
+            nickname: None,
+            nickname_changed_at: None,
+        }
+    }
+
+    pub fn restore(
+        id: Uuid,
+        username: Username,
+        password_hash: String,
+        role: AccountRole,
+        nickname: Option<Nickname>,
+        nickname_changed_at: Option<u64>,
+    ) -> Self {
+        Self { id, username, password_hash, role, role_changes: vec![], nickname, nickname_changed_at }
+    }
+
+    pub fn id(&self) -> Uuid {
+        self.id
+    }
+
+    pub fn username(&self) -> &Username {
+        &self.username
+    }
+
+    pub fn password_hash(&self) -> &str {
+        &self.password_hash
+    }
+
+    pub fn role(&self) -> AccountRole {
+        self.role
+    }
+
+    pub fn nickname(&self) -> Option<&Nickname> {
+        self.nickname.as_ref()
+    }
+
+    pub fn nickname_changed_at(&self) -> Option<u64> {
+        self.nickname_changed_at
+    }
+
+    pub fn hashed_password(&self) -> HashedPassword {
+        HashedPassword { value: self.password_hash.clone() }
+    }
+
+    pub fn verify_password(&self, password: &str) -> Result<(), AccountError> {
+        self.hashed_password().verify(password)
+    }
+
+    // Takes the hash the current password was verified against, which fails if the password was
+    // changed since
+    pub fn change_password(&mut self, verified: &HashedPassword, new_password: HashedPassword) -> Result<(), AccountError> {
+        if verified.value != self.password_hash {
+            return Err(AccountError::InvalidCredentials);
+        }
+        self.password_hash = new_password.value;
+        Ok(())
+    }
+
+    pub fn promote(&mut self, changed_by: Option<Uuid>, now: u64) -> Result<AccountRole, AccountError> {
+        let role = self.role.higher().ok_or(AccountError::NoHigherRole)?;
+        self.change_role(role, changed_by, now)?;
+        Ok(role)
+    }
+
+    pub fn demote(&mut self, changed_by: Option<Uuid>, now: u64) -> Result<AccountRole, AccountError> {
+        let role = self.role.lower().ok_or(AccountError::NoLowerRole)?;
+        self.change_role(role, changed_by, now)?;
+        Ok(role)
+    }
+
+    // Registering the first nickname is not subject to the cooldown
+    pub fn change_nickname(&mut self, nickname: Nickname, now: u64) -> Result<(), AccountError> {
+        if self.nickname.as_ref() == Some(&nickname) {
+            return Ok(());
+        }
+        if let Some(changed_at) = self.nickname_changed_at {
+            let allowed_at = changed_at + NICKNAME_COOLDOWN.as_secs();
+            if now < allowed_at {
+                return Err(AccountError::NicknameChangeTooSoon { retry_after_secs: allowed_at - now });
+            }
+        }
+        self.nickname = Some(nickname);
+        self.nickname_changed_at = Some(now);
+        Ok(())
+    }
+
+    // Joins use the registered nickname, so accounts register one before their first join. A
+    // nickname given along with a join only has to match it.
+    pub fn nickname_for_join(&self, nickname: Option<&Nickname>) -> Result<Nickname, AccountError> {
+        let registered = self.nickname.as_ref().ok_or(AccountError::NicknameNotRegistered)?;
+        if nickname.is_some_and(|nickname| nickname.key() != registered.key()) {
+            return Err(AccountError::NicknameMismatch);
+        }
+        Ok(registered.clone())
+    }
+
+    // Role changes to be saved to the audit log along with the account
+    pub fn collect_role_changes(&mut self) -> Vec<RoleChange> {
+        std::mem::take(&mut self.role_changes)
+    }
+
+    fn change_role(&mut self, role: AccountRole, changed_by: Option<Uuid>, now: u64) -> Result<(), AccountError> {
+        if changed_by == Some(self.id) {
+            return Err(AccountError::OwnRoleChange);
+        }
+        self.role_changes.push(RoleChange { account_id: self.id, changed_by, old_role: self.role, new_role: role, changed_at: now });
+        self.role = role;
+        Ok(())
+    }
+}
+
+
+fn hash_password(password: &str) -> Result<String, AccountError> {
+    let length = password.chars().count();
+    if length < *PASSWORD_LENGTH.start() {
+        return Err(AccountError::PasswordTooShort);
+    }
+    if length > *PASSWORD_LENGTH.end() {
+        return Err(AccountError::PasswordTooLong);
+    }
+    let salt = SaltString::encode_b64(Uuid::new_v4().as_bytes()).expect("16 bytes are a valid salt");
+    let hash = Argon2::default().hash_password(password.as_bytes(), &salt).expect("default parameters are valid");
+    Ok(hash.to_string())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: u64 = 1_700_000_000;
+
+    #[test]
+    fn usernames_are_validated_and_lowercased() {
+        assert_eq!(Username::new("Daniel_42").unwrap().as_str(), "daniel_42");
+        assert!(matches!(Username::new("Jo"), Err(AccountError::UsernameLengthInvalid)));
+        assert!(matches!(Username::new("a".repeat(33)), Err(AccountError::UsernameLengthInvalid)));
+        assert!(matches!(Username::new("James Bond"), Err(AccountError::UsernameCharactersInvalid)));
+        assert!(matches!(Username::new("Jürgen"), Err(AccountError::UsernameCharactersInvalid)));
+    }
+
+    #[test]
+    fn passwords_are_salted_and_verified() {
+        let account = Account::register(Username::new("daniel").unwrap(), "correct horse").unwrap();
+        let other = Account::register(Username::new("james").unwrap(), "correct horse").unwrap();
+        assert_ne!(account.password_hash(), other.password_hash());
+        assert!(!account.password_hash().contains("correct horse"));
+        assert!(account.verify_password("correct horse").is_ok());
+        assert!(matches!(account.verify_password("wrong horse"), Err(AccountError::InvalidCredentials)));
+        assert_eq!(account.role(), AccountRole::Member);
+    }
+
+    #[test]
+    fn passwords_must_have_valid_length() {
+        let username = Username::new("daniel").unwrap();
+        assert!(matches!(Account::register(username.clone(), "short"), Err(AccountError::PasswordTooShort)));
+        assert!(matches!(Account::register(username, &"x".repeat(129)), Err(AccountError::PasswordTooLong)));
+    }
+
+    #[test]
+    fn change_password_requires_the_current_hash() {
+        let mut account = Account::register(Username::new("daniel").unwrap(), "correct horse").unwrap();
+        let verified = account.hashed_password();
+        let other = HashedPassword::new("correct horse").unwrap();
+        assert!(matches!(account.change_password(&other, HashedPassword::new("battery staple").unwrap()), Err(AccountError::InvalidCredentials)));
+        account.change_password(&verified, HashedPassword::new("battery staple").unwrap()).unwrap();
+        assert!(account.verify_password("battery staple").is_ok());
+        assert!(account.verify_password("correct horse").is_err());
+        assert!(matches!(account.change_password(&verified, HashedPassword::new("horse battery").unwrap()), Err(AccountError::InvalidCredentials)));
+    }
+
+    #[test]
+    fn dummy_password_matches_nothing() {
+        assert!(matches!(HashedPassword::dummy().verify("dummy"), Err(AccountError::InvalidCredentials)));
+        assert!(matches!(HashedPassword::dummy().verify(""), Err(AccountError::InvalidCredentials)));
+    }
+
+    #[test]
+    fn role_changes_are_recorded() {
+        let administrator_id = Uuid::new_v4();
+        let mut account = Account::register(Username::new("daniel").unwrap(), "correct horse").unwrap();
+        assert!(matches!(account.demote(Some(administrator_id), NOW), Err(AccountError::NoLowerRole)));
+        assert_eq!(account.promote(None, NOW).unwrap(), AccountRole::Moderator);
+        assert_eq!(account.promote(Some(administrator_id), NOW).unwrap(), AccountRole::Administrator);
+        assert!(matches!(account.promote(Some(administrator_id), NOW), Err(AccountError::NoHigherRole)));
+        assert!(matches!(account.demote(Some(account.id()), NOW), Err(AccountError::OwnRoleChange)));
+        assert_eq!(account.role(), AccountRole::Administrator);
+        let changes = account.collect_role_changes();
+        assert_eq!(changes.len(), 2);
+        assert_eq!((changes[0].changed_by, changes[0].old_role, changes[0].new_role), (None, AccountRole::Member, AccountRole::Moderator));
+        assert_eq!(changes[1].changed_by, Some(administrator_id));
+        assert_eq!(changes[1].new_role, AccountRole::Administrator);
+        assert_eq!(account.collect_role_changes(), vec![]);
+    }
+
+    #[test]
+    fn nickname_changes_have_a_cooldown() {
+        let mut account = Account::register(Username::new("daniel").unwrap(), "correct horse").unwrap();
+        account.change_nickname(Nickname::new("Daniel").unwrap(), NOW).unwrap();
+        account.change_nickname(Nickname::new("Daniel").unwrap(), NOW).unwrap();
+        let result = account.change_nickname(Nickname::new("denyo").unwrap(), NOW + 1);
+        assert!(matches!(result, Err(AccountError::NicknameChangeTooSoon { retry_after_secs }) if retry_after_secs == NICKNAME_COOLDOWN.as_secs() - 1));
+        account.change_nickname(Nickname::new("denyo").unwrap(), NOW + NICKNAME_COOLDOWN.as_secs()).unwrap();
+        assert_eq!(account.nickname(), Some(&Nickname::new("denyo").unwrap()));
+    }
+
+    #[test]
+    fn joins_use_the_registered_nickname() {
+        let mut account = Account::register(Username::new("daniel").unwrap(), "correct horse").unwrap();
+        assert!(matches!(account.nickname_for_join(None), Err(AccountError::NicknameNotRegistered)));
+        assert!(matches!(account.nickname_for_join(Some(&Nickname::new("Daniel").unwrap())), Err(AccountError::NicknameNotRegistered)));
+        assert_eq!(account.nickname(), None);
+        account.change_nickname(Nickname::new("Daniel").unwrap(), NOW).unwrap();
+        assert_eq!(account.nickname_for_join(None).unwrap(), Nickname::new("Daniel").unwrap());
+        assert_eq!(account.nickname_for_join(Some(&Nickname::new("DANIEL").unwrap())).unwrap(), Nickname::new("Daniel").unwrap());
+        assert!(matches!(account.nickname_for_join(Some(&Nickname::new("James").unwrap())), Err(AccountError::NicknameMismatch)));
+    }
+}
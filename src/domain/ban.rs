@@ -0,0 +1,153 @@
+use super::account::Account;
+use super::account::AccountRole;
+
+use thiserror::Error;
+use uuid::Uuid;
+
+use std::time::Duration;
+
+
+#[derive(Debug, Error)]
+pub enum BanError {
+    #[error("Account is banned")]
+    AccountBanned,
+    #[error("Account is excluded from this tournament")]
+    AccountExcluded,
+    #[error("Only member accounts can be banned")]
+    AccountProtected,
+    #[error("Ban is no longer in force")]
+    BanNotInForce,
+}
+
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BanScope {
+    Platform,
+    Tournament {
+        tournament_id: Uuid,
+    },
+}
+
+
+// Kept after it expires or is lifted, as a record of who banned whom and why
+#[derive(Debug, Clone, PartialEq)]
+pub struct Ban {
+    pub id: Uuid,
+    pub account_id: Uuid,
+    pub scope: BanScope,
+    pub banned_by: Uuid,
+    pub reason: String,
+    pub banned_at: u64,             // Seconds since the Unix epoch
+    pub expires_at: Option<u64>,    // None for bans without time limit
+    pub lifted_by: Option<Uuid>,
+}
+
+impl Ban {
+    // Moderators and administrators are not banned but demoted first
+    pub fn issue(account: &Account, scope: BanScope, banned_by: Uuid, reason: String, duration: Option<Duration>, now: u64) -> Result<Self, BanError> {
+        if account.role() != AccountRole::Member {
+            return Err(BanError::AccountProtected);
+        }
+        let banned_at = now;
+        Ok(Self {
+            id: Uuid::new_v4(),
+            account_id: account.id(),
+            scope,
+            banned_by,
+            reason,
+            banned_at,
+            expires_at: duration.map(|duration| banned_at.saturating_add(duration.as_secs())),
+            lifted_by: None,
+        })
+    }
+
+    pub fn is_in_force(&self, at: u64) -> bool {
+        self.expires_at.is_none_or(|expires_at| at < expires_at)
+    }
+
+    // Ends the ban now
+    pub fn lift(&mut self, lifted_by: Uuid, now: u64) -> Result<(), BanError> {
+        if !self.is_in_force(now) {
+            return Err(BanError::BanNotInForce);
+        }
+        self.expires_at = Some(now);
+        self.lifted_by = Some(lifted_by);
+        Ok(())
+    }
+}
+
+
+// Checks the bans of an account against the platform and, if given, a tournament
+pub fn ensure_not_banned(bans: &[Ban], tournament_id: Option<Uuid>, now: u64) -> Result<(), BanError> {
+    for ban in bans.iter().filter(|ban| ban.is_in_force(now)) {
+        match ban.scope {
+            BanScope::Platform => return Err(BanError::AccountBanned),
+            BanScope::Tournament { tournament_id: id } if Some(id) == tournament_id => return Err(BanError::AccountExcluded),
+            BanScope::Tournament { .. } => {},
+        }
+    }
+    Ok(())
+}
+
+
+// Tells whether a tournament excludes anyone at the moment
+pub fn has_exclusions_in_force(bans: &[Ban], now: u64) -> bool {
+    bans.iter().any(|ban| ban.is_in_force(now) && matches!(ban.scope, BanScope::Tournament { .. }))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::super::account::Username;
+
+    use super::*;
+
+    const NOW: u64 = 1_700_000_000;
+
+    fn member() -> Account {
+        Account::register(Username::new("daniel").unwrap(), "correct horse").unwrap()
+    }
+
+    #[test]
+    fn only_members_are_banned() {
+        let mut account = member();
+        assert!(Ban::issue(&account, BanScope::Platform, Uuid::new_v4(), "spam".into(), None, NOW).is_ok());
+        account.promote(None, NOW).unwrap();
+        assert!(matches!(Ban::issue(&account, BanScope::Platform, Uuid::new_v4(), "spam".into(), None, NOW), Err(BanError::AccountProtected)));
+    }
+
+    #[test]
+    fn bans_cover_their_scope_until_they_end() {
+        let account = member();
+        let tournament_id = Uuid::new_v4();
+        let exclusion = Ban::issue(&account, BanScope::Tournament { tournament_id }, Uuid::new_v4(), "collusion".into(), None, NOW).unwrap();
+        let bans = vec![exclusion.clone()];
+        assert!(ensure_not_banned(&bans, None, NOW).is_ok());
+        assert!(ensure_not_banned(&bans, Some(Uuid::new_v4()), NOW).is_ok());
+        assert!(matches!(ensure_not_banned(&bans, Some(tournament_id), NOW), Err(BanError::AccountExcluded)));
+        let mut ban = Ban::issue(&account, BanScope::Platform, Uuid::new_v4(), "spam".into(), Some(Duration::from_secs(60)), NOW).unwrap();
+        assert!(ban.is_in_force(ban.banned_at + 59));
+        assert!(!ban.is_in_force(ban.banned_at + 60));
+        assert!(matches!(ensure_not_banned(&[exclusion, ban.clone()], None, NOW), Err(BanError::AccountBanned)));
+        let moderator_id = Uuid::new_v4();
+        ban.lift(moderator_id, NOW).unwrap();
+        assert_eq!(ban.lifted_by, Some(moderator_id));
+        assert!(ensure_not_banned(&[ban.clone()], None, NOW).is_ok());
+        assert!(matches!(ban.lift(moderator_id, NOW), Err(BanError::BanNotInForce)));
+    }
+
+    #[test]
+    fn bans_expire_after_their_duration() {
+        let account = member();
+        let tournament_id = Uuid::new_v4();
+        let duration = Some(Duration::from_secs(60));
+        let ban = Ban::issue(&account, BanScope::Platform, Uuid::new_v4(), "spam".into(), duration, NOW).unwrap();
+        let exclusion = Ban::issue(&account, BanScope::Tournament { tournament_id }, Uuid::new_v4(), "collusion".into(), duration, NOW + 30).unwrap();
+        let bans = vec![ban, exclusion];
+        assert!(matches!(ensure_not_banned(&bans, None, NOW + 59), Err(BanError::AccountBanned)));
+        assert!(ensure_not_banned(&bans, None, NOW + 60).is_ok());
+        assert!(matches!(ensure_not_banned(&bans, Some(tournament_id), NOW + 60), Err(BanError::AccountExcluded)));
+        assert!(has_exclusions_in_force(&bans, NOW + 89));
+        assert!(!has_exclusions_in_force(&bans, NOW + 90));
+    }
+}
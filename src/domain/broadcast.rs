@@ -1,63 +1,669 @@
 use std::collections::HashMap;
+use std::collections::VecDeque;
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::time::Duration;
+use std::time::Instant;
 
+use log::debug;
 use log::info;
 use uuid::Uuid;
 
+use crate::domain::MonitorTableMessageBroadcast;
+use crate::domain::OutboxEntry;
 use crate::domain::PublishTournamentMessages;
 use crate::domain::SubscribeTableMessages;
+use crate::domain::SubscribeTournamentMessages;
 use crate::domain::TournamentMessage;
 use crate::domain::TournamentMessageType;
 
 
 use crate::domain::TableMessage;
+use crate::domain::TableState;
 
 use tokio::sync::broadcast::Receiver;
 use tokio::sync::broadcast::Sender;
+use tokio::sync::broadcast::error::RecvError;
 
-pub type TableMessageReceiver = Receiver<TableMessage>;
-pub type TableMessageSender = Sender<TableMessage>;
+pub type TableMessageReceiver = MessageReceiver<TableMessage>;
+pub type TournamentMessageReceiver = MessageReceiver<TournamentMessage>;
 
 
+const CHANNEL_CAPACITY: usize = 16;
+const LOG_CAPACITY: usize = 64;
+const IDLE_TIMEOUT: Duration = Duration::from_secs(60);
+const EVICTED_TIMEOUT: Duration = Duration::from_secs(60 * 60);
+
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct SequencedMessage<Message> {
+    pub sequence: u64,
+    pub message: Message,
+    #[serde(skip)]
+    pub published_at: Instant,
+}
+
+pub type SequencedTableMessage = SequencedMessage<TableMessage>;
+pub type SequencedTournamentMessage = SequencedMessage<TournamentMessage>;
+
+
+#[derive(Debug)]
+pub struct Subscription<Message> {
+    pub receiver: Receiver<SequencedMessage<Message>>,
+    pub last_sequence: u64,                                       // Sequence of the last published message, 0 if none
+    pub missed_messages: Option<Vec<SequencedMessage<Message>>>,  // None if the gap cannot be replayed from the log
+}
+
+pub type TableSubscription = Subscription<TableMessage>;
+pub type TournamentSubscription = Subscription<TournamentMessage>;
+
+
+// Hands out the messages of a subscription, each one not before the delay has passed since it was
+// published. Messages arriving in the meantime are held back, so that the subscription does not lag.
+#[derive(Debug)]
+pub struct MessageReceiver<Message> {
+    receiver: Receiver<SequencedMessage<Message>>,
+    held_back: VecDeque<SequencedMessage<Message>>,
+    delay: Duration,
+    error: Option<RecvError>,       // Returned once the held back messages are handed out
+}
+
+impl<Message: Clone> MessageReceiver<Message> {
+    pub fn new(receiver: Receiver<SequencedMessage<Message>>) -> Self {
+        Self { receiver, held_back: VecDeque::new(), delay: Duration::ZERO, error: None }
+    }
+
+    // Missed messages still within the delay are held back as well, only the others are returned
+    pub fn delayed(
+        receiver: Receiver<SequencedMessage<Message>>,
+        missed_messages: Option<Vec<SequencedMessage<Message>>>,
+        delay: Duration,
+        now: Instant,
+    ) -> (Self, Option<Vec<SequencedMessage<Message>>>) {
+        let mut held_back = VecDeque::new();
+        let missed_messages = missed_messages.map(|messages| {
+            let (due, not_due): (Vec<_>, Vec<_>) = messages.into_iter().partition(|message| message.published_at + delay <= now);
+            held_back.extend(not_due);
+            due
+        });
+        (Self { receiver, held_back, delay, error: None }, missed_messages)
+    }
+
+    pub async fn recv(&mut self) -> Result<SequencedMessage<Message>, RecvError> {
+        loop {
+            let due = self.held_back.front().map(|message| message.published_at + self.delay);
+            match (due, self.error.clone()) {
+                (Some(due), _) if due <= Instant::now() => return Ok(self.held_back.pop_front().unwrap()),
+                (Some(due), Some(_)) => tokio::time::sleep_until(due.into()).await,
+                (Some(due), None) => if let Ok(result) = tokio::time::timeout_at(due.into(), self.receiver.recv()).await {
+                    self.receive(result);
+                },
+                (None, Some(error)) => return Err(error),
+                (None, None) => {
+                    let result = self.receiver.recv().await;
+                    self.receive(result);
+                },
+            }
+        }
+    }
+
+    fn receive(&mut self, result: Result<SequencedMessage<Message>, RecvError>) {
+        match result {
+            Ok(message) => self.held_back.push_back(message),
+            Err(error) => self.error = Some(error),
+        }
+    }
+}
+
+
+// State built from the messages of a channel, so that observers who are shown the messages with a
+// delay can start from the state as it was back then
+trait ChannelState<Message>: Clone {
+    fn apply(&mut self, message: &Message);
+}
+
+impl<Message> ChannelState<Message> for () {
+    fn apply(&mut self, _message: &Message) {}
+}
+
+impl ChannelState<TableMessage> for TableState {
+    fn apply(&mut self, message: &TableMessage) {
+        TableState::apply(self, message);
+    }
+}
+
+
+#[derive(Debug, Clone)]
+struct Checkpoint<State> {
+    sequence: u64,              // Sequence of the last message reflected in the state
+    current_since: Instant,     // When that message was published
+    state: State,
+}
+
+impl<State> Checkpoint<State> {
+    fn apply<Message>(&mut self, message: &SequencedMessage<Message>)
+    where
+        State: ChannelState<Message>
+    {
+        if message.sequence > self.sequence {
+            self.state.apply(&message.message);
+            self.sequence = message.sequence;
+            self.current_since = message.published_at;
+        }
+    }
+}
+
+
+// What is kept of an evicted channel, so that a recreated one continues where it left off
+#[derive(Debug)]
+struct EvictedChannel<State> {
+    next_sequence: u64,
+    checkpoint: Option<Checkpoint<State>>,
+    evicted_at: Instant,
+}
+
+
+#[derive(Debug)]
+struct Channel<Message, State> {
+    sender: Sender<SequencedMessage<Message>>,
+    log: VecDeque<SequencedMessage<Message>>,
+    next_sequence: u64,
+    idle_since: Option<Instant>,
+    checkpoint: Option<Checkpoint<State>>,      // Follows the messages dropped from the log
+}
+
+impl<Message: Clone, State: ChannelState<Message>> Channel<Message, State> {
+    fn new(evicted: Option<EvictedChannel<State>>, now: Instant) -> Self {
+        let (next_sequence, checkpoint) = evicted.map_or((1, None), |evicted| (evicted.next_sequence, evicted.checkpoint));
+        Self { sender: Sender::new(CHANNEL_CAPACITY), log: VecDeque::new(), next_sequence, idle_since: Some(now), checkpoint }
+    }
+
+    fn subscriber_count(&self) -> usize {
+        self.sender.receiver_count()
+    }
+
+    fn is_expired(&mut self, now: Instant) -> bool {
+        if self.subscriber_count() > 0 {
+            self.idle_since = None;
+            false
+        } else {
+            let idle_since = *self.idle_since.get_or_insert(now);
+            now.duration_since(idle_since) >= IDLE_TIMEOUT
+        }
+    }
+
+    fn last_sequence(&self) -> u64 {
+        self.next_sequence - 1
+    }
+
+    fn publish(&mut self, message: Message, now: Instant) {
+        let message = SequencedMessage { sequence: self.next_sequence, message, published_at: now };
+        self.next_sequence += 1;
+        if self.log.len() == LOG_CAPACITY {
+            let dropped = self.log.pop_front().unwrap();
+            if let Some(checkpoint) = &mut self.checkpoint {
+                checkpoint.apply(&dropped);
+            }
+        }
+        self.log.push_back(message.clone());
+        _ = self.sender.send(message);
+    }
+
+    fn subscribe(&self, last_seen_sequence: Option<u64>) -> Subscription<Message> {
+        Subscription {
+            receiver: self.sender.subscribe(),
+            last_sequence: self.last_sequence(),
+            missed_messages: last_seen_sequence.and_then(|sequence| self.missed_messages(sequence)),
+        }
+    }
+
+    fn missed_messages(&self, last_seen_sequence: u64) -> Option<Vec<SequencedMessage<Message>>> {
+        let first_logged_sequence = self.next_sequence - self.log.len() as u64;
+        if last_seen_sequence > self.last_sequence() || last_seen_sequence + 1 < first_logged_sequence {
+            None
+        } else {
+            Some(self.log.iter().filter(|message| message.sequence > last_seen_sequence).cloned().collect())
+        }
+    }
+
+    // The current state reflects the last published message. It is only taken while there is no
+    // checkpoint yet, later ones follow from the messages.
+    fn keep_state(&mut self, state: &State, now: Instant) {
+        if self.checkpoint.is_none() {
+            let current_since = self.log.back().map_or(now, |message| message.published_at);
+            self.checkpoint = Some(Checkpoint { sequence: self.last_sequence(), current_since, state: state.clone() });
+        }
+    }
+
+    fn state_at(&self, at: Instant) -> Option<(u64, State)> {
+        let mut checkpoint = self.checkpoint.clone().filter(|checkpoint| checkpoint.current_since <= at)?;
+        for message in self.log.iter().take_while(|message| message.published_at <= at) {
+            checkpoint.apply(message);
+        }
+        Some((checkpoint.sequence, checkpoint.state))
+    }
+
+    fn evict(self, now: Instant) -> EvictedChannel<State> {
+        let mut checkpoint = self.checkpoint;
+        if let Some(checkpoint) = &mut checkpoint {
+            self.log.iter().for_each(|message| checkpoint.apply(message));
+        }
+        EvictedChannel { next_sequence: self.next_sequence, checkpoint, evicted_at: now }
+    }
+}
+
+
+// Channels without subscribers are kept for a while, so that reconnecting observers
+// can resume from the log. Sequences and states of evicted channels are kept for longer, until
+// the tournament finishes at the latest, so that a recreated channel continues the numbering.
+// Observers returning after that are told to resync.
+struct Channels<Key, Message, State> {
+    channels: HashMap<Key, Channel<Message, State>>,
+    evicted: HashMap<Key, EvictedChannel<State>>,
+}
+
+impl<Key: Copy + Eq + Hash + Debug, Message: Clone, State: ChannelState<Message>> Channels<Key, Message, State> {
+    fn new() -> Self {
+        Self { channels: HashMap::new(), evicted: HashMap::new() }
+    }
+
+    fn channel(&mut self, key: Key, now: Instant) -> &mut Channel<Message, State> {
+        self.channels.entry(key).or_insert_with(|| Channel::new(self.evicted.remove(&key), now))
+    }
+
+    fn evict_idle_channels(&mut self, now: Instant) {
+        let expired_keys: Vec<_> = self.channels.iter_mut()
+            .filter_map(|(key, channel)| channel.is_expired(now).then_some(*key))
+            .collect();
+        for key in expired_keys {
+            debug!("evicting idle channel {:?}", key);
+            let channel = self.channels.remove(&key).unwrap();
+            self.evicted.insert(key, channel.evict(now));
+        }
+        self.evicted.retain(|_, evicted| now.duration_since(evicted.evicted_at) < EVICTED_TIMEOUT);
+    }
+
+    fn remove_channels(&mut self, predicate: impl Fn(&Key) -> bool) {
+        self.channels.retain(|key, _| !predicate(key));
+        self.evicted.retain(|key, _| !predicate(key));
+    }
+
+    fn subscriber_count(&self) -> usize {
+        self.channels.values().map(|channel| channel.subscriber_count()).sum()
+    }
+}
+
 
 pub struct TableMessageBroadcast {
-    senders: HashMap<(Uuid, usize), TableMessageSender>,
+    table_channels: Channels<(Uuid, usize), TableMessage, TableState>,
+    tournament_channels: Channels<Uuid, TournamentMessage, ()>,
+    lobby_channel: Channel<TournamentMessage, ()>,
+    published_position: u64,        // Position of the last outbox entry published, 0 if none
 }
 
 impl TableMessageBroadcast {
     pub fn new() -> Self {
-        Self { senders: HashMap::new() }
+        Self {
+            table_channels: Channels::new(),
+            tournament_channels: Channels::new(),
+            lobby_channel: Channel::new(None, Instant::now()),
+            published_position: 0,
+        }
     }
-}
 
+    fn evict_idle_channels(&mut self, now: Instant) {
+        self.table_channels.evict_idle_channels(now);
+        self.tournament_channels.evict_idle_channels(now);
+    }
 
-impl PublishTournamentMessages for TableMessageBroadcast {
-    fn publish_tournament_messages(&self, messages: Vec<TournamentMessage>) {
+    fn publish_at(&mut self, messages: Vec<TournamentMessage>, now: Instant) {
         info!("publishing {:?}", messages);
+        self.evict_idle_channels(now);
         for tournament_message in messages {
+            let tournament_id = tournament_message.tournament_id;
             match tournament_message.message_type {
                 TournamentMessageType::TableMessage { table_number, message_type } => {
-                    let key = (tournament_message.tournament_id, table_number);
-                    if let Some(sender) = self.senders.get(&key) {
-                        _ = sender.send(message_type);
-                    }
+                    self.table_channels.channel((tournament_id, table_number), now).publish(message_type, now);
+                },
+                _ => {
+                    self.tournament_channels.channel(tournament_id, now).publish(tournament_message.clone(), now);
+                    self.lobby_channel.publish(tournament_message, now);
                 }
             }
         }
     }
+
+    fn subscribe_at(&mut self, tournament_id: Uuid, table_number: usize, last_seen_sequence: Option<u64>, table_state: &TableState, now: Instant) -> TableSubscription {
+        self.evict_idle_channels(now);
+        let channel = self.table_channels.channel((tournament_id, table_number), now);
+        channel.keep_state(table_state, now);
+        channel.subscribe(last_seen_sequence)
+    }
+}
+
+
+impl PublishTournamentMessages for TableMessageBroadcast {
+    fn close_tournament_channels(&mut self, tournament_id: Uuid) {
+        info!("closing channels of tournament {}", tournament_id);
+        // Dropping the senders ends the streams of all remaining subscribers
+        self.table_channels.remove_channels(|(id, _)| *id == tournament_id);
+        self.tournament_channels.remove_channels(|id| *id == tournament_id);
+    }
+
+    fn publish_outbox_entries(&mut self, entries: Vec<OutboxEntry>) {
+        let published_position = self.published_position;
+        let messages = entries.into_iter()
+            .filter(|entry| entry.position > published_position)
+            .map(|entry| {
+                self.published_position = entry.position;
+                entry.message
+            })
+            .collect();
+        self.publish_at(messages, Instant::now());
+    }
+
+    fn sweep_channels(&mut self) {
+        self.evict_idle_channels(Instant::now());
+    }
 }
 
 
 impl SubscribeTableMessages for TableMessageBroadcast {
-    fn subscribe_table_messages(&mut self, tournament_id: Uuid, table_number: usize) -> TableMessageReceiver {
+    fn subscribe_table_messages(&mut self, tournament_id: Uuid, table_number: usize, last_seen_sequence: Option<u64>, table_state: &TableState) -> TableSubscription {
+        self.subscribe_at(tournament_id, table_number, last_seen_sequence, table_state, Instant::now())
+    }
+
+    fn table_state_at(&self, tournament_id: Uuid, table_number: usize, at: Instant) -> Option<(u64, TableState)> {
         let key = (tournament_id, table_number);
+        match self.table_channels.channels.get(&key) {
+            Some(channel) => channel.state_at(at),
+            None => self.table_channels.evicted.get(&key)
+                .and_then(|evicted| evicted.checkpoint.clone())
+                .filter(|checkpoint| checkpoint.current_since <= at)
+                .map(|checkpoint| (checkpoint.sequence, checkpoint.state)),
+        }
+    }
+}
 
-        if let Some(sender) = self.senders.get(&key) {
-            sender.subscribe()
-        } else {
-            let sender = TableMessageSender::new(16);
-            let receiver = sender.subscribe();
-            self.senders.insert(key, sender);
-            receiver
+
+impl SubscribeTournamentMessages for TableMessageBroadcast {
+    fn subscribe_tournament_messages(&mut self, tournament_id: Uuid, last_seen_sequence: Option<u64>) -> TournamentSubscription {
+        let now = Instant::now();
+        self.evict_idle_channels(now);
+        self.tournament_channels.channel(tournament_id, now).subscribe(last_seen_sequence)
+    }
+
+    fn subscribe_lobby_messages(&mut self, last_seen_sequence: Option<u64>) -> TournamentSubscription {
+        self.lobby_channel.subscribe(last_seen_sequence)
+    }
+}
+
+
+impl MonitorTableMessageBroadcast for TableMessageBroadcast {
+    fn channel_count(&self) -> usize {
+        self.table_channels.channels.len() + self.tournament_channels.channels.len()
+    }
+
+    fn subscriber_count(&self) -> usize {
+        self.table_channels.subscriber_count() + self.tournament_channels.subscriber_count() + self.lobby_channel.subscriber_count()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::domain::Nickname;
+
+    use super::*;
+
+    fn table_state() -> TableState {
+        TableState { seats: vec![None; 3] }
+    }
+
+    // Seats the same player again and again, with the stack telling the messages apart
+    fn seated_message(tournament_id: Uuid, stack: u32) -> TournamentMessage {
+        TournamentMessage {
+            tournament_id,
+            message_type: TournamentMessageType::TableMessage {
+                table_number: 0,
+                message_type: TableMessage::PlayerSeated { nickname: Nickname::new("James").unwrap(), stack, position: 0 },
+            },
+        }
+    }
+
+    fn stack_at(state: &TableState) -> Option<u32> {
+        state.seats[0].as_ref().map(|seat| seat.stack)
+    }
+
+    fn publish_left_messages(broadcast: &mut TableMessageBroadcast, tournament_id: Uuid, count: usize) {
+        let messages = (0..count).map(|position| TournamentMessage {
+            tournament_id,
+            message_type: TournamentMessageType::TableMessage {
+                table_number: 0,
+                message_type: TableMessage::PlayerLeft { position },
+            },
+        }).collect();
+        publish(broadcast, messages);
+    }
+
+    // Numbers the messages after the ones published so far, as the outbox does
+    fn publish(broadcast: &mut TableMessageBroadcast, messages: Vec<TournamentMessage>) {
+        let entries = (broadcast.published_position + 1..).zip(messages).map(|(position, message)| OutboxEntry { position, message }).collect();
+        broadcast.publish_outbox_entries(entries);
+    }
+
+    #[test]
+    fn subscribe_without_last_seen_sequence() {
+        let mut broadcast = TableMessageBroadcast::new();
+        let tournament_id = Uuid::new_v4();
+        publish_left_messages(&mut broadcast, tournament_id, 3);
+        let subscription = broadcast.subscribe_table_messages(tournament_id, 0, None, &table_state());
+        assert_eq!(subscription.last_sequence, 3);
+        assert_eq!(subscription.missed_messages, None);
+    }
+
+    #[test]
+    fn subscribe_with_replayable_gap() {
+        let mut broadcast = TableMessageBroadcast::new();
+        let tournament_id = Uuid::new_v4();
+        publish_left_messages(&mut broadcast, tournament_id, 5);
+        let subscription = broadcast.subscribe_table_messages(tournament_id, 0, Some(3), &table_state());
+        let missed_messages = subscription.missed_messages.unwrap();
+        assert_eq!(missed_messages.iter().map(|message| message.sequence).collect::<Vec<_>>(), vec![4, 5]);
+        let subscription = broadcast.subscribe_table_messages(tournament_id, 0, Some(5), &table_state());
+        assert_eq!(subscription.missed_messages, Some(vec![]));
+    }
+
+    #[test]
+    fn subscribe_with_too_large_gap() {
+        let mut broadcast = TableMessageBroadcast::new();
+        let tournament_id = Uuid::new_v4();
+        publish_left_messages(&mut broadcast, tournament_id, LOG_CAPACITY + 2);
+        let subscription = broadcast.subscribe_table_messages(tournament_id, 0, Some(1), &table_state());
+        assert_eq!(subscription.missed_messages, None);
+        let subscription = broadcast.subscribe_table_messages(tournament_id, 0, Some(2), &table_state());
+        assert_eq!(subscription.missed_messages.unwrap().len(), LOG_CAPACITY);
+    }
+
+    #[test]
+    fn subscribe_with_unknown_sequence() {
+        let mut broadcast = TableMessageBroadcast::new();
+        let tournament_id = Uuid::new_v4();
+        publish_left_messages(&mut broadcast, tournament_id, 2);
+        let subscription = broadcast.subscribe_table_messages(tournament_id, 0, Some(7), &table_state());
+        assert_eq!(subscription.missed_messages, None);
+    }
+
+    #[test]
+    fn subscribers_receive_sequenced_messages() {
+        let mut broadcast = TableMessageBroadcast::new();
+        let tournament_id = Uuid::new_v4();
+        let mut receiver = broadcast.subscribe_table_messages(tournament_id, 0, None, &table_state()).receiver;
+        publish_left_messages(&mut broadcast, tournament_id, 2);
+        assert_eq!(receiver.try_recv().unwrap().sequence, 1);
+        assert_eq!(receiver.try_recv().unwrap().sequence, 2);
+    }
+
+    #[test]
+    fn repeated_outbox_entries_are_published_once() {
+        let mut broadcast = TableMessageBroadcast::new();
+        let tournament_id = Uuid::new_v4();
+        let mut receiver = broadcast.subscribe_table_messages(tournament_id, 0, None, &table_state()).receiver;
+        let entries: Vec<OutboxEntry> = (1..=3).map(|position| OutboxEntry {
+            position,
+            message: TournamentMessage {
+                tournament_id,
+                message_type: TournamentMessageType::TableMessage {
+                    table_number: 0,
+                    message_type: TableMessage::PlayerLeft { position: position as usize },
+                },
+            },
+        }).collect();
+        broadcast.publish_outbox_entries(entries[..2].to_vec());
+        broadcast.publish_outbox_entries(entries.clone());
+        let sequences: Vec<u64> = std::iter::from_fn(|| receiver.try_recv().ok()).map(|message| message.sequence).collect();
+        assert_eq!(sequences, vec![1, 2, 3]);
+        assert_eq!(broadcast.subscribe_table_messages(tournament_id, 0, None, &table_state()).last_sequence, 3);
+    }
+
+    #[test]
+    fn idle_channels_are_evicted_after_timeout() {
+        let mut broadcast = TableMessageBroadcast::new();
+        let tournament_id = Uuid::new_v4();
+        let now = Instant::now();
+        let receiver = broadcast.subscribe_at(tournament_id, 0, None, &table_state(), now).receiver;
+        _ = broadcast.subscribe_at(tournament_id, 1, None, &table_state(), now);
+        assert_eq!(broadcast.channel_count(), 2);
+        assert_eq!(broadcast.subscriber_count(), 1);
+        broadcast.evict_idle_channels(now + IDLE_TIMEOUT);
+        assert_eq!(broadcast.channel_count(), 1);
+        drop(receiver);
+        broadcast.evict_idle_channels(now + IDLE_TIMEOUT);
+        assert_eq!(broadcast.channel_count(), 1);
+        assert_eq!(broadcast.subscriber_count(), 0);
+        broadcast.evict_idle_channels(now + IDLE_TIMEOUT * 2);
+        assert_eq!(broadcast.channel_count(), 0);
+    }
+
+    #[test]
+    fn evicted_channels_continue_sequence() {
+        let mut broadcast = TableMessageBroadcast::new();
+        let tournament_id = Uuid::new_v4();
+        publish_left_messages(&mut broadcast, tournament_id, 3);
+        let now = Instant::now();
+        broadcast.evict_idle_channels(now + IDLE_TIMEOUT);
+        assert_eq!(broadcast.channel_count(), 0);
+        let subscription = broadcast.subscribe_at(tournament_id, 0, Some(2), &table_state(), now + IDLE_TIMEOUT);
+        assert_eq!(subscription.last_sequence, 3);
+        assert_eq!(subscription.missed_messages, None);
+    }
+
+    #[test]
+    fn evicted_channels_are_forgotten_after_timeout() {
+        let mut broadcast = TableMessageBroadcast::new();
+        let tournament_id = Uuid::new_v4();
+        let now = Instant::now();
+        _ = broadcast.subscribe_at(tournament_id, 0, None, &table_state(), now);
+        broadcast.publish_at(vec![seated_message(tournament_id, 1)], now);
+        broadcast.evict_idle_channels(now + IDLE_TIMEOUT);
+        broadcast.evict_idle_channels(now + IDLE_TIMEOUT + EVICTED_TIMEOUT - Duration::from_secs(1));
+        assert_eq!(broadcast.table_channels.evicted.len(), 1);
+        broadcast.evict_idle_channels(now + IDLE_TIMEOUT + EVICTED_TIMEOUT);
+        assert_eq!(broadcast.table_channels.evicted.len(), 0);
+        assert_eq!(broadcast.table_state_at(tournament_id, 0, now + IDLE_TIMEOUT + EVICTED_TIMEOUT), None);
+        let subscription = broadcast.subscribe_at(tournament_id, 0, Some(1), &table_state(), now + IDLE_TIMEOUT + EVICTED_TIMEOUT);
+        assert_eq!((subscription.last_sequence, subscription.missed_messages), (0, None));
+    }
+
+    #[test]
+    fn closing_tournament_channels_ends_subscriptions() {
+        let mut broadcast = TableMessageBroadcast::new();
+        let tournament_id = Uuid::new_v4();
+        let other_tournament_id = Uuid::new_v4();
+        let mut receiver = broadcast.subscribe_table_messages(tournament_id, 0, None, &table_state()).receiver;
+        _ = broadcast.subscribe_table_messages(other_tournament_id, 0, None, &table_state());
+        broadcast.close_tournament_channels(tournament_id);
+        assert_eq!(broadcast.channel_count(), 1);
+        assert!(matches!(receiver.try_recv(), Err(tokio::sync::broadcast::error::TryRecvError::Closed)));
+    }
+
+    #[test]
+    fn tournament_level_messages_reach_tournament_and_lobby_subscribers() {
+        let mut broadcast = TableMessageBroadcast::new();
+        let tournament_id = Uuid::new_v4();
+        let mut table_receiver = broadcast.subscribe_table_messages(tournament_id, 0, None, &table_state()).receiver;
+        let mut tournament_receiver = broadcast.subscribe_tournament_messages(tournament_id, None).receiver;
+        let mut other_tournament_receiver = broadcast.subscribe_tournament_messages(Uuid::new_v4(), None).receiver;
+        let mut lobby_receiver = broadcast.subscribe_lobby_messages(None).receiver;
+        publish_left_messages(&mut broadcast, tournament_id, 1);
+        let message = TournamentMessage { tournament_id, message_type: TournamentMessageType::TournamentStarted };
+        publish(&mut broadcast, vec![message.clone()]);
+        assert_eq!(table_receiver.try_recv().unwrap().sequence, 1);
+        assert!(table_receiver.try_recv().is_err());
+        let tournament_message = tournament_receiver.try_recv().unwrap();
+        assert_eq!((tournament_message.sequence, tournament_message.message), (1, message.clone()));
+        assert!(other_tournament_receiver.try_recv().is_err());
+        let lobby_message = lobby_receiver.try_recv().unwrap();
+        assert_eq!((lobby_message.sequence, lobby_message.message), (1, message));
+    }
+
+    #[test]
+    fn delayed_receivers_hold_messages_back() {
+        let runtime = tokio::runtime::Builder::new_current_thread().enable_time().build().unwrap();
+        let mut broadcast = TableMessageBroadcast::new();
+        let tournament_id = Uuid::new_v4();
+        publish_left_messages(&mut broadcast, tournament_id, 2);
+        let subscription = broadcast.subscribe_table_messages(tournament_id, 0, Some(0), &table_state());
+        let delay = Duration::from_millis(50);
+        let (mut receiver, missed_messages) = MessageReceiver::delayed(subscription.receiver, subscription.missed_messages, delay, Instant::now());
+        assert_eq!(missed_messages, Some(vec![]));
+        publish_left_messages(&mut broadcast, tournament_id, 1);
+        broadcast.close_tournament_channels(tournament_id);
+        runtime.block_on(async {
+            for sequence in 1..=3 {
+                let message = receiver.recv().await.unwrap();
+                assert_eq!(message.sequence, sequence);
+                assert!(Instant::now() >= message.published_at + delay);
+            }
+            assert!(matches!(receiver.recv().await, Err(RecvError::Closed)));
+        });
+    }
+
+    #[test]
+    fn delayed_table_states_follow_rotated_logs() {
+        let mut broadcast = TableMessageBroadcast::new();
+        let tournament_id = Uuid::new_v4();
+        let now = Instant::now();
+        let _subscription = broadcast.subscribe_at(tournament_id, 0, None, &table_state(), now);
+        assert_eq!(broadcast.table_state_at(tournament_id, 0, now), Some((0, table_state())));
+        let count = LOG_CAPACITY as u32 + 10;
+        for stack in 1..=count {
+            broadcast.publish_at(vec![seated_message(tournament_id, stack)], now + Duration::from_secs(stack as u64));
+        }
+        assert_eq!(broadcast.table_state_at(tournament_id, 0, now + Duration::from_secs(5)), None);
+        let (sequence, state) = broadcast.table_state_at(tournament_id, 0, now + Duration::from_secs(12)).unwrap();
+        assert_eq!((sequence, stack_at(&state)), (12, Some(12)));
+        let (sequence, state) = broadcast.table_state_at(tournament_id, 0, now + Duration::from_secs(1000)).unwrap();
+        assert_eq!((sequence, stack_at(&state)), (count as u64, Some(count)));
+    }
+
+    #[test]
+    fn delayed_table_states_survive_eviction() {
+        let mut broadcast = TableMessageBroadcast::new();
+        let tournament_id = Uuid::new_v4();
+        let now = Instant::now();
+        _ = broadcast.subscribe_at(tournament_id, 0, None, &table_state(), now);
+        for stack in 1..=2 {
+            broadcast.publish_at(vec![seated_message(tournament_id, stack)], now + Duration::from_secs(stack as u64));
         }
+        broadcast.evict_idle_channels(now + IDLE_TIMEOUT * 2);
+        assert_eq!(broadcast.channel_count(), 0);
+        assert_eq!(broadcast.table_state_at(tournament_id, 0, now + Duration::from_secs(1)), None);
+        let (sequence, state) = broadcast.table_state_at(tournament_id, 0, now + Duration::from_secs(2)).unwrap();
+        assert_eq!((sequence, stack_at(&state)), (2, Some(2)));
+        let subscription = broadcast.subscribe_at(tournament_id, 0, None, &table_state(), now + IDLE_TIMEOUT * 2);
+        assert_eq!(subscription.last_sequence, 2);
+        let (sequence, state) = broadcast.table_state_at(tournament_id, 0, now + IDLE_TIMEOUT * 2).unwrap();
+        assert_eq!((sequence, stack_at(&state)), (2, Some(2)));
     }
 }
@@ -0,0 +1,9 @@
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+
+
+// Seconds since the Unix epoch, the time stored with accounts and bans. Domain rules depending on
+// the time take it as an argument, so that they can be checked at any time.
+pub fn unix_time() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |duration| duration.as_secs())
+}
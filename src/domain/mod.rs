@@ -1,14 +1,29 @@
+mod account;
+mod ban;
 mod broadcast;
+mod clock;
 mod nickname;
+mod observation;
 mod player;
+mod process;
+mod projection;
+mod query;
+mod restore;
 mod services;
 mod table;
 mod tournament;
 mod traits;
-mod tryout;
 
+pub use account::*;
+pub use ban::*;
 pub use broadcast::*;
+pub use clock::*;
 pub use nickname::*;
+pub use observation::*;
+pub use process::*;
+pub use projection::*;
+pub use query::*;
+pub use restore::*;
 pub use services::*;
 pub use table::*;
 pub use tournament::*;
@@ -1,33 +1,150 @@
 use thiserror::Error;
+use unicode_normalization::UnicodeNormalization;
+use unicode_security::MixedScript;
+use unicode_security::skeleton;
+use unicode_segmentation::UnicodeSegmentation;
 
 use std::fmt::Display;
 
 
+const MAX_CHARACTERS: usize = 12;
+const MAX_CODE_POINTS_PER_CHARACTER: usize = 16;    // Keeps characters stacked with combining marks in check, yet fits every emoji sequence
+
+
 #[derive(Debug, Error)]
 pub enum NicknameError {
     #[error("Nicknames must have at least one character")]
     NicknameTooShort,
     #[error("Nicknames must not have more than 12 characters")]
     NicknameTooLong,
+    #[error("Nicknames must not contain control characters")]
+    ControlCharacter,
+    #[error("Nicknames must not contain invisible characters or surrounding spaces")]
+    InvisibleCharacter,
+    #[error("Nicknames must not mix letters of different scripts")]
+    ConfusableCharacters,
+    #[error("Nickname contains a blocked word")]
+    BlockedWord,
 }
 
 
-#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(try_from = "String", into = "String")]
 pub struct Nickname {
     value: String,
 }
 
 impl Nickname {
+    // Characters are counted as grapheme clusters, so that accented letters and emoji count as one
     pub fn new(value: impl Into<String>) -> Result<Self, NicknameError> {
+        let value: String = value.into().nfc().collect();
+        let character_count = value.graphemes(true).count();
+        if character_count == 0 {
+            Err(NicknameError::NicknameTooShort)
+        } else if character_count > MAX_CHARACTERS
+            || value.graphemes(true).any(|grapheme| grapheme.chars().count() > MAX_CODE_POINTS_PER_CHARACTER) {
+            Err(NicknameError::NicknameTooLong)
+        } else if value.chars().any(char::is_control) {
+            Err(NicknameError::ControlCharacter)
+        } else if value.trim() != value || value.graphemes(true).any(is_invisible) {
+            Err(NicknameError::InvisibleCharacter)
+        } else if !value.chars().filter(|c| c.is_alphanumeric()).collect::<String>().is_single_script() {
+            Err(NicknameError::ConfusableCharacters)
+        } else {
+            Ok(Self { value })
+        }
+    }
+
+    // Stored nicknames were accepted by the rules of their time, which may have been laxer, so only
+    // what every version required is checked again
+    pub fn restore(value: impl Into<String>) -> Result<Self, NicknameError> {
         let value = value.into();
         if value.is_empty() {
             Err(NicknameError::NicknameTooShort)
-        } else if value.len() > 12 {
-            Err(NicknameError::NicknameTooLong)
         } else {
             Ok(Self { value })
         }
     }
+
+    // Nicknames are unique regardless of case and of characters that look alike, such as a Latin
+    // and a Cyrillic "a" or "rn" and "m"
+    pub fn key(&self) -> String {
+        key_of(&self.value)
+    }
+}
+
+
+// Words are matched against the keys of nicknames, so they are found regardless of case and of
+// characters that look alike
+#[derive(Debug, Clone, Default)]
+pub struct BlockedWords {
+    keys: Vec<String>,
+}
+
+impl BlockedWords {
+    pub fn new<Word: AsRef<str>>(words: impl IntoIterator<Item = Word>) -> Self {
+        let keys = words.into_iter()
+            .map(|word| key_of(word.as_ref().trim()))
+            .filter(|key| !key.is_empty())
+            .collect();
+        Self { keys }
+    }
+
+    pub fn check(&self, nickname: &Nickname) -> Result<(), NicknameError> {
+        let key = nickname.key();
+        if self.keys.iter().any(|blocked| key.contains(blocked.as_str())) {
+            Err(NicknameError::BlockedWord)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+
+fn key_of(value: &str) -> String {
+    skeleton(&value.to_lowercase()).collect::<String>().to_lowercase()
+}
+
+
+// A grapheme cluster is invisible if all its characters are, or if it contains a character that is
+// invisible everywhere but in emoji sequences, outside of one
+fn is_invisible(grapheme: &str) -> bool {
+    let is_emoji = grapheme.chars().any(is_emoji);
+    grapheme.chars().all(|c| is_invisible_char(c) || is_joiner_or_selector(c))
+        || grapheme.chars().any(is_invisible_char)
+        || (!is_emoji && grapheme.chars().any(is_joiner_or_selector))
+}
+
+fn is_invisible_char(c: char) -> bool {
+    matches!(c,
+        '\u{00A0}' | '\u{00AD}' | '\u{034F}' | '\u{061C}' | '\u{115F}' | '\u{1160}' | '\u{17B4}' | '\u{17B5}' | '\u{180E}'
+        | '\u{2000}'..='\u{200C}' | '\u{200E}'..='\u{200F}' | '\u{202A}'..='\u{202F}' | '\u{205F}'..='\u{206F}'
+        | '\u{3000}' | '\u{3164}' | '\u{FEFF}' | '\u{FFA0}' | '\u{1D173}'..='\u{1D17A}' | '\u{E0000}'..='\u{E007F}'
+    )
+}
+
+// Zero width joiners and variation selectors, which build emoji sequences
+fn is_joiner_or_selector(c: char) -> bool {
+    matches!(c, '\u{200D}' | '\u{FE00}'..='\u{FE0F}' | '\u{E0100}'..='\u{E01EF}')
+}
+
+fn is_emoji(c: char) -> bool {
+    matches!(c, '\u{2600}'..='\u{27BF}' | '\u{2B00}'..='\u{2BFF}' | '\u{1F000}'..='\u{1FAFF}')
+}
+
+// Nicknames are only deserialized from stored events and snapshots, user input goes through new
+impl TryFrom<String> for Nickname {
+    type Error = NicknameError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Self::restore(value)
+    }
+}
+
+impl From<Nickname> for String {
+    fn from(nickname: Nickname) -> Self {
+        nickname.value
+    }
 }
 
 impl Display for Nickname {
@@ -47,6 +164,53 @@ mod tests {
         assert!(matches!(result, Err(NicknameError::NicknameTooShort)));
         let result = Nickname::new("a".repeat(13));
         assert!(matches!(result, Err(NicknameError::NicknameTooLong)));
+        let result = Nickname::new(format!("a{}", "\u{0301}".repeat(17)));
+        assert!(matches!(result, Err(NicknameError::NicknameTooLong)));
+        let result = Nickname::new("den\nyo");
+        assert!(matches!(result, Err(NicknameError::ControlCharacter)));
+        for value in ["den\u{200B}yo", "den\u{200D}yo", "\u{202E}denyo", " denyo", "\u{3164}"] {
+            assert!(matches!(Nickname::new(value), Err(NicknameError::InvisibleCharacter)), "{:?}", value);
+        }
+        let result = Nickname::new("p\u{0430}ypal");
+        assert!(matches!(result, Err(NicknameError::ConfusableCharacters)));
+    }
+
+    #[test]
+    fn characters_are_grapheme_clusters() {
+        assert!(Nickname::new("Jürgen Müler").is_ok());
+        assert!(Nickname::new("Jü\u{0308}rgen").is_ok());
+        assert!(Nickname::new("🂡🂱🃁🃑♠♥♦♣🂡🂱🃁🃑").is_ok());
+        assert!(Nickname::new("👨\u{200D}👩\u{200D}👧 Family").is_ok());
+        assert!(Nickname::new("❤\u{FE0F}").is_ok());
+        assert!(Nickname::new("a\u{0301}\u{0301}\u{0301}".repeat(12)).is_ok());
+    }
+
+    #[test]
+    fn emoji_sequences_fit() {
+        assert!(Nickname::new("🇩🇪🇫🇷🇯🇵🇺🇸🇧🇷🇮🇳🇨🇦🇪🇸🇮🇹🇳🇱🇸🇪🇳🇴").is_ok());
+        let family = "👨\u{1F3FD}\u{200D}👩\u{1F3FD}\u{200D}👧\u{1F3FD}\u{200D}👦\u{1F3FD}";
+        assert!(Nickname::new(family.repeat(12)).is_ok());
+        let kiss = "👩\u{1F3FB}\u{200D}❤\u{FE0F}\u{200D}💋\u{200D}👨\u{1F3FC}";
+        assert!(Nickname::new(format!("{}{}", kiss.repeat(6), "🏳\u{FE0F}\u{200D}🌈".repeat(6))).is_ok());
+        assert!(Nickname::new("Дмитрий").is_ok());
+        assert!(Nickname::new("山田太郎").is_ok());
+    }
+
+    #[test]
+    fn nicknames_are_normalized() {
+        let decomposed = Nickname::new("Ju\u{0308}rgen").unwrap();
+        assert_eq!(decomposed, Nickname::new("J\u{00FC}rgen").unwrap());
+        assert_eq!(format!("{}", decomposed), "J\u{00FC}rgen");
+    }
+
+    #[test]
+    fn blocked_words_are_found_regardless_of_spelling() {
+        let blocked_words = BlockedWords::new(["admin", " "]);
+        assert!(blocked_words.check(&Nickname::new("Denyo").unwrap()).is_ok());
+        for value in ["Admin", "TheADMIN", "adrnin", "AdmIn"] {
+            assert!(matches!(blocked_words.check(&Nickname::new(value).unwrap()), Err(NicknameError::BlockedWord)), "{:?}", value);
+        }
+        assert!(BlockedWords::default().check(&Nickname::new("Admin").unwrap()).is_ok());
     }
 
     #[test]
@@ -56,4 +220,30 @@ mod tests {
         let nickname = result.unwrap();
         assert_eq!(format!("{}", nickname), "denyo");
     }
+
+    #[test]
+    fn keys_ignore_case_and_confusables() {
+        assert_eq!(Nickname::new("Denyo").unwrap().key(), Nickname::new("DENYO").unwrap().key());
+        assert_eq!(Nickname::new("Pay").unwrap().key(), Nickname::new("\u{0440}\u{0430}\u{0443}").unwrap().key());
+        assert_eq!(Nickname::new("Mike").unwrap().key(), Nickname::new("rnike").unwrap().key());
+        assert_ne!(Nickname::new("Denyo").unwrap().key(), Nickname::new("Denya").unwrap().key());
+    }
+
+    #[test]
+    fn serde_as_plain_string() {
+        let nickname = Nickname::new("denyo").unwrap();
+        assert_eq!(serde_json::to_string(&nickname).unwrap(), "\"denyo\"");
+        assert_eq!(serde_json::from_str::<Nickname>("\"denyo\"").unwrap(), nickname);
+        assert!(serde_json::from_str::<Nickname>("\"\"").is_err());
+    }
+
+    #[test]
+    fn restore_nicknames_accepted_by_earlier_rules() {
+        for value in ["Bob ", "Pаypal", "a\u{200B}b"] {
+            assert!(Nickname::new(value).is_err());
+            assert_eq!(Nickname::restore(value).unwrap().to_string(), value);
+            assert_eq!(serde_json::from_value::<Nickname>(serde_json::json!(value)).unwrap(), Nickname::restore(value).unwrap());
+        }
+        assert!(matches!(Nickname::restore(""), Err(NicknameError::NicknameTooShort)));
+    }
 }
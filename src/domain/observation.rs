@@ -0,0 +1,41 @@
+use thiserror::Error;
+
+use std::time::Duration;
+
+
+#[derive(Debug, Error)]
+pub enum ObservationError {
+    #[error("Observing the tables of this tournament is disabled")]
+    ObservationDisabled,
+    #[error("Only players of this tournament may observe its tables")]
+    NotParticipating,
+    #[error("The table cannot be shown with the spectator delay yet, try again later")]
+    DelayedStateUnavailable,
+}
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ObservationAccess {
+    #[default]
+    Public,
+    MembersOnly,
+    ParticipantsOnly,
+    Disabled,
+}
+
+
+// Who may observe the tables of a tournament, and how long spectators lag behind the game so
+// that they cannot pass on live information to the players (ghosting)
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct ObservationPolicy {
+    pub access: ObservationAccess,
+    pub spectator_delay: Duration,
+}
+
+impl ObservationPolicy {
+    pub const MAX_SPECTATOR_DELAY: Duration = Duration::from_secs(600);
+
+    pub fn new(access: ObservationAccess, spectator_delay: Duration) -> Self {
+        Self { access, spectator_delay }
+    }
+}
@@ -19,4 +19,12 @@ impl Player {
     pub fn account_id(&self) -> Uuid {
         self.account_id
     }
+
+    pub fn nickname(&self) -> &Nickname {
+        &self.nickname
+    }
+
+    pub fn stack(&self) -> u32 {
+        self.stack
+    }
 }
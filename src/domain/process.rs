@@ -0,0 +1,507 @@
+use crate::domain::AccessEventStreams;
+use crate::domain::AccessTables;
+use crate::domain::EventStream;
+use crate::domain::LoadCheckpointError;
+use crate::domain::LoadEventsError;
+use crate::domain::LoadTableError;
+use crate::domain::LoadTournament;
+use crate::domain::LoadTournamentError;
+use crate::domain::STARTING_STACK;
+use crate::domain::SaveCheckpointError;
+use crate::domain::SaveTableError;
+use crate::domain::SaveTournament;
+use crate::domain::SaveTournamentError;
+use crate::domain::Table;
+use crate::domain::TableError;
+use crate::domain::TableEvent;
+use crate::domain::Tournament;
+use crate::domain::TournamentError;
+use crate::domain::TournamentEvent;
+
+use log::debug;
+use log::warn;
+use thiserror::Error;
+use uuid::Uuid;
+
+use std::collections::HashMap;
+use std::collections::VecDeque;
+
+
+#[derive(Debug, Error)]
+pub enum ProcessEventsError {
+    #[error(transparent)]
+    LoadEventsError(#[from] LoadEventsError),
+    #[error(transparent)]
+    LoadCheckpointError(#[from] LoadCheckpointError),
+    #[error(transparent)]
+    SaveCheckpointError(#[from] SaveCheckpointError),
+    #[error(transparent)]
+    LoadTournamentError(#[from] LoadTournamentError),
+    #[error(transparent)]
+    SaveTournamentError(#[from] SaveTournamentError),
+    #[error(transparent)]
+    LoadTableError(#[from] LoadTableError),
+    #[error(transparent)]
+    SaveTableError(#[from] SaveTableError),
+    #[error(transparent)]
+    TournamentError(#[from] TournamentError),
+    #[error(transparent)]
+    TableError(#[from] TableError),
+}
+
+
+// Coordinates tournaments and their tables, which are separate aggregates: tournament events are
+// turned into table commands and table events into tournament commands, until all affected streams
+// are handled up to their end. The checkpoint of a stream only advances after an event was handled,
+// so an interrupted run delivers the event again the next time its stream is processed. Handlers
+// therefore compare each event with the current state of the commanded aggregate instead of
+// blindly repeating the command. The messages of the commanded aggregates go to the outbox when
+// they are saved.
+pub fn process_events<Repository: LoadTournament + SaveTournament + AccessTables + AccessEventStreams>(
+    streams: impl IntoIterator<Item = EventStream>,
+    repository: &mut Repository,
+) -> Result<(), ProcessEventsError> {
+    let mut manager = ProcessManager { repository, pending: VecDeque::new() };
+    for stream in streams {
+        manager.schedule(stream);
+    }
+    while let Some(stream) = manager.pending.pop_front() {
+        manager.process_stream(stream)?;
+    }
+    Ok(())
+}
+
+
+// Tournaments whose streams may lag behind their events, because the server stopped between saving
+// an aggregate and processing its events, or because processing failed. Every stored tournament is
+// checked on startup, after that only those whose processing failed since, so the periodic retries
+// need not look at every stream. A tournament is forgotten once its streams have caught up. Each
+// stream is processed on its own, so that one failing stream does not hold up the others.
+#[derive(Debug, Default)]
+pub struct PendingEvents {
+    // Whether a failure to process the events of the tournament was logged already, retries that
+    // keep failing are only logged once
+    tournament_ids: HashMap<Uuid, bool>,
+}
+
+impl PendingEvents {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn all<Repository: AccessEventStreams>(repository: &Repository) -> Result<Self, LoadEventsError> {
+        let tournament_ids = repository.load_tournament_ids()?.into_iter().map(|tournament_id| (tournament_id, false)).collect();
+        Ok(Self { tournament_ids })
+    }
+
+    pub fn add(&mut self, tournament_id: Uuid) {
+        self.tournament_ids.entry(tournament_id).or_insert(false);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tournament_ids.is_empty()
+    }
+
+    // Returns the number of streams that were behind
+    pub fn process<Repository: LoadTournament + SaveTournament + AccessTables + AccessEventStreams>(&mut self, repository: &mut Repository) -> usize {
+        let mut pending_count = 0;
+        let tournament_ids: Vec<Uuid> = self.tournament_ids.keys().copied().collect();
+        for tournament_id in tournament_ids {
+            let result = pending_streams(tournament_id, repository).and_then(|streams| {
+                pending_count += streams.len();
+                streams.into_iter().map(|stream| process_events([stream], repository)).fold(Ok(()), Result::and)
+            });
+            match result {
+                Ok(()) => _ = self.tournament_ids.remove(&tournament_id),
+                Err(error) => {
+                    let logged = self.tournament_ids.insert(tournament_id, true).unwrap_or(false);
+                    if logged {
+                        debug!("still cannot process events of tournament {}: {}", tournament_id, error);
+                    } else {
+                        warn!("cannot process events of tournament {}: {}", tournament_id, error);
+                    }
+                },
+            }
+        }
+        pending_count
+    }
+}
+
+
+fn pending_streams<Repository: LoadTournament + AccessTables + AccessEventStreams>(
+    tournament_id: Uuid,
+    repository: &Repository,
+) -> Result<Vec<EventStream>, ProcessEventsError> {
+    let tournament = repository.load_tournament(tournament_id)?;
+    let mut streams = Vec::new();
+    let stream = EventStream::Tournament { tournament_id };
+    if repository.load_checkpoint(stream)? < tournament.version() {
+        streams.push(stream);
+    }
+    for table_number in 0..tournament.spec().table_count() as usize {
+        // Tables are created by processing the tournament events, so some may not exist yet
+        let table = match repository.load_table(tournament_id, table_number) {
+            Err(LoadTableError::TableNotFound) => continue,
+            result => result?,
+        };
+        let stream = EventStream::Table { tournament_id, table_number };
+        if repository.load_checkpoint(stream)? < table.version() {
+            streams.push(stream);
+        }
+    }
+    Ok(streams)
+}
+
+
+struct ProcessManager<'a, Repository> {
+    repository: &'a mut Repository,
+    pending: VecDeque<EventStream>,
+}
+
+impl<Repository: LoadTournament + SaveTournament + AccessTables + AccessEventStreams> ProcessManager<'_, Repository> {
+    fn schedule(&mut self, stream: EventStream) {
+        if !self.pending.contains(&stream) {
+            self.pending.push_back(stream);
+        }
+    }
+
+    fn process_stream(&mut self, stream: EventStream) -> Result<(), ProcessEventsError> {
+        let checkpoint = self.repository.load_checkpoint(stream)?;
+        debug!("process {:?} from checkpoint {}", stream, checkpoint);
+        match stream {
+            EventStream::Tournament { tournament_id } => {
+                let events = self.repository.load_tournament_events(tournament_id, checkpoint)?;
+                for (checkpoint, event) in (checkpoint + 1..).zip(events) {
+                    self.handle_tournament_event(tournament_id, event)?;
+                    self.repository.save_checkpoint(stream, checkpoint)?;
+                }
+            },
+            EventStream::Table { tournament_id, table_number } => {
+                let events = self.repository.load_table_events(tournament_id, table_number, checkpoint)?;
+                for (checkpoint, event) in (checkpoint + 1..).zip(events) {
+                    self.handle_table_event(tournament_id, event)?;
+                    self.repository.save_checkpoint(stream, checkpoint)?;
+                }
+            },
+        }
+        Ok(())
+    }
+
+    fn handle_tournament_event(&mut self, tournament_id: Uuid, event: TournamentEvent) -> Result<(), ProcessEventsError> {
+        match event {
+            TournamentEvent::TournamentCreated { id, spec } => {
+                for table_number in 0..spec.table_count() as usize {
+                    match self.repository.load_table(id, table_number) {
+                        Err(LoadTableError::TableNotFound) => self.save_table(Table::new(id, table_number, &spec.table_spec()))?,
+                        result => _ = result?,
+                    }
+                }
+            },
+            TournamentEvent::PlayerJoined { account_id, nickname } => {
+                // The player may have been moved or eliminated since, so the current seating decides
+                let tournament = self.repository.load_tournament(tournament_id)?;
+                if let Some(table_number) = tournament.players_table_number(account_id) {
+                    let mut table = self.repository.load_table(tournament_id, table_number)?;
+                    if !table.has_player(account_id) {
+                        table.sit_down(account_id, nickname, STARTING_STACK)?;
+                        self.save_table(table)?;
+                    }
+                }
+            },
+            TournamentEvent::PlayerMoved { account_id, from_table, to_table } => {
+                // The player takes the new seat before leaving the old one, so that an interruption
+                // in between cannot lose their stack
+                let mut from = self.repository.load_table(tournament_id, from_table)?;
+                if let Some(seat) = from.player_seat(account_id) {
+                    let mut to = self.repository.load_table(tournament_id, to_table)?;
+                    if !to.has_player(account_id) {
+                        to.sit_down(account_id, seat.nickname, seat.stack)?;
+                        self.save_table(to)?;
+                    }
+                    from.stand_up(account_id)?;
+                    self.save_table(from)?;
+                }
+            },
+            TournamentEvent::PlayerLeft { account_id } => {
+                // The player may have joined again since, so only seats the tournament no longer
+                // assigns them are freed
+                let tournament = self.repository.load_tournament(tournament_id)?;
+                for table_number in 0..tournament.table_count() {
+                    if tournament.players_table_number(account_id) == Some(table_number) {
+                        continue;
+                    }
+                    let mut table = match self.repository.load_table(tournament_id, table_number) {
+                        Err(LoadTableError::TableNotFound) => continue,
+                        result => result?,
+                    };
+                    if table.has_player(account_id) {
+                        table.stand_up(account_id)?;
+                        self.save_table(table)?;
+                    }
+                }
+            },
+            // The table already freed the seat when the player was wiped out
+            TournamentEvent::PlayerEliminated { .. } => {},
+            // TODO: start the first game at every table once there is game logic
+            TournamentEvent::TournamentStarted => {},
+        }
+        Ok(())
+    }
+
+    fn handle_table_event(&mut self, tournament_id: Uuid, event: TableEvent) -> Result<(), ProcessEventsError> {
+        match event {
+            TableEvent::PlayerWipedOut { account_id, nickname, .. } => {
+                let mut tournament = self.repository.load_tournament(tournament_id)?;
+                tournament.eliminate(account_id, nickname)?;
+                self.save_tournament(tournament)?;
+            },
+            TableEvent::TableCreated { .. } | TableEvent::PlayerSeated { .. } | TableEvent::PlayerLeft { .. } => {},
+        }
+        Ok(())
+    }
+
+    fn save_tournament(&mut self, tournament: Tournament) -> Result<(), ProcessEventsError> {
+        if tournament.new_events().is_empty() {
+            return Ok(());
+        }
+        let tournament_id = tournament.id();
+        self.repository.save_tournament(tournament)?;
+        self.schedule(EventStream::Tournament { tournament_id });
+        Ok(())
+    }
+
+    fn save_table(&mut self, table: Table) -> Result<(), ProcessEventsError> {
+        let tournament_id = table.tournament_id();
+        let table_number = table.table_number();
+        self.repository.save_table(table)?;
+        self.schedule(EventStream::Table { tournament_id, table_number });
+        Ok(())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::domain::LoadCheckpoint;
+    use crate::domain::LoadOutbox;
+    use crate::domain::LoadTable;
+    use crate::domain::MarkDelivered;
+    use crate::domain::Nickname;
+    use crate::domain::SaveCheckpoint;
+    use crate::domain::SaveTable;
+    use crate::domain::TableMessage;
+    use crate::domain::TournamentMessage;
+    use crate::domain::TournamentMessageType;
+    use crate::domain::TournamentSpecification;
+    use crate::domain::TournamentStage;
+    use crate::infrastructure::InMemoryTournamentRepository;
+
+    use super::*;
+
+    // Takes the messages saved to the outbox so far
+    fn take_outbox_messages(repository: &mut InMemoryTournamentRepository) -> Vec<TournamentMessage> {
+        let entries = repository.load_pending_messages().unwrap();
+        if let Some(entry) = entries.last() {
+            repository.mark_delivered(entry.position).unwrap();
+        }
+        entries.into_iter().map(|entry| entry.message).collect()
+    }
+
+    fn save_and_process(tournament: Tournament, repository: &mut InMemoryTournamentRepository) -> Vec<TournamentMessage> {
+        let tournament_id = tournament.id();
+        repository.save_tournament(tournament).unwrap();
+        process_events([EventStream::Tournament { tournament_id }], repository).unwrap();
+        take_outbox_messages(repository)
+    }
+
+    fn wipe_out(tournament_id: Uuid, account_id: Uuid, repository: &mut InMemoryTournamentRepository) -> Vec<TournamentMessage> {
+        let tournament = repository.load_tournament(tournament_id).unwrap();
+        let table_number = tournament.players_table_number(account_id).unwrap();
+        let mut table = repository.load_table(tournament_id, table_number).unwrap();
+        table.wipe_out(account_id).unwrap();
+        repository.save_table(table).unwrap();
+        process_events([EventStream::Table { tournament_id, table_number }], repository).unwrap();
+        take_outbox_messages(repository)
+    }
+
+    fn seated_players(tournament_id: Uuid, table_count: usize, repository: &InMemoryTournamentRepository) -> Vec<u8> {
+        (0..table_count).map(|table_number| repository.load_table(tournament_id, table_number).unwrap().player_count()).collect()
+    }
+
+    fn running_tournament(repository: &mut InMemoryTournamentRepository) -> (Uuid, Vec<Uuid>) {
+        let spec = TournamentSpecification::new(3, 2).unwrap();
+        let mut tournament = Tournament::new(&spec);
+        let tournament_id = tournament.id();
+        let account_ids: Vec<Uuid> = (0..6).map(|_| Uuid::new_v4()).collect();
+        for (index, account_id) in account_ids.iter().enumerate() {
+            _ = tournament.join(*account_id, Nickname::new(format!("Player{}", index)).unwrap());
+        }
+        tournament.start();
+        save_and_process(tournament, repository);
+        (tournament_id, account_ids)
+    }
+
+    #[test]
+    fn tournament_events_command_tables() {
+        let mut repository = InMemoryTournamentRepository::new();
+        let spec = TournamentSpecification::new(2, 2).unwrap();
+        let tournament = Tournament::new(&spec);
+        let tournament_id = tournament.id();
+        assert_eq!(save_and_process(tournament, &mut repository), vec![]);
+        assert_eq!(seated_players(tournament_id, 2, &repository), vec![0, 0]);
+
+        let mut tournament = repository.load_tournament(tournament_id).unwrap();
+        for nickname in ["James", "Patricia", "Daniel"] {
+            _ = tournament.join(Uuid::new_v4(), Nickname::new(nickname).unwrap());
+        }
+        let messages = save_and_process(tournament, &mut repository);
+        assert_eq!(seated_players(tournament_id, 2, &repository), vec![2, 1]);
+        assert_eq!(messages.len(), 6);
+        assert_eq!(messages[2].message_type, TournamentMessageType::RegistrationCountChanged { player_count: 3 });
+        assert_eq!(messages[5].message_type, TournamentMessageType::TableMessage {
+            table_number: 1,
+            message_type: TableMessage::PlayerSeated { nickname: Nickname::new("Daniel").unwrap(), stack: STARTING_STACK, position: 0 },
+        });
+    }
+
+    #[test]
+    fn redelivered_events_are_handled_once() {
+        let mut repository = InMemoryTournamentRepository::new();
+        let (tournament_id, _) = running_tournament(&mut repository);
+        let table_versions: Vec<usize> = (0..3).map(|table_number| repository.load_table(tournament_id, table_number).unwrap().version()).collect();
+        let streams: Vec<EventStream> = std::iter::once(EventStream::Tournament { tournament_id })
+            .chain((0..3).map(|table_number| EventStream::Table { tournament_id, table_number }))
+            .collect();
+        for stream in &streams {
+            assert!(repository.load_checkpoint(*stream).unwrap() > 0);
+            repository.save_checkpoint(*stream, 0).unwrap();
+        }
+        process_events(streams, &mut repository).unwrap();
+        assert_eq!(take_outbox_messages(&mut repository), vec![]);
+        for (table_number, version) in table_versions.into_iter().enumerate() {
+            assert_eq!(repository.load_table(tournament_id, table_number).unwrap().version(), version);
+        }
+        assert_eq!(repository.load_tournament(tournament_id).unwrap().version(), 8);
+    }
+
+    #[test]
+    fn wiped_out_players_are_eliminated_and_tables_broken() {
+        let mut repository = InMemoryTournamentRepository::new();
+        let (tournament_id, account_ids) = running_tournament(&mut repository);
+        let messages = wipe_out(tournament_id, account_ids[0], &mut repository);
+        assert!(messages.iter().any(|message| message.message_type == TournamentMessageType::PlayerEliminated {
+            nickname: Nickname::new("Player0").unwrap(), rank: 6
+        }));
+        assert_eq!(seated_players(tournament_id, 3, &repository), vec![1, 2, 2]);
+
+        wipe_out(tournament_id, account_ids[2], &mut repository);
+        assert_eq!(seated_players(tournament_id, 3, &repository), vec![0, 2, 2]);
+        let table = repository.load_table(tournament_id, 1).unwrap();
+        assert_eq!(table.player_seat(account_ids[1]).unwrap().stack, STARTING_STACK);
+
+        for account_id in [account_ids[3], account_ids[1], account_ids[4]] {
+            wipe_out(tournament_id, account_id, &mut repository);
+        }
+        let tournament = repository.load_tournament(tournament_id).unwrap();
+        assert_eq!(tournament.stage(), TournamentStage::Finished);
+        assert_eq!(tournament.players_table_number(account_ids[5]), Some(2));
+        assert_eq!(seated_players(tournament_id, 3, &repository), vec![0, 0, 1]);
+    }
+
+    #[test]
+    fn leaving_players_stand_up() {
+        let mut repository = InMemoryTournamentRepository::new();
+        let (tournament_id, account_ids) = running_tournament(&mut repository);
+        let mut tournament = repository.load_tournament(tournament_id).unwrap();
+        tournament.leave(account_ids[0]).unwrap();
+        let messages = save_and_process(tournament, &mut repository);
+        assert!(messages.iter().any(|message| matches!(message.message_type, TournamentMessageType::TableMessage {
+            table_number: 0,
+            message_type: TableMessage::PlayerLeft { .. },
+        })));
+        assert_eq!(seated_players(tournament_id, 3, &repository), vec![1, 2, 2]);
+        assert!(!repository.load_table(tournament_id, 0).unwrap().has_player(account_ids[0]));
+    }
+
+    #[test]
+    fn players_who_joined_again_keep_their_seat() {
+        let mut repository = InMemoryTournamentRepository::new();
+        let spec = TournamentSpecification::new(1, 3).unwrap();
+        let mut tournament = Tournament::new(&spec);
+        let tournament_id = tournament.id();
+        let account_id = Uuid::new_v4();
+        _ = tournament.join(account_id, Nickname::new("James").unwrap());
+        tournament.leave(account_id).unwrap();
+        _ = tournament.join(account_id, Nickname::new("James").unwrap());
+        save_and_process(tournament, &mut repository);
+        assert!(repository.load_table(tournament_id, 0).unwrap().has_player(account_id));
+        assert_eq!(seated_players(tournament_id, 1, &repository), vec![1]);
+    }
+
+    #[test]
+    fn pending_events_are_processed_after_an_interruption() {
+        let mut repository = InMemoryTournamentRepository::new();
+        let (tournament_id, _) = running_tournament(&mut repository);
+        assert_eq!(PendingEvents::all(&repository).unwrap().process(&mut repository), 0);
+
+        // Save without processing, as if the server had stopped right after the commit
+        let spec = TournamentSpecification::new(2, 2).unwrap();
+        let mut tournament = Tournament::new(&spec);
+        let pending_id = tournament.id();
+        _ = tournament.join(Uuid::new_v4(), Nickname::new("James").unwrap());
+        repository.save_tournament(tournament).unwrap();
+        assert!(matches!(repository.load_table(pending_id, 0), Err(LoadTableError::TableNotFound)));
+
+        let mut pending = PendingEvents::all(&repository).unwrap();
+        assert!(pending.process(&mut repository) > 0);
+        assert!(pending.is_empty());
+        assert_eq!(seated_players(pending_id, 2, &repository), vec![1, 0]);
+        assert_eq!(seated_players(tournament_id, 3, &repository), vec![2, 2, 2]);
+        assert_eq!(PendingEvents::all(&repository).unwrap().process(&mut repository), 0);
+    }
+
+    #[test]
+    fn only_added_tournaments_are_processed_after_startup() {
+        let mut repository = InMemoryTournamentRepository::new();
+        let mut pending = PendingEvents::all(&repository).unwrap();
+        let spec = TournamentSpecification::new(2, 2).unwrap();
+        let tournament = Tournament::new(&spec);
+        let tournament_id = tournament.id();
+        repository.save_tournament(tournament).unwrap();
+        assert_eq!(pending.process(&mut repository), 0);
+        assert!(matches!(repository.load_table(tournament_id, 0), Err(LoadTableError::TableNotFound)));
+
+        pending.add(tournament_id);
+        assert_eq!(pending.process(&mut repository), 1);
+        assert!(pending.is_empty());
+        assert_eq!(seated_players(tournament_id, 2, &repository), vec![0, 0]);
+    }
+
+    #[test]
+    fn interrupted_move_is_completed_on_redelivery() {
+        let mut repository = InMemoryTournamentRepository::new();
+        let (tournament_id, account_ids) = running_tournament(&mut repository);
+        wipe_out(tournament_id, account_ids[0], &mut repository);
+        let stream = EventStream::Tournament { tournament_id };
+        let checkpoint = repository.load_checkpoint(stream).unwrap();
+
+        // Eliminate the second player of the first two tables without letting the tables follow
+        let mut table = repository.load_table(tournament_id, 1).unwrap();
+        table.wipe_out(account_ids[2]).unwrap();
+        table.collect_messages();
+        repository.save_table(table).unwrap();
+        let mut tournament = repository.load_tournament(tournament_id).unwrap();
+        tournament.eliminate(account_ids[2], Nickname::new("Player2").unwrap()).unwrap();
+        tournament.collect_messages();
+        repository.save_tournament(tournament).unwrap();
+        assert_eq!(seated_players(tournament_id, 3, &repository), vec![1, 1, 2]);
+
+        process_events([stream], &mut repository).unwrap();
+        assert_eq!(seated_players(tournament_id, 3, &repository), vec![0, 2, 2]);
+        assert_eq!(take_outbox_messages(&mut repository).len(), 2);
+        assert_eq!(repository.load_checkpoint(stream).unwrap(), checkpoint + 2);
+        let table_stream = EventStream::Table { tournament_id, table_number: 1 };
+        process_events([table_stream], &mut repository).unwrap();
+        assert_eq!(take_outbox_messages(&mut repository), vec![]);
+    }
+}
@@ -0,0 +1,315 @@
+use super::nickname::Nickname;
+use super::query::TournamentPage;
+use super::query::TournamentQuery;
+use super::tournament::TournamentEvent;
+use super::tournament::TournamentStage;
+use super::traits::LoadEvents;
+use super::traits::LoadEventsError;
+use super::traits::QueryTournaments;
+
+use log::{debug, error};
+use uuid::Uuid;
+
+use std::collections::HashMap;
+
+
+// A tournament as listed in the lobby
+#[derive(Debug, Clone, PartialEq)]
+pub struct TournamentSummary {
+    tournament_id: Uuid,
+    stage: TournamentStage,
+    table_seat_count: u8,
+    tables: Vec<Vec<Uuid>>,     // Account ids of the players seated at each table
+    version: usize,             // Number of events projected
+}
+
+impl TournamentSummary {
+    pub fn id(&self) -> Uuid {
+        self.tournament_id
+    }
+
+    pub fn stage(&self) -> TournamentStage {
+        self.stage.clone()
+    }
+
+    pub fn table_count(&self) -> usize {
+        self.tables.len()
+    }
+
+    pub fn table_seat_count(&self) -> u8 {
+        self.table_seat_count
+    }
+
+    pub fn player_count(&self) -> usize {
+        self.tables.iter().map(|players| players.len()).sum()
+    }
+
+    pub fn free_seat_count(&self) -> usize {
+        self.table_count() * self.table_seat_count as usize - self.player_count()
+    }
+
+    pub fn players_table_number(&self, account_id: Uuid) -> Option<usize> {
+        self.tables.iter().position(|players| players.contains(&account_id))
+    }
+}
+
+
+// A tournament an account has joined
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlayerHistoryEntry {
+    pub tournament_id: Uuid,
+    pub nickname: Nickname,
+    pub rank: Option<usize>,    // None as long as the player is still in the tournament
+}
+
+
+// Read models denormalized from the committed tournament events: the summaries listed in the lobby
+// and the tournament history of every account. Each summary remembers how many events it has seen,
+// so catching up after a commit only projects the events saved since.
+#[derive(Debug)]
+pub struct TournamentProjection {
+    summaries: HashMap<Uuid, TournamentSummary>,
+    histories: HashMap<Uuid, Vec<PlayerHistoryEntry>>,
+}
+
+impl TournamentProjection {
+    pub fn new() -> Self {
+        Self { summaries: HashMap::new(), histories: HashMap::new() }
+    }
+
+    // Projects all stored tournaments from scratch. Tournaments with corrupted events are left out.
+    pub fn rebuild<Repository: LoadEvents>(repository: &Repository) -> Result<Self, LoadEventsError> {
+        let mut projection = Self::new();
+        for tournament_id in repository.load_tournament_ids()? {
+            match projection.catch_up(tournament_id, repository) {
+                Err(LoadEventsError::EventsCorrupted) => error!("cannot project corrupted tournament {}", tournament_id),
+                result => result?,
+            }
+        }
+        Ok(projection)
+    }
+
+    pub fn catch_up<Repository: LoadEvents>(&mut self, tournament_id: Uuid, repository: &Repository) -> Result<(), LoadEventsError> {
+        let version = self.summaries.get(&tournament_id).map_or(0, |summary| summary.version);
+        let events = repository.load_tournament_events(tournament_id, version)?;
+        debug!("project {} event(s) of tournament {} from version {}", events.len(), tournament_id, version);
+        self.project(tournament_id, events)
+    }
+
+    pub fn project(&mut self, tournament_id: Uuid, events: impl IntoIterator<Item = TournamentEvent>) -> Result<(), LoadEventsError> {
+        for event in events {
+            self.project_event(tournament_id, event)?;
+        }
+        Ok(())
+    }
+
+    pub fn tournament_count(&self) -> usize {
+        self.summaries.len()
+    }
+
+    // Most recently joined first
+    pub fn player_history(&self, account_id: Uuid) -> Vec<PlayerHistoryEntry> {
+        self.histories.get(&account_id).map_or(vec![], |history| history.iter().rev().cloned().collect())
+    }
+
+    // The events were validated by the aggregate when they were committed, so an event that does
+    // not fit the projected state means the stream is corrupted
+    fn project_event(&mut self, tournament_id: Uuid, event: TournamentEvent) -> Result<(), LoadEventsError> {
+        if let TournamentEvent::TournamentCreated { id, spec } = event {
+            if id != tournament_id || self.summaries.contains_key(&tournament_id) {
+                return Err(LoadEventsError::EventsCorrupted);
+            }
+            self.summaries.insert(tournament_id, TournamentSummary {
+                tournament_id,
+                stage: TournamentStage::WaitingForPlayers,
+                table_seat_count: spec.table_seat_count(),
+                tables: vec![vec![]; spec.table_count() as usize],
+                version: 1,
+            });
+            return Ok(());
+        }
+        let summary = self.summaries.get_mut(&tournament_id).ok_or(LoadEventsError::EventsCorrupted)?;
+        match event {
+            TournamentEvent::PlayerJoined { account_id, nickname } => {
+                let seat_count = summary.table_seat_count as usize;
+                let table_number = summary.tables.iter().position(|players| players.len() < seat_count).ok_or(LoadEventsError::EventsCorrupted)?;
+                summary.tables[table_number].push(account_id);
+                if summary.free_seat_count() == 0 {
+                    summary.stage = TournamentStage::ReadyToStart;
+                }
+                self.histories.entry(account_id).or_default().push(PlayerHistoryEntry { tournament_id, nickname, rank: None });
+            },
+            TournamentEvent::TournamentStarted => {
+                summary.stage = TournamentStage::Running;
+            },
+            TournamentEvent::PlayerEliminated { account_id } => {
+                eliminate(summary, &mut self.histories, account_id)?;
+            },
+            TournamentEvent::PlayerLeft { account_id } if summary.stage == TournamentStage::Running => {
+                eliminate(summary, &mut self.histories, account_id)?;
+            },
+            // Leaving before the start gives up the registration
+            TournamentEvent::PlayerLeft { account_id } => {
+                let table_number = summary.players_table_number(account_id).ok_or(LoadEventsError::EventsCorrupted)?;
+                summary.tables[table_number].retain(|&player| player != account_id);
+                summary.stage = TournamentStage::WaitingForPlayers;
+                if let Some(history) = self.histories.get_mut(&account_id) {
+                    history.retain(|entry| entry.tournament_id != tournament_id);
+                }
+            },
+            TournamentEvent::PlayerMoved { account_id, from_table, to_table } => {
+                if summary.players_table_number(account_id) != Some(from_table) || to_table >= summary.table_count() {
+                    return Err(LoadEventsError::EventsCorrupted);
+                }
+                summary.tables[from_table].retain(|&player| player != account_id);
+                summary.tables[to_table].push(account_id);
+            },
+            TournamentEvent::TournamentCreated { .. } => unreachable!("handled above"),
+        }
+        summary.version += 1;
+        Ok(())
+    }
+}
+
+
+impl QueryTournaments for TournamentProjection {
+    fn query_tournaments(&self, query: &TournamentQuery) -> TournamentPage {
+        debug!("query tournaments {:?}", query);
+        query.evaluate(self.summaries.values())
+    }
+}
+
+
+fn eliminate(summary: &mut TournamentSummary, histories: &mut HashMap<Uuid, Vec<PlayerHistoryEntry>>, account_id: Uuid) -> Result<(), LoadEventsError> {
+    let rank = summary.player_count();
+    let table_number = summary.players_table_number(account_id).ok_or(LoadEventsError::EventsCorrupted)?;
+    summary.tables[table_number].retain(|&player| player != account_id);
+    set_rank(histories, summary.tournament_id, account_id, rank);
+    if summary.player_count() <= 1 {
+        summary.stage = TournamentStage::Finished;
+        if let Some(&winner) = summary.tables.iter().flatten().next() {
+            set_rank(histories, summary.tournament_id, winner, 1);
+        }
+    }
+    Ok(())
+}
+
+
+fn set_rank(histories: &mut HashMap<Uuid, Vec<PlayerHistoryEntry>>, tournament_id: Uuid, account_id: Uuid, rank: usize) {
+    let entry = histories.get_mut(&account_id)
+        .and_then(|history| history.iter_mut().find(|entry| entry.tournament_id == tournament_id));
+    if let Some(entry) = entry {
+        entry.rank = Some(rank);
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::domain::SaveTournament;
+    use crate::domain::Tournament;
+    use crate::domain::TournamentSpecification;
+    use crate::infrastructure::InMemoryTournamentRepository;
+
+    use super::*;
+
+    fn joined_tournament(table_count: u8, account_ids: &[Uuid]) -> Tournament {
+        let spec = TournamentSpecification::new(table_count, 2).unwrap();
+        let mut tournament = Tournament::new(&spec);
+        for (index, account_id) in account_ids.iter().enumerate() {
+            _ = tournament.join(*account_id, Nickname::new(format!("Player{}", index)).unwrap());
+        }
+        tournament
+    }
+
+    #[test]
+    fn summaries_follow_the_aggregate() {
+        let account_ids: Vec<Uuid> = (0..4).map(|_| Uuid::new_v4()).collect();
+        let mut tournament = joined_tournament(2, &account_ids);
+        tournament.start();
+        tournament.eliminate(account_ids[0], Nickname::new("Player0").unwrap()).unwrap();
+        let mut projection = TournamentProjection::new();
+        projection.project(tournament.id(), tournament.new_events()).unwrap();
+        let summary = &projection.summaries[&tournament.id()];
+        assert_eq!(summary.stage(), tournament.stage());
+        assert_eq!(summary.player_count(), 3);
+        for account_id in &account_ids {
+            assert_eq!(summary.players_table_number(*account_id), tournament.players_table_number(*account_id));
+        }
+        assert_eq!(summary.version, tournament.new_events().len());
+    }
+
+    #[test]
+    fn player_history_records_ranks() {
+        let account_ids: Vec<Uuid> = (0..2).map(|_| Uuid::new_v4()).collect();
+        let mut tournament = joined_tournament(1, &account_ids);
+        let mut projection = TournamentProjection::new();
+        projection.project(tournament.id(), tournament.new_events()).unwrap();
+        assert_eq!(projection.player_history(account_ids[0])[0].rank, None);
+        tournament.mark_saved();
+        tournament.start();
+        tournament.eliminate(account_ids[0], Nickname::new("Player0").unwrap()).unwrap();
+        projection.project(tournament.id(), tournament.new_events()).unwrap();
+        assert_eq!(projection.player_history(account_ids[0]), vec![PlayerHistoryEntry {
+            tournament_id: tournament.id(),
+            nickname: Nickname::new("Player0").unwrap(),
+            rank: Some(2),
+        }]);
+        assert_eq!(projection.player_history(account_ids[1])[0].rank, Some(1));
+        assert_eq!(projection.player_history(Uuid::new_v4()), vec![]);
+    }
+
+    #[test]
+    fn player_history_follows_leaving_players() {
+        let account_ids: Vec<Uuid> = (0..3).map(|_| Uuid::new_v4()).collect();
+        let mut tournament = joined_tournament(2, &account_ids);
+        tournament.leave(account_ids[2]).unwrap();
+        let mut projection = TournamentProjection::new();
+        projection.project(tournament.id(), tournament.new_events()).unwrap();
+        assert_eq!(projection.player_history(account_ids[2]), vec![]);
+        assert_eq!(projection.summaries[&tournament.id()].player_count(), 2);
+
+        tournament.mark_saved();
+        _ = tournament.join(account_ids[2], Nickname::new("Player2").unwrap());
+        _ = tournament.join(Uuid::new_v4(), Nickname::new("Player3").unwrap());
+        tournament.start();
+        tournament.leave(account_ids[0]).unwrap();
+        projection.project(tournament.id(), tournament.new_events()).unwrap();
+        assert_eq!(projection.player_history(account_ids[0])[0].rank, Some(4));
+        assert_eq!(projection.player_history(account_ids[2]).len(), 1);
+        let summary = &projection.summaries[&tournament.id()];
+        assert_eq!(summary.stage(), tournament.stage());
+        for account_id in &account_ids {
+            assert_eq!(summary.players_table_number(*account_id), tournament.players_table_number(*account_id));
+        }
+    }
+
+    #[test]
+    fn catch_up_and_rebuild_from_stored_events() {
+        let mut repository = InMemoryTournamentRepository::new();
+        let account_id = Uuid::new_v4();
+        let tournament = joined_tournament(1, &[account_id]);
+        let tournament_id = tournament.id();
+        repository.save_tournament(tournament).unwrap();
+        let mut projection = TournamentProjection::new();
+        projection.catch_up(tournament_id, &repository).unwrap();
+        projection.catch_up(tournament_id, &repository).unwrap();
+        assert_eq!(projection.summaries[&tournament_id].player_count(), 1);
+        assert_eq!(projection.player_history(account_id).len(), 1);
+
+        repository.save_tournament(joined_tournament(2, &[])).unwrap();
+        let rebuilt = TournamentProjection::rebuild(&repository).unwrap();
+        assert_eq!(rebuilt.summaries.len(), 2);
+        assert_eq!(rebuilt.summaries[&tournament_id], projection.summaries[&tournament_id]);
+        assert_eq!(rebuilt.player_history(account_id), projection.player_history(account_id));
+    }
+
+    #[test]
+    fn inconsistent_events_are_rejected() {
+        let mut projection = TournamentProjection::new();
+        let tournament = joined_tournament(1, &[]);
+        assert!(matches!(projection.project(tournament.id(), [TournamentEvent::TournamentStarted]), Err(LoadEventsError::EventsCorrupted)));
+        projection.project(tournament.id(), tournament.new_events()).unwrap();
+        assert!(matches!(projection.project(tournament.id(), tournament.new_events()), Err(LoadEventsError::EventsCorrupted)));
+    }
+}
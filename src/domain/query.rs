@@ -0,0 +1,199 @@
+use super::projection::TournamentSummary;
+use super::tournament::TournamentStage;
+
+use uuid::Uuid;
+
+use std::cmp::Ordering;
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum TournamentSortKey {
+    #[default]
+    Players,
+    FreeSeats,
+    TableSeats,
+    Tables,
+}
+
+impl TournamentSortKey {
+    pub fn value(&self, tournament: &TournamentSummary) -> usize {
+        match self {
+            Self::Players => tournament.player_count(),
+            Self::FreeSeats => tournament.free_seat_count(),
+            Self::TableSeats => tournament.table_seat_count() as usize,
+            Self::Tables => tournament.table_count(),
+        }
+    }
+}
+
+
+// Position after the last tournament of a page: its sort value, ties broken by id
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TournamentCursor {
+    pub sort_value: usize,
+    pub tournament_id: Uuid,
+}
+
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TournamentQuery {
+    pub stages: Vec<TournamentStage>,   // Empty matches all stages
+    pub table_seat_count: Option<u8>,
+    pub joined_by: Option<Uuid>,
+    pub with_free_seats: bool,
+    pub sort_key: TournamentSortKey,
+    pub descending: bool,
+    pub cursor: Option<TournamentCursor>,
+    pub limit: usize,
+}
+
+impl Default for TournamentQuery {
+    fn default() -> Self {
+        Self {
+            stages: vec![],
+            table_seat_count: None,
+            joined_by: None,
+            with_free_seats: false,
+            sort_key: TournamentSortKey::default(),
+            descending: false,
+            cursor: None,
+            limit: 20,
+        }
+    }
+}
+
+impl TournamentQuery {
+    pub fn matches(&self, tournament: &TournamentSummary) -> bool {
+        (self.stages.is_empty() || self.stages.contains(&tournament.stage()))
+            && self.table_seat_count.is_none_or(|seat_count| tournament.table_seat_count() == seat_count)
+            && self.joined_by.is_none_or(|account_id| tournament.players_table_number(account_id).is_some())
+            && (!self.with_free_seats || tournament.free_seat_count() > 0)
+    }
+
+    pub fn compare(&self, a: &TournamentSummary, b: &TournamentSummary) -> Ordering {
+        self.compare_positions((self.sort_key.value(a), a.id()), (self.sort_key.value(b), b.id()))
+    }
+
+    pub fn is_after_cursor(&self, tournament: &TournamentSummary) -> bool {
+        self.cursor.is_none_or(|cursor| {
+            let position = (self.sort_key.value(tournament), tournament.id());
+            self.compare_positions(position, (cursor.sort_value, cursor.tournament_id)) == Ordering::Greater
+        })
+    }
+
+    pub fn cursor_of(&self, tournament: &TournamentSummary) -> TournamentCursor {
+        TournamentCursor { sort_value: self.sort_key.value(tournament), tournament_id: tournament.id() }
+    }
+
+    // Evaluates the query on the complete set of tournament summaries
+    pub fn evaluate<'a>(&self, tournaments: impl IntoIterator<Item = &'a TournamentSummary>) -> TournamentPage {
+        let mut matching: Vec<&TournamentSummary> = tournaments.into_iter().filter(|tournament| self.matches(tournament)).collect();
+        let total_count = matching.len();
+        matching.sort_by(|a, b| self.compare(a, b));
+        let mut page: Vec<TournamentSummary> = matching.into_iter()
+            .filter(|tournament| self.is_after_cursor(tournament))
+            .take(self.limit + 1)
+            .cloned()
+            .collect();
+        let next_cursor = if page.len() > self.limit {
+            page.truncate(self.limit);
+            page.last().map(|tournament| self.cursor_of(tournament))
+        } else {
+            None
+        };
+        TournamentPage { tournaments: page, total_count, next_cursor }
+    }
+
+    fn compare_positions(&self, a: (usize, Uuid), b: (usize, Uuid)) -> Ordering {
+        let ordering = a.0.cmp(&b.0);
+        let ordering = if self.descending { ordering.reverse() } else { ordering };
+        ordering.then(a.1.cmp(&b.1))
+    }
+}
+
+
+#[derive(Debug)]
+pub struct TournamentPage {
+    pub tournaments: Vec<TournamentSummary>,
+    pub total_count: usize,                     // Number of tournaments matching the filters
+    pub next_cursor: Option<TournamentCursor>,  // None if this is the last page
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::Nickname;
+    use crate::domain::QueryTournaments;
+    use crate::domain::Tournament;
+    use crate::domain::TournamentProjection;
+    use crate::domain::TournamentSpecification;
+
+    fn tournament_with_players(table_seat_count: u8, player_count: usize) -> Tournament {
+        let spec = TournamentSpecification::new(1, table_seat_count).unwrap();
+        let mut tournament = Tournament::new(&spec);
+        for _ in 0..player_count {
+            _ = tournament.join(Uuid::new_v4(), Nickname::new("James").unwrap());
+        }
+        tournament
+    }
+
+    fn projection_of(tournaments: &[Tournament]) -> TournamentProjection {
+        let mut projection = TournamentProjection::new();
+        for tournament in tournaments {
+            projection.project(tournament.id(), tournament.new_events()).unwrap();
+        }
+        projection
+    }
+
+    fn ids(page: &TournamentPage) -> Vec<Uuid> {
+        page.tournaments.iter().map(|tournament| tournament.id()).collect()
+    }
+
+    #[test]
+    fn evaluate_with_filters() {
+        let tournaments = vec![
+            tournament_with_players(2, 2),
+            tournament_with_players(2, 1),
+            tournament_with_players(6, 3),
+        ];
+        let projection = projection_of(&tournaments);
+        let query = TournamentQuery { with_free_seats: true, ..TournamentQuery::default() };
+        assert_eq!(projection.query_tournaments(&query).total_count, 2);
+        let query = TournamentQuery { table_seat_count: Some(2), ..TournamentQuery::default() };
+        assert_eq!(projection.query_tournaments(&query).total_count, 2);
+        let query = TournamentQuery { stages: vec![TournamentStage::ReadyToStart], ..TournamentQuery::default() };
+        let page = projection.query_tournaments(&query);
+        assert_eq!(ids(&page), vec![tournaments[0].id()]);
+    }
+
+    #[test]
+    fn evaluate_joined_by() {
+        let account_id = Uuid::new_v4();
+        let mut tournaments = vec![tournament_with_players(3, 1), tournament_with_players(3, 1)];
+        _ = tournaments[1].join(account_id, Nickname::new("Daniel").unwrap());
+        let projection = projection_of(&tournaments);
+        let query = TournamentQuery { joined_by: Some(account_id), ..TournamentQuery::default() };
+        let page = projection.query_tournaments(&query);
+        assert_eq!(ids(&page), vec![tournaments[1].id()]);
+    }
+
+    #[test]
+    fn evaluate_sorted_pages() {
+        let tournaments: Vec<_> = [1, 4, 2, 2, 3].into_iter().map(|player_count| tournament_with_players(6, player_count)).collect();
+        let projection = projection_of(&tournaments);
+        let mut query = TournamentQuery { descending: true, limit: 2, ..TournamentQuery::default() };
+        let mut player_counts = vec![];
+        loop {
+            let page = projection.query_tournaments(&query);
+            assert_eq!(page.total_count, 5);
+            assert!(page.tournaments.len() <= 2);
+            player_counts.extend(page.tournaments.iter().map(|tournament| tournament.player_count()));
+            match page.next_cursor {
+                Some(cursor) => query.cursor = Some(cursor),
+                None => break,
+            }
+        }
+        assert_eq!(player_counts, vec![4, 3, 2, 2, 1]);
+    }
+}
@@ -0,0 +1,30 @@
+use super::table::TableError;
+use super::tournament::TournamentError;
+
+use thiserror::Error;
+
+
+#[derive(Debug, Error)]
+#[error("Cannot restore from event {event_index}: {reason}")]
+pub struct RestoreError {
+    pub event_index: usize,
+    pub reason: RestoreErrorReason,
+}
+
+#[derive(Debug, Error)]
+pub enum RestoreErrorReason {
+    #[error("No events found")]
+    NoEvents,
+    #[error("Expected the tournament to be created first")]
+    TournamentNotCreated,
+    #[error("Tournament already created")]
+    TournamentAlreadyCreated,
+    #[error("Expected the table to be created first")]
+    TableNotCreated,
+    #[error("Table already created")]
+    TableAlreadyCreated,
+    #[error(transparent)]
+    TournamentError(#[from] TournamentError),
+    #[error(transparent)]
+    TableError(#[from] TableError),
+}
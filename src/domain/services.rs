@@ -1,16 +1,109 @@
+use crate::domain::AccessEventStreams;
+use crate::domain::AccessOutbox;
+use crate::domain::AccessTables;
+use crate::domain::EventStream;
+use crate::domain::LoadOutboxError;
+use crate::domain::LoadTournament;
+use crate::domain::MarkDeliveredError;
+use crate::domain::PendingEvents;
 use crate::domain::PublishTournamentMessages;
 use crate::domain::SaveTournament;
 use crate::domain::SaveTournamentError;
 use crate::domain::Tournament;
+use crate::domain::TournamentProjection;
+use crate::domain::TournamentMessageType;
+use crate::domain::process_events;
 
+use log::warn;
+use thiserror::Error;
 
-pub fn save_tournament_and_publish_messages<Repository: SaveTournament, Publisher: PublishTournamentMessages>(
-    mut tournament: Tournament,
+
+// Kept in memory to follow the events of saved tournaments: the lobby projection, and the
+// tournaments whose events are left to the pending event processing
+#[derive(Debug)]
+pub struct EventFollowers {
+    pub projection: TournamentProjection,
+    pub pending: PendingEvents,
+}
+
+impl EventFollowers {
+    pub fn new() -> Self {
+        Self { projection: TournamentProjection::new(), pending: PendingEvents::new() }
+    }
+}
+
+
+#[derive(Debug, Error)]
+pub enum SaveTournamentAndPublishMessagesError {
+    #[error(transparent)]
+    SaveTournamentError(#[from] SaveTournamentError),
+}
+
+
+// Saves the tournament and lets the process manager bring its tables in line with the new events.
+// The messages of all of them are saved to the outbox along the way and relayed right away. Should
+// relaying fail, they stay in the outbox for the next relay. The projection then catches up with
+// the committed events; if that fails it lags behind until the next commit or rebuild. Once the
+// tournament is saved the command has succeeded, so a failure to process its events is only
+// logged and the tournament left to the pending event processing of the relay.
+pub fn save_tournament_and_publish_messages<Repository, Publisher>(
+    tournament: Tournament,
     repository: &mut Repository,
-    publisher: &Publisher,
-) -> Result<(), SaveTournamentError> {
-    let tournament_messages = tournament.collect_messages();
+    publisher: &mut Publisher,
+    followers: &mut EventFollowers,
+) -> Result<(), SaveTournamentAndPublishMessagesError>
+where
+    Repository: LoadTournament + SaveTournament + AccessTables + AccessEventStreams + AccessOutbox,
+    Publisher: PublishTournamentMessages,
+{
+    let tournament_id = tournament.id();
     repository.save_tournament(tournament)?;
-    publisher.publish_tournament_messages(tournament_messages);
+    if let Err(error) = process_events([EventStream::Tournament { tournament_id }], repository) {
+        warn!("cannot process events of tournament {}: {}", tournament_id, error);
+        followers.pending.add(tournament_id);
+    }
+    if let Err(error) = relay_outbox_messages(repository, publisher) {
+        warn!("cannot relay messages of tournament {}: {}", tournament_id, error);
+    }
+    if let Err(error) = followers.projection.catch_up(tournament_id, repository) {
+        warn!("cannot project events of tournament {}: {}", tournament_id, error);
+    }
     Ok(())
 }
+
+
+#[derive(Debug, Error)]
+pub enum RelayOutboxMessagesError {
+    #[error(transparent)]
+    LoadOutboxError(#[from] LoadOutboxError),
+    #[error(transparent)]
+    MarkDeliveredError(#[from] MarkDeliveredError),
+}
+
+
+// Publishes the pending messages of the outbox and marks them delivered, returning their number.
+// Entries published before marking them failed are handed to the publisher again, which skips them.
+pub fn relay_outbox_messages<Repository, Publisher>(
+    repository: &mut Repository,
+    publisher: &mut Publisher,
+) -> Result<usize, RelayOutboxMessagesError>
+where
+    Repository: AccessOutbox,
+    Publisher: PublishTournamentMessages,
+{
+    let entries = repository.load_pending_messages()?;
+    let Some(last_position) = entries.last().map(|entry| entry.position) else {
+        return Ok(0);
+    };
+    let count = entries.len();
+    let finished_tournament_ids: Vec<_> = entries.iter()
+        .filter(|entry| entry.message.message_type == TournamentMessageType::TournamentFinished)
+        .map(|entry| entry.message.tournament_id)
+        .collect();
+    publisher.publish_outbox_entries(entries);
+    for tournament_id in finished_tournament_ids {
+        publisher.close_tournament_channels(tournament_id);
+    }
+    repository.mark_delivered(last_position)?;
+    Ok(count)
+}
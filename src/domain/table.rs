@@ -1,5 +1,9 @@
 use super::nickname::Nickname;
 use super::player::Player;
+use super::restore::RestoreError;
+use super::restore::RestoreErrorReason;
+use super::tournament::TournamentMessage;
+use super::tournament::TournamentMessageType;
 
 use thiserror::Error;
 use uuid::Uuid;
@@ -28,6 +32,10 @@ impl TableSpecification {
             Ok(Self { seat_count })
         }
     }
+
+    pub fn seat_count(&self) -> u8 {
+        self.seat_count
+    }
 }
 
 
@@ -35,27 +43,122 @@ impl TableSpecification {
 pub enum TableError {
     #[error("Not player's turn")]
     NotPlayersTurn,
+    #[error("Player already seated")]
+    PlayerAlreadySeated,
+    #[error("Player not seated")]
+    PlayerNotSeated,
+    #[error("No free seat")]
+    NoFreeSeat,
+    #[error("Seat {position} is not free")]
+    SeatNotFree { position: usize },
+}
+
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct SeatState {
+    pub nickname: Nickname,
+    pub stack: u32,
 }
 
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
 pub struct TableState {
+    pub seats: Vec<Option<SeatState>>,
+}
+
+impl TableState {
+    // Brings the state in line with a message of the table, as observers do
+    pub fn apply(&mut self, message: &TableMessage) {
+        match message {
+            TableMessage::PlayerSeated { nickname, stack, position } => if let Some(seat) = self.seats.get_mut(*position) {
+                *seat = Some(SeatState { nickname: nickname.clone(), stack: *stack });
+            },
+            TableMessage::PlayerLeft { position } | TableMessage::PlayerWipedOut { position } => if let Some(seat) = self.seats.get_mut(*position) {
+                *seat = None;
+            },
+            TableMessage::GameStarted { .. } => {},
+        }
+    }
+}
+
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TableEvent {
+    TableCreated {
+        tournament_id: Uuid,
+        table_number: usize,
+        spec: TableSpecification,
+    },
+    PlayerSeated {
+        account_id: Uuid,
+        nickname: Nickname,
+        stack: u32,
+        position: usize,
+    },
+    PlayerLeft {
+        position: usize,
+    },
+    PlayerWipedOut {
+        account_id: Uuid,
+        nickname: Nickname,
+        position: usize,
+    },
 }
 
 
+// Tables are identified by their tournament and their number within it
 #[derive(Debug, Clone)]
 pub struct Table {
+    tournament_id: Uuid,
+    table_number: usize,
     seats: Vec<Option<Player>>,
     messages: Vec<TableMessage>,
+    events: Vec<TableEvent>,     // Events not saved yet
+    version: usize,     // Number of events already saved
 }
 
 impl Table {
-    pub fn new(spec: &TableSpecification) -> Self {
-        let mut seats = vec![];
-        for _ in 0..spec.seat_count {
-            seats.push(None);
+    pub fn new(tournament_id: Uuid, table_number: usize, spec: &TableSpecification) -> Self {
+        let mut table = Self::create(tournament_id, table_number, spec);
+        table.events.push(TableEvent::TableCreated { tournament_id, table_number, spec: spec.clone() });
+        table
+    }
+
+    pub fn restore(events: impl IntoIterator<Item = TableEvent>) -> Result<Self, RestoreError> {
+        let mut event_iterator = events.into_iter();
+        let mut table = match event_iterator.next() {
+            Some(TableEvent::TableCreated { tournament_id, table_number, spec }) => Self::create(tournament_id, table_number, &spec),
+            Some(_) => return Err(RestoreError { event_index: 0, reason: RestoreErrorReason::TableNotCreated }),
+            None => return Err(RestoreError { event_index: 0, reason: RestoreErrorReason::NoEvents }),
+        };
+        table.version = 1;
+        for event in event_iterator {
+            table.apply(event).map_err(|reason| RestoreError { event_index: table.version, reason })?;
+            table.version += 1;
         }
-        Self { seats, messages: vec![] }
+        table.messages.clear();
+        Ok(table)
+    }
+
+    pub fn tournament_id(&self) -> Uuid {
+        self.tournament_id
+    }
+
+    pub fn table_number(&self) -> usize {
+        self.table_number
+    }
+
+    pub fn version(&self) -> usize {
+        self.version
+    }
+
+    pub fn new_events(&self) -> Vec<TableEvent> {
+        self.events.clone()
+    }
+
+    pub fn mark_saved(&mut self) {
+        self.version += self.events.len();
+        self.events.clear();
     }
 
     pub fn spec(&self) -> TableSpecification {
@@ -63,7 +166,10 @@ impl Table {
     }
 
     pub fn state(&self) -> TableState {
-        TableState {}
+        let seats = self.seats.iter().map(|seat| {
+            seat.as_ref().map(|player| SeatState { nickname: player.nickname().clone(), stack: player.stack() })
+        }).collect();
+        TableState { seats }
     }
 
     pub fn has_free_seat(&self) -> bool {
@@ -79,30 +185,34 @@ impl Table {
     }
 
     pub fn has_player(&self, account_id: Uuid) -> bool {
-        self.seats.iter().flatten().any(|player| player.account_id() == account_id)
+        self.player_position(account_id).is_some()
     }
 
-    pub fn sit_down(&mut self, account_id: Uuid, nickname: Nickname, stack: u32) {
-        let position = self.seats.iter_mut().position(|seat| seat.is_none()).unwrap();
-        let player = Player::new(account_id, nickname.clone(), stack);
-        _ = self.seats[position].insert(player);
-        self.messages.push(
-            TableMessage::PlayerSeated {
-                nickname,
-                stack,
-                position,
-            }
-        );
+    pub fn player_seat(&self, account_id: Uuid) -> Option<SeatState> {
+        self.seats.iter().flatten().find(|player| player.account_id() == account_id).map(|player| {
+            SeatState { nickname: player.nickname().clone(), stack: player.stack() }
+        })
     }
 
-    pub fn stand_up(&mut self, account_id: Uuid) {
-        let position = self.player_position(account_id).unwrap();
-        self.seats[position].take();
-        self.messages.push(
-            TableMessage::PlayerLeft {
-                position,
-            }
-        );
+    pub fn sit_down(&mut self, account_id: Uuid, nickname: Nickname, stack: u32) -> Result<usize, TableError> {
+        if self.has_player(account_id) {
+            return Err(TableError::PlayerAlreadySeated);
+        }
+        let position = self.seats.iter().position(|seat| seat.is_none()).ok_or(TableError::NoFreeSeat)?;
+        self.apply_and_push_event(TableEvent::PlayerSeated { account_id, nickname, stack, position })?;
+        Ok(position)
+    }
+
+    pub fn stand_up(&mut self, account_id: Uuid) -> Result<(), TableError> {
+        let position = self.player_position(account_id).ok_or(TableError::PlayerNotSeated)?;
+        self.apply_and_push_event(TableEvent::PlayerLeft { position })
+    }
+
+    // Removes a player who lost their whole stack, which the tournament learns about from the event
+    pub fn wipe_out(&mut self, account_id: Uuid) -> Result<(), TableError> {
+        let position = self.player_position(account_id).ok_or(TableError::PlayerNotSeated)?;
+        let nickname = self.seats[position].as_ref().unwrap().nickname().clone();
+        self.apply_and_push_event(TableEvent::PlayerWipedOut { account_id, nickname, position })
     }
 
     pub fn can_start_game(&self) -> bool {
@@ -122,6 +232,63 @@ impl Table {
         std::mem::take(&mut self.messages)
     }
 
+    // Collects the messages addressed to the channel of this table
+    pub fn collect_tournament_messages(&mut self) -> Vec<TournamentMessage> {
+        let (tournament_id, table_number) = (self.tournament_id, self.table_number);
+        self.collect_messages().into_iter().map(|message_type| TournamentMessage {
+            tournament_id,
+            message_type: TournamentMessageType::TableMessage { table_number, message_type },
+        }).collect()
+    }
+
+    fn create(tournament_id: Uuid, table_number: usize, spec: &TableSpecification) -> Self {
+        Self {
+            tournament_id,
+            table_number,
+            seats: vec![None; spec.seat_count as usize],
+            messages: vec![],
+            events: vec![],
+            version: 0,
+        }
+    }
+
+    fn apply_and_push_event(&mut self, event: TableEvent) -> Result<(), TableError> {
+        self.apply(event.clone()).map_err(|reason| match reason {
+            RestoreErrorReason::TableError(error) => error,
+            _ => unreachable!("tables only create events they can apply"),
+        })?;
+        self.events.push(event);
+        Ok(())
+    }
+
+    fn apply(&mut self, event: TableEvent) -> Result<(), RestoreErrorReason> {
+        match event {
+            TableEvent::TableCreated { .. } => return Err(RestoreErrorReason::TableAlreadyCreated),
+            TableEvent::PlayerSeated { account_id, nickname, stack, position } => {
+                let seat = self.seats.get_mut(position).ok_or(TableError::SeatNotFree { position })?;
+                if seat.is_some() {
+                    return Err(TableError::SeatNotFree { position }.into());
+                }
+                _ = seat.insert(Player::new(account_id, nickname.clone(), stack));
+                self.messages.push(TableMessage::PlayerSeated { nickname, stack, position });
+            },
+            TableEvent::PlayerLeft { position } => {
+                let seat = self.seats.get_mut(position).ok_or(TableError::PlayerNotSeated)?;
+                seat.take().ok_or(TableError::PlayerNotSeated)?;
+                self.messages.push(TableMessage::PlayerLeft { position });
+            },
+            TableEvent::PlayerWipedOut { account_id, position, .. } => {
+                let seat = self.seats.get_mut(position).ok_or(TableError::PlayerNotSeated)?;
+                if seat.as_ref().is_none_or(|player| player.account_id() != account_id) {
+                    return Err(TableError::PlayerNotSeated.into());
+                }
+                seat.take();
+                self.messages.push(TableMessage::PlayerWipedOut { position });
+            },
+        }
+        Ok(())
+    }
+
     fn player_position(&self, account_id: Uuid) -> Option<usize> {
         self.seats.iter().position(|seat| {
             seat.as_ref().is_some_and(|player| player.account_id() == account_id)
@@ -130,7 +297,7 @@ impl Table {
 }
 
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
 pub enum TableMessage {
     PlayerSeated {
         nickname: Nickname,
@@ -140,8 +307,94 @@ pub enum TableMessage {
     PlayerLeft {
         position: usize,
     },
+    PlayerWipedOut {
+        position: usize,
+    },
     GameStarted {
         button: u8,
         // TODO: further information, blinds, etc.
     },
 }
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table_with_players(nicknames: &[&str]) -> Table {
+        let spec = TableSpecification::new(3).unwrap();
+        let mut table = Table::new(Uuid::new_v4(), 0, &spec);
+        for nickname in nicknames {
+            table.sit_down(Uuid::new_v4(), Nickname::new(*nickname).unwrap(), 1500).unwrap();
+        }
+        table
+    }
+
+    #[test]
+    fn table_sit_down_and_stand_up() {
+        let mut table = table_with_players(&["James"]);
+        let account_id = Uuid::new_v4();
+        assert_eq!(table.sit_down(account_id, Nickname::new("Daniel").unwrap(), 1500).unwrap(), 1);
+        assert!(matches!(table.sit_down(account_id, Nickname::new("Daniel").unwrap(), 1500), Err(TableError::PlayerAlreadySeated)));
+        table.stand_up(account_id).unwrap();
+        assert!(!table.has_player(account_id));
+        assert!(matches!(table.stand_up(account_id), Err(TableError::PlayerNotSeated)));
+        assert_eq!(table.new_events().len(), 4);
+        assert_eq!(table.collect_messages(), vec![
+            TableMessage::PlayerSeated { nickname: Nickname::new("James").unwrap(), stack: 1500, position: 0 },
+            TableMessage::PlayerSeated { nickname: Nickname::new("Daniel").unwrap(), stack: 1500, position: 1 },
+            TableMessage::PlayerLeft { position: 1 },
+        ]);
+    }
+
+    #[test]
+    fn table_wipe_out() {
+        let mut table = table_with_players(&["James", "Daniel"]);
+        let account_id = table.seats[1].as_ref().unwrap().account_id();
+        table.collect_messages();
+        table.wipe_out(account_id).unwrap();
+        assert_eq!(table.player_seat(account_id), None);
+        assert!(matches!(table.wipe_out(account_id), Err(TableError::PlayerNotSeated)));
+        assert_eq!(table.new_events().last(), Some(&TableEvent::PlayerWipedOut {
+            account_id, nickname: Nickname::new("Daniel").unwrap(), position: 1
+        }));
+        assert_eq!(table.collect_messages(), vec![TableMessage::PlayerWipedOut { position: 1 }]);
+        assert_eq!(Table::restore(table.new_events()).unwrap().state(), table.state());
+    }
+
+    #[test]
+    fn table_state_follows_messages() {
+        let mut table = table_with_players(&["James", "Patricia", "Daniel"]);
+        let account_id = table.seats[1].as_ref().unwrap().account_id();
+        table.wipe_out(account_id).unwrap();
+        let mut state = TableState { seats: vec![None; 3] };
+        for message in table.collect_messages() {
+            state.apply(&message);
+        }
+        assert_eq!(state, table.state());
+    }
+
+    #[test]
+    fn table_restore() {
+        let table = table_with_players(&["James", "Patricia", "Daniel"]);
+        let mut restored = Table::restore(table.new_events()).unwrap();
+        assert_eq!(restored.tournament_id(), table.tournament_id());
+        assert_eq!(restored.state(), table.state());
+        assert_eq!(restored.version(), 4);
+        assert_eq!(restored.new_events(), vec![]);
+        assert_eq!(restored.collect_messages(), vec![]);
+        assert!(matches!(restored.sit_down(Uuid::new_v4(), Nickname::new("Mary").unwrap(), 1500), Err(TableError::NoFreeSeat)));
+    }
+
+    #[test]
+    fn restore_corrupt_table_event_streams() {
+        let mut events = table_with_players(&["James"]).new_events();
+        events.push(events[1].clone());
+        let result = Table::restore(events.clone());
+        assert!(matches!(result, Err(RestoreError {
+            event_index: 2, reason: RestoreErrorReason::TableError(TableError::SeatNotFree { position: 0 })
+        })));
+        let result = Table::restore(events[1..].to_vec());
+        assert!(matches!(result, Err(RestoreError { event_index: 0, reason: RestoreErrorReason::TableNotCreated })));
+    }
+}
@@ -1,10 +1,11 @@
 use super::nickname::Nickname;
-use super::table::Table;
+use super::observation::ObservationPolicy;
+use super::restore::RestoreError;
+use super::restore::RestoreErrorReason;
 use super::table::TableError;
 use super::table::TableMessage;
 use super::table::TableSpecification;
 use super::table::TableSpecificationError;
-use super::table::TableState;
 
 use log::debug;
 use thiserror::Error;
@@ -17,6 +18,8 @@ pub enum TournamentSpecificationError {
     NotEnoughTables { min: u8, found: u8 },
     #[error("There must not be more than {max} tables, but found {found}")]
     TooManyTables { max: u8, found: u8 },
+    #[error("The spectator delay must not exceed {max_secs} seconds, but found {found_secs}")]
+    SpectatorDelayTooLong { max_secs: u64, found_secs: u64 },
     #[error(transparent)]
     TableSpecificationError(#[from] TableSpecificationError)
 }
@@ -25,6 +28,7 @@ pub enum TournamentSpecificationError {
 pub struct TournamentSpecification {
     table_count: u8,
     table_spec: TableSpecification,
+    observation_policy: ObservationPolicy,
 }
 
 impl TournamentSpecification {
@@ -37,9 +41,36 @@ impl TournamentSpecification {
             Err(TournamentSpecificationError::TooManyTables { max: MAX_TABLES, found: table_count })
         } else {
             let table_spec = TableSpecification::new(table_seat_count)?;
-            Ok(Self { table_count, table_spec })
+            Ok(Self { table_count, table_spec, observation_policy: ObservationPolicy::default() })
         }
     }
+
+    pub fn with_observation_policy(self, observation_policy: ObservationPolicy) -> Result<Self, TournamentSpecificationError> {
+        if observation_policy.spectator_delay > ObservationPolicy::MAX_SPECTATOR_DELAY {
+            Err(TournamentSpecificationError::SpectatorDelayTooLong {
+                max_secs: ObservationPolicy::MAX_SPECTATOR_DELAY.as_secs(),
+                found_secs: observation_policy.spectator_delay.as_secs(),
+            })
+        } else {
+            Ok(Self { observation_policy, ..self })
+        }
+    }
+
+    pub fn table_count(&self) -> u8 {
+        self.table_count
+    }
+
+    pub fn table_seat_count(&self) -> u8 {
+        self.table_spec.seat_count()
+    }
+
+    pub fn table_spec(&self) -> TableSpecification {
+        self.table_spec.clone()
+    }
+
+    pub fn observation_policy(&self) -> ObservationPolicy {
+        self.observation_policy
+    }
 }
 
 
@@ -58,6 +89,10 @@ pub enum TournamentError {
     TournamentAlreadyStarted,
     #[error("Player already joined")]
     PlayerAlreadyJoined,
+    #[error("Tournament not ready to start")]
+    TournamentNotReadyToStart,
+    #[error("Tournament not running")]
+    TournamentNotRunning,
     #[error("No such table")]
     NotSuchTable,
     #[error(transparent)]
@@ -74,43 +109,98 @@ pub enum TournamentEvent {
     PlayerJoined {
         account_id: Uuid,
         nickname: Nickname,
-    }
+    },
+    TournamentStarted,
+    PlayerEliminated {
+        account_id: Uuid,
+    },
+    PlayerMoved {
+        account_id: Uuid,
+        from_table: usize,
+        to_table: usize,
+    },
+    PlayerLeft {
+        account_id: Uuid,
+    },
 }
 
 
+pub const STARTING_STACK: u32 = 1500;
+
+
+// Tables are separate aggregates, so the tournament only tracks who is seated at which table
 #[derive(Debug, Clone)]
 pub struct Tournament {
     id: Uuid,
     stage: TournamentStage,
-    tables: Vec<Table>,
+    level: u32,
+    table_spec: TableSpecification,
+    observation_policy: ObservationPolicy,
+    tables: Vec<Vec<Uuid>>,     // Account ids of the players seated at each table
     messages: Vec<TournamentMessage>,
-    events: Vec<TournamentEvent>,
+    events: Vec<TournamentEvent>,     // Events not saved yet
+    version: usize,     // Number of events already saved
 }
 
 impl Tournament {
     pub fn new(spec: &TournamentSpecification) -> Self {
-        Self::create(Uuid::new_v4(), spec)
+        let id = Uuid::new_v4();
+        let mut tournament = Self::create(id, spec);
+        tournament.events.push(TournamentEvent::TournamentCreated { id, spec: spec.clone() });
+        tournament
     }
 
-    pub fn restore(events: impl IntoIterator<Item = TournamentEvent>) -> Self {
+    pub fn restore(events: impl IntoIterator<Item = TournamentEvent>) -> Result<Self, RestoreError> {
         let mut event_iterator = events.into_iter();
-        let first_event = event_iterator.next().unwrap();
-        let mut tournament = match first_event {
-            TournamentEvent::TournamentCreated { id, spec } => {
-                Self::create(id, &spec)
-            },
-            _ => panic!("programming error")
+        let mut tournament = match event_iterator.next() {
+            Some(TournamentEvent::TournamentCreated { id, spec }) => Self::create(id, &spec),
+            Some(_) => return Err(RestoreError { event_index: 0, reason: RestoreErrorReason::TournamentNotCreated }),
+            None => return Err(RestoreError { event_index: 0, reason: RestoreErrorReason::NoEvents }),
         };
-        for event in event_iterator {
-            tournament.apply(event);
+        tournament.version = 1;
+        tournament.replay(event_iterator)
+    }
+
+    // Restores the tournament from a snapshot and the events saved after it was taken
+    pub fn restore_from_snapshot(snapshot: TournamentSnapshot, events: impl IntoIterator<Item = TournamentEvent>) -> Result<Self, RestoreError> {
+        let tournament = Self {
+            id: snapshot.tournament_id,
+            stage: snapshot.stage,
+            level: snapshot.level,
+            table_spec: snapshot.spec.table_spec,
+            observation_policy: snapshot.spec.observation_policy,
+            tables: snapshot.tables,
+            messages: vec![],
+            events: vec![],
+            version: snapshot.version,
+        };
+        tournament.replay(events)
+    }
+
+    pub fn snapshot(&self) -> TournamentSnapshot {
+        TournamentSnapshot {
+            tournament_id: self.id,
+            version: self.version + self.events.len(),
+            stage: self.stage.clone(),
+            level: self.level,
+            spec: self.spec(),
+            tables: self.tables.clone(),
         }
-        tournament
     }
 
-    pub fn events(&self) -> Vec<TournamentEvent> {
+    pub fn version(&self) -> usize {
+        self.version
+    }
+
+    pub fn new_events(&self) -> Vec<TournamentEvent> {
         self.events.clone()
     }
 
+    pub fn mark_saved(&mut self) {
+        self.version += self.events.len();
+        self.events.clear();
+    }
+
     pub fn id(&self) -> Uuid {
         self.id
     }
@@ -118,7 +208,8 @@ impl Tournament {
     pub fn spec(&self) -> TournamentSpecification {
         TournamentSpecification {
             table_count: self.tables.len() as u8,
-            table_spec: self.tables[0].spec(),
+            table_spec: self.table_spec.clone(),
+            observation_policy: self.observation_policy,
         }
     }
 
@@ -127,15 +218,23 @@ impl Tournament {
     }
 
     pub fn table_seat_count(&self) -> u8 {
-        self.tables[0].seat_count()
+        self.table_spec.seat_count()
+    }
+
+    pub fn observation_policy(&self) -> ObservationPolicy {
+        self.observation_policy
     }
 
     pub fn player_count(&self) -> usize {
-        self.tables.iter().map(|table| table.player_count() as usize).sum()
+        self.tables.iter().map(|players| players.len()).sum()
     }
 
-    pub fn is_waiting_for_players(&self) -> bool {
-        self.stage == TournamentStage::WaitingForPlayers
+    pub fn free_seat_count(&self) -> usize {
+        self.table_count() * self.table_seat_count() as usize - self.player_count()
+    }
+
+    pub fn stage(&self) -> TournamentStage {
+        self.stage.clone()
     }
 
     pub fn is_finished(&self) -> bool {
@@ -143,52 +242,71 @@ impl Tournament {
     }
 
     pub fn players_table_number(&self, account_id: Uuid) -> Option<usize> {
-        self.tables.iter().position(|table| table.has_player(account_id))
+        self.tables.iter().position(|players| players.contains(&account_id))
+    }
+
+    pub fn has_player(&self, account_id: Uuid) -> bool {
+        self.players_table_number(account_id).is_some()
     }
 
     pub fn is_ready_to_start(&self) -> bool {
         self.stage == TournamentStage::ReadyToStart
     }
 
-    pub fn table_state(&self, table_number: usize) -> Result<TableState, TournamentError> {
-        let table = self.tables.get(table_number).ok_or_else(|| TournamentError::NotSuchTable)?;
-        Ok(table.state())
+    pub fn current_level(&self) -> Option<u32> {
+        (self.level > 0).then_some(self.level)
+    }
+
+    pub fn ensure_table_exists(&self, table_number: usize) -> Result<(), TournamentError> {
+        if table_number < self.table_count() {
+            Ok(())
+        } else {
+            Err(TournamentError::NotSuchTable)
+        }
     }
 
     pub fn join(&mut self, account_id: Uuid, nickname: Nickname) -> Result<usize, TournamentError> {
         debug!("join account_id {} with nickname {} within tournament {}", account_id, nickname, self.id);
-        if self.stage == TournamentStage::WaitingForPlayers {
-            if self.has_player(account_id) {
-                Err(TournamentError::PlayerAlreadyJoined)
-            } else {
-                let table_number = self.seat_player(account_id, nickname.clone());
-                if self.all_seats_are_taken() {
-                    self.stage = TournamentStage::ReadyToStart;
-                }
-                Ok(table_number)
-            }
+        self.apply_and_push_event(TournamentEvent::PlayerJoined { account_id, nickname })?;
+        self.push_tournament_message(TournamentMessageType::RegistrationCountChanged { player_count: self.player_count() });
+        Ok(self.players_table_number(account_id).unwrap())
+    }
+
+    // Eliminating a player who is no longer part of the tournament does nothing, so that the
+    // process manager may deliver the same wipe out more than once
+    pub fn eliminate(&mut self, account_id: Uuid, nickname: Nickname) -> Result<(), TournamentError> {
+        debug!("eliminate account_id {} with nickname {} within tournament {}", account_id, nickname, self.id);
+        if !self.has_player(account_id) {
+            return Ok(());
+        }
+        let rank = self.player_count();
+        self.apply_and_push_event(TournamentEvent::PlayerEliminated { account_id })?;
+        self.push_tournament_message(TournamentMessageType::PlayerEliminated { nickname, rank });
+        if self.is_finished() {
+            self.push_tournament_message(TournamentMessageType::TournamentFinished);
         } else {
-            Err(TournamentError::TournamentAlreadyStarted)
+            self.balance_tables()?;
         }
+        Ok(())
+    }
+
+    // Gives up the registration, or the seat and stack once the tournament is running, in which
+    // case the player ranks as if they had been eliminated
+    pub fn leave(&mut self, account_id: Uuid) -> Result<(), TournamentError> {
+        debug!("leave account_id {} within tournament {}", account_id, self.id);
+        self.apply_and_push_event(TournamentEvent::PlayerLeft { account_id })?;
+        match self.stage {
+            TournamentStage::Running => self.balance_tables()?,
+            TournamentStage::Finished => self.push_tournament_message(TournamentMessageType::TournamentFinished),
+            _ => self.push_tournament_message(TournamentMessageType::RegistrationCountChanged { player_count: self.player_count() }),
+        }
+        Ok(())
     }
 
     pub fn start(&mut self) {
         assert!(self.is_ready_to_start());
-        for (table_number, table) in self.tables.iter_mut().enumerate() {
-            table.start_game();
-
-            let table_messages = table.collect_messages();
-            let tournament_messages = table_messages.iter().map(|table_message|
-                TournamentMessage {
-                    tournament_id: self.id,
-                    message_type: TournamentMessageType::TableMessage {
-                        table_number, message_type: table_message.clone()
-                    },
-                }
-            );
-            self.messages.extend(tournament_messages);
-        }
-        self.stage = TournamentStage::Running;
+        self.apply_and_push_event(TournamentEvent::TournamentStarted).unwrap();
+        self.push_tournament_message(TournamentMessageType::TournamentStarted);
     }
 
     pub fn collect_messages(&mut self) -> Vec<TournamentMessage> {
@@ -196,57 +314,140 @@ impl Tournament {
     }
 
     fn create(id: Uuid, spec: &TournamentSpecification) -> Self {
-        let mut tables = vec![];
-        for _ in 0..spec.table_count {
-            tables.push(Table::new(&spec.table_spec));
-        }
         Self {
             id,
             stage: TournamentStage::WaitingForPlayers,
-            tables,
+            level: 0,
+            table_spec: spec.table_spec.clone(),
+            observation_policy: spec.observation_policy,
+            tables: vec![vec![]; spec.table_count as usize],
             messages: vec![],
-            events: vec![TournamentEvent::TournamentCreated { id, spec: spec.clone() }],
+            events: vec![],
+            version: 0,
         }
     }
 
-    fn apply(&mut self, event: TournamentEvent) {
-        match event {
-            TournamentEvent::PlayerJoined { account_id, nickname } => {
-                _ = self.join(account_id, nickname).unwrap()
-            },
-            TournamentEvent::TournamentCreated { .. } => panic!("programming error")
+    // Breaks tables as long as the remaining players fit onto fewer tables, then moves players from
+    // the fullest to the emptiest table until their player counts differ by at most one
+    fn balance_tables(&mut self) -> Result<(), TournamentError> {
+        while let Some((account_id, from_table, to_table)) = self.next_move() {
+            self.apply_and_push_event(TournamentEvent::PlayerMoved { account_id, from_table, to_table })?;
         }
+        Ok(())
     }
 
-    fn seat_player(&mut self, account_id: Uuid, nickname: Nickname) -> usize {
-        let (table_number, table_messages) = {
-            let table_number = self.find_table_with_free_seats();
-            let table = &mut self.tables[table_number];
-            table.sit_down(account_id, nickname.clone(), 1500);
-            let table_messages = table.collect_messages();
-            (table_number, table_messages)
+    fn next_move(&self) -> Option<(Uuid, usize, usize)> {
+        let active_tables: Vec<usize> = (0..self.table_count()).filter(|&table_number| !self.tables[table_number].is_empty()).collect();
+        let required_table_count = self.player_count().div_ceil(self.table_seat_count() as usize);
+        let player_count = |table_number: &&usize| self.tables[**table_number].len();
+        let emptiest = active_tables.iter().min_by_key(player_count)?;
+        let (from_table, to_table) = if active_tables.len() > required_table_count {
+            let to_table = active_tables.iter().filter(|&table_number| table_number != emptiest).min_by_key(player_count)?;
+            (*emptiest, *to_table)
+        } else {
+            let fullest = active_tables.iter().max_by_key(player_count)?;
+            if self.tables[*fullest].len() - self.tables[*emptiest].len() <= 1 {
+                return None;
+            }
+            (*fullest, *emptiest)
         };
-        let tournament_messages = table_messages.iter().map(|table_message| TournamentMessage {
-            tournament_id: self.id,
-            message_type: TournamentMessageType::TableMessage {
-                table_number, message_type: table_message.clone()
+        self.tables[from_table].last().map(|&account_id| (account_id, from_table, to_table))
+    }
+
+    fn apply_and_push_event(&mut self, event: TournamentEvent) -> Result<(), TournamentError> {
+        self.apply(event.clone())?;
+        self.events.push(event);
+        Ok(())
+    }
+
+    fn replay(mut self, events: impl IntoIterator<Item = TournamentEvent>) -> Result<Self, RestoreError> {
+        for (event_index, event) in (self.version..).zip(events) {
+            match event {
+                TournamentEvent::TournamentCreated { .. } => {
+                    return Err(RestoreError { event_index, reason: RestoreErrorReason::TournamentAlreadyCreated });
+                },
+                event => {
+                    self.apply(event).map_err(|error| RestoreError { event_index, reason: error.into() })?;
+                    self.version += 1;
+                },
+            }
+        }
+        Ok(self)
+    }
+
+    fn apply(&mut self, event: TournamentEvent) -> Result<(), TournamentError> {
+        match event {
+            TournamentEvent::PlayerJoined { account_id, .. } => {
+                if self.stage != TournamentStage::WaitingForPlayers {
+                    return Err(TournamentError::TournamentAlreadyStarted);
+                }
+                if self.has_player(account_id) {
+                    return Err(TournamentError::PlayerAlreadyJoined);
+                }
+                let table_number = self.find_table_with_free_seats();
+                self.tables[table_number].push(account_id);
+                if self.all_seats_are_taken() {
+                    self.stage = TournamentStage::ReadyToStart;
+                }
+            },
+            TournamentEvent::TournamentStarted => {
+                if self.stage != TournamentStage::ReadyToStart {
+                    return Err(TournamentError::TournamentNotReadyToStart);
+                }
+                self.stage = TournamentStage::Running;
+                self.level = 1;
+            },
+            TournamentEvent::PlayerEliminated { account_id } => {
+                if self.stage != TournamentStage::Running {
+                    return Err(TournamentError::TournamentNotRunning);
+                }
+                let table_number = self.players_table_number(account_id).ok_or(TableError::PlayerNotSeated)?;
+                self.tables[table_number].retain(|&player| player != account_id);
+                if self.player_count() <= 1 {
+                    self.stage = TournamentStage::Finished;
+                }
             },
-        });
-        self.messages.extend(tournament_messages);
-        self.events.push(TournamentEvent::PlayerJoined { account_id, nickname });
-        table_number
+            TournamentEvent::PlayerMoved { account_id, from_table, to_table } => {
+                if self.stage != TournamentStage::Running {
+                    return Err(TournamentError::TournamentNotRunning);
+                }
+                self.ensure_table_exists(to_table)?;
+                if self.players_table_number(account_id) != Some(from_table) {
+                    return Err(TableError::PlayerNotSeated.into());
+                }
+                if self.tables[to_table].len() >= self.table_seat_count() as usize {
+                    return Err(TableError::NoFreeSeat.into());
+                }
+                self.tables[from_table].retain(|&player| player != account_id);
+                self.tables[to_table].push(account_id);
+            },
+            TournamentEvent::PlayerLeft { account_id } => {
+                if self.stage == TournamentStage::Finished {
+                    return Err(TournamentError::TournamentNotRunning);
+                }
+                let table_number = self.players_table_number(account_id).ok_or(TableError::PlayerNotSeated)?;
+                self.tables[table_number].retain(|&player| player != account_id);
+                if self.stage == TournamentStage::ReadyToStart {
+                    self.stage = TournamentStage::WaitingForPlayers;
+                } else if self.stage == TournamentStage::Running && self.player_count() <= 1 {
+                    self.stage = TournamentStage::Finished;
+                }
+            },
+            TournamentEvent::TournamentCreated { .. } => unreachable!("tournaments are created only once"),
+        }
+        Ok(())
     }
 
-    fn all_seats_are_taken(&self) -> bool {
-        self.tables.iter().all(|table| !table.has_free_seat())
+    fn push_tournament_message(&mut self, message_type: TournamentMessageType) {
+        self.messages.push(TournamentMessage { tournament_id: self.id, message_type });
     }
 
-    fn find_table_with_free_seats(&self) -> usize {
-        self.tables.iter().enumerate().find(|(_, table)| table.has_free_seat()).map(|(index, _)| index).unwrap()
+    fn all_seats_are_taken(&self) -> bool {
+        self.free_seat_count() == 0
     }
 
-    fn has_player(&self, account_id: Uuid) -> bool {
-        self.tables.iter().any(|table| table.has_player(account_id))
+    fn find_table_with_free_seats(&self) -> usize {
+        self.tables.iter().position(|players| players.len() < self.table_seat_count() as usize).unwrap()
     }
 }
 
@@ -257,19 +458,40 @@ impl PartialEq for Tournament {
 }
 
 
+// The complete state of a tournament after the first `version` events
 #[derive(Debug, Clone, PartialEq)]
+pub struct TournamentSnapshot {
+    pub tournament_id: Uuid,
+    pub version: usize,
+    pub stage: TournamentStage,
+    pub level: u32,
+    pub spec: TournamentSpecification,
+    pub tables: Vec<Vec<Uuid>>,     // Account ids of the players seated at each table
+}
+
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
 pub struct TournamentMessage {
     pub tournament_id: Uuid,
     pub message_type: TournamentMessageType,
 }
 
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
 pub enum TournamentMessageType {
     TableMessage {
         table_number: usize,
         message_type: TableMessage,
     },
+    RegistrationCountChanged {
+        player_count: usize,
+    },
+    TournamentStarted,
+    PlayerEliminated {
+        nickname: Nickname,
+        rank: usize,
+    },
+    TournamentFinished,
 }
 
 
@@ -277,17 +499,29 @@ pub enum TournamentMessageType {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::domain::ObservationAccess;
+
+    use std::time::Duration;
+
+    #[test]
+    fn spectator_delay_is_limited() {
+        let policy = ObservationPolicy::new(ObservationAccess::Public, ObservationPolicy::MAX_SPECTATOR_DELAY);
+        assert!(TournamentSpecification::new(1, 2).unwrap().with_observation_policy(policy).is_ok());
+        let policy = ObservationPolicy::new(ObservationAccess::Public, ObservationPolicy::MAX_SPECTATOR_DELAY + Duration::from_secs(1));
+        let result = TournamentSpecification::new(1, 2).unwrap().with_observation_policy(policy);
+        assert!(matches!(result, Err(TournamentSpecificationError::SpectatorDelayTooLong { .. })));
+    }
 
     #[test]
     fn tournament_creation_and_join() {
         let spec = TournamentSpecification::new(1, 3).unwrap();
         let tournament = Tournament::new(&spec);
-        assert_eq!(tournament.events(), vec![TournamentEvent::TournamentCreated { id: tournament.id(), spec: spec.clone() }]);
+        assert_eq!(tournament.new_events(), vec![TournamentEvent::TournamentCreated { id: tournament.id(), spec: spec.clone() }]);
         let mut tournament = tournament;
         let account_id = Uuid::new_v4();
         let nickname = Nickname::new("Daniel").unwrap();
         let _ = tournament.join(account_id, nickname.clone()); // TODO: do not return value in join
-        assert_eq!(tournament.events(), vec![
+        assert_eq!(tournament.new_events(), vec![
             TournamentEvent::TournamentCreated { id: tournament.id(), spec },
             TournamentEvent::PlayerJoined { account_id, nickname }
         ]);
@@ -303,10 +537,151 @@ mod tests {
             TournamentEvent::TournamentCreated { id: tournament_id, spec: spec.clone() },
             TournamentEvent::PlayerJoined { account_id, nickname }
         ];
-        let tournament = Tournament::restore(events.clone());
+        let tournament = Tournament::restore(events.clone()).unwrap();
         assert_eq!(tournament.id(), tournament_id);
         assert_eq!(tournament.spec(), spec);
         assert!(tournament.has_player(account_id));
-        assert_eq!(tournament.events(), events);
+        assert_eq!(tournament.version(), 2);
+        assert_eq!(tournament.new_events(), vec![]);
+        let mut tournament = tournament;
+        assert_eq!(tournament.collect_messages(), vec![]);
+    }
+
+    #[test]
+    fn tournament_version_tracks_saved_events() {
+        let spec = TournamentSpecification::new(1, 3).unwrap();
+        let mut tournament = Tournament::new(&spec);
+        assert_eq!(tournament.version(), 0);
+        assert_eq!(tournament.new_events().len(), 1);
+        tournament.mark_saved();
+        let account_id = Uuid::new_v4();
+        let nickname = Nickname::new("Daniel").unwrap();
+        _ = tournament.join(account_id, nickname.clone());
+        assert_eq!(tournament.version(), 1);
+        assert_eq!(tournament.new_events(), vec![TournamentEvent::PlayerJoined { account_id, nickname }]);
+    }
+
+    #[test]
+    fn snapshot_plus_tail_equals_full_replay() {
+        let spec = TournamentSpecification::new(2, 3).unwrap();
+        let tournament_id = Uuid::new_v4();
+        let mut events = vec![TournamentEvent::TournamentCreated { id: tournament_id, spec }];
+        for index in 0..5 {
+            events.push(TournamentEvent::PlayerJoined { account_id: Uuid::new_v4(), nickname: Nickname::new(format!("Player{}", index)).unwrap() });
+        }
+        let full_replay = Tournament::restore(events.clone()).unwrap();
+        for snapshot_version in 1..events.len() {
+            let snapshot = Tournament::restore(events[..snapshot_version].to_vec()).unwrap().snapshot();
+            assert_eq!(snapshot.version, snapshot_version);
+            let mut tournament = Tournament::restore_from_snapshot(snapshot, events[snapshot_version..].to_vec()).unwrap();
+            assert_eq!(tournament.snapshot(), full_replay.snapshot());
+            assert_eq!(tournament.version(), events.len());
+            assert_eq!(tournament.collect_messages(), vec![]);
+        }
+    }
+
+    fn running_tournament(table_count: u8, table_seat_count: u8) -> Tournament {
+        let spec = TournamentSpecification::new(table_count, table_seat_count).unwrap();
+        let mut tournament = Tournament::new(&spec);
+        for index in 0..table_count as usize * table_seat_count as usize {
+            _ = tournament.join(Uuid::new_v4(), Nickname::new(format!("Player{}", index)).unwrap());
+        }
+        tournament.start();
+        tournament.collect_messages();
+        tournament
+    }
+
+    fn player_counts(tournament: &Tournament) -> Vec<usize> {
+        tournament.tables.iter().map(|players| players.len()).collect()
+    }
+
+    #[test]
+    fn eliminations_balance_and_break_tables() {
+        let mut tournament = running_tournament(3, 2);
+        let nickname = Nickname::new("Daniel").unwrap();
+        tournament.eliminate(tournament.tables[0][0], nickname.clone()).unwrap();
+        assert_eq!(player_counts(&tournament), vec![1, 2, 2]);
+        assert_eq!(tournament.collect_messages()[0].message_type, TournamentMessageType::PlayerEliminated { nickname: nickname.clone(), rank: 6 });
+
+        let account_id = tournament.tables[0][0];
+        tournament.eliminate(tournament.tables[1][0], nickname.clone()).unwrap();
+        assert_eq!(player_counts(&tournament), vec![0, 2, 2]);
+        assert_eq!(tournament.players_table_number(account_id), Some(1));
+        assert_eq!(tournament.new_events().last(), Some(&TournamentEvent::PlayerMoved { account_id, from_table: 0, to_table: 1 }));
+
+        tournament.eliminate(tournament.tables[1][0], nickname.clone()).unwrap();
+        tournament.eliminate(account_id, nickname.clone()).unwrap();
+        assert_eq!(player_counts(&tournament), vec![0, 0, 2]);
+        let events = tournament.new_events();
+        tournament.eliminate(account_id, nickname.clone()).unwrap();
+        assert_eq!(tournament.new_events(), events);
+
+        tournament.collect_messages();
+        tournament.eliminate(tournament.tables[2][0], nickname.clone()).unwrap();
+        assert!(tournament.is_finished());
+        assert_eq!(tournament.collect_messages().last().unwrap().message_type, TournamentMessageType::TournamentFinished);
+        let restored = Tournament::restore(tournament.new_events()).unwrap();
+        assert_eq!(restored.snapshot(), tournament.snapshot());
+    }
+
+    #[test]
+    fn only_running_tournaments_eliminate_players() {
+        let spec = TournamentSpecification::new(1, 3).unwrap();
+        let mut tournament = Tournament::new(&spec);
+        let account_id = Uuid::new_v4();
+        _ = tournament.join(account_id, Nickname::new("Daniel").unwrap());
+        let result = tournament.eliminate(account_id, Nickname::new("Daniel").unwrap());
+        assert!(matches!(result, Err(TournamentError::TournamentNotRunning)));
+        assert_eq!(tournament.player_count(), 1);
+    }
+
+    #[test]
+    fn players_leave_before_the_start() {
+        let spec = TournamentSpecification::new(1, 2).unwrap();
+        let mut tournament = Tournament::new(&spec);
+        let account_id = Uuid::new_v4();
+        _ = tournament.join(account_id, Nickname::new("Daniel").unwrap());
+        _ = tournament.join(Uuid::new_v4(), Nickname::new("James").unwrap());
+        assert_eq!(tournament.stage(), TournamentStage::ReadyToStart);
+        tournament.collect_messages();
+        tournament.leave(account_id).unwrap();
+        assert_eq!(tournament.stage(), TournamentStage::WaitingForPlayers);
+        assert!(!tournament.has_player(account_id));
+        assert_eq!(tournament.collect_messages()[0].message_type, TournamentMessageType::RegistrationCountChanged { player_count: 1 });
+        assert!(matches!(tournament.leave(account_id), Err(TournamentError::TableError(TableError::PlayerNotSeated))));
+        _ = tournament.join(account_id, Nickname::new("Daniel").unwrap());
+        assert!(tournament.has_player(account_id));
+    }
+
+    #[test]
+    fn players_leave_running_tournaments() {
+        let mut tournament = running_tournament(2, 2);
+        tournament.leave(tournament.tables[0][0]).unwrap();
+        tournament.leave(tournament.tables[0][0]).unwrap();
+        assert_eq!(player_counts(&tournament), vec![0, 2]);
+        tournament.leave(tournament.tables[1][0]).unwrap();
+        assert!(tournament.is_finished());
+        assert_eq!(tournament.collect_messages().last().unwrap().message_type, TournamentMessageType::TournamentFinished);
+        assert!(matches!(tournament.leave(tournament.tables[1][0]), Err(TournamentError::TournamentNotRunning)));
+        let restored = Tournament::restore(tournament.new_events()).unwrap();
+        assert_eq!(restored.snapshot(), tournament.snapshot());
+    }
+
+    #[test]
+    fn restore_corrupt_event_streams() {
+        let spec = TournamentSpecification::new(1, 3).unwrap();
+        let created = TournamentEvent::TournamentCreated { id: Uuid::new_v4(), spec };
+        let joined = TournamentEvent::PlayerJoined { account_id: Uuid::new_v4(), nickname: Nickname::new("Daniel").unwrap() };
+
+        let result = Tournament::restore(vec![]);
+        assert!(matches!(result, Err(RestoreError { event_index: 0, reason: RestoreErrorReason::NoEvents })));
+        let result = Tournament::restore(vec![joined.clone(), created.clone()]);
+        assert!(matches!(result, Err(RestoreError { event_index: 0, reason: RestoreErrorReason::TournamentNotCreated })));
+        let result = Tournament::restore(vec![created.clone(), joined.clone(), created.clone()]);
+        assert!(matches!(result, Err(RestoreError { event_index: 2, reason: RestoreErrorReason::TournamentAlreadyCreated })));
+        let result = Tournament::restore(vec![created, joined.clone(), joined]);
+        assert!(matches!(result, Err(RestoreError {
+            event_index: 2, reason: RestoreErrorReason::TournamentError(TournamentError::PlayerAlreadyJoined)
+        })));
     }
 }
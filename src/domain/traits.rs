@@ -1,4 +1,15 @@
+use super::account::Account;
+use super::account::AccountRole;
+use super::account::RoleChange;
+use super::ban::Ban;
+use super::account::Username;
+use super::query::TournamentPage;
+use super::query::TournamentQuery;
+use super::table::Table;
+use super::table::TableEvent;
 use super::tournament::Tournament;
+use super::tournament::TournamentEvent;
+use super::tournament::TournamentMessage;
 
 use thiserror::Error;
 use uuid::Uuid;
@@ -10,6 +21,8 @@ pub enum LoadTournamentError {
     TournamentNotFound,
     #[error("Cannot access tournament database for reading")]
     DatabaseReadingError,
+    #[error("Stored tournament is corrupted")]
+    TournamentCorrupted,
 }
 
 pub trait LoadTournament {
@@ -25,41 +38,263 @@ pub enum SaveTournamentError {
     DatabaseWritingError,
 }
 
+// Saving puts the pending messages of the tournament into the outbox, in the same write as its events
 pub trait SaveTournament {
     fn save_tournament(&mut self, tournament: Tournament) -> Result<(), SaveTournamentError>;
 }
 
 
-#[derive(Debug, Error)]
-pub enum QueryTournamentsError {
-    #[error("Cannot access tournament database for querying")]
-    DatabaseQueryError,
+// Served from the lobby projection, which is kept in memory
+pub trait QueryTournaments {
+    fn query_tournaments(&self, query: &TournamentQuery) -> TournamentPage;
 }
 
-pub trait QueryTournaments {
-    fn query_tournaments(&self) -> Result<Vec<Tournament>, QueryTournamentsError>;
+
+#[derive(Debug, Error, Clone, Copy)]
+pub enum LoadTableError {
+    #[error("Table not found")]
+    TableNotFound,
+    #[error("Cannot access table database for reading")]
+    DatabaseReadingError,
+    #[error("Stored table is corrupted")]
+    TableCorrupted,
+}
+
+pub trait LoadTable {
+    fn load_table(&self, tournament_id: Uuid, table_number: usize) -> Result<Table, LoadTableError>;
+}
+
+
+#[derive(Debug, Error, Clone, Copy)]
+pub enum SaveTableError {
+    #[error("There is a newer version of the given table in the database")]
+    TableOutdated,
+    #[error("Cannot access table database for writing")]
+    DatabaseWritingError,
+}
+
+// Saving puts the pending messages of the table into the outbox, in the same write as its events
+pub trait SaveTable {
+    fn save_table(&mut self, table: Table) -> Result<(), SaveTableError>;
+}
+
+
+pub trait AccessTables: LoadTable + SaveTable {}
+impl<T: LoadTable + SaveTable> AccessTables for T {}
+
+
+// The event stream of a single aggregate
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EventStream {
+    Tournament { tournament_id: Uuid },
+    Table { tournament_id: Uuid, table_number: usize },
+}
+
+
+#[derive(Debug, Error, Clone, Copy)]
+pub enum LoadEventsError {
+    #[error("Cannot access event database for reading")]
+    DatabaseReadingError,
+    #[error("Stored events are corrupted")]
+    EventsCorrupted,
+}
+
+// Events are numbered from zero within their stream, streams without events are empty
+pub trait LoadEvents {
+    fn load_tournament_ids(&self) -> Result<Vec<Uuid>, LoadEventsError>;
+    fn load_tournament_events(&self, tournament_id: Uuid, from_version: usize) -> Result<Vec<TournamentEvent>, LoadEventsError>;
+    fn load_table_events(&self, tournament_id: Uuid, table_number: usize, from_version: usize) -> Result<Vec<TableEvent>, LoadEventsError>;
+}
+
+
+#[derive(Debug, Error, Clone, Copy)]
+pub enum LoadCheckpointError {
+    #[error("Cannot access checkpoint database for reading")]
+    DatabaseReadingError,
+}
+
+// A checkpoint is the number of events of a stream already handled, zero if none was stored yet
+pub trait LoadCheckpoint {
+    fn load_checkpoint(&self, stream: EventStream) -> Result<usize, LoadCheckpointError>;
+}
+
+
+#[derive(Debug, Error, Clone, Copy)]
+pub enum SaveCheckpointError {
+    #[error("Cannot access checkpoint database for writing")]
+    DatabaseWritingError,
+}
+
+pub trait SaveCheckpoint {
+    fn save_checkpoint(&mut self, stream: EventStream, checkpoint: usize) -> Result<(), SaveCheckpointError>;
+}
+
+
+pub trait AccessEventStreams: LoadEvents + LoadCheckpoint + SaveCheckpoint {}
+impl<T: LoadEvents + LoadCheckpoint + SaveCheckpoint> AccessEventStreams for T {}
+
+
+// A saved message waiting to be published. Positions increase across all tournaments in the order
+// in which the messages were saved.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutboxEntry {
+    pub position: u64,
+    pub message: TournamentMessage,
+}
+
+
+#[derive(Debug, Error, Clone, Copy)]
+pub enum LoadOutboxError {
+    #[error("Cannot access outbox database for reading")]
+    DatabaseReadingError,
+}
+
+// Returns the entries not yet marked delivered, ordered by position
+pub trait LoadOutbox {
+    fn load_pending_messages(&self) -> Result<Vec<OutboxEntry>, LoadOutboxError>;
+}
+
+
+#[derive(Debug, Error, Clone, Copy)]
+pub enum MarkDeliveredError {
+    #[error("Cannot access outbox database for writing")]
+    DatabaseWritingError,
 }
 
+// Marks all entries up to and including the given position as delivered
+pub trait MarkDelivered {
+    fn mark_delivered(&mut self, position: u64) -> Result<(), MarkDeliveredError>;
+}
+
+
+pub trait AccessOutbox: LoadOutbox + MarkDelivered {}
+impl<T: LoadOutbox + MarkDelivered> AccessOutbox for T {}
 
-pub trait AccessTournaments: LoadTournament + SaveTournament + QueryTournaments {}
-impl<T: LoadTournament + SaveTournament + QueryTournaments> AccessTournaments for T {}
+
+pub trait AccessTournaments: LoadTournament + SaveTournament + AccessTables + AccessEventStreams + AccessOutbox {}
+impl<T: LoadTournament + SaveTournament + AccessTables + AccessEventStreams + AccessOutbox> AccessTournaments for T {}
+
+
+#[derive(Debug, Error, Clone, Copy)]
+pub enum LoadAccountError {
+    #[error("Account not found")]
+    AccountNotFound,
+    #[error("Cannot access account database for reading")]
+    DatabaseReadingError,
+}
+
+pub trait LoadAccount {
+    fn load_account(&self, account_id: Uuid) -> Result<Account, LoadAccountError>;
+    fn load_account_by_username(&self, username: &Username) -> Result<Account, LoadAccountError>;
+    // Ordered by username
+    fn load_accounts_with_role(&self, role: AccountRole) -> Result<Vec<Account>, LoadAccountError>;
+}
+
+
+#[derive(Debug, Error, Clone, Copy)]
+pub enum SaveAccountError {
+    #[error("Username is already taken")]
+    UsernameTaken,
+    #[error("Nickname is already taken")]
+    NicknameTaken,
+    #[error("Cannot access account database for writing")]
+    DatabaseWritingError,
+}
+
+// Inserts new accounts and replaces stored ones with the same id. Saving appends the pending role
+// changes of the account to the audit log, in the same write as the account.
+pub trait SaveAccount {
+    fn save_account(&mut self, account: Account) -> Result<(), SaveAccountError>;
+}
+
+
+#[derive(Debug, Error, Clone, Copy)]
+pub enum LoadRoleChangesError {
+    #[error("Cannot access audit log for reading")]
+    DatabaseReadingError,
+}
+
+// Oldest change first
+pub trait LoadRoleChanges {
+    fn load_role_changes(&self, account_id: Uuid) -> Result<Vec<RoleChange>, LoadRoleChangesError>;
+}
+
+
+#[derive(Debug, Error, Clone, Copy)]
+pub enum LoadBanError {
+    #[error("Ban not found")]
+    BanNotFound,
+    #[error("Cannot access ban list for reading")]
+    DatabaseReadingError,
+}
+
+pub trait LoadBans {
+    fn load_ban(&self, ban_id: Uuid) -> Result<Ban, LoadBanError>;
+    // All bans ever issued against the account, including those no longer in force
+    fn load_bans(&self, account_id: Uuid) -> Result<Vec<Ban>, LoadBanError>;
+    // All exclusions ever issued from the tournament, including those no longer in force
+    fn load_tournament_bans(&self, tournament_id: Uuid) -> Result<Vec<Ban>, LoadBanError>;
+}
+
+
+#[derive(Debug, Error, Clone, Copy)]
+pub enum SaveBanError {
+    #[error("Cannot access ban list for writing")]
+    DatabaseWritingError,
+}
+
+// Inserts new bans and replaces stored ones with the same id
+pub trait SaveBan {
+    fn save_ban(&mut self, ban: Ban) -> Result<(), SaveBanError>;
+}
+
+
+pub trait AccessAccounts: LoadAccount + SaveAccount + LoadRoleChanges {}
+impl<T: LoadAccount + SaveAccount + LoadRoleChanges> AccessAccounts for T {}
+
+pub trait AccessBans: LoadBans + SaveBan {}
+impl<T: LoadBans + SaveBan> AccessBans for T {}
 
 
 // ----------------------- tryout:
 
-use crate::domain::TableMessageReceiver;
-use crate::domain::TournamentMessage;
+use crate::domain::TableState;
+use crate::domain::TableSubscription;
+use crate::domain::TournamentSubscription;
+
+use std::time::Instant;
 
 
 pub trait PublishTournamentMessages {
-    fn publish_tournament_messages(&self, messages: Vec<TournamentMessage>);
+    fn close_tournament_channels(&mut self, tournament_id: Uuid);
+    // Skips entries at or below the last published position, so that a relay repeating them after a
+    // failure does not number their messages twice
+    fn publish_outbox_entries(&mut self, entries: Vec<OutboxEntry>);
+    // Channels are otherwise only swept when messages are published or subscribed to
+    fn sweep_channels(&mut self);
 }
 
 
 pub trait SubscribeTableMessages {
-    fn subscribe_table_messages(&mut self, tournament_id: Uuid, table_number: usize) -> TableMessageReceiver;
+    // The current state of the table is kept for observers who are shown the table with a delay
+    fn subscribe_table_messages(&mut self, tournament_id: Uuid, table_number: usize, last_seen_sequence: Option<u64>, table_state: &TableState) -> TableSubscription;
+    // State of the table as of the last message published at or before the time, along with the
+    // sequence of that message. None if the state back then is no longer or not yet known.
+    fn table_state_at(&self, tournament_id: Uuid, table_number: usize, at: Instant) -> Option<(u64, TableState)>;
+}
+
+
+pub trait SubscribeTournamentMessages {
+    fn subscribe_tournament_messages(&mut self, tournament_id: Uuid, last_seen_sequence: Option<u64>) -> TournamentSubscription;
+    fn subscribe_lobby_messages(&mut self, last_seen_sequence: Option<u64>) -> TournamentSubscription;
+}
+
+
+pub trait MonitorTableMessageBroadcast {
+    fn channel_count(&self) -> usize;
+    fn subscriber_count(&self) -> usize;
 }
 
 
-pub trait AccessTableMessageBroadcast: PublishTournamentMessages + SubscribeTableMessages {}
-impl<T: PublishTournamentMessages + SubscribeTableMessages> AccessTableMessageBroadcast for T {}
+pub trait AccessTableMessageBroadcast: PublishTournamentMessages + SubscribeTableMessages + SubscribeTournamentMessages + MonitorTableMessageBroadcast {}
+impl<T: PublishTournamentMessages + SubscribeTableMessages + SubscribeTournamentMessages + MonitorTableMessageBroadcast> AccessTableMessageBroadcast for T {}
@@ -0,0 +1,199 @@
+use crate::application::AuthInfo;
+use crate::application::AuthRole;
+use crate::domain::unix_time;
+
+use axum::extract::FromRequestParts;
+use axum::http::header;
+use axum::http::request::Parts;
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use hmac::Hmac;
+use hmac::Mac;
+use log::debug;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use thiserror::Error;
+use uuid::Uuid;
+
+use std::convert::Infallible;
+use std::time::Duration;
+
+
+const MIN_KEY_LENGTH: usize = 32;
+
+
+#[derive(Debug, Error)]
+pub enum TokenKeyError {
+    #[error("Token keys must have at least {MIN_KEY_LENGTH} bytes")]
+    KeyTooShort,
+}
+
+
+#[derive(Debug, Error)]
+pub enum TokenError {
+    #[error("Token is malformed")]
+    TokenMalformed,
+    #[error("Token signature is invalid")]
+    SignatureInvalid,
+    #[error("Token has expired")]
+    TokenExpired,
+}
+
+
+#[derive(Debug, Serialize, Deserialize)]
+struct TokenClaims {
+    account_id: Uuid,
+    role: AuthRole,
+    expires_at: u64,            // Seconds since the Unix epoch
+}
+
+
+// Key for signing and verifying bearer tokens. A token is the URL-safe base64 of its JSON claims
+// and of their HMAC-SHA256, joined by a dot.
+#[derive(Clone)]
+pub struct TokenKey {
+    key: Vec<u8>,
+}
+
+impl TokenKey {
+    pub fn new(key: impl Into<Vec<u8>>) -> Result<Self, TokenKeyError> {
+        let key = key.into();
+        if key.len() < MIN_KEY_LENGTH {
+            return Err(TokenKeyError::KeyTooShort);
+        }
+        Ok(Self { key })
+    }
+
+    // Tokens signed with a random key become invalid when the server restarts
+    pub fn random() -> Self {
+        let key = (0..MIN_KEY_LENGTH / 16).flat_map(|_| Uuid::new_v4().into_bytes()).collect();
+        Self { key }
+    }
+
+    pub fn sign(&self, account_id: Uuid, role: AuthRole, lifetime: Duration, now: u64) -> String {
+        let expires_at = now.saturating_add(lifetime.as_secs());
+        let claims = TokenClaims { account_id, role, expires_at };
+        let payload = URL_SAFE_NO_PAD.encode(serde_json::to_vec(&claims).expect("claims are serializable"));
+        let signature = URL_SAFE_NO_PAD.encode(self.mac(&payload).finalize().into_bytes());
+        format!("{}.{}", payload, signature)
+    }
+
+    pub fn verify(&self, token: &str, now: u64) -> Result<AuthInfo, TokenError> {
+        let (payload, signature) = token.split_once('.').ok_or(TokenError::TokenMalformed)?;
+        let signature = URL_SAFE_NO_PAD.decode(signature).map_err(|_| TokenError::TokenMalformed)?;
+        self.mac(payload).verify_slice(&signature).map_err(|_| TokenError::SignatureInvalid)?;
+        let claims = URL_SAFE_NO_PAD.decode(payload).map_err(|_| TokenError::TokenMalformed)?;
+        let claims: TokenClaims = serde_json::from_slice(&claims).map_err(|_| TokenError::TokenMalformed)?;
+        if claims.expires_at <= now {
+            return Err(TokenError::TokenExpired);
+        }
+        Ok(AuthInfo::Authenticated { account_id: claims.account_id, role: claims.role })
+    }
+
+    fn mac(&self, payload: &str) -> Hmac<Sha256> {
+        let mut mac = Hmac::<Sha256>::new_from_slice(&self.key).expect("HMAC accepts keys of any length");
+        mac.update(payload.as_bytes());
+        mac
+    }
+}
+
+// Keeps the key out of logs
+impl std::fmt::Debug for TokenKey {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        formatter.write_str("TokenKey")
+    }
+}
+
+
+// Takes the token from the Authorization header, or from the access_token query parameter for
+// browsers, which cannot set headers on WebSocket and EventSource requests. Requests without a
+// valid token are unauthenticated and rejected by the services that require authentication.
+impl<S: Send + Sync> FromRequestParts<S> for AuthInfo {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let Some(token) = bearer_token(parts) else {
+            return Ok(AuthInfo::Unauthenticated);
+        };
+        let Some(key) = parts.extensions.get::<TokenKey>() else {
+            return Ok(AuthInfo::Unauthenticated);
+        };
+        Ok(key.verify(token, unix_time()).unwrap_or_else(|error| {
+            debug!("rejected bearer token: {}", error);
+            AuthInfo::Unauthenticated
+        }))
+    }
+}
+
+
+fn bearer_token(parts: &Parts) -> Option<&str> {
+    let header_token = parts.headers.get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    let query_token = || parts.uri.query()?
+        .split('&')
+        .find_map(|pair| pair.strip_prefix("access_token="));
+    header_token.or_else(query_token)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use axum::http::Request;
+    use futures_util::FutureExt;
+
+    use super::*;
+
+    const LIFETIME: Duration = Duration::from_secs(60);
+    const NOW: u64 = 1_700_000_000;
+
+    fn extract(request: Request<()>) -> AuthInfo {
+        let (mut parts, _) = request.into_parts();
+        let Ok(auth_info) = AuthInfo::from_request_parts(&mut parts, &()).now_or_never().unwrap();
+        auth_info
+    }
+
+    #[test]
+    fn short_keys_are_rejected() {
+        assert!(matches!(TokenKey::new(vec![0; MIN_KEY_LENGTH - 1]), Err(TokenKeyError::KeyTooShort)));
+        assert!(TokenKey::new(vec![0; MIN_KEY_LENGTH]).is_ok());
+    }
+
+    #[test]
+    fn signed_tokens_are_verified() {
+        let key = TokenKey::random();
+        let account_id = Uuid::new_v4();
+        let token = key.sign(account_id, AuthRole::Moderator, LIFETIME, NOW);
+        let auth_info = key.verify(&token, NOW).unwrap();
+        assert!(matches!(auth_info, AuthInfo::Authenticated { account_id: id, role: AuthRole::Moderator } if id == account_id));
+    }
+
+    #[test]
+    fn forged_and_expired_tokens_are_rejected() {
+        let key = TokenKey::random();
+        let token = key.sign(Uuid::new_v4(), AuthRole::Member, LIFETIME, NOW);
+        assert!(matches!(TokenKey::random().verify(&token, NOW), Err(TokenError::SignatureInvalid)));
+        let (_, signature) = token.split_once('.').unwrap();
+        let claims = TokenClaims { account_id: Uuid::new_v4(), role: AuthRole::Administrator, expires_at: u64::MAX };
+        let forged = format!("{}.{}", URL_SAFE_NO_PAD.encode(serde_json::to_vec(&claims).unwrap()), signature);
+        assert!(matches!(key.verify(&forged, NOW), Err(TokenError::SignatureInvalid)));
+        assert!(matches!(key.verify("token", NOW), Err(TokenError::TokenMalformed)));
+        assert!(key.verify(&token, NOW + LIFETIME.as_secs() - 1).is_ok());
+        assert!(matches!(key.verify(&token, NOW + LIFETIME.as_secs()), Err(TokenError::TokenExpired)));
+    }
+
+    #[test]
+    fn extract_auth_info_from_requests() {
+        let key = TokenKey::random();
+        let account_id = Uuid::new_v4();
+        let token = key.sign(account_id, AuthRole::Member, LIFETIME, unix_time());
+        let request = Request::builder().header(header::AUTHORIZATION, format!("Bearer {}", token)).extension(key.clone()).body(()).unwrap();
+        assert!(matches!(extract(request), AuthInfo::Authenticated { account_id: id, .. } if id == account_id));
+        let request = Request::builder().uri(format!("/lobby?access_token={}", token)).extension(key.clone()).body(()).unwrap();
+        assert!(matches!(extract(request), AuthInfo::Authenticated { account_id: id, .. } if id == account_id));
+        let request = Request::builder().header(header::AUTHORIZATION, "Bearer token").extension(key.clone()).body(()).unwrap();
+        assert!(matches!(extract(request), AuthInfo::Unauthenticated));
+        let request = Request::builder().extension(key).body(()).unwrap();
+        assert!(matches!(extract(request), AuthInfo::Unauthenticated));
+    }
+}
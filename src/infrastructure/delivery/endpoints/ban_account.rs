@@ -0,0 +1,61 @@
+use crate::application::AuthInfo;
+use crate::application::BanAccountRequest;
+use crate::application::BanAccountError;
+use crate::application::BanAccount;
+
+use axum::{extract, Json, response};
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+use std::sync::Arc;
+use std::time::Duration;
+
+
+#[derive(Debug, Deserialize)]
+pub struct RequestBody {
+    tournament_id: Option<Uuid>,
+    duration: Option<u64>,      // Seconds
+    reason: String,
+}
+
+
+#[derive(Debug, Serialize)]
+pub struct ResponseBody {
+    ban_id: Uuid,
+    expires_at: Option<u64>,
+}
+
+
+pub async fn handle_request(
+    extract::State(service): extract::State<Arc<Mutex<impl BanAccount>>>,
+    auth_info: AuthInfo,
+    extract::Path(account_id): extract::Path<Uuid>,
+    extract::Json(request): extract::Json<RequestBody>,
+) -> Result<Json<ResponseBody>, BanAccountError> {
+    let request = BanAccountRequest {
+        account_id,
+        tournament_id: request.tournament_id,
+        duration: request.duration.map(Duration::from_secs),
+        reason: request.reason,
+    };
+
+    let mut service = service.lock().await;
+    let response = service.ban_account(request, &auth_info)?;
+    Ok(Json(ResponseBody { ban_id: response.ban_id, expires_at: response.expires_at }))
+}
+
+
+impl response::IntoResponse for BanAccountError {
+    fn into_response(self) -> response::Response {
+        match self {
+            BanAccountError::AuthError(error) => error.into_response(),
+            BanAccountError::LoadAccountError(error) => error.into_response(),
+            BanAccountError::BanError(error) => error.into_response(),
+            BanAccountError::LoadTournamentError(error) => error.into_response(),
+            BanAccountError::TournamentError(error) => error.into_response(),
+            BanAccountError::SaveTournamentAndPublishMessagesError(error) => error.into_response(),
+            BanAccountError::SaveBanError(error) => error.into_response(),
+        }
+    }
+}
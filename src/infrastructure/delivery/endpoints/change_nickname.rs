@@ -0,0 +1,45 @@
+use super::build_response;
+
+use crate::application::AuthInfo;
+use crate::application::ChangeNicknameRequest;
+use crate::application::ChangeNicknameError;
+use crate::application::ChangeNickname;
+
+use axum::http::StatusCode;
+use axum::{extract, response};
+use serde::Deserialize;
+use tokio::sync::Mutex;
+
+use std::sync::Arc;
+
+
+#[derive(Debug, Deserialize)]
+pub struct RequestBody {
+    nickname: String,
+}
+
+
+pub async fn handle_request(
+    extract::State(service): extract::State<Arc<Mutex<impl ChangeNickname>>>,
+    auth_info: AuthInfo,
+    extract::Json(request): extract::Json<RequestBody>,
+) -> Result<StatusCode, ChangeNicknameError> {
+    let request = ChangeNicknameRequest { nickname: request.nickname };
+
+    let mut service = service.lock().await;
+    service.change_nickname(request, &auth_info)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+
+impl response::IntoResponse for ChangeNicknameError {
+    fn into_response(self) -> response::Response {
+        match self {
+            ChangeNicknameError::AuthError(error) => error.into_response(),
+            ChangeNicknameError::NicknameError(error) => build_response(StatusCode::BAD_REQUEST, error.to_string()),
+            ChangeNicknameError::LoadAccountError(error) => error.into_response(),
+            ChangeNicknameError::AccountError(error) => error.into_response(),
+            ChangeNicknameError::SaveAccountError(error) => error.into_response(),
+        }
+    }
+}
@@ -0,0 +1,45 @@
+use crate::application::AuthInfo;
+use crate::application::ChangePasswordRequest;
+use crate::application::ChangePasswordError;
+use crate::application::ChangePassword;
+
+use axum::http::StatusCode;
+use axum::{extract, response};
+use serde::Deserialize;
+use tokio::sync::Mutex;
+use tokio::task;
+
+use std::sync::Arc;
+
+
+#[derive(Debug, Deserialize)]
+pub struct RequestBody {
+    current_password: String,
+    new_password: String,
+}
+
+
+pub async fn handle_request(
+    extract::State(service): extract::State<Arc<Mutex<impl ChangePassword>>>,
+    auth_info: AuthInfo,
+    extract::Json(request): extract::Json<RequestBody>,
+) -> Result<StatusCode, ChangePasswordError> {
+    let request = ChangePasswordRequest { current_password: request.current_password, new_password: request.new_password };
+
+    let pending_password_change = service.lock().await.change_password(request, &auth_info)?;
+    let password_change = task::spawn_blocking(move || pending_password_change.hash()).await.expect("password hashing does not panic")?;
+    service.lock().await.complete_password_change(password_change, &auth_info)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+
+impl response::IntoResponse for ChangePasswordError {
+    fn into_response(self) -> response::Response {
+        match self {
+            ChangePasswordError::AuthError(error) => error.into_response(),
+            ChangePasswordError::LoadAccountError(error) => error.into_response(),
+            ChangePasswordError::AccountError(error) => error.into_response(),
+            ChangePasswordError::SaveAccountError(error) => error.into_response(),
+        }
+    }
+}
@@ -4,6 +4,8 @@ use crate::application::AuthInfo;
 use crate::application::CreateTournamentRequest;
 use crate::application::CreateTournamentError;
 use crate::application::CreateTournament;
+use crate::domain::ObservationAccess;
+use crate::domain::ObservationPolicy;
 
 use axum::http::StatusCode;
 use axum::{extract, Json, response};
@@ -12,12 +14,28 @@ use tokio::sync::Mutex;
 use uuid::Uuid;
 
 use std::sync::Arc;
+use std::time::Duration;
+
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Observation {
+    #[default]
+    Public,
+    MembersOnly,
+    ParticipantsOnly,
+    Disabled,
+}
 
 
 #[derive(Debug, Deserialize)]
 pub struct RequestBody {
     table_count: u32,
-    table_seat_count: u8
+    table_seat_count: u8,
+    #[serde(default)]
+    observation: Observation,
+    #[serde(default)]
+    spectator_delay: u64,       // Seconds
 }
 
 
@@ -29,12 +47,20 @@ pub struct ResponseBody {
 
 pub async fn handle_request(
     extract::State(service): extract::State<Arc<Mutex<impl CreateTournament>>>,
+    auth_info: AuthInfo,
     extract::Json(request): extract::Json<RequestBody>,
 ) -> Result<Json<ResponseBody>, CreateTournamentError> {
-    let request = CreateTournamentRequest { table_count: request.table_count as u8, table_seat_count: request.table_seat_count };
-
-    // let auth_info = AuthInfo::Unauthenticated;
-    let auth_info = AuthInfo::Authenticated { account_id: Uuid::new_v4(), role: crate::application::AuthRole::Member };
+    let access = match request.observation {
+        Observation::Public => ObservationAccess::Public,
+        Observation::MembersOnly => ObservationAccess::MembersOnly,
+        Observation::ParticipantsOnly => ObservationAccess::ParticipantsOnly,
+        Observation::Disabled => ObservationAccess::Disabled,
+    };
+    let request = CreateTournamentRequest {
+        table_count: request.table_count as u8,
+        table_seat_count: request.table_seat_count,
+        observation_policy: ObservationPolicy::new(access, Duration::from_secs(request.spectator_delay)),
+    };
 
     let mut service = service.lock().await;
     let response = service.create_tournament(request, &auth_info)?;
@@ -46,7 +72,7 @@ impl response::IntoResponse for CreateTournamentError {
     fn into_response(self) -> response::Response {
         match self {
             CreateTournamentError::TournamentSpecificationError(error) => build_response(StatusCode::BAD_REQUEST, error.to_string()),
-            CreateTournamentError::SaveTournamentError(error) => build_response(StatusCode::INTERNAL_SERVER_ERROR, error.to_string()),
+            CreateTournamentError::SaveTournamentAndPublishMessagesError(error) => error.into_response(),
             CreateTournamentError::AuthError(error) => error.into_response(),
         }
     }
@@ -0,0 +1,43 @@
+use crate::application::AuthInfo;
+use crate::application::AuthRole;
+use crate::application::DemoteAccountRequest;
+use crate::application::DemoteAccountError;
+use crate::application::DemoteAccount;
+
+use axum::{extract, Json, response};
+use serde::Serialize;
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+use std::sync::Arc;
+
+
+#[derive(Debug, Serialize)]
+pub struct ResponseBody {
+    role: AuthRole,
+}
+
+
+pub async fn handle_request(
+    extract::State(service): extract::State<Arc<Mutex<impl DemoteAccount>>>,
+    auth_info: AuthInfo,
+    extract::Path(account_id): extract::Path<Uuid>,
+) -> Result<Json<ResponseBody>, DemoteAccountError> {
+    let request = DemoteAccountRequest { account_id };
+
+    let mut service = service.lock().await;
+    let response = service.demote_account(request, &auth_info)?;
+    Ok(Json(ResponseBody { role: response.role }))
+}
+
+
+impl response::IntoResponse for DemoteAccountError {
+    fn into_response(self) -> response::Response {
+        match self {
+            DemoteAccountError::AuthError(error) => error.into_response(),
+            DemoteAccountError::LoadAccountError(error) => error.into_response(),
+            DemoteAccountError::AccountError(error) => error.into_response(),
+            DemoteAccountError::SaveAccountError(error) => error.into_response(),
+        }
+    }
+}
@@ -2,40 +2,122 @@ use crate::application::AuthInfo;
 use crate::application::FindTournamentsRequest;
 use crate::application::FindTournamentsError;
 use crate::application::FindTournaments;
+use crate::application::TournamentInfo;
+use crate::application::TournamentStageFilter;
+use crate::domain::TournamentCursor;
+use crate::domain::TournamentSortKey;
 
 use axum::{extract, Json, response};
-use serde::Serialize;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use tokio::sync::Mutex;
-use uuid::Uuid;
 
 use std::sync::Arc;
 
 
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Stage {
+    WaitingForPlayers,
+    Running,
+    Finished,
+}
+
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortKey {
+    Players,
+    FreeSeats,
+    TableSeats,
+    Tables,
+}
+
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortOrder {
+    Asc,
+    Desc,
+}
+
+
+#[derive(Debug, Deserialize)]
+pub struct RequestQuery {
+    stage: Option<Stage>,
+    table_seat_count: Option<u8>,
+    #[serde(default)]
+    joined: bool,
+    #[serde(default)]
+    free_seats: bool,
+    sort: Option<SortKey>,
+    order: Option<SortOrder>,
+    #[serde(default, deserialize_with = "deserialize_cursor")]
+    cursor: Option<TournamentCursor>,
+    limit: Option<usize>,
+}
+
+
 #[derive(Debug, Serialize)]
 pub struct ResponseBody {
-    // TODO: use serialized application's TournamentInfo
-    pub tournament_count: usize
+    pub tournaments: Vec<TournamentInfo>,
+    pub total_count: usize,
+    #[serde(serialize_with = "serialize_cursor")]
+    pub next_cursor: Option<TournamentCursor>,
 }
 
 
 pub async fn handle_request(
     extract::State(service): extract::State<Arc<Mutex<impl FindTournaments>>>,
+    auth_info: AuthInfo,
+    extract::Query(query): extract::Query<RequestQuery>,
 ) -> Result<Json<ResponseBody>, FindTournamentsError> {
-    let request = FindTournamentsRequest { };
-
-    // let auth_info = AuthInfo::Unauthenticated;
-    let auth_info = AuthInfo::Authenticated { account_id: Uuid::new_v4(), role: crate::application::AuthRole::Member };
+    let request = FindTournamentsRequest {
+        stage: query.stage.map(|stage| match stage {
+            Stage::WaitingForPlayers => TournamentStageFilter::WaitingForPlayers,
+            Stage::Running => TournamentStageFilter::Running,
+            Stage::Finished => TournamentStageFilter::Finished,
+        }),
+        table_seat_count: query.table_seat_count,
+        joined_only: query.joined,
+        with_free_seats: query.free_seats,
+        sort_key: match query.sort.unwrap_or(SortKey::Players) {
+            SortKey::Players => TournamentSortKey::Players,
+            SortKey::FreeSeats => TournamentSortKey::FreeSeats,
+            SortKey::TableSeats => TournamentSortKey::TableSeats,
+            SortKey::Tables => TournamentSortKey::Tables,
+        },
+        descending: matches!(query.order, Some(SortOrder::Desc)),
+        cursor: query.cursor,
+        limit: query.limit,
+    };
 
     let service = service.lock().await;
     let response = service.find_tournaments(request, &auth_info)?;
-    Ok(Json(ResponseBody { tournament_count: response.infos.len() }))
+    Ok(Json(ResponseBody { tournaments: response.infos, total_count: response.total_count, next_cursor: response.next_cursor }))
+}
+
+
+// Cursors are passed to clients as opaque strings of the form "<sort value>_<tournament id>"
+fn serialize_cursor<S: Serializer>(cursor: &Option<TournamentCursor>, serializer: S) -> Result<S::Ok, S::Error> {
+    match cursor {
+        Some(cursor) => serializer.serialize_str(&format!("{}_{}", cursor.sort_value, cursor.tournament_id)),
+        None => serializer.serialize_none(),
+    }
+}
+
+
+fn deserialize_cursor<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<TournamentCursor>, D::Error> {
+    let value = String::deserialize(deserializer)?;
+    let (sort_value, tournament_id) = value.split_once('_').ok_or_else(|| serde::de::Error::custom("invalid cursor"))?;
+    let sort_value = sort_value.parse().map_err(serde::de::Error::custom)?;
+    let tournament_id = tournament_id.parse().map_err(serde::de::Error::custom)?;
+    Ok(Some(TournamentCursor { sort_value, tournament_id }))
 }
 
 
 impl response::IntoResponse for FindTournamentsError {
     fn into_response(self) -> response::Response {
         match self {
-            FindTournamentsError::QueryTournamentsError(error) => error.into_response(),
             FindTournamentsError::AuthError(error) => error.into_response(),
         }
     }
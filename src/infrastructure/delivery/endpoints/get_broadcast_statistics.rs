@@ -0,0 +1,39 @@
+use crate::application::AuthInfo;
+use crate::application::GetBroadcastStatisticsRequest;
+use crate::application::GetBroadcastStatisticsError;
+use crate::application::GetBroadcastStatistics;
+
+use axum::{extract, Json, response};
+use serde::Serialize;
+use tokio::sync::Mutex;
+
+use std::sync::Arc;
+
+
+#[derive(Debug, Serialize)]
+pub struct ResponseBody {
+    channel_count: usize,
+    subscriber_count: usize,
+}
+
+
+pub async fn handle_request(
+    extract::State(service): extract::State<Arc<Mutex<impl GetBroadcastStatistics>>>,
+    auth_info: AuthInfo,
+) -> Result<Json<ResponseBody>, GetBroadcastStatisticsError> {
+    let request = GetBroadcastStatisticsRequest { };
+
+    let service = service.lock().await;
+    let response = service.get_broadcast_statistics(request, &auth_info)?;
+    Ok(Json(ResponseBody { channel_count: response.channel_count, subscriber_count: response.subscriber_count }))
+}
+
+
+impl response::IntoResponse for GetBroadcastStatisticsError {
+    fn into_response(self) -> response::Response {
+        match self {
+            GetBroadcastStatisticsError::AuthError(error) => error.into_response(),
+            GetBroadcastStatisticsError::LoadAccountError(error) => error.into_response(),
+        }
+    }
+}
@@ -0,0 +1,33 @@
+use crate::application::AuthInfo;
+use crate::application::GetPlayerHistoryRequest;
+use crate::application::GetPlayerHistoryResponse;
+use crate::application::GetPlayerHistoryError;
+use crate::application::GetPlayerHistory;
+
+use axum::{extract, Json, response};
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+use std::sync::Arc;
+
+
+pub async fn handle_request(
+    extract::State(service): extract::State<Arc<Mutex<impl GetPlayerHistory>>>,
+    auth_info: AuthInfo,
+    extract::Path(account_id): extract::Path<Uuid>,
+) -> Result<Json<GetPlayerHistoryResponse>, GetPlayerHistoryError> {
+    let request = GetPlayerHistoryRequest { account_id };
+
+    let service = service.lock().await;
+    let response = service.get_player_history(request, &auth_info)?;
+    Ok(Json(response))
+}
+
+
+impl response::IntoResponse for GetPlayerHistoryError {
+    fn into_response(self) -> response::Response {
+        match self {
+            GetPlayerHistoryError::AuthError(error) => error.into_response(),
+        }
+    }
+}
@@ -0,0 +1,35 @@
+use crate::application::AuthInfo;
+use crate::application::GetRoleChangesRequest;
+use crate::application::GetRoleChangesResponse;
+use crate::application::GetRoleChangesError;
+use crate::application::GetRoleChanges;
+
+use axum::{extract, Json, response};
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+use std::sync::Arc;
+
+
+pub async fn handle_request(
+    extract::State(service): extract::State<Arc<Mutex<impl GetRoleChanges>>>,
+    auth_info: AuthInfo,
+    extract::Path(account_id): extract::Path<Uuid>,
+) -> Result<Json<GetRoleChangesResponse>, GetRoleChangesError> {
+    let request = GetRoleChangesRequest { account_id };
+
+    let service = service.lock().await;
+    let response = service.get_role_changes(request, &auth_info)?;
+    Ok(Json(response))
+}
+
+
+impl response::IntoResponse for GetRoleChangesError {
+    fn into_response(self) -> response::Response {
+        match self {
+            GetRoleChangesError::AuthError(error) => error.into_response(),
+            GetRoleChangesError::LoadAccountError(error) => error.into_response(),
+            GetRoleChangesError::LoadRoleChangesError(error) => error.into_response(),
+        }
+    }
+}
@@ -0,0 +1,35 @@
+use crate::application::AuthInfo;
+use crate::application::GetTournamentRequest;
+use crate::application::GetTournamentResponse;
+use crate::application::GetTournamentError;
+use crate::application::GetTournament;
+
+use axum::{extract, Json, response};
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+use std::sync::Arc;
+
+
+pub async fn handle_request(
+    extract::State(service): extract::State<Arc<Mutex<impl GetTournament>>>,
+    auth_info: AuthInfo,
+    extract::Path(tournament_id): extract::Path<Uuid>,
+) -> Result<Json<GetTournamentResponse>, GetTournamentError> {
+    let request = GetTournamentRequest { tournament_id };
+
+    let service = service.lock().await;
+    let response = service.get_tournament(request, &auth_info)?;
+    Ok(Json(response))
+}
+
+
+impl response::IntoResponse for GetTournamentError {
+    fn into_response(self) -> response::Response {
+        match self {
+            GetTournamentError::AuthError(error) => error.into_response(),
+            GetTournamentError::LoadTournamentError(error) => error.into_response(),
+            GetTournamentError::LoadTableError(error) => error.into_response(),
+        }
+    }
+}
@@ -17,7 +17,7 @@ use std::sync::Arc;
 
 #[derive(Debug, Deserialize)]
 pub struct RequestBody {
-    nickname: String,
+    nickname: Option<String>,     // The registered nickname is used if omitted
 }
 
 
@@ -29,14 +29,12 @@ pub struct ResponseBody {
 
 pub async fn handle_request(
     extract::State(service): extract::State<Arc<Mutex<impl JoinTournament>>>,
+    auth_info: AuthInfo,
     extract::Path(tournament_id): extract::Path<Uuid>,
     extract::Json(request): extract::Json<RequestBody>,
 ) -> Result<Json<ResponseBody>, JoinTournamentError> {
     let request = JoinTournamentRequest { tournament_id, nickname: request.nickname };
 
-    // let auth_info = AuthInfo::Unauthenticated;
-    let auth_info = AuthInfo::Authenticated { account_id: Uuid::new_v4(), role: crate::application::AuthRole::Member };
-
     let mut service = service.lock().await;
     let response = service.join_tournament(request, &auth_info)?;
     Ok(Json(ResponseBody { table_number: response.table_number }))
@@ -52,10 +50,14 @@ impl response::IntoResponse for JoinTournamentError {
                     _ => build_response(StatusCode::INTERNAL_SERVER_ERROR, error.to_string()),
                 }
             },
-            JoinTournamentError::SaveTournamentError(error) => build_response(StatusCode::INTERNAL_SERVER_ERROR, error.to_string()),
+            JoinTournamentError::SaveTournamentAndPublishMessagesError(error) => error.into_response(),
             JoinTournamentError::AuthError(error) => error.into_response(),
             JoinTournamentError::NicknameError(error) => build_response(StatusCode::BAD_REQUEST, error.to_string()),
             JoinTournamentError::TournamentError(error) => build_response(StatusCode::BAD_REQUEST, error.to_string()),
+            JoinTournamentError::LoadBanError(error) => error.into_response(),
+            JoinTournamentError::BanError(error) => error.into_response(),
+            JoinTournamentError::LoadAccountError(error) => error.into_response(),
+            JoinTournamentError::AccountError(error) => error.into_response(),
         }
     }
 }
@@ -0,0 +1,37 @@
+use crate::application::AuthInfo;
+use crate::application::LiftBanRequest;
+use crate::application::LiftBanError;
+use crate::application::LiftBan;
+
+use axum::http::StatusCode;
+use axum::{extract, response};
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+use std::sync::Arc;
+
+
+pub async fn handle_request(
+    extract::State(service): extract::State<Arc<Mutex<impl LiftBan>>>,
+    auth_info: AuthInfo,
+    extract::Path(ban_id): extract::Path<Uuid>,
+) -> Result<StatusCode, LiftBanError> {
+    let request = LiftBanRequest { ban_id };
+
+    let mut service = service.lock().await;
+    service.lift_ban(request, &auth_info)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+
+impl response::IntoResponse for LiftBanError {
+    fn into_response(self) -> response::Response {
+        match self {
+            LiftBanError::AuthError(error) => error.into_response(),
+            LiftBanError::LoadAccountError(error) => error.into_response(),
+            LiftBanError::LoadBanError(error) => error.into_response(),
+            LiftBanError::BanError(error) => error.into_response(),
+            LiftBanError::SaveBanError(error) => error.into_response(),
+        }
+    }
+}
@@ -0,0 +1,55 @@
+use crate::application::AuthInfo;
+use crate::application::AuthRole;
+use crate::application::ListAccountsRequest;
+use crate::application::ListAccountsResponse;
+use crate::application::ListAccountsError;
+use crate::application::ListAccounts;
+
+use axum::{extract, Json, response};
+use serde::Deserialize;
+use tokio::sync::Mutex;
+
+use std::sync::Arc;
+
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    Member,
+    Moderator,
+    Administrator,
+}
+
+
+#[derive(Debug, Deserialize)]
+pub struct RequestQuery {
+    role: Role,
+}
+
+
+pub async fn handle_request(
+    extract::State(service): extract::State<Arc<Mutex<impl ListAccounts>>>,
+    auth_info: AuthInfo,
+    extract::Query(query): extract::Query<RequestQuery>,
+) -> Result<Json<ListAccountsResponse>, ListAccountsError> {
+    let role = match query.role {
+        Role::Member => AuthRole::Member,
+        Role::Moderator => AuthRole::Moderator,
+        Role::Administrator => AuthRole::Administrator,
+    };
+    let request = ListAccountsRequest { role };
+
+    let service = service.lock().await;
+    let response = service.list_accounts(request, &auth_info)?;
+    Ok(Json(response))
+}
+
+
+impl response::IntoResponse for ListAccountsError {
+    fn into_response(self) -> response::Response {
+        match self {
+            ListAccountsError::AuthError(error) => error.into_response(),
+            ListAccountsError::LoadAccountError(error) => error.into_response(),
+        }
+    }
+}
@@ -0,0 +1,58 @@
+use crate::application::AuthInfo;
+use crate::application::LoginRequest;
+use crate::application::LoginError;
+use crate::application::Login;
+use crate::domain::unix_time;
+use crate::infrastructure::TokenKey;
+
+use axum::{extract, Extension, Json, response};
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+use tokio::task;
+use uuid::Uuid;
+
+use std::sync::Arc;
+use std::time::Duration;
+
+
+const SESSION_LIFETIME: Duration = Duration::from_secs(24 * 60 * 60);
+
+
+#[derive(Debug, Deserialize)]
+pub struct RequestBody {
+    username: String,
+    password: String,
+}
+
+
+#[derive(Debug, Serialize)]
+pub struct ResponseBody {
+    account_id: Uuid,
+    token: String,
+    expires_in: u64,            // Seconds
+}
+
+
+pub async fn handle_request(
+    extract::State(service): extract::State<Arc<Mutex<impl Login>>>,
+    Extension(token_key): Extension<TokenKey>,
+    auth_info: AuthInfo,
+    extract::Json(request): extract::Json<RequestBody>,
+) -> Result<Json<ResponseBody>, LoginError> {
+    let request = LoginRequest { username: request.username, password: request.password };
+
+    let pending_login = service.lock().await.login(request, &auth_info)?;
+    let response = task::spawn_blocking(move || pending_login.verify()).await.expect("password verification does not panic")?;
+    let token = token_key.sign(response.account_id, response.role, SESSION_LIFETIME, unix_time());
+    Ok(Json(ResponseBody { account_id: response.account_id, token, expires_in: SESSION_LIFETIME.as_secs() }))
+}
+
+
+impl response::IntoResponse for LoginError {
+    fn into_response(self) -> response::Response {
+        match self {
+            LoginError::AccountError(error) => error.into_response(),
+            LoginError::LoadAccountError(error) => error.into_response(),
+        }
+    }
+}
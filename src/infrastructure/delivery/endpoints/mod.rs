@@ -1,22 +1,85 @@
+mod ban_account;
+mod change_nickname;
+mod change_password;
 mod create_tournament;
+mod demote_account;
 mod find_tournaments;
+mod get_broadcast_statistics;
+mod get_player_history;
+mod get_role_changes;
+mod get_tournament;
 mod join_tournament;
+mod lift_ban;
+mod list_accounts;
+mod login;
+mod observe_lobby;
 mod observe_table;
+mod observe_tournament;
+mod promote_account;
+mod rebuild_projections;
+mod register_account;
 
 use crate::application::AuthError;
+use crate::domain::AccountError;
+use crate::domain::BanError;
+use crate::domain::LoadAccountError;
+use crate::domain::LoadBanError;
+use crate::domain::LoadEventsError;
+use crate::domain::LoadRoleChangesError;
+use crate::domain::LoadTableError;
 use crate::domain::LoadTournamentError;
-use crate::domain::QueryTournamentsError;
+use crate::domain::MessageReceiver;
+use crate::domain::ObservationError;
+use crate::domain::SaveAccountError;
+use crate::domain::SaveBanError;
+use crate::domain::SaveTournamentAndPublishMessagesError;
+use crate::domain::SaveTournamentError;
+use crate::domain::SequencedMessage;
+use crate::domain::SequencedTableMessage;
+use crate::domain::SequencedTournamentMessage;
+use crate::domain::TableState;
 use crate::domain::TournamentError;
 
+use axum::extract::ws::Message;
+use axum::extract::ws::WebSocket;
+use axum::http::HeaderMap;
 use axum::response::IntoResponse;
 use axum::response::Response;
+use axum::response::sse::Event;
+use axum::response::sse::KeepAlive;
+use axum::response::sse::Sse;
 use axum::http::StatusCode;
+use futures_util::Stream;
+use futures_util::StreamExt;
+use futures_util::stream;
+use serde::Serialize;
 
+use std::convert::Infallible;
 
+
+pub use ban_account::handle_request as ban_account;
+pub use change_nickname::handle_request as change_nickname;
+pub use change_password::handle_request as change_password;
 pub use create_tournament::handle_request as create_tournament;
+pub use demote_account::handle_request as demote_account;
 pub use find_tournaments::handle_request as find_tournaments;
+pub use get_broadcast_statistics::handle_request as get_broadcast_statistics;
+pub use get_player_history::handle_request as get_player_history;
+pub use get_role_changes::handle_request as get_role_changes;
+pub use get_tournament::handle_request as get_tournament;
 pub use join_tournament::handle_request as join_tournament;
+pub use lift_ban::handle_request as lift_ban;
+pub use list_accounts::handle_request as list_accounts;
+pub use login::handle_request as login;
+pub use observe_lobby::handle_request as observe_lobby;
+pub use observe_lobby::handle_sse_request as observe_lobby_sse;
 pub use observe_table::handle_request as observe_table;
+pub use observe_table::handle_sse_request as observe_table_sse;
+pub use observe_tournament::handle_request as observe_tournament;
+pub use observe_tournament::handle_sse_request as observe_tournament_sse;
+pub use promote_account::handle_request as promote_account;
+pub use rebuild_projections::handle_request as rebuild_projections;
+pub use register_account::handle_request as register_account;
 
 
 fn build_response(status_code: axum::http::StatusCode, message: String) -> Response {
@@ -24,6 +87,120 @@ fn build_response(status_code: axum::http::StatusCode, message: String) -> Respo
 }
 
 
+#[derive(Debug, Serialize)]
+#[serde(tag = "type")]
+enum ServerMessage<'a> {
+    TableState {
+        sequence: u64,
+        state: &'a TableState,
+    },
+    TableMessage(&'a SequencedTableMessage),
+    TournamentMessage(&'a SequencedTournamentMessage),
+    // Missed messages could not be replayed, the observer has to query the state anew
+    Resync {
+        sequence: u64,
+    },
+}
+
+impl ServerMessage<'_> {
+    fn sequence(&self) -> u64 {
+        match self {
+            Self::TableState { sequence, .. } => *sequence,
+            Self::TableMessage(message) => message.sequence,
+            Self::TournamentMessage(message) => message.sequence,
+            Self::Resync { sequence } => *sequence,
+        }
+    }
+
+    fn to_ws_message(&self) -> Message {
+        Message::Text(serde_json::to_string(self).unwrap().into())
+    }
+
+    fn to_sse_event(&self) -> Event {
+        Event::default().id(self.sequence().to_string()).data(serde_json::to_string(self).unwrap())
+    }
+}
+
+
+trait AsServerMessage {
+    fn as_server_message(&self) -> ServerMessage<'_>;
+}
+
+impl AsServerMessage for SequencedTableMessage {
+    fn as_server_message(&self) -> ServerMessage<'_> {
+        ServerMessage::TableMessage(self)
+    }
+}
+
+impl AsServerMessage for SequencedTournamentMessage {
+    fn as_server_message(&self) -> ServerMessage<'_> {
+        ServerMessage::TournamentMessage(self)
+    }
+}
+
+
+// Messages sent before the live ones, a resync if the missed messages cannot be replayed
+fn initial_messages<Content>(missed_messages: &Option<Vec<SequencedMessage<Content>>>, last_sequence: u64) -> Vec<ServerMessage<'_>>
+where
+    SequencedMessage<Content>: AsServerMessage
+{
+    match missed_messages {
+        Some(missed_messages) => missed_messages.iter().map(|message| message.as_server_message()).collect(),
+        None => vec![ServerMessage::Resync { sequence: last_sequence }],
+    }
+}
+
+
+async fn send_messages<'a>(socket: &mut WebSocket, messages: impl IntoIterator<Item = ServerMessage<'a>>) -> bool {
+    for message in messages {
+        if socket.send(message.to_ws_message()).await.is_err() {
+            return false;
+        }
+    }
+    true
+}
+
+
+async fn forward_messages<Content: Clone>(socket: &mut WebSocket, mut receiver: MessageReceiver<Content>)
+where
+    SequencedMessage<Content>: AsServerMessage
+{
+    // A lagging receiver ends the stream, the client reconnects with its last seen sequence
+    while let Ok(message) = receiver.recv().await {
+        if socket.send(message.as_server_message().to_ws_message()).await.is_err() {
+            break;
+        }
+    }
+}
+
+
+// The Last-Event-ID header, sent by reconnecting event sources, takes precedence over the query parameter
+fn last_seen_sequence(headers: &HeaderMap, last_seen_sequence: Option<u64>) -> Option<u64> {
+    headers.get("last-event-id")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse().ok())
+        .or(last_seen_sequence)
+}
+
+
+fn event_stream<Content: Clone + Send + 'static>(
+    initial_events: Vec<Event>,
+    receiver: MessageReceiver<Content>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>>
+where
+    SequencedMessage<Content>: AsServerMessage
+{
+    // A lagging receiver ends the stream, the event source reconnects with its last event id
+    let live_events = stream::unfold(receiver, |mut receiver| async move {
+        let message = receiver.recv().await.ok()?;
+        let event = message.as_server_message().to_sse_event();
+        Some((Ok(event), receiver))
+    });
+    let events = stream::iter(initial_events.into_iter().map(Ok)).chain(live_events);
+    Sse::new(events).keep_alive(KeepAlive::default())
+}
+
+
 impl IntoResponse for AuthError {
     fn into_response(self) -> Response {
         match self {
@@ -44,15 +221,42 @@ impl IntoResponse for LoadTournamentError {
 }
 
 
-impl IntoResponse for QueryTournamentsError {
+impl IntoResponse for SaveTournamentError {
+    fn into_response(self) -> Response {
+        match self {
+            SaveTournamentError::TournamentOutdated => build_response(StatusCode::CONFLICT, self.to_string()),
+            _ => build_response(StatusCode::INTERNAL_SERVER_ERROR, self.to_string()),
+        }
+    }
+}
+
+
+impl IntoResponse for SaveTournamentAndPublishMessagesError {
     fn into_response(self) -> Response {
         match self {
-            QueryTournamentsError::DatabaseQueryError => build_response(StatusCode::INTERNAL_SERVER_ERROR, self.to_string()),
+            SaveTournamentAndPublishMessagesError::SaveTournamentError(error) => error.into_response(),
         }
     }
 }
 
 
+impl IntoResponse for LoadTableError {
+    fn into_response(self) -> Response {
+        match self {
+            LoadTableError::TableNotFound => build_response(StatusCode::NOT_FOUND, self.to_string()),
+            _ => build_response(StatusCode::INTERNAL_SERVER_ERROR, self.to_string()),
+        }
+    }
+}
+
+
+impl IntoResponse for LoadEventsError {
+    fn into_response(self) -> Response {
+        build_response(StatusCode::INTERNAL_SERVER_ERROR, self.to_string())
+    }
+}
+
+
 impl IntoResponse for TournamentError {
     fn into_response(self) -> Response {
         match self {
@@ -61,3 +265,170 @@ impl IntoResponse for TournamentError {
         }
     }
 }
+
+
+impl IntoResponse for AccountError {
+    fn into_response(self) -> Response {
+        match self {
+            AccountError::InvalidCredentials => build_response(StatusCode::UNAUTHORIZED, self.to_string()),
+            AccountError::OwnRoleChange => build_response(StatusCode::FORBIDDEN, self.to_string()),
+            AccountError::NoHigherRole | AccountError::NoLowerRole => build_response(StatusCode::CONFLICT, self.to_string()),
+            AccountError::NicknameMismatch | AccountError::NicknameChangeTooSoon { .. } => build_response(StatusCode::CONFLICT, self.to_string()),
+            _ => build_response(StatusCode::BAD_REQUEST, self.to_string()),
+        }
+    }
+}
+
+
+impl IntoResponse for LoadAccountError {
+    fn into_response(self) -> Response {
+        match self {
+            LoadAccountError::AccountNotFound => build_response(StatusCode::NOT_FOUND, self.to_string()),
+            _ => build_response(StatusCode::INTERNAL_SERVER_ERROR, self.to_string()),
+        }
+    }
+}
+
+
+impl IntoResponse for SaveAccountError {
+    fn into_response(self) -> Response {
+        match self {
+            SaveAccountError::UsernameTaken | SaveAccountError::NicknameTaken => build_response(StatusCode::CONFLICT, self.to_string()),
+            _ => build_response(StatusCode::INTERNAL_SERVER_ERROR, self.to_string()),
+        }
+    }
+}
+
+
+impl IntoResponse for LoadRoleChangesError {
+    fn into_response(self) -> Response {
+        build_response(StatusCode::INTERNAL_SERVER_ERROR, self.to_string())
+    }
+}
+
+
+impl IntoResponse for BanError {
+    fn into_response(self) -> Response {
+        match self {
+            BanError::BanNotInForce => build_response(StatusCode::CONFLICT, self.to_string()),
+            _ => build_response(StatusCode::FORBIDDEN, self.to_string()),
+        }
+    }
+}
+
+
+impl IntoResponse for ObservationError {
+    fn into_response(self) -> Response {
+        match self {
+            ObservationError::DelayedStateUnavailable => build_response(StatusCode::SERVICE_UNAVAILABLE, self.to_string()),
+            _ => build_response(StatusCode::FORBIDDEN, self.to_string()),
+        }
+    }
+}
+
+
+impl IntoResponse for LoadBanError {
+    fn into_response(self) -> Response {
+        match self {
+            LoadBanError::BanNotFound => build_response(StatusCode::NOT_FOUND, self.to_string()),
+            _ => build_response(StatusCode::INTERNAL_SERVER_ERROR, self.to_string()),
+        }
+    }
+}
+
+
+impl IntoResponse for SaveBanError {
+    fn into_response(self) -> Response {
+        build_response(StatusCode::INTERNAL_SERVER_ERROR, self.to_string())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::domain::TableMessage;
+    use crate::domain::TournamentMessage;
+    use crate::domain::TournamentMessageType;
+
+    use axum::http::HeaderValue;
+    use tokio::sync::broadcast;
+    use uuid::Uuid;
+
+    use std::time::Instant;
+
+    use super::*;
+
+    fn tournament_message(sequence: u64) -> SequencedTournamentMessage {
+        let message = TournamentMessage { tournament_id: Uuid::new_v4(), message_type: TournamentMessageType::RegistrationCountChanged { player_count: 2 } };
+        SequencedMessage { sequence, message, published_at: Instant::now() }
+    }
+
+    // Responses are built within the runtime, as keeping streams alive takes a timer. The streams end
+    // once their events are written, as the receivers see their senders dropped.
+    fn body_of<Body: IntoResponse>(response: impl FnOnce() -> Body) -> String {
+        let runtime = tokio::runtime::Builder::new_current_thread().enable_time().build().unwrap();
+        let response = runtime.block_on(async { response().into_response() });
+        let body = runtime.block_on(axum::body::to_bytes(response.into_body(), usize::MAX)).unwrap();
+        String::from_utf8(body.to_vec()).unwrap()
+    }
+
+    #[test]
+    fn last_event_id_takes_precedence_over_the_query_parameter() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(last_seen_sequence(&headers, Some(3)), Some(3));
+        assert_eq!(last_seen_sequence(&headers, None), None);
+        headers.insert("last-event-id", HeaderValue::from_static("8"));
+        assert_eq!(last_seen_sequence(&headers, Some(3)), Some(8));
+        assert_eq!(last_seen_sequence(&headers, None), Some(8));
+    }
+
+    #[test]
+    fn malformed_last_event_id_falls_back_to_the_query_parameter() {
+        let mut headers = HeaderMap::new();
+        for value in ["", "eight", "-1", "8.5"] {
+            headers.insert("last-event-id", HeaderValue::from_static(value));
+            assert_eq!(last_seen_sequence(&headers, Some(3)), Some(3), "{:?}", value);
+            assert_eq!(last_seen_sequence(&headers, None), None, "{:?}", value);
+        }
+    }
+
+    #[test]
+    fn event_ids_are_message_sequences() {
+        let message = tournament_message(12);
+        let event = message.as_server_message().to_sse_event();
+        let body = body_of(|| Sse::new(stream::iter([Ok::<_, Infallible>(event)])));
+        assert!(body.contains("id: 12\n"), "{}", body);
+        assert!(body.contains(r#"data: {"type":"TournamentMessage","sequence":12,"#), "{}", body);
+        let body = body_of(|| Sse::new(stream::iter([Ok::<_, Infallible>(ServerMessage::Resync { sequence: 7 }.to_sse_event())])));
+        assert!(body.contains("id: 7\n"), "{}", body);
+    }
+
+    #[test]
+    fn initial_events_come_before_live_ones() {
+        let (sender, receiver) = broadcast::channel(4);
+        sender.send(tournament_message(5)).unwrap();
+        sender.send(tournament_message(6)).unwrap();
+        drop(sender);
+        let initial_events = [3, 4].map(|sequence| tournament_message(sequence).as_server_message().to_sse_event()).to_vec();
+        let body = body_of(|| event_stream(initial_events, MessageReceiver::new(receiver)));
+        let ids: Vec<&str> = body.lines().filter_map(|line| line.strip_prefix("id: ")).collect();
+        assert_eq!(ids, ["3", "4", "5", "6"]);
+    }
+
+    #[test]
+    fn initial_messages_replay_missed_messages() {
+        let message = TournamentMessage { tournament_id: Uuid::new_v4(), message_type: TournamentMessageType::RegistrationCountChanged { player_count: 2 } };
+        let missed_messages = Some(vec![SequencedMessage { sequence: 4, message, published_at: Instant::now() }]);
+        let messages = initial_messages(&missed_messages, 4);
+        assert_eq!(messages.len(), 1);
+        assert!(matches!(messages[0], ServerMessage::TournamentMessage(message) if message.sequence == 4));
+    }
+
+    #[test]
+    fn initial_messages_resync_when_missed_messages_are_unknown() {
+        let messages = initial_messages::<TableMessage>(&None, 7);
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].sequence(), 7);
+        assert_eq!(serde_json::to_string(&messages[0]).unwrap(), r#"{"type":"Resync","sequence":7}"#);
+    }
+}
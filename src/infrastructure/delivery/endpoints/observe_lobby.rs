@@ -0,0 +1,75 @@
+use crate::application::AuthInfo;
+use crate::application::ObserveLobbyError;
+use crate::application::ObserveLobby;
+use crate::application::ObserveLobbyRequest;
+use crate::application::ObserveLobbyResponse;
+
+use super::event_stream;
+use super::forward_messages;
+use super::initial_messages;
+use super::last_seen_sequence;
+use super::send_messages;
+
+use axum::extract::WebSocketUpgrade;
+use axum::extract::ws::WebSocket;
+use axum::http::HeaderMap;
+use axum::response::IntoResponse;
+use axum::response::Response;
+use axum::{extract, response};
+use serde::Deserialize;
+use tokio::sync::Mutex;
+
+use std::sync::Arc;
+
+
+#[derive(Debug, Deserialize)]
+pub struct RequestQuery {
+    last_seen_sequence: Option<u64>,
+}
+
+
+pub async fn handle_request(
+    wsu: WebSocketUpgrade,
+    extract::Query(query): extract::Query<RequestQuery>,
+    extract::State(service): extract::State<Arc<Mutex<impl ObserveLobby>>>,
+    auth_info: AuthInfo,
+) -> Result<Response, ObserveLobbyError> {
+    let request = ObserveLobbyRequest { last_seen_sequence: query.last_seen_sequence };
+
+    let mut service = service.lock().await;
+    let response = service.observe_lobby(request, &auth_info)?;
+    Ok(wsu.on_upgrade(|socket| observe_lobby(socket, response)))
+}
+
+
+pub async fn handle_sse_request(
+    headers: HeaderMap,
+    extract::Query(query): extract::Query<RequestQuery>,
+    extract::State(service): extract::State<Arc<Mutex<impl ObserveLobby>>>,
+    auth_info: AuthInfo,
+) -> Result<Response, ObserveLobbyError> {
+    let request = ObserveLobbyRequest { last_seen_sequence: last_seen_sequence(&headers, query.last_seen_sequence) };
+
+    let mut service = service.lock().await;
+    let response = service.observe_lobby(request, &auth_info)?;
+    let initial_events = initial_messages(&response.missed_messages, response.last_sequence).iter().map(|message| message.to_sse_event()).collect();
+    Ok(event_stream(initial_events, response.receiver).into_response())
+}
+
+
+pub async fn observe_lobby(mut socket: WebSocket, response: ObserveLobbyResponse) {
+    log::info!("{:?} observes lobby", socket);
+    if send_messages(&mut socket, initial_messages(&response.missed_messages, response.last_sequence)).await {
+        forward_messages(&mut socket, response.receiver).await;
+    }
+    log::info!("{:?} finished observing lobby", socket);
+}
+
+
+impl response::IntoResponse for ObserveLobbyError {
+    fn into_response(self) -> response::Response {
+        match self {
+            ObserveLobbyError::AuthError(error) => error.into_response(),
+        }
+    }
+}
@@ -4,29 +4,45 @@ use crate::application::ObserveTable;
 use crate::application::ObserveTableRequest;
 use crate::application::ObserveTableResponse;
 
+use super::ServerMessage;
+use super::event_stream;
+use super::forward_messages;
+use super::initial_messages;
+use super::last_seen_sequence;
+use super::send_messages;
+
 use axum::extract::WebSocketUpgrade;
 use axum::extract::ws::WebSocket;
+use axum::http::HeaderMap;
+use axum::response::IntoResponse;
 use axum::response::Response;
 use axum::{extract, response};
+use serde::Deserialize;
 use tokio::sync::Mutex;
 use uuid::Uuid;
 
 use std::sync::Arc;
 
 
+#[derive(Debug, Deserialize)]
+pub struct RequestQuery {
+    last_seen_sequence: Option<u64>,
+}
+
+
 pub async fn handle_request(
     wsu: WebSocketUpgrade,
     extract::Path((tournament_id, table_number)): extract::Path<(Uuid, usize)>,
+    extract::Query(query): extract::Query<RequestQuery>,
     extract::State(service): extract::State<Arc<Mutex<impl ObserveTable>>>,
+    auth_info: AuthInfo,
 ) -> Result<Response, ObserveTableError> {
     log::info!("WEBSOCKET REQUEST");
 
-    // TODO: check auth
-    let auth_info = AuthInfo::Authenticated { account_id: Uuid::new_v4(), role: crate::application::AuthRole::Member };
-
     let request = ObserveTableRequest {
         tournament_id,
         table_number,
+        last_seen_sequence: query.last_seen_sequence,
     };
 
     let mut service = service.lock().await;
@@ -35,30 +51,55 @@ pub async fn handle_request(
 }
 
 
+pub async fn handle_sse_request(
+    headers: HeaderMap,
+    extract::Path((tournament_id, table_number)): extract::Path<(Uuid, usize)>,
+    extract::Query(query): extract::Query<RequestQuery>,
+    extract::State(service): extract::State<Arc<Mutex<impl ObserveTable>>>,
+    auth_info: AuthInfo,
+) -> Result<Response, ObserveTableError> {
+    let request = ObserveTableRequest {
+        tournament_id,
+        table_number,
+        last_seen_sequence: last_seen_sequence(&headers, query.last_seen_sequence),
+    };
+
+    let mut service = service.lock().await;
+    let response = service.observe_table(request, &auth_info)?;
+    let initial_events = initial_table_messages(&response).iter().map(|message| message.to_sse_event()).collect();
+    Ok(event_stream(initial_events, response.receiver).into_response())
+}
+
+
 pub async fn observe_table(mut socket: WebSocket, response: ObserveTableResponse) {
     log::info!("{:?} observes table", socket);
-    let _table_state = response.table_state;
-    // TODO: serialize table state and send it as first message
-    if socket.send(extract::ws::Message::Text("table_state".into())).await.is_ok() {
-        let mut receiver = response.receiver;
-        while let Ok(message) = receiver.recv().await {
-            // TODO: error handling
-            log::info!("sending message {:?} to {:?}", message, socket);
-            if socket.send(extract::ws::Message::Text("table_message".into())).await.is_err() {
-                break;
-            }
-        }
+    if send_messages(&mut socket, initial_table_messages(&response)).await {
+        forward_messages(&mut socket, response.receiver).await;
     }
     log::info!("{:?} finished observing table", socket);
 }
 
 
+// Observers who cannot catch up from missed messages start from the table state
+fn initial_table_messages(response: &ObserveTableResponse) -> Vec<ServerMessage<'_>> {
+    match (&response.missed_messages, &response.table_state) {
+        (None, Some(state)) => vec![ServerMessage::TableState { sequence: response.table_sequence, state }],
+        (missed_messages, _) => initial_messages(missed_messages, response.table_sequence),
+    }
+}
+
+
 impl response::IntoResponse for ObserveTableError {
     fn into_response(self) -> response::Response {
         match self {
             ObserveTableError::AuthError(error) => error.into_response(),
             ObserveTableError::LoadTournamentError(error) => error.into_response(),
+            ObserveTableError::LoadTableError(error) => error.into_response(),
             ObserveTableError::TournamentError(error) => error.into_response(),
+            ObserveTableError::LoadAccountError(error) => error.into_response(),
+            ObserveTableError::LoadBanError(error) => error.into_response(),
+            ObserveTableError::BanError(error) => error.into_response(),
+            ObserveTableError::ObservationError(error) => error.into_response(),
         }
     }
 }
@@ -0,0 +1,85 @@
+use crate::application::AuthInfo;
+use crate::application::ObserveTournamentError;
+use crate::application::ObserveTournament;
+use crate::application::ObserveTournamentRequest;
+use crate::application::ObserveTournamentResponse;
+
+use super::event_stream;
+use super::forward_messages;
+use super::initial_messages;
+use super::last_seen_sequence;
+use super::send_messages;
+
+use axum::extract::WebSocketUpgrade;
+use axum::extract::ws::WebSocket;
+use axum::http::HeaderMap;
+use axum::response::IntoResponse;
+use axum::response::Response;
+use axum::{extract, response};
+use serde::Deserialize;
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+use std::sync::Arc;
+
+
+#[derive(Debug, Deserialize)]
+pub struct RequestQuery {
+    last_seen_sequence: Option<u64>,
+}
+
+
+pub async fn handle_request(
+    wsu: WebSocketUpgrade,
+    extract::Path(tournament_id): extract::Path<Uuid>,
+    extract::Query(query): extract::Query<RequestQuery>,
+    extract::State(service): extract::State<Arc<Mutex<impl ObserveTournament>>>,
+    auth_info: AuthInfo,
+) -> Result<Response, ObserveTournamentError> {
+    let request = ObserveTournamentRequest {
+        tournament_id,
+        last_seen_sequence: query.last_seen_sequence,
+    };
+
+    let mut service = service.lock().await;
+    let response = service.observe_tournament(request, &auth_info)?;
+    Ok(wsu.on_upgrade(|socket| observe_tournament(socket, response)))
+}
+
+
+pub async fn handle_sse_request(
+    headers: HeaderMap,
+    extract::Path(tournament_id): extract::Path<Uuid>,
+    extract::Query(query): extract::Query<RequestQuery>,
+    extract::State(service): extract::State<Arc<Mutex<impl ObserveTournament>>>,
+    auth_info: AuthInfo,
+) -> Result<Response, ObserveTournamentError> {
+    let request = ObserveTournamentRequest {
+        tournament_id,
+        last_seen_sequence: last_seen_sequence(&headers, query.last_seen_sequence),
+    };
+
+    let mut service = service.lock().await;
+    let response = service.observe_tournament(request, &auth_info)?;
+    let initial_events = initial_messages(&response.missed_messages, response.last_sequence).iter().map(|message| message.to_sse_event()).collect();
+    Ok(event_stream(initial_events, response.receiver).into_response())
+}
+
+
+pub async fn observe_tournament(mut socket: WebSocket, response: ObserveTournamentResponse) {
+    log::info!("{:?} observes tournament", socket);
+    if send_messages(&mut socket, initial_messages(&response.missed_messages, response.last_sequence)).await {
+        forward_messages(&mut socket, response.receiver).await;
+    }
+    log::info!("{:?} finished observing tournament", socket);
+}
+
+
+impl response::IntoResponse for ObserveTournamentError {
+    fn into_response(self) -> response::Response {
+        match self {
+            ObserveTournamentError::AuthError(error) => error.into_response(),
+            ObserveTournamentError::LoadTournamentError(error) => error.into_response(),
+        }
+    }
+}
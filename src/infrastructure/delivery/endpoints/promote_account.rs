@@ -0,0 +1,43 @@
+use crate::application::AuthInfo;
+use crate::application::AuthRole;
+use crate::application::PromoteAccountRequest;
+use crate::application::PromoteAccountError;
+use crate::application::PromoteAccount;
+
+use axum::{extract, Json, response};
+use serde::Serialize;
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+use std::sync::Arc;
+
+
+#[derive(Debug, Serialize)]
+pub struct ResponseBody {
+    role: AuthRole,
+}
+
+
+pub async fn handle_request(
+    extract::State(service): extract::State<Arc<Mutex<impl PromoteAccount>>>,
+    auth_info: AuthInfo,
+    extract::Path(account_id): extract::Path<Uuid>,
+) -> Result<Json<ResponseBody>, PromoteAccountError> {
+    let request = PromoteAccountRequest { account_id };
+
+    let mut service = service.lock().await;
+    let response = service.promote_account(request, &auth_info)?;
+    Ok(Json(ResponseBody { role: response.role }))
+}
+
+
+impl response::IntoResponse for PromoteAccountError {
+    fn into_response(self) -> response::Response {
+        match self {
+            PromoteAccountError::AuthError(error) => error.into_response(),
+            PromoteAccountError::LoadAccountError(error) => error.into_response(),
+            PromoteAccountError::AccountError(error) => error.into_response(),
+            PromoteAccountError::SaveAccountError(error) => error.into_response(),
+        }
+    }
+}
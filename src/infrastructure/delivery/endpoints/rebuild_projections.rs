@@ -0,0 +1,39 @@
+use crate::application::AuthInfo;
+use crate::application::RebuildProjectionsRequest;
+use crate::application::RebuildProjectionsError;
+use crate::application::RebuildProjections;
+
+use axum::{extract, Json, response};
+use serde::Serialize;
+use tokio::sync::Mutex;
+
+use std::sync::Arc;
+
+
+#[derive(Debug, Serialize)]
+pub struct ResponseBody {
+    tournament_count: usize,
+}
+
+
+pub async fn handle_request(
+    extract::State(service): extract::State<Arc<Mutex<impl RebuildProjections>>>,
+    auth_info: AuthInfo,
+) -> Result<Json<ResponseBody>, RebuildProjectionsError> {
+    let request = RebuildProjectionsRequest { };
+
+    let mut service = service.lock().await;
+    let response = service.rebuild_projections(request, &auth_info)?;
+    Ok(Json(ResponseBody { tournament_count: response.tournament_count }))
+}
+
+
+impl response::IntoResponse for RebuildProjectionsError {
+    fn into_response(self) -> response::Response {
+        match self {
+            RebuildProjectionsError::AuthError(error) => error.into_response(),
+            RebuildProjectionsError::LoadAccountError(error) => error.into_response(),
+            RebuildProjectionsError::LoadEventsError(error) => error.into_response(),
+        }
+    }
+}
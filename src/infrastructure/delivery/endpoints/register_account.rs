@@ -0,0 +1,50 @@
+use crate::application::AuthInfo;
+use crate::application::RegisterAccountRequest;
+use crate::application::RegisterAccountError;
+use crate::application::RegisterAccount;
+use crate::domain::HashedPassword;
+
+use axum::{extract, Json, response};
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+use tokio::task;
+use uuid::Uuid;
+
+use std::sync::Arc;
+
+
+#[derive(Debug, Deserialize)]
+pub struct RequestBody {
+    username: String,
+    password: String,
+}
+
+
+#[derive(Debug, Serialize)]
+pub struct ResponseBody {
+    account_id: Uuid,
+}
+
+
+pub async fn handle_request(
+    extract::State(service): extract::State<Arc<Mutex<impl RegisterAccount>>>,
+    auth_info: AuthInfo,
+    extract::Json(request): extract::Json<RequestBody>,
+) -> Result<Json<ResponseBody>, RegisterAccountError> {
+    let password = task::spawn_blocking(move || HashedPassword::new(&request.password)).await.expect("password hashing does not panic")?;
+    let request = RegisterAccountRequest { username: request.username, password };
+
+    let mut service = service.lock().await;
+    let response = service.register_account(request, &auth_info)?;
+    Ok(Json(ResponseBody { account_id: response.account_id }))
+}
+
+
+impl response::IntoResponse for RegisterAccountError {
+    fn into_response(self) -> response::Response {
+        match self {
+            RegisterAccountError::AccountError(error) => error.into_response(),
+            RegisterAccountError::SaveAccountError(error) => error.into_response(),
+        }
+    }
+}
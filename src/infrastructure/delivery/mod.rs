@@ -1,4 +1,8 @@
+mod auth;
 mod endpoints;
+mod rate_limit;
 mod server;
 
+pub use auth::TokenKey;
+pub use rate_limit::RateLimits;
 pub use server::AxumServer;
@@ -0,0 +1,315 @@
+use crate::application::AuthInfo;
+
+use axum::extract::ConnectInfo;
+use axum::extract::Request;
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::http::header;
+use axum::middleware::Next;
+use axum::response::Response;
+use log::debug;
+use thiserror::Error;
+use uuid::Uuid;
+
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::net::Ipv4Addr;
+use std::net::SocketAddr;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::time::Duration;
+use std::time::Instant;
+
+
+// Buckets that have refilled completely are dropped at this interval, they behave like new ones
+const CLEANUP_INTERVAL: Duration = Duration::from_secs(60);
+
+
+#[derive(Debug, Error)]
+pub enum RateBudgetError {
+    #[error("Rate budgets must have the form <requests>/<seconds>")]
+    BudgetMalformed,
+    #[error("Rate budgets must allow at least one request per non-zero period")]
+    BudgetEmpty,
+}
+
+
+// Mutating endpoints are grouped by what they are abused for, so that hammering one group does
+// not use up the budget of another
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RouteGroup {
+    Accounts,
+    Tournaments,
+    Moderation,
+}
+
+
+// Number of requests allowed per period. Up to that many requests may be made at once, after
+// which the budget refills evenly over the period.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateBudget {
+    requests: u32,
+    period: Duration,
+}
+
+impl RateBudget {
+    pub fn new(requests: u32, period: Duration) -> Result<Self, RateBudgetError> {
+        if requests == 0 || period.is_zero() {
+            return Err(RateBudgetError::BudgetEmpty);
+        }
+        Ok(Self { requests, period })
+    }
+
+    fn refill_rate(&self) -> f64 {
+        self.requests as f64 / self.period.as_secs_f64()
+    }
+}
+
+impl FromStr for RateBudget {
+    type Err = RateBudgetError;
+
+    fn from_str(budget: &str) -> Result<Self, Self::Err> {
+        let (requests, seconds) = budget.split_once('/').ok_or(RateBudgetError::BudgetMalformed)?;
+        let requests = requests.trim().parse().map_err(|_| RateBudgetError::BudgetMalformed)?;
+        let seconds = seconds.trim().parse().map_err(|_| RateBudgetError::BudgetMalformed)?;
+        Self::new(requests, Duration::from_secs(seconds))
+    }
+}
+
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimits {
+    pub accounts: RateBudget,
+    pub tournaments: RateBudget,
+    pub moderation: RateBudget,
+}
+
+impl RateLimits {
+    fn budget(&self, group: RouteGroup) -> RateBudget {
+        match group {
+            RouteGroup::Accounts => self.accounts,
+            RouteGroup::Tournaments => self.tournaments,
+            RouteGroup::Moderation => self.moderation,
+        }
+    }
+}
+
+impl Default for RateLimits {
+    fn default() -> Self {
+        let minute = Duration::from_secs(60);
+        Self {
+            accounts: RateBudget { requests: 10, period: minute },
+            tournaments: RateBudget { requests: 30, period: minute },
+            moderation: RateBudget { requests: 60, period: minute },
+        }
+    }
+}
+
+
+pub trait Clock: Send + Sync {
+    fn now(&self) -> Instant;
+}
+
+
+#[derive(Debug)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+
+// Requests of authenticated accounts are counted per account, wherever they come from. Others
+// are counted per address, so that logins and registrations are limited as well.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum RateLimitKey {
+    Account(Uuid),
+    Address(IpAddr),
+}
+
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    updated_at: Instant,
+}
+
+
+#[derive(Debug)]
+struct Buckets {
+    buckets: HashMap<(RouteGroup, RateLimitKey), Bucket>,
+    cleaned_up_at: Instant,
+}
+
+
+// Token buckets shared by all routes of the server
+#[derive(Clone)]
+pub struct RateLimiter {
+    limits: RateLimits,
+    clock: Arc<dyn Clock>,
+    buckets: Arc<Mutex<Buckets>>,
+}
+
+impl RateLimiter {
+    pub fn new(limits: RateLimits, clock: impl Clock + 'static) -> Self {
+        let buckets = Buckets { buckets: HashMap::new(), cleaned_up_at: clock.now() };
+        Self { limits, clock: Arc::new(clock), buckets: Arc::new(Mutex::new(buckets)) }
+    }
+
+    // Takes one request from the budget of the key, or tells how long to wait for the next one
+    fn acquire(&self, group: RouteGroup, key: RateLimitKey) -> Result<(), Duration> {
+        let now = self.clock.now();
+        let budget = self.limits.budget(group);
+        let capacity = budget.requests as f64;
+        let rate = budget.refill_rate();
+        let mut buckets = self.buckets.lock().unwrap_or_else(|error| error.into_inner());
+
+        if now.saturating_duration_since(buckets.cleaned_up_at) >= CLEANUP_INTERVAL {
+            let limits = self.limits;
+            buckets.buckets.retain(|(group, _), bucket| {
+                let budget = limits.budget(*group);
+                let refilled = bucket.tokens + now.saturating_duration_since(bucket.updated_at).as_secs_f64() * budget.refill_rate();
+                refilled < budget.requests as f64
+            });
+            buckets.cleaned_up_at = now;
+        }
+
+        let bucket = buckets.buckets.entry((group, key)).or_insert(Bucket { tokens: capacity, updated_at: now });
+        let elapsed = now.saturating_duration_since(bucket.updated_at).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * rate).min(capacity);
+        bucket.updated_at = now;
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - bucket.tokens) / rate))
+        }
+    }
+}
+
+impl std::fmt::Debug for RateLimiter {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        formatter.debug_struct("RateLimiter").field("limits", &self.limits).finish_non_exhaustive()
+    }
+}
+
+
+// Middleware for the routes of a group. The client address is only known when the server is
+// run with connect info, without it all unauthenticated requests share one budget.
+pub async fn limit_rate(State((limiter, group)): State<(RateLimiter, RouteGroup)>, auth_info: AuthInfo, request: Request, next: Next) -> Response {
+    let key = match auth_info {
+        AuthInfo::Authenticated { account_id, .. } => RateLimitKey::Account(account_id),
+        AuthInfo::Unauthenticated => {
+            let address = request.extensions().get::<ConnectInfo<SocketAddr>>()
+                .map_or(IpAddr::V4(Ipv4Addr::UNSPECIFIED), |ConnectInfo(address)| address.ip());
+            RateLimitKey::Address(address)
+        },
+    };
+    match limiter.acquire(group, key) {
+        Ok(()) => next.run(request).await,
+        Err(retry_after) => {
+            debug!("rate limited {:?} on {:?} routes", key, group);
+            too_many_requests(retry_after)
+        },
+    }
+}
+
+
+fn too_many_requests(retry_after: Duration) -> Response {
+    let seconds = retry_after.as_secs_f64().ceil().max(1.0) as u64;
+    Response::builder()
+        .status(StatusCode::TOO_MANY_REQUESTS)
+        .header(header::RETRY_AFTER, seconds)
+        .body(format!("Too many requests, retry after {} second(s)", seconds).into())
+        .unwrap()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Clone)]
+    struct MockClock {
+        now: Arc<Mutex<Instant>>,
+    }
+
+    impl MockClock {
+        fn new() -> Self {
+            Self { now: Arc::new(Mutex::new(Instant::now())) }
+        }
+
+        fn advance(&self, duration: Duration) {
+            *self.now.lock().unwrap() += duration;
+        }
+    }
+
+    impl Clock for MockClock {
+        fn now(&self) -> Instant {
+            *self.now.lock().unwrap()
+        }
+    }
+
+    fn limits() -> RateLimits {
+        RateLimits {
+            accounts: "2/60".parse().unwrap(),
+            tournaments: "3/3".parse().unwrap(),
+            moderation: "1/1".parse().unwrap(),
+        }
+    }
+
+    #[test]
+    fn parse_rate_budgets() {
+        assert_eq!("10/60".parse::<RateBudget>().unwrap(), RateBudget { requests: 10, period: Duration::from_secs(60) });
+        assert!(matches!("10".parse::<RateBudget>(), Err(RateBudgetError::BudgetMalformed)));
+        assert!(matches!("ten/60".parse::<RateBudget>(), Err(RateBudgetError::BudgetMalformed)));
+        assert!(matches!("0/60".parse::<RateBudget>(), Err(RateBudgetError::BudgetEmpty)));
+        assert!(matches!("10/0".parse::<RateBudget>(), Err(RateBudgetError::BudgetEmpty)));
+    }
+
+    #[test]
+    fn budgets_refill_over_their_period() {
+        let clock = MockClock::new();
+        let limiter = RateLimiter::new(limits(), clock.clone());
+        let key = RateLimitKey::Account(Uuid::new_v4());
+        assert!(limiter.acquire(RouteGroup::Accounts, key).is_ok());
+        assert!(limiter.acquire(RouteGroup::Accounts, key).is_ok());
+        assert!(matches!(limiter.acquire(RouteGroup::Accounts, key), Err(retry_after) if retry_after.as_secs_f64().round() == 30.0));
+        clock.advance(Duration::from_secs(20));
+        assert!(matches!(limiter.acquire(RouteGroup::Accounts, key), Err(retry_after) if retry_after.as_secs_f64().round() == 10.0));
+        clock.advance(Duration::from_secs(10));
+        assert!(limiter.acquire(RouteGroup::Accounts, key).is_ok());
+        assert!(limiter.acquire(RouteGroup::Accounts, key).is_err());
+    }
+
+    #[test]
+    fn budgets_are_kept_per_key_and_group() {
+        let clock = MockClock::new();
+        let limiter = RateLimiter::new(limits(), clock.clone());
+        let account = RateLimitKey::Account(Uuid::new_v4());
+        let address = RateLimitKey::Address(IpAddr::V4(Ipv4Addr::LOCALHOST));
+        assert!(limiter.acquire(RouteGroup::Moderation, account).is_ok());
+        assert!(limiter.acquire(RouteGroup::Moderation, account).is_err());
+        assert!(limiter.acquire(RouteGroup::Moderation, address).is_ok());
+        assert!(limiter.acquire(RouteGroup::Accounts, account).is_ok());
+        for _ in 0..3 {
+            assert!(limiter.acquire(RouteGroup::Tournaments, account).is_ok());
+        }
+        assert!(limiter.acquire(RouteGroup::Tournaments, account).is_err());
+        clock.advance(CLEANUP_INTERVAL);
+        assert!(limiter.acquire(RouteGroup::Tournaments, account).is_ok());
+        assert_eq!(limiter.buckets.lock().unwrap().buckets.len(), 1);
+    }
+
+    #[test]
+    fn rejections_tell_when_to_retry() {
+        let response = too_many_requests(Duration::from_millis(2500));
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers()[header::RETRY_AFTER], "3");
+        let response = too_many_requests(Duration::ZERO);
+        assert_eq!(response.headers()[header::RETRY_AFTER], "1");
+    }
+}
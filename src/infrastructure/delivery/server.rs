@@ -1,25 +1,47 @@
+use super::auth::TokenKey;
 use super::endpoints;
+use super::rate_limit;
+use super::rate_limit::RateLimiter;
+use super::rate_limit::RateLimits;
+use super::rate_limit::RouteGroup;
+use super::rate_limit::SystemClock;
 
 use crate::application::ProvideServices;
+use crate::application::RelayMessagesRequest;
 
+use axum::Extension;
 use axum::Router;
+use axum::middleware;
 use axum::routing;
-use log::info;
+use log::{debug, info, warn};
 use tokio::net::TcpListener;
 use tokio::sync::Mutex;
 
 use std::io::Error;
+use std::net::SocketAddr;
 use std::sync::Arc;
+use std::time::Duration;
+
+
+// Messages are relayed and events processed right after each save, so the relay task only picks up
+// those left behind by a failure or a restart
+const RELAY_INTERVAL: Duration = Duration::from_secs(1);
 
 
 #[derive(Debug)]
 pub struct AxumServer {
     port: u16,
+    token_key: TokenKey,
+    rate_limits: RateLimits,
 }
 
 impl AxumServer {
-    pub fn new( port: u16) -> Self {
-        Self { port }
+    pub fn new(port: u16, token_key: TokenKey) -> Self {
+        Self { port, token_key, rate_limits: RateLimits::default() }
+    }
+
+    pub fn with_rate_limits(self, rate_limits: RateLimits) -> Self {
+        Self { rate_limits, ..self }
     }
 
     pub async fn serve<Provider: ProvideServices + Send + 'static>(&self, provider: Provider) -> Result<(), Error> {
@@ -28,27 +50,129 @@ impl AxumServer {
 
         info!("listening on {}", listener.local_addr()?);
 
+        let provider = Arc::new(Mutex::new(provider));
+        let rate_limiter = RateLimiter::new(self.rate_limits, SystemClock);
+        let limit_rate = |group| middleware::from_fn_with_state((rate_limiter.clone(), group), rate_limit::limit_rate);
+
         let router = Router::new()
+            .route(
+                "/accounts",
+                routing::post(endpoints::register_account).route_layer(limit_rate(RouteGroup::Accounts))
+            )
+            .route(
+                "/accounts",
+                routing::get(endpoints::list_accounts)
+            )
+            .route(
+                "/accounts/me/password",
+                routing::put(endpoints::change_password).route_layer(limit_rate(RouteGroup::Accounts))
+            )
+            .route(
+                "/accounts/me/nickname",
+                routing::put(endpoints::change_nickname).route_layer(limit_rate(RouteGroup::Accounts))
+            )
+            .route(
+                "/accounts/{account_id}/promote",
+                routing::post(endpoints::promote_account).route_layer(limit_rate(RouteGroup::Moderation))
+            )
+            .route(
+                "/accounts/{account_id}/demote",
+                routing::post(endpoints::demote_account).route_layer(limit_rate(RouteGroup::Moderation))
+            )
+            .route(
+                "/accounts/{account_id}/role-changes",
+                routing::get(endpoints::get_role_changes)
+            )
+            .route(
+                "/accounts/{account_id}/bans",
+                routing::post(endpoints::ban_account).route_layer(limit_rate(RouteGroup::Moderation))
+            )
+            .route(
+                "/bans/{ban_id}",
+                routing::delete(endpoints::lift_ban).route_layer(limit_rate(RouteGroup::Moderation))
+            )
+            .route(
+                "/sessions",
+                routing::post(endpoints::login).route_layer(limit_rate(RouteGroup::Accounts))
+            )
             .route(
                 "/tournaments",
                 routing::get(endpoints::find_tournaments)
             )
             .route(
                 "/tournaments",
-                routing::post(endpoints::create_tournament)
+                routing::post(endpoints::create_tournament).route_layer(limit_rate(RouteGroup::Tournaments))
+            )
+            .route(
+                "/tournaments/{tournament_id}",
+                routing::get(endpoints::get_tournament)
             )
             .route(
                 "/tournaments/{tournament_id}/join",
-                routing::post(endpoints::join_tournament)
+                routing::post(endpoints::join_tournament).route_layer(limit_rate(RouteGroup::Tournaments))
+            )
+            .route(
+                "/tournaments/{tournament_id}/events",
+                routing::any(endpoints::observe_tournament)
+            )
+            .route(
+                "/tournaments/{tournament_id}/events/sse",
+                routing::get(endpoints::observe_tournament_sse)
             )
             .route(
                 "/tournaments/{tournament_id}/tables/{table_number}",
                 routing::any(endpoints::observe_table)
             )
-            .with_state(Arc::new(Mutex::new(provider)));
+            .route(
+                "/tournaments/{tournament_id}/tables/{table_number}/sse",
+                routing::get(endpoints::observe_table_sse)
+            )
+            .route(
+                "/players/{account_id}/tournaments",
+                routing::get(endpoints::get_player_history)
+            )
+            .route(
+                "/lobby",
+                routing::any(endpoints::observe_lobby)
+            )
+            .route(
+                "/lobby/sse",
+                routing::get(endpoints::observe_lobby_sse)
+            )
+            .route(
+                "/monitoring/broadcast",
+                routing::get(endpoints::get_broadcast_statistics)
+            )
+            .route(
+                "/monitoring/projections/rebuild",
+                routing::post(endpoints::rebuild_projections).route_layer(limit_rate(RouteGroup::Moderation))
+            )
+            .layer(Extension(self.token_key.clone()))
+            .with_state(provider.clone());
+
+        tokio::spawn(relay_messages(provider));
 
         info!("serving cardroom application ...");
 
-        axum::serve(listener, router).await
+        axum::serve(listener, router.into_make_service_with_connect_info::<SocketAddr>()).await
+    }
+}
+
+
+async fn relay_messages<Provider: ProvideServices>(provider: Arc<Mutex<Provider>>) {
+    let mut interval = tokio::time::interval(RELAY_INTERVAL);
+    loop {
+        interval.tick().await;
+        match provider.lock().await.relay_messages(RelayMessagesRequest {}) {
+            Ok(response) => {
+                if response.pending_stream_count > 0 {
+                    debug!("processed pending events of {} stream(s)", response.pending_stream_count);
+                }
+                if response.message_count > 0 {
+                    debug!("relayed {} pending message(s)", response.message_count);
+                }
+            },
+            Err(error) => warn!("cannot relay pending messages: {}", error),
+        }
     }
 }
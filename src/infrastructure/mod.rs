@@ -2,4 +2,12 @@ mod delivery;
 mod persistence;
 
 pub use delivery::AxumServer;
+pub use delivery::RateLimits;
+pub use delivery::TokenKey;
+pub use persistence::FileTournamentRepository;
+pub use persistence::InMemoryAccountRepository;
 pub use persistence::InMemoryTournamentRepository;
+#[cfg(feature = "sqlite")]
+pub use persistence::SqliteAccountRepository;
+#[cfg(feature = "sqlite")]
+pub use persistence::SqliteTournamentRepository;
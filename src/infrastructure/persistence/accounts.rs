@@ -0,0 +1,162 @@
+use crate::domain::Account;
+use crate::domain::AccountRole;
+use crate::domain::Ban;
+use crate::domain::BanScope;
+use crate::domain::LoadBanError;
+use crate::domain::LoadBans;
+use crate::domain::LoadAccount;
+use crate::domain::LoadAccountError;
+use crate::domain::LoadRoleChanges;
+use crate::domain::LoadRoleChangesError;
+use crate::domain::RoleChange;
+use crate::domain::SaveAccount;
+use crate::domain::SaveAccountError;
+use crate::domain::SaveBan;
+use crate::domain::SaveBanError;
+use crate::domain::Username;
+
+use log::debug;
+use uuid::Uuid;
+
+use std::collections::HashMap;
+
+
+#[derive(Debug)]
+pub struct InMemoryAccountRepository {
+    accounts: HashMap<Uuid, Account>,
+    role_changes: Vec<RoleChange>,
+    bans: HashMap<Uuid, Ban>,
+}
+
+impl InMemoryAccountRepository {
+    pub fn new() -> Self {
+        Self { accounts: HashMap::new(), role_changes: vec![], bans: HashMap::new() }
+    }
+}
+
+
+impl LoadAccount for InMemoryAccountRepository {
+    fn load_account(&self, account_id: Uuid) -> Result<Account, LoadAccountError> {
+        debug!("load account {}", account_id);
+        self.accounts.get(&account_id).cloned().ok_or(LoadAccountError::AccountNotFound)
+    }
+
+    fn load_account_by_username(&self, username: &Username) -> Result<Account, LoadAccountError> {
+        debug!("load account of {}", username);
+        self.accounts.values().find(|account| account.username() == username).cloned().ok_or(LoadAccountError::AccountNotFound)
+    }
+
+    fn load_accounts_with_role(&self, role: AccountRole) -> Result<Vec<Account>, LoadAccountError> {
+        debug!("load accounts with role {:?}", role);
+        let mut accounts: Vec<_> = self.accounts.values().filter(|account| account.role() == role).cloned().collect();
+        accounts.sort_by(|a, b| a.username().as_str().cmp(b.username().as_str()));
+        Ok(accounts)
+    }
+}
+
+
+impl SaveAccount for InMemoryAccountRepository {
+    fn save_account(&mut self, mut account: Account) -> Result<(), SaveAccountError> {
+        debug!("save account {}", account.id());
+        if self.accounts.values().any(|other| other.username() == account.username() && other.id() != account.id()) {
+            return Err(SaveAccountError::UsernameTaken);
+        }
+        let nickname_key = account.nickname().map(|nickname| nickname.key());
+        if nickname_key.is_some() && self.accounts.values().any(|other| other.nickname().map(|nickname| nickname.key()) == nickname_key && other.id() != account.id()) {
+            return Err(SaveAccountError::NicknameTaken);
+        }
+        self.role_changes.extend(account.collect_role_changes());
+        self.accounts.insert(account.id(), account);
+        Ok(())
+    }
+}
+
+
+impl LoadRoleChanges for InMemoryAccountRepository {
+    fn load_role_changes(&self, account_id: Uuid) -> Result<Vec<RoleChange>, LoadRoleChangesError> {
+        debug!("load role changes of account {}", account_id);
+        Ok(self.role_changes.iter().filter(|change| change.account_id == account_id).cloned().collect())
+    }
+}
+
+
+impl LoadBans for InMemoryAccountRepository {
+    fn load_ban(&self, ban_id: Uuid) -> Result<Ban, LoadBanError> {
+        debug!("load ban {}", ban_id);
+        self.bans.get(&ban_id).cloned().ok_or(LoadBanError::BanNotFound)
+    }
+
+    fn load_bans(&self, account_id: Uuid) -> Result<Vec<Ban>, LoadBanError> {
+        debug!("load bans of account {}", account_id);
+        let mut bans: Vec<_> = self.bans.values().filter(|ban| ban.account_id == account_id).cloned().collect();
+        bans.sort_by_key(|ban| ban.banned_at);
+        Ok(bans)
+    }
+
+    fn load_tournament_bans(&self, tournament_id: Uuid) -> Result<Vec<Ban>, LoadBanError> {
+        debug!("load bans of tournament {}", tournament_id);
+        let mut bans: Vec<_> = self.bans.values().filter(|ban| ban.scope == BanScope::Tournament { tournament_id }).cloned().collect();
+        bans.sort_by_key(|ban| ban.banned_at);
+        Ok(bans)
+    }
+}
+
+
+impl SaveBan for InMemoryAccountRepository {
+    fn save_ban(&mut self, ban: Ban) -> Result<(), SaveBanError> {
+        debug!("save ban {}", ban.id);
+        self.bans.insert(ban.id, ban);
+        Ok(())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::HashedPassword;
+    use crate::domain::Nickname;
+    use crate::domain::unix_time;
+
+    #[test]
+    fn save_and_load_accounts() {
+        let mut repository = InMemoryAccountRepository::new();
+        let username = Username::new("daniel").unwrap();
+        let mut account = Account::register(username.clone(), "correct horse").unwrap();
+        repository.save_account(account.clone()).unwrap();
+        assert_eq!(repository.load_account(account.id()).unwrap(), account);
+        account.change_password(&account.hashed_password(), HashedPassword::new("battery staple").unwrap()).unwrap();
+        repository.save_account(account.clone()).unwrap();
+        assert_eq!(repository.load_account_by_username(&username).unwrap(), account);
+        let other = Account::register(Username::new("Daniel").unwrap(), "correct horse").unwrap();
+        assert!(matches!(repository.save_account(other), Err(SaveAccountError::UsernameTaken)));
+        assert!(matches!(repository.load_account(Uuid::new_v4()), Err(LoadAccountError::AccountNotFound)));
+    }
+
+    #[test]
+    fn nicknames_are_unique_regardless_of_case() {
+        let mut repository = InMemoryAccountRepository::new();
+        let mut account = Account::register(Username::new("daniel").unwrap(), "correct horse").unwrap();
+        account.change_nickname(Nickname::new("Denyo").unwrap(), unix_time()).unwrap();
+        repository.save_account(account.clone()).unwrap();
+        repository.save_account(account).unwrap();
+        let mut other = Account::register(Username::new("james").unwrap(), "correct horse").unwrap();
+        repository.save_account(other.clone()).unwrap();
+        other.change_nickname(Nickname::new("DENYO").unwrap(), unix_time()).unwrap();
+        assert!(matches!(repository.save_account(other), Err(SaveAccountError::NicknameTaken)));
+    }
+
+    #[test]
+    fn role_changes_are_audited() {
+        let mut repository = InMemoryAccountRepository::new();
+        let mut account = Account::register(Username::new("daniel").unwrap(), "correct horse").unwrap();
+        repository.save_account(Account::register(Username::new("james").unwrap(), "correct horse").unwrap()).unwrap();
+        account.promote(None, unix_time()).unwrap();
+        let changes = account.clone().collect_role_changes();
+        repository.save_account(account.clone()).unwrap();
+        let moderators = repository.load_accounts_with_role(AccountRole::Moderator).unwrap();
+        assert_eq!(moderators.iter().map(Account::id).collect::<Vec<_>>(), vec![account.id()]);
+        assert_eq!(repository.load_role_changes(account.id()).unwrap(), changes);
+        assert_eq!(repository.load_role_changes(Uuid::new_v4()).unwrap(), vec![]);
+    }
+}
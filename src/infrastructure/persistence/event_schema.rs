@@ -0,0 +1,662 @@
+use crate::domain::EventStream;
+use crate::domain::Nickname;
+use crate::domain::ObservationAccess;
+use crate::domain::ObservationPolicy;
+use crate::domain::OutboxEntry;
+use crate::domain::TableEvent;
+use crate::domain::TableMessage;
+use crate::domain::TableSpecification;
+use crate::domain::TournamentEvent;
+use crate::domain::TournamentMessage;
+use crate::domain::TournamentMessageType;
+use crate::domain::TournamentSnapshot;
+use crate::domain::TournamentSpecification;
+use crate::domain::TournamentSpecificationError;
+use crate::domain::TournamentStage;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use thiserror::Error;
+use uuid::Uuid;
+
+use std::time::Duration;
+
+
+pub const CURRENT_SCHEMA_VERSION: u64 = 3;
+
+// Table events have their own schema, which starts over at version 1
+pub const TABLE_EVENT_SCHEMA_VERSION: u64 = 1;
+
+// Snapshots can always be rebuilt from the events, so they are not upcast but simply ignored
+// once their schema version is outdated
+pub const SNAPSHOT_SCHEMA_VERSION: u64 = 3;
+
+// Messages waiting in the outbox have their own schema as well
+pub const MESSAGE_SCHEMA_VERSION: u64 = 1;
+
+type Upcaster = fn(Value) -> Result<Value, EventSchemaError>;
+
+// Each entry upcasts a stored event from the version given by its index plus one to the next one
+const UPCASTERS: &[Upcaster] = &[
+    upcast_from_version_1,
+    upcast_from_version_2,
+];
+
+
+#[derive(Debug, Error)]
+pub enum EventSchemaError {
+    #[error("Unsupported event schema version {found}, expected at most {CURRENT_SCHEMA_VERSION}")]
+    UnsupportedSchemaVersion { found: u64 },
+    #[error("Malformed event of schema version {version}")]
+    MalformedEvent { version: u64 },
+    #[error(transparent)]
+    InvalidEvent(#[from] serde_json::Error),
+    #[error(transparent)]
+    TournamentSpecificationError(#[from] TournamentSpecificationError),
+}
+
+
+// The stored form of the current schema version. Changing it requires a new schema version
+// together with an upcaster from the previous one.
+#[derive(Debug, Serialize, Deserialize)]
+struct StoredEvent {
+    schema_version: u64,
+    #[serde(flatten)]
+    payload: StoredEventPayload,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type")]
+enum StoredEventPayload {
+    TournamentCreated {
+        tournament_id: Uuid,
+        table_count: u8,
+        table_seat_count: u8,
+        observation_policy: StoredObservationPolicy,
+    },
+    PlayerJoined {
+        account_id: Uuid,
+        nickname: Nickname,
+    },
+    TournamentStarted,
+    PlayerEliminated {
+        account_id: Uuid,
+    },
+    PlayerMoved {
+        account_id: Uuid,
+        from_table: usize,
+        to_table: usize,
+    },
+    PlayerLeft {
+        account_id: Uuid,
+    },
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct StoredObservationPolicy {
+    access: StoredObservationAccess,
+    spectator_delay: u64,     // In seconds
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum StoredObservationAccess {
+    Public,
+    MembersOnly,
+    ParticipantsOnly,
+    Disabled,
+}
+
+fn stored_observation_policy(policy: ObservationPolicy) -> StoredObservationPolicy {
+    StoredObservationPolicy {
+        access: match policy.access {
+            ObservationAccess::Public => StoredObservationAccess::Public,
+            ObservationAccess::MembersOnly => StoredObservationAccess::MembersOnly,
+            ObservationAccess::ParticipantsOnly => StoredObservationAccess::ParticipantsOnly,
+            ObservationAccess::Disabled => StoredObservationAccess::Disabled,
+        },
+        spectator_delay: policy.spectator_delay.as_secs(),
+    }
+}
+
+fn observation_policy(stored_policy: StoredObservationPolicy) -> ObservationPolicy {
+    let access = match stored_policy.access {
+        StoredObservationAccess::Public => ObservationAccess::Public,
+        StoredObservationAccess::MembersOnly => ObservationAccess::MembersOnly,
+        StoredObservationAccess::ParticipantsOnly => ObservationAccess::ParticipantsOnly,
+        StoredObservationAccess::Disabled => ObservationAccess::Disabled,
+    };
+    ObservationPolicy::new(access, Duration::from_secs(stored_policy.spectator_delay))
+}
+
+fn tournament_spec(table_count: u8, table_seat_count: u8, stored_policy: StoredObservationPolicy) -> Result<TournamentSpecification, EventSchemaError> {
+    let spec = TournamentSpecification::new(table_count, table_seat_count)?;
+    Ok(spec.with_observation_policy(observation_policy(stored_policy))?)
+}
+
+
+pub fn encode_event(event: &TournamentEvent) -> Value {
+    let payload = match event {
+        TournamentEvent::TournamentCreated { id, spec } => StoredEventPayload::TournamentCreated {
+            tournament_id: *id,
+            table_count: spec.table_count(),
+            table_seat_count: spec.table_seat_count(),
+            observation_policy: stored_observation_policy(spec.observation_policy()),
+        },
+        TournamentEvent::PlayerJoined { account_id, nickname } => StoredEventPayload::PlayerJoined {
+            account_id: *account_id,
+            nickname: nickname.clone(),
+        },
+        TournamentEvent::TournamentStarted => StoredEventPayload::TournamentStarted,
+        TournamentEvent::PlayerEliminated { account_id } => StoredEventPayload::PlayerEliminated { account_id: *account_id },
+        TournamentEvent::PlayerMoved { account_id, from_table, to_table } => StoredEventPayload::PlayerMoved {
+            account_id: *account_id,
+            from_table: *from_table,
+            to_table: *to_table,
+        },
+        TournamentEvent::PlayerLeft { account_id } => StoredEventPayload::PlayerLeft { account_id: *account_id },
+    };
+    serde_json::to_value(StoredEvent { schema_version: CURRENT_SCHEMA_VERSION, payload }).unwrap()
+}
+
+
+pub fn decode_event(mut value: Value) -> Result<TournamentEvent, EventSchemaError> {
+    // Events stored before the schema was versioned carry no version field
+    let mut version = value.get("schema_version").and_then(Value::as_u64).unwrap_or(1);
+    if version == 0 || version > CURRENT_SCHEMA_VERSION {
+        return Err(EventSchemaError::UnsupportedSchemaVersion { found: version });
+    }
+    while version < CURRENT_SCHEMA_VERSION {
+        value = UPCASTERS[version as usize - 1](value)?;
+        version += 1;
+    }
+    let stored_event: StoredEvent = serde_json::from_value(value)?;
+    let event = match stored_event.payload {
+        StoredEventPayload::TournamentCreated { tournament_id, table_count, table_seat_count, observation_policy } => TournamentEvent::TournamentCreated {
+            id: tournament_id,
+            spec: tournament_spec(table_count, table_seat_count, observation_policy)?,
+        },
+        StoredEventPayload::PlayerJoined { account_id, nickname } => TournamentEvent::PlayerJoined { account_id, nickname },
+        StoredEventPayload::TournamentStarted => TournamentEvent::TournamentStarted,
+        StoredEventPayload::PlayerEliminated { account_id } => TournamentEvent::PlayerEliminated { account_id },
+        StoredEventPayload::PlayerMoved { account_id, from_table, to_table } => TournamentEvent::PlayerMoved { account_id, from_table, to_table },
+        StoredEventPayload::PlayerLeft { account_id } => TournamentEvent::PlayerLeft { account_id },
+    };
+    Ok(event)
+}
+
+
+#[derive(Debug, Serialize, Deserialize)]
+struct StoredTableEvent {
+    schema_version: u64,
+    #[serde(flatten)]
+    payload: StoredTableEventPayload,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type")]
+enum StoredTableEventPayload {
+    TableCreated {
+        tournament_id: Uuid,
+        table_number: usize,
+        seat_count: u8,
+    },
+    PlayerSeated {
+        account_id: Uuid,
+        nickname: Nickname,
+        stack: u32,
+        position: usize,
+    },
+    PlayerLeft {
+        position: usize,
+    },
+    PlayerWipedOut {
+        account_id: Uuid,
+        nickname: Nickname,
+        position: usize,
+    },
+}
+
+
+pub fn encode_table_event(event: &TableEvent) -> Value {
+    let payload = match event {
+        TableEvent::TableCreated { tournament_id, table_number, spec } => StoredTableEventPayload::TableCreated {
+            tournament_id: *tournament_id,
+            table_number: *table_number,
+            seat_count: spec.seat_count(),
+        },
+        TableEvent::PlayerSeated { account_id, nickname, stack, position } => StoredTableEventPayload::PlayerSeated {
+            account_id: *account_id,
+            nickname: nickname.clone(),
+            stack: *stack,
+            position: *position,
+        },
+        TableEvent::PlayerLeft { position } => StoredTableEventPayload::PlayerLeft { position: *position },
+        TableEvent::PlayerWipedOut { account_id, nickname, position } => StoredTableEventPayload::PlayerWipedOut {
+            account_id: *account_id,
+            nickname: nickname.clone(),
+            position: *position,
+        },
+    };
+    serde_json::to_value(StoredTableEvent { schema_version: TABLE_EVENT_SCHEMA_VERSION, payload }).unwrap()
+}
+
+
+pub fn decode_table_event(value: Value) -> Result<TableEvent, EventSchemaError> {
+    let version = value.get("schema_version").and_then(Value::as_u64).unwrap_or(0);
+    if version != TABLE_EVENT_SCHEMA_VERSION {
+        return Err(EventSchemaError::UnsupportedSchemaVersion { found: version });
+    }
+    let stored_event: StoredTableEvent = serde_json::from_value(value)?;
+    let event = match stored_event.payload {
+        StoredTableEventPayload::TableCreated { tournament_id, table_number, seat_count } => TableEvent::TableCreated {
+            tournament_id,
+            table_number,
+            spec: TableSpecification::new(seat_count).map_err(TournamentSpecificationError::from)?,
+        },
+        StoredTableEventPayload::PlayerSeated { account_id, nickname, stack, position } => TableEvent::PlayerSeated { account_id, nickname, stack, position },
+        StoredTableEventPayload::PlayerLeft { position } => TableEvent::PlayerLeft { position },
+        StoredTableEventPayload::PlayerWipedOut { account_id, nickname, position } => TableEvent::PlayerWipedOut { account_id, nickname, position },
+    };
+    Ok(event)
+}
+
+
+#[derive(Debug, Serialize, Deserialize)]
+struct StoredSnapshot {
+    schema_version: u64,
+    tournament_id: Uuid,
+    version: usize,
+    stage: StoredStage,
+    level: u32,
+    table_seat_count: u8,
+    observation_policy: StoredObservationPolicy,
+    tables: Vec<Vec<Uuid>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum StoredStage {
+    WaitingForPlayers,
+    ReadyToStart,
+    Running,
+    Finished,
+}
+
+pub fn encode_snapshot(snapshot: &TournamentSnapshot) -> Value {
+    let stored_snapshot = StoredSnapshot {
+        schema_version: SNAPSHOT_SCHEMA_VERSION,
+        tournament_id: snapshot.tournament_id,
+        version: snapshot.version,
+        stage: match snapshot.stage {
+            TournamentStage::WaitingForPlayers => StoredStage::WaitingForPlayers,
+            TournamentStage::ReadyToStart => StoredStage::ReadyToStart,
+            TournamentStage::Running => StoredStage::Running,
+            TournamentStage::Finished => StoredStage::Finished,
+        },
+        level: snapshot.level,
+        table_seat_count: snapshot.spec.table_seat_count(),
+        observation_policy: stored_observation_policy(snapshot.spec.observation_policy()),
+        tables: snapshot.tables.clone(),
+    };
+    serde_json::to_value(stored_snapshot).unwrap()
+}
+
+
+pub fn decode_snapshot(value: Value) -> Result<TournamentSnapshot, EventSchemaError> {
+    let version = value.get("schema_version").and_then(Value::as_u64).unwrap_or(0);
+    if version != SNAPSHOT_SCHEMA_VERSION {
+        return Err(EventSchemaError::UnsupportedSchemaVersion { found: version });
+    }
+    let stored_snapshot: StoredSnapshot = serde_json::from_value(value)?;
+    Ok(TournamentSnapshot {
+        tournament_id: stored_snapshot.tournament_id,
+        version: stored_snapshot.version,
+        stage: match stored_snapshot.stage {
+            StoredStage::WaitingForPlayers => TournamentStage::WaitingForPlayers,
+            StoredStage::ReadyToStart => TournamentStage::ReadyToStart,
+            StoredStage::Running => TournamentStage::Running,
+            StoredStage::Finished => TournamentStage::Finished,
+        },
+        level: stored_snapshot.level,
+        spec: tournament_spec(stored_snapshot.tables.len() as u8, stored_snapshot.table_seat_count, stored_snapshot.observation_policy)?,
+        tables: stored_snapshot.tables,
+    })
+}
+
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum StoredEventStream {
+    Tournament {
+        tournament_id: Uuid,
+    },
+    Table {
+        tournament_id: Uuid,
+        table_number: usize,
+    },
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct StoredCheckpoint {
+    stream: StoredEventStream,
+    checkpoint: usize,
+}
+
+fn stored_stream(stream: EventStream) -> StoredEventStream {
+    match stream {
+        EventStream::Tournament { tournament_id } => StoredEventStream::Tournament { tournament_id },
+        EventStream::Table { tournament_id, table_number } => StoredEventStream::Table { tournament_id, table_number },
+    }
+}
+
+// A stable key identifying the stream, e.g. as a database column
+#[cfg(feature = "sqlite")]
+pub fn encode_stream(stream: EventStream) -> String {
+    serde_json::to_string(&stored_stream(stream)).unwrap()
+}
+
+pub fn encode_checkpoint(stream: EventStream, checkpoint: usize) -> Value {
+    serde_json::to_value(StoredCheckpoint { stream: stored_stream(stream), checkpoint }).unwrap()
+}
+
+pub fn decode_checkpoint(value: Value) -> Result<(EventStream, usize), EventSchemaError> {
+    let stored_checkpoint: StoredCheckpoint = serde_json::from_value(value)?;
+    let stream = match stored_checkpoint.stream {
+        StoredEventStream::Tournament { tournament_id } => EventStream::Tournament { tournament_id },
+        StoredEventStream::Table { tournament_id, table_number } => EventStream::Table { tournament_id, table_number },
+    };
+    Ok((stream, stored_checkpoint.checkpoint))
+}
+
+
+#[derive(Debug, Serialize, Deserialize)]
+struct StoredMessage {
+    schema_version: u64,
+    tournament_id: Uuid,
+    #[serde(flatten)]
+    payload: StoredMessagePayload,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type")]
+enum StoredMessagePayload {
+    TableMessage {
+        table_number: usize,
+        message: StoredTableMessage,
+    },
+    RegistrationCountChanged {
+        player_count: usize,
+    },
+    TournamentStarted,
+    PlayerEliminated {
+        nickname: Nickname,
+        rank: usize,
+    },
+    TournamentFinished,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type")]
+enum StoredTableMessage {
+    PlayerSeated {
+        nickname: Nickname,
+        stack: u32,
+        position: usize,
+    },
+    PlayerLeft {
+        position: usize,
+    },
+    PlayerWipedOut {
+        position: usize,
+    },
+    GameStarted {
+        button: u8,
+    },
+}
+
+pub fn encode_message(message: &TournamentMessage) -> Value {
+    let payload = match &message.message_type {
+        TournamentMessageType::TableMessage { table_number, message_type } => StoredMessagePayload::TableMessage {
+            table_number: *table_number,
+            message: match message_type {
+                TableMessage::PlayerSeated { nickname, stack, position } => StoredTableMessage::PlayerSeated {
+                    nickname: nickname.clone(),
+                    stack: *stack,
+                    position: *position,
+                },
+                TableMessage::PlayerLeft { position } => StoredTableMessage::PlayerLeft { position: *position },
+                TableMessage::PlayerWipedOut { position } => StoredTableMessage::PlayerWipedOut { position: *position },
+                TableMessage::GameStarted { button } => StoredTableMessage::GameStarted { button: *button },
+            },
+        },
+        TournamentMessageType::RegistrationCountChanged { player_count } => StoredMessagePayload::RegistrationCountChanged { player_count: *player_count },
+        TournamentMessageType::TournamentStarted => StoredMessagePayload::TournamentStarted,
+        TournamentMessageType::PlayerEliminated { nickname, rank } => StoredMessagePayload::PlayerEliminated { nickname: nickname.clone(), rank: *rank },
+        TournamentMessageType::TournamentFinished => StoredMessagePayload::TournamentFinished,
+    };
+    serde_json::to_value(StoredMessage { schema_version: MESSAGE_SCHEMA_VERSION, tournament_id: message.tournament_id, payload }).unwrap()
+}
+
+pub fn decode_message(value: Value) -> Result<TournamentMessage, EventSchemaError> {
+    let version = value.get("schema_version").and_then(Value::as_u64).unwrap_or(0);
+    if version != MESSAGE_SCHEMA_VERSION {
+        return Err(EventSchemaError::UnsupportedSchemaVersion { found: version });
+    }
+    let stored_message: StoredMessage = serde_json::from_value(value)?;
+    let message_type = match stored_message.payload {
+        StoredMessagePayload::TableMessage { table_number, message } => TournamentMessageType::TableMessage {
+            table_number,
+            message_type: match message {
+                StoredTableMessage::PlayerSeated { nickname, stack, position } => TableMessage::PlayerSeated { nickname, stack, position },
+                StoredTableMessage::PlayerLeft { position } => TableMessage::PlayerLeft { position },
+                StoredTableMessage::PlayerWipedOut { position } => TableMessage::PlayerWipedOut { position },
+                StoredTableMessage::GameStarted { button } => TableMessage::GameStarted { button },
+            },
+        },
+        StoredMessagePayload::RegistrationCountChanged { player_count } => TournamentMessageType::RegistrationCountChanged { player_count },
+        StoredMessagePayload::TournamentStarted => TournamentMessageType::TournamentStarted,
+        StoredMessagePayload::PlayerEliminated { nickname, rank } => TournamentMessageType::PlayerEliminated { nickname, rank },
+        StoredMessagePayload::TournamentFinished => TournamentMessageType::TournamentFinished,
+    };
+    Ok(TournamentMessage { tournament_id: stored_message.tournament_id, message_type })
+}
+
+
+#[derive(Debug, Serialize, Deserialize)]
+struct StoredOutboxEntry {
+    position: u64,
+    message: Value,
+}
+
+pub fn encode_outbox_entry(entry: &OutboxEntry) -> Value {
+    serde_json::to_value(StoredOutboxEntry { position: entry.position, message: encode_message(&entry.message) }).unwrap()
+}
+
+pub fn decode_outbox_entry(value: Value) -> Result<OutboxEntry, EventSchemaError> {
+    let stored_entry: StoredOutboxEntry = serde_json::from_value(value)?;
+    Ok(OutboxEntry { position: stored_entry.position, message: decode_message(stored_entry.message)? })
+}
+
+
+// Version 1 was the externally tagged form derived from the domain types
+fn upcast_from_version_1(value: Value) -> Result<Value, EventSchemaError> {
+    let malformed = || EventSchemaError::MalformedEvent { version: 1 };
+    let (event_type, fields) = value.as_object().and_then(|object| object.iter().next()).ok_or_else(malformed)?;
+    match event_type.as_str() {
+        "TournamentCreated" => Ok(json!({
+            "schema_version": 2,
+            "type": "TournamentCreated",
+            "tournament_id": fields["id"],
+            "table_count": fields["spec"]["table_count"],
+            "table_seat_count": fields["spec"]["table_spec"]["seat_count"],
+        })),
+        "PlayerJoined" => Ok(json!({
+            "schema_version": 2,
+            "type": "PlayerJoined",
+            "account_id": fields["account_id"],
+            "nickname": fields["nickname"],
+        })),
+        _ => Err(malformed()),
+    }
+}
+
+// Version 3 added the observation policy to created tournaments, which had none before
+fn upcast_from_version_2(mut value: Value) -> Result<Value, EventSchemaError> {
+    let object = value.as_object_mut().ok_or(EventSchemaError::MalformedEvent { version: 2 })?;
+    if object.get("type").and_then(Value::as_str) == Some("TournamentCreated") {
+        object.insert("observation_policy".to_string(), json!({ "access": "public", "spectator_delay": 0 }));
+    }
+    object.insert("schema_version".to_string(), json!(3));
+    Ok(value)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn events_round_trip() {
+        let events = vec![
+            TournamentEvent::TournamentCreated { id: Uuid::new_v4(), spec: TournamentSpecification::new(3, 6).unwrap() },
+            TournamentEvent::PlayerJoined { account_id: Uuid::new_v4(), nickname: Nickname::new("Daniel").unwrap() },
+            TournamentEvent::TournamentStarted,
+            TournamentEvent::PlayerEliminated { account_id: Uuid::new_v4() },
+            TournamentEvent::PlayerMoved { account_id: Uuid::new_v4(), from_table: 2, to_table: 0 },
+            TournamentEvent::PlayerLeft { account_id: Uuid::new_v4() },
+        ];
+        for event in events {
+            assert_eq!(decode_event(encode_event(&event)).unwrap(), event);
+        }
+    }
+
+    #[test]
+    fn encoded_events_have_stable_schema() {
+        let tournament_id = Uuid::new_v4();
+        let event = TournamentEvent::TournamentCreated { id: tournament_id, spec: TournamentSpecification::new(3, 6).unwrap() };
+        assert_eq!(encode_event(&event), json!({
+            "schema_version": 3,
+            "type": "TournamentCreated",
+            "tournament_id": tournament_id,
+            "table_count": 3,
+            "table_seat_count": 6,
+            "observation_policy": { "access": "public", "spectator_delay": 0 },
+        }));
+    }
+
+    #[test]
+    fn version_1_events_are_upcast() {
+        let tournament_id = Uuid::new_v4();
+        let account_id = Uuid::new_v4();
+        let created = json!({ "TournamentCreated": { "id": tournament_id, "spec": { "table_count": 2, "table_spec": { "seat_count": 4 } } } });
+        let joined = json!({ "PlayerJoined": { "account_id": account_id, "nickname": "Daniel" } });
+        assert_eq!(decode_event(created).unwrap(), TournamentEvent::TournamentCreated {
+            id: tournament_id, spec: TournamentSpecification::new(2, 4).unwrap()
+        });
+        assert_eq!(decode_event(joined).unwrap(), TournamentEvent::PlayerJoined {
+            account_id, nickname: Nickname::new("Daniel").unwrap()
+        });
+    }
+
+    #[test]
+    fn nicknames_predating_the_current_rules_are_decoded() {
+        let account_id = Uuid::new_v4();
+        let joined = json!({ "schema_version": 2, "type": "PlayerJoined", "account_id": account_id, "nickname": "Bob " });
+        assert_eq!(decode_event(joined).unwrap(), TournamentEvent::PlayerJoined {
+            account_id, nickname: Nickname::restore("Bob ").unwrap()
+        });
+    }
+
+    #[test]
+    fn version_2_events_are_upcast() {
+        let tournament_id = Uuid::new_v4();
+        let created = json!({ "schema_version": 2, "type": "TournamentCreated", "tournament_id": tournament_id, "table_count": 2, "table_seat_count": 4 });
+        assert_eq!(decode_event(created).unwrap(), TournamentEvent::TournamentCreated {
+            id: tournament_id, spec: TournamentSpecification::new(2, 4).unwrap()
+        });
+        assert_eq!(decode_event(json!({ "schema_version": 2, "type": "TournamentStarted" })).unwrap(), TournamentEvent::TournamentStarted);
+    }
+
+    #[test]
+    fn observation_policies_round_trip() {
+        let policy = ObservationPolicy::new(ObservationAccess::ParticipantsOnly, Duration::from_secs(90));
+        let spec = TournamentSpecification::new(3, 6).unwrap().with_observation_policy(policy).unwrap();
+        let event = TournamentEvent::TournamentCreated { id: Uuid::new_v4(), spec };
+        assert_eq!(decode_event(encode_event(&event)).unwrap(), event);
+    }
+
+    #[test]
+    fn table_events_round_trip() {
+        let events = vec![
+            TableEvent::TableCreated { tournament_id: Uuid::new_v4(), table_number: 3, spec: TableSpecification::new(6).unwrap() },
+            TableEvent::PlayerSeated { account_id: Uuid::new_v4(), nickname: Nickname::new("Daniel").unwrap(), stack: 1500, position: 2 },
+            TableEvent::PlayerLeft { position: 2 },
+            TableEvent::PlayerWipedOut { account_id: Uuid::new_v4(), nickname: Nickname::new("Daniel").unwrap(), position: 1 },
+        ];
+        for event in events {
+            assert_eq!(decode_table_event(encode_table_event(&event)).unwrap(), event);
+        }
+    }
+
+    #[test]
+    fn checkpoints_round_trip() {
+        let streams = [
+            EventStream::Tournament { tournament_id: Uuid::new_v4() },
+            EventStream::Table { tournament_id: Uuid::new_v4(), table_number: 4 },
+        ];
+        for stream in streams {
+            assert_eq!(decode_checkpoint(encode_checkpoint(stream, 7)).unwrap(), (stream, 7));
+        }
+    }
+
+    #[test]
+    fn snapshots_round_trip() {
+        let snapshot = TournamentSnapshot {
+            tournament_id: Uuid::new_v4(),
+            version: 3,
+            stage: TournamentStage::Running,
+            level: 2,
+            spec: TournamentSpecification::new(2, 2).unwrap()
+                .with_observation_policy(ObservationPolicy::new(ObservationAccess::MembersOnly, Duration::from_secs(30))).unwrap(),
+            tables: vec![vec![Uuid::new_v4(), Uuid::new_v4()], vec![Uuid::new_v4()]],
+        };
+        assert_eq!(decode_snapshot(encode_snapshot(&snapshot)).unwrap(), snapshot);
+        let mut outdated = encode_snapshot(&snapshot);
+        outdated["schema_version"] = json!(SNAPSHOT_SCHEMA_VERSION + 1);
+        assert!(matches!(decode_snapshot(outdated), Err(EventSchemaError::UnsupportedSchemaVersion { .. })));
+    }
+
+    #[test]
+    fn invalid_events_are_rejected() {
+        let future_event = json!({ "schema_version": CURRENT_SCHEMA_VERSION + 1, "type": "PlayerLeft" });
+        assert!(matches!(decode_event(future_event), Err(EventSchemaError::UnsupportedSchemaVersion { .. })));
+        assert!(matches!(decode_event(json!({ "PlayerLeft": {} })), Err(EventSchemaError::MalformedEvent { version: 1 })));
+        let invalid_spec = json!({ "schema_version": 2, "type": "TournamentCreated", "tournament_id": Uuid::new_v4(), "table_count": 0, "table_seat_count": 6 });
+        assert!(matches!(decode_event(invalid_spec), Err(EventSchemaError::TournamentSpecificationError(_))));
+        let invalid_policy = json!({
+            "schema_version": 3, "type": "TournamentCreated", "tournament_id": Uuid::new_v4(), "table_count": 1, "table_seat_count": 6,
+            "observation_policy": { "access": "public", "spectator_delay": 3600 },
+        });
+        assert!(matches!(decode_event(invalid_policy), Err(EventSchemaError::TournamentSpecificationError(_))));
+    }
+
+    #[test]
+    fn outbox_entries_round_trip() {
+        let tournament_id = Uuid::new_v4();
+        let message_types = vec![
+            TournamentMessageType::TableMessage {
+                table_number: 2,
+                message_type: TableMessage::PlayerSeated { nickname: Nickname::new("Daniel").unwrap(), stack: 1500, position: 1 },
+            },
+            TournamentMessageType::TableMessage { table_number: 0, message_type: TableMessage::GameStarted { button: 3 } },
+            TournamentMessageType::RegistrationCountChanged { player_count: 4 },
+            TournamentMessageType::PlayerEliminated { nickname: Nickname::new("James").unwrap(), rank: 4 },
+            TournamentMessageType::TournamentFinished,
+        ];
+        for (position, message_type) in (1..).zip(message_types) {
+            let entry = OutboxEntry { position, message: TournamentMessage { tournament_id, message_type } };
+            assert_eq!(decode_outbox_entry(encode_outbox_entry(&entry)).unwrap(), entry);
+        }
+    }
+}
@@ -0,0 +1,756 @@
+use crate::domain::EventStream;
+use crate::domain::LoadCheckpoint;
+use crate::domain::LoadCheckpointError;
+use crate::domain::LoadEvents;
+use crate::domain::LoadEventsError;
+use crate::domain::LoadOutbox;
+use crate::domain::LoadOutboxError;
+use crate::domain::LoadTable;
+use crate::domain::LoadTableError;
+use crate::domain::LoadTournament;
+use crate::domain::LoadTournamentError;
+use crate::domain::MarkDelivered;
+use crate::domain::MarkDeliveredError;
+use crate::domain::OutboxEntry;
+use crate::domain::SaveCheckpoint;
+use crate::domain::SaveCheckpointError;
+use crate::domain::SaveTable;
+use crate::domain::SaveTableError;
+use crate::domain::SaveTournament;
+use crate::domain::SaveTournamentError;
+use crate::domain::Table;
+use crate::domain::TableEvent;
+use crate::domain::Tournament;
+use crate::domain::TournamentEvent;
+use crate::domain::TournamentMessage;
+
+use super::event_schema::EventSchemaError;
+use super::event_schema::decode_checkpoint;
+use super::event_schema::decode_event;
+use super::event_schema::decode_outbox_entry;
+use super::event_schema::decode_table_event;
+use super::event_schema::encode_checkpoint;
+use super::event_schema::encode_event;
+use super::event_schema::encode_outbox_entry;
+use super::event_schema::encode_table_event;
+
+use log::{debug, error, info, warn};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use uuid::Uuid;
+
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::fs::File;
+use std::fs::OpenOptions;
+use std::io;
+use std::io::Read;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+
+
+const LOG_EXTENSION: &str = "log";
+const TABLE_DIRECTORY: &str = "tables";
+
+// Have no log extension, so that they are not mistaken for the log of a tournament
+const CHECKPOINT_LOG: &str = "checkpoints";
+const DELIVERED_LOG: &str = "delivered";
+
+// Every record starts with the payload length and the CRC-32 of the payload, both little endian
+const HEADER_SIZE: usize = 8;
+
+
+// Keeps one append-only log file per tournament and one per table in a subdirectory. Each save
+// appends a single record holding the new events together with the messages for the outbox, so a
+// commit is either replayed completely or not at all. Checkpoints are appended to a log of their
+// own, in which the last record of a stream wins, and so are the positions of delivered messages.
+// The logs are only read when the repository is opened, their events are kept in memory after.
+#[derive(Debug)]
+pub struct FileTournamentRepository {
+    directory: PathBuf,
+    tournaments: HashMap<Uuid, Tournament>,
+    corrupted_tournaments: HashSet<Uuid>,
+    tables: HashMap<(Uuid, usize), Table>,
+    corrupted_tables: HashSet<(Uuid, usize)>,
+    tournament_events: HashMap<Uuid, Vec<TournamentEvent>>,
+    table_events: HashMap<(Uuid, usize), Vec<TableEvent>>,
+    checkpoints: HashMap<EventStream, usize>,
+    outbox: Vec<OutboxEntry>,
+    last_position: u64,
+}
+
+
+// Records written before the outbox existed, as well as those of checkpoints and deliveries, are
+// plain arrays
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+enum StoredRecord {
+    Values(Vec<Value>),
+    Commit {
+        events: Vec<Value>,
+        messages: Vec<Value>,
+    },
+}
+
+
+struct Replay<Event> {
+    events: Vec<Event>,
+    messages: Vec<OutboxEntry>,
+    // Up to the end of the last complete record, anything after it is the remains of a torn write
+    intact_length: usize,
+}
+
+impl FileTournamentRepository {
+    pub fn open(directory: impl AsRef<Path>) -> Result<Self, io::Error> {
+        let directory = directory.as_ref().to_path_buf();
+        std::fs::create_dir_all(directory.join(TABLE_DIRECTORY))?;
+        let mut tournaments = HashMap::new();
+        let mut corrupted_tournaments = HashSet::new();
+        let mut tournament_events = HashMap::new();
+        let mut messages = vec![];
+        for entry in std::fs::read_dir(&directory)? {
+            let path = entry?.path();
+            if path.extension().is_none_or(|extension| extension != LOG_EXTENSION) {
+                continue;
+            }
+            let Some(tournament_id) = path.file_stem().and_then(|stem| stem.to_str()).and_then(|stem| stem.parse().ok()) else {
+                warn!("ignoring unexpected file {}", path.display());
+                continue;
+            };
+            let Replay { events, messages: log_messages, .. } = match repair_log(&path, decode_event) {
+                Err(error) if error.kind() == io::ErrorKind::InvalidData => {
+                    error!("cannot replay log of tournament {}: {}", tournament_id, error);
+                    corrupted_tournaments.insert(tournament_id);
+                    continue;
+                },
+                result => result?,
+            };
+            messages.extend(log_messages);
+            if events.is_empty() {
+                continue;
+            }
+            match Tournament::restore(events.clone()) {
+                Ok(tournament) if tournament.id() == tournament_id => {
+                    tournaments.insert(tournament_id, tournament);
+                    tournament_events.insert(tournament_id, events);
+                },
+                Ok(tournament) => {
+                    error!("log {} contains tournament {}", path.display(), tournament.id());
+                    corrupted_tournaments.insert(tournament_id);
+                },
+                Err(error) => {
+                    error!("cannot restore tournament {}: {}", tournament_id, error);
+                    corrupted_tournaments.insert(tournament_id);
+                },
+            }
+        }
+        info!("restored {} tournament(s) from {}", tournaments.len(), directory.display());
+
+        let mut tables = HashMap::new();
+        let mut corrupted_tables = HashSet::new();
+        let mut table_events = HashMap::new();
+        for entry in std::fs::read_dir(directory.join(TABLE_DIRECTORY))? {
+            let path = entry?.path();
+            if path.extension().is_none_or(|extension| extension != LOG_EXTENSION) {
+                continue;
+            }
+            let Some(key) = path.file_stem().and_then(|stem| stem.to_str()).and_then(parse_table_key) else {
+                warn!("ignoring unexpected file {}", path.display());
+                continue;
+            };
+            let Replay { events, messages: log_messages, .. } = match repair_log(&path, decode_table_event) {
+                Err(error) if error.kind() == io::ErrorKind::InvalidData => {
+                    error!("cannot replay log of table {} of tournament {}: {}", key.1, key.0, error);
+                    corrupted_tables.insert(key);
+                    continue;
+                },
+                result => result?,
+            };
+            messages.extend(log_messages);
+            if events.is_empty() {
+                continue;
+            }
+            match Table::restore(events.clone()) {
+                Ok(table) if (table.tournament_id(), table.table_number()) == key => {
+                    tables.insert(key, table);
+                    table_events.insert(key, events);
+                },
+                Ok(table) => {
+                    error!("log {} contains table {} of tournament {}", path.display(), table.table_number(), table.tournament_id());
+                    corrupted_tables.insert(key);
+                },
+                Err(error) => {
+                    error!("cannot restore table {} of tournament {}: {}", key.1, key.0, error);
+                    corrupted_tables.insert(key);
+                },
+            }
+        }
+        info!("restored {} table(s) from {}", tables.len(), directory.display());
+
+        let checkpoints = read_events(&directory.join(CHECKPOINT_LOG), decode_checkpoint)?.into_iter().collect();
+
+        let delivered_position = read_events(&directory.join(DELIVERED_LOG), decode_position)?.into_iter().max().unwrap_or(0);
+        let last_position = messages.iter().map(|entry| entry.position).max().unwrap_or(0).max(delivered_position);
+        let mut outbox: Vec<OutboxEntry> = messages.into_iter().filter(|entry| entry.position > delivered_position).collect();
+        outbox.sort_by_key(|entry| entry.position);
+        info!("restored {} pending message(s) from {}", outbox.len(), directory.display());
+        Ok(Self { directory, tournaments, corrupted_tournaments, tables, corrupted_tables, tournament_events, table_events, checkpoints, outbox, last_position })
+    }
+
+    // The positions are only taken once the entries are committed, see commit_outbox_entries
+    fn outbox_entries(&self, messages: Vec<TournamentMessage>) -> Vec<OutboxEntry> {
+        messages.into_iter().zip(self.last_position + 1..).map(|(message, position)| OutboxEntry { position, message }).collect()
+    }
+
+    fn commit_outbox_entries(&mut self, entries: Vec<OutboxEntry>) {
+        if let Some(last) = entries.last() {
+            self.last_position = last.position;
+        }
+        self.outbox.extend(entries);
+    }
+
+    fn log_path(&self, tournament_id: Uuid) -> PathBuf {
+        self.directory.join(format!("{}.{}", tournament_id, LOG_EXTENSION))
+    }
+
+    fn table_log_path(&self, tournament_id: Uuid, table_number: usize) -> PathBuf {
+        self.directory.join(TABLE_DIRECTORY).join(format!("{}_{}.{}", tournament_id, table_number, LOG_EXTENSION))
+    }
+}
+
+
+impl LoadTournament for FileTournamentRepository {
+    fn load_tournament(&self, tournament_id: Uuid) -> Result<Tournament, LoadTournamentError> {
+        debug!("load tournament {}", tournament_id);
+        if let Some(tournament) = self.tournaments.get(&tournament_id) {
+            Ok(tournament.clone())
+        } else if self.corrupted_tournaments.contains(&tournament_id) {
+            Err(LoadTournamentError::TournamentCorrupted)
+        } else {
+            Err(LoadTournamentError::TournamentNotFound)
+        }
+    }
+}
+
+
+impl SaveTournament for FileTournamentRepository {
+    fn save_tournament(&mut self, mut tournament: Tournament) -> Result<(), SaveTournamentError> {
+        debug!("save tournament {} at version {}", tournament.id(), tournament.version());
+        if self.corrupted_tournaments.contains(&tournament.id()) {
+            return Err(SaveTournamentError::DatabaseWritingError);
+        }
+        let stored_version = self.tournaments.get(&tournament.id()).map_or(0, |stored| stored.version());
+        if stored_version != tournament.version() {
+            return Err(SaveTournamentError::TournamentOutdated);
+        }
+        let new_events = tournament.new_events();
+        let entries = self.outbox_entries(tournament.collect_messages());
+        if !new_events.is_empty() || !entries.is_empty() {
+            let record = StoredRecord::Commit {
+                events: new_events.iter().map(encode_event).collect(),
+                messages: entries.iter().map(encode_outbox_entry).collect(),
+            };
+            append_record(&self.log_path(tournament.id()), &record).map_err(|error| {
+                error!("cannot append events of tournament {}: {}", tournament.id(), error);
+                SaveTournamentError::DatabaseWritingError
+            })?;
+        }
+        self.commit_outbox_entries(entries);
+        self.tournament_events.entry(tournament.id()).or_default().extend(new_events);
+        tournament.mark_saved();
+        self.tournaments.insert(tournament.id(), tournament);
+        Ok(())
+    }
+}
+
+
+impl LoadTable for FileTournamentRepository {
+    fn load_table(&self, tournament_id: Uuid, table_number: usize) -> Result<Table, LoadTableError> {
+        debug!("load table {} of tournament {}", table_number, tournament_id);
+        let key = (tournament_id, table_number);
+        if let Some(table) = self.tables.get(&key) {
+            Ok(table.clone())
+        } else if self.corrupted_tables.contains(&key) {
+            Err(LoadTableError::TableCorrupted)
+        } else {
+            Err(LoadTableError::TableNotFound)
+        }
+    }
+}
+
+
+impl SaveTable for FileTournamentRepository {
+    fn save_table(&mut self, mut table: Table) -> Result<(), SaveTableError> {
+        let key = (table.tournament_id(), table.table_number());
+        debug!("save table {} of tournament {} at version {}", key.1, key.0, table.version());
+        if self.corrupted_tables.contains(&key) {
+            return Err(SaveTableError::DatabaseWritingError);
+        }
+        let stored_version = self.tables.get(&key).map_or(0, |stored| stored.version());
+        if stored_version != table.version() {
+            return Err(SaveTableError::TableOutdated);
+        }
+        let new_events = table.new_events();
+        let entries = self.outbox_entries(table.collect_tournament_messages());
+        if !new_events.is_empty() || !entries.is_empty() {
+            let record = StoredRecord::Commit {
+                events: new_events.iter().map(encode_table_event).collect(),
+                messages: entries.iter().map(encode_outbox_entry).collect(),
+            };
+            append_record(&self.table_log_path(key.0, key.1), &record).map_err(|error| {
+                error!("cannot append events of table {} of tournament {}: {}", key.1, key.0, error);
+                SaveTableError::DatabaseWritingError
+            })?;
+        }
+        self.commit_outbox_entries(entries);
+        self.table_events.entry(key).or_default().extend(new_events);
+        table.mark_saved();
+        self.tables.insert(key, table);
+        Ok(())
+    }
+}
+
+
+impl LoadEvents for FileTournamentRepository {
+    // Corrupted tournaments are listed too, loading their events reports the corruption
+    fn load_tournament_ids(&self) -> Result<Vec<Uuid>, LoadEventsError> {
+        Ok(self.tournaments.keys().chain(&self.corrupted_tournaments).copied().collect())
+    }
+
+    fn load_tournament_events(&self, tournament_id: Uuid, from_version: usize) -> Result<Vec<TournamentEvent>, LoadEventsError> {
+        if self.corrupted_tournaments.contains(&tournament_id) {
+            return Err(LoadEventsError::EventsCorrupted);
+        }
+        let events = self.tournament_events.get(&tournament_id).map_or(&[][..], Vec::as_slice);
+        Ok(events.iter().skip(from_version).cloned().collect())
+    }
+
+    fn load_table_events(&self, tournament_id: Uuid, table_number: usize, from_version: usize) -> Result<Vec<TableEvent>, LoadEventsError> {
+        if self.corrupted_tables.contains(&(tournament_id, table_number)) {
+            return Err(LoadEventsError::EventsCorrupted);
+        }
+        let events = self.table_events.get(&(tournament_id, table_number)).map_or(&[][..], Vec::as_slice);
+        Ok(events.iter().skip(from_version).cloned().collect())
+    }
+}
+
+
+impl LoadCheckpoint for FileTournamentRepository {
+    fn load_checkpoint(&self, stream: EventStream) -> Result<usize, LoadCheckpointError> {
+        Ok(self.checkpoints.get(&stream).copied().unwrap_or(0))
+    }
+}
+
+
+impl SaveCheckpoint for FileTournamentRepository {
+    fn save_checkpoint(&mut self, stream: EventStream, checkpoint: usize) -> Result<(), SaveCheckpointError> {
+        debug!("save checkpoint {} of {:?}", checkpoint, stream);
+        let record = StoredRecord::Values(vec![encode_checkpoint(stream, checkpoint)]);
+        append_record(&self.directory.join(CHECKPOINT_LOG), &record).map_err(|error| {
+            error!("cannot append checkpoint of {:?}: {}", stream, error);
+            SaveCheckpointError::DatabaseWritingError
+        })?;
+        self.checkpoints.insert(stream, checkpoint);
+        Ok(())
+    }
+}
+
+
+impl LoadOutbox for FileTournamentRepository {
+    fn load_pending_messages(&self) -> Result<Vec<OutboxEntry>, LoadOutboxError> {
+        Ok(self.outbox.clone())
+    }
+}
+
+
+impl MarkDelivered for FileTournamentRepository {
+    fn mark_delivered(&mut self, position: u64) -> Result<(), MarkDeliveredError> {
+        debug!("mark messages up to position {} delivered", position);
+        append_record(&self.directory.join(DELIVERED_LOG), &StoredRecord::Values(vec![Value::from(position)])).map_err(|error| {
+            error!("cannot append delivered position {}: {}", position, error);
+            MarkDeliveredError::DatabaseWritingError
+        })?;
+        self.outbox.retain(|entry| entry.position > position);
+        Ok(())
+    }
+}
+
+
+fn parse_table_key(stem: &str) -> Option<(Uuid, usize)> {
+    let (tournament_id, table_number) = stem.split_once('_')?;
+    Some((tournament_id.parse().ok()?, table_number.parse().ok()?))
+}
+
+
+fn decode_position(value: Value) -> Result<u64, EventSchemaError> {
+    Ok(serde_json::from_value(value)?)
+}
+
+
+// A stream without a log has no events yet
+fn read_events<Event>(path: &Path, decode: fn(Value) -> Result<Event, EventSchemaError>) -> Result<Vec<Event>, io::Error> {
+    match repair_log(path, decode) {
+        Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(vec![]),
+        result => result.map(|replay| replay.events),
+    }
+}
+
+
+fn append_record(path: &Path, record: &StoredRecord) -> Result<(), io::Error> {
+    let is_new = !path.exists();
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    let length = file.metadata()?.len();
+    if let Err(error) = file.write_all(&encode_record(record)).and_then(|()| file.sync_data()) {
+        // Leave no partial record behind for the next append to follow
+        let _ = file.set_len(length);
+        return Err(error);
+    }
+    if is_new {
+        // Make the directory entry of the new log durable as well
+        File::open(path.parent().unwrap())?.sync_all()?;
+    }
+    Ok(())
+}
+
+
+fn encode_record(record: &StoredRecord) -> Vec<u8> {
+    let payload = serde_json::to_vec(record).unwrap();
+    let mut record = Vec::with_capacity(HEADER_SIZE + payload.len());
+    record.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+    record.extend_from_slice(&crc32fast::hash(&payload).to_le_bytes());
+    record.extend_from_slice(&payload);
+    record
+}
+
+
+// Replays a log and truncates the remains of a torn write at its end, so that new records are
+// appended right after the last complete one. Only done when opening the repository.
+fn repair_log<Event>(path: &Path, decode: fn(Value) -> Result<Event, EventSchemaError>) -> Result<Replay<Event>, io::Error> {
+    let mut data = vec![];
+    File::open(path)?.read_to_end(&mut data)?;
+    let replay = replay_log(&data, decode)?;
+    if replay.intact_length < data.len() {
+        warn!("truncating torn write of {} byte(s) at the end of {}", data.len() - replay.intact_length, path.display());
+        let file = OpenOptions::new().write(true).open(path)?;
+        file.set_len(replay.intact_length as u64)?;
+        file.sync_all()?;
+    }
+    Ok(replay)
+}
+
+
+// Reads all complete records of a log. Only the last record can stem from a write interrupted by a
+// crash: it is cut short or fails its checksum, and no valid record follows it. Any other record
+// that cannot be read means the log is corrupted.
+fn replay_log<Event>(data: &[u8], decode: fn(Value) -> Result<Event, EventSchemaError>) -> Result<Replay<Event>, io::Error> {
+    let invalid_data = |error: EventSchemaError| io::Error::new(io::ErrorKind::InvalidData, error);
+    let mut replay = Replay { events: vec![], messages: vec![], intact_length: 0 };
+    let mut offset = 0;
+    while offset < data.len() {
+        let (payload, record_size) = match decode_record(&data[offset..]) {
+            RecordRead::Complete { payload, record_size } => (payload, record_size),
+            RecordRead::Incomplete if !contains_record(&data[offset + 1..]) => break,
+            RecordRead::ChecksumMismatch { record_size } if offset + record_size == data.len() => break,
+            _ => {
+                let message = format!("record at offset {} is damaged but followed by further records", offset);
+                return Err(io::Error::new(io::ErrorKind::InvalidData, message));
+            },
+        };
+        let record: StoredRecord = serde_json::from_slice(payload)
+            .map_err(|error| invalid_data(error.into()))?;
+        let (record_events, record_messages) = match record {
+            StoredRecord::Values(values) => (values, vec![]),
+            StoredRecord::Commit { events, messages } => (events, messages),
+        };
+        for value in record_events {
+            replay.events.push(decode(value).map_err(invalid_data)?);
+        }
+        for value in record_messages {
+            replay.messages.push(decode_outbox_entry(value).map_err(invalid_data)?);
+        }
+        offset += record_size;
+    }
+    replay.intact_length = offset;
+    Ok(replay)
+}
+
+
+enum RecordRead<'a> {
+    Complete {
+        payload: &'a [u8],
+        record_size: usize,
+    },
+    // The header or the payload runs past the end of the data
+    Incomplete,
+    ChecksumMismatch {
+        record_size: usize,
+    },
+}
+
+
+fn decode_record(data: &[u8]) -> RecordRead<'_> {
+    let Some(header) = data.get(..HEADER_SIZE) else {
+        return RecordRead::Incomplete;
+    };
+    let length = u32::from_le_bytes(header[0..4].try_into().unwrap()) as usize;
+    let checksum = u32::from_le_bytes(header[4..8].try_into().unwrap());
+    let Some(payload) = data.get(HEADER_SIZE..HEADER_SIZE + length) else {
+        return RecordRead::Incomplete;
+    };
+    if crc32fast::hash(payload) == checksum {
+        RecordRead::Complete { payload, record_size: HEADER_SIZE + length }
+    } else {
+        RecordRead::ChecksumMismatch { record_size: HEADER_SIZE + length }
+    }
+}
+
+
+// Whether a valid record starts anywhere in the data, which tells a damaged length in the middle of
+// a log from a torn write at its end. The payload must parse as well, since the zeros a torn write
+// may leave behind form records with an empty payload and a matching checksum.
+fn contains_record(data: &[u8]) -> bool {
+    (0..data.len()).any(|start| matches!(
+        decode_record(&data[start..]),
+        RecordRead::Complete { payload, .. } if serde_json::from_slice::<StoredRecord>(payload).is_ok()
+    ))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::domain::Nickname;
+    use crate::domain::TableSpecification;
+    use crate::domain::TournamentEvent;
+    use crate::domain::TournamentMessageType;
+    use crate::domain::TournamentSpecification;
+
+    use tempfile::TempDir;
+
+    use super::*;
+
+    fn tournament_with_players(player_count: usize) -> Tournament {
+        let spec = TournamentSpecification::new(2, 3).unwrap();
+        let mut tournament = Tournament::new(&spec);
+        for index in 0..player_count {
+            _ = tournament.join(Uuid::new_v4(), Nickname::new(format!("Player{}", index)).unwrap());
+        }
+        tournament
+    }
+
+    #[test]
+    fn saved_tournaments_are_replayed_on_open() {
+        let directory = TempDir::new().unwrap();
+        let tournament = tournament_with_players(2);
+        let tournament_id = tournament.id();
+        {
+            let mut repository = FileTournamentRepository::open(directory.path()).unwrap();
+            repository.save_tournament(tournament.clone()).unwrap();
+            let mut loaded = repository.load_tournament(tournament_id).unwrap();
+            _ = loaded.join(Uuid::new_v4(), Nickname::new("Daniel").unwrap());
+            repository.save_tournament(loaded).unwrap();
+        }
+        let repository = FileTournamentRepository::open(directory.path()).unwrap();
+        let loaded = repository.load_tournament(tournament_id).unwrap();
+        assert_eq!(loaded.player_count(), 3);
+        assert_eq!(loaded.version(), 4);
+    }
+
+    #[test]
+    fn saved_tables_are_replayed_on_open() {
+        let directory = TempDir::new().unwrap();
+        let tournament_id = Uuid::new_v4();
+        {
+            let mut repository = FileTournamentRepository::open(directory.path()).unwrap();
+            let mut table = Table::new(tournament_id, 1, &TableSpecification::new(3).unwrap());
+            table.sit_down(Uuid::new_v4(), Nickname::new("James").unwrap(), 1500).unwrap();
+            repository.save_table(table).unwrap();
+            let mut table = repository.load_table(tournament_id, 1).unwrap();
+            table.sit_down(Uuid::new_v4(), Nickname::new("Daniel").unwrap(), 1500).unwrap();
+            repository.save_table(table.clone()).unwrap();
+            assert!(matches!(repository.save_table(table), Err(SaveTableError::TableOutdated)));
+        }
+        let repository = FileTournamentRepository::open(directory.path()).unwrap();
+        let table = repository.load_table(tournament_id, 1).unwrap();
+        assert_eq!(table.player_count(), 2);
+        assert_eq!(table.version(), 3);
+        assert!(matches!(repository.load_table(tournament_id, 0), Err(LoadTableError::TableNotFound)));
+    }
+
+    #[test]
+    fn events_and_checkpoints_are_replayed_on_open() {
+        let directory = TempDir::new().unwrap();
+        let tournament = tournament_with_players(2);
+        let tournament_id = tournament.id();
+        let stream = EventStream::Tournament { tournament_id };
+        let table_stream = EventStream::Table { tournament_id, table_number: 1 };
+        {
+            let mut repository = FileTournamentRepository::open(directory.path()).unwrap();
+            repository.save_tournament(tournament).unwrap();
+            repository.save_checkpoint(stream, 1).unwrap();
+            repository.save_checkpoint(stream, 2).unwrap();
+            repository.save_checkpoint(table_stream, 4).unwrap();
+        }
+        let repository = FileTournamentRepository::open(directory.path()).unwrap();
+        assert_eq!(repository.load_checkpoint(stream).unwrap(), 2);
+        assert_eq!(repository.load_checkpoint(table_stream).unwrap(), 4);
+        assert_eq!(repository.load_checkpoint(EventStream::Table { tournament_id, table_number: 0 }).unwrap(), 0);
+        let events = repository.load_tournament_events(tournament_id, 2).unwrap();
+        assert!(matches!(events[..], [TournamentEvent::PlayerJoined { .. }]));
+        assert_eq!(repository.load_table_events(tournament_id, 1, 0).unwrap(), vec![]);
+    }
+
+    #[test]
+    fn pending_messages_are_replayed_on_open() {
+        let directory = TempDir::new().unwrap();
+        let tournament_id = {
+            let mut repository = FileTournamentRepository::open(directory.path()).unwrap();
+            let tournament = tournament_with_players(3);
+            let tournament_id = tournament.id();
+            repository.save_tournament(tournament).unwrap();
+            let mut table = Table::new(tournament_id, 0, &TableSpecification::new(3).unwrap());
+            table.sit_down(Uuid::new_v4(), Nickname::new("James").unwrap(), 1500).unwrap();
+            repository.save_table(table).unwrap();
+            let entries = repository.load_pending_messages().unwrap();
+            assert_eq!(entries.iter().map(|entry| entry.position).collect::<Vec<_>>(), vec![1, 2, 3, 4]);
+            repository.mark_delivered(2).unwrap();
+            tournament_id
+        };
+        let mut repository = FileTournamentRepository::open(directory.path()).unwrap();
+        let entries = repository.load_pending_messages().unwrap();
+        assert_eq!(entries.iter().map(|entry| entry.position).collect::<Vec<_>>(), vec![3, 4]);
+        assert!(matches!(entries[1].message.message_type, TournamentMessageType::TableMessage { table_number: 0, .. }));
+        let mut tournament = repository.load_tournament(tournament_id).unwrap();
+        _ = tournament.join(Uuid::new_v4(), Nickname::new("Daniel").unwrap());
+        repository.save_tournament(tournament).unwrap();
+        assert_eq!(repository.load_pending_messages().unwrap().last().unwrap().position, 5);
+    }
+
+    #[test]
+    fn failed_appends_take_no_outbox_positions() {
+        let directory = TempDir::new().unwrap();
+        let mut repository = FileTournamentRepository::open(directory.path()).unwrap();
+        let tournament = tournament_with_players(3);
+        let tournament_id = tournament.id();
+        repository.save_tournament(tournament).unwrap();
+        let mut table = Table::new(tournament_id, 0, &TableSpecification::new(3).unwrap());
+        table.sit_down(Uuid::new_v4(), Nickname::new("James").unwrap(), 1500).unwrap();
+
+        // A directory in place of the log makes the append fail
+        let path = repository.table_log_path(tournament_id, 0);
+        std::fs::create_dir_all(&path).unwrap();
+        assert!(matches!(repository.save_table(table.clone()), Err(SaveTableError::DatabaseWritingError)));
+        std::fs::remove_dir(&path).unwrap();
+
+        repository.save_table(table).unwrap();
+        let entries = repository.load_pending_messages().unwrap();
+        assert_eq!(entries.iter().map(|entry| entry.position).collect::<Vec<_>>(), vec![1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn save_rejects_outdated_tournament() {
+        let directory = TempDir::new().unwrap();
+        let mut repository = FileTournamentRepository::open(directory.path()).unwrap();
+        let tournament = tournament_with_players(0);
+        let tournament_id = tournament.id();
+        repository.save_tournament(tournament).unwrap();
+        let mut first = repository.load_tournament(tournament_id).unwrap();
+        let mut second = repository.load_tournament(tournament_id).unwrap();
+        _ = first.join(Uuid::new_v4(), Nickname::new("James").unwrap());
+        _ = second.join(Uuid::new_v4(), Nickname::new("Patricia").unwrap());
+        repository.save_tournament(first).unwrap();
+        assert!(matches!(repository.save_tournament(second), Err(SaveTournamentError::TournamentOutdated)));
+    }
+
+    #[test]
+    fn corrupted_logs_are_reported_on_load() {
+        let directory = TempDir::new().unwrap();
+        let tournament_id = Uuid::new_v4();
+        let joined = TournamentEvent::PlayerJoined { account_id: Uuid::new_v4(), nickname: Nickname::new("Daniel").unwrap() };
+        {
+            let repository = FileTournamentRepository::open(directory.path()).unwrap();
+            append_record(&repository.log_path(tournament_id), &StoredRecord::Values(vec![encode_event(&joined)])).unwrap();
+        }
+        let repository = FileTournamentRepository::open(directory.path()).unwrap();
+        assert!(matches!(repository.load_tournament(tournament_id), Err(LoadTournamentError::TournamentCorrupted)));
+        assert!(matches!(repository.load_tournament(Uuid::new_v4()), Err(LoadTournamentError::TournamentNotFound)));
+    }
+
+    #[test]
+    fn torn_writes_are_truncated() {
+        let directory = TempDir::new().unwrap();
+        let tournament = tournament_with_players(1);
+        let tournament_id = tournament.id();
+        let path = {
+            let mut repository = FileTournamentRepository::open(directory.path()).unwrap();
+            repository.save_tournament(tournament).unwrap();
+            repository.log_path(tournament_id)
+        };
+        let intact_length = std::fs::metadata(&path).unwrap().len();
+
+        // A record whose payload was only partially written
+        let record = encode_record(&StoredRecord::Values(vec![encode_event(&TournamentEvent::PlayerJoined { account_id: Uuid::new_v4(), nickname: Nickname::new("Daniel").unwrap() })]));
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(&record[..record.len() - 3]).unwrap();
+        drop(file);
+
+        let repository = FileTournamentRepository::open(directory.path()).unwrap();
+        assert_eq!(repository.load_tournament(tournament_id).unwrap().player_count(), 1);
+        assert_eq!(std::fs::metadata(&path).unwrap().len(), intact_length);
+    }
+
+    #[test]
+    fn records_with_bad_checksum_are_truncated() {
+        let directory = TempDir::new().unwrap();
+        let tournament = tournament_with_players(1);
+        let tournament_id = tournament.id();
+        let path = {
+            let mut repository = FileTournamentRepository::open(directory.path()).unwrap();
+            repository.save_tournament(tournament).unwrap();
+            let mut loaded = repository.load_tournament(tournament_id).unwrap();
+            _ = loaded.join(Uuid::new_v4(), Nickname::new("Daniel").unwrap());
+            repository.save_tournament(loaded).unwrap();
+            repository.log_path(tournament_id)
+        };
+        let mut data = std::fs::read(&path).unwrap();
+        let last_byte = data.len() - 2;
+        data[last_byte] ^= 0xff;
+        std::fs::write(&path, &data).unwrap();
+
+        let repository = FileTournamentRepository::open(directory.path()).unwrap();
+        let loaded = repository.load_tournament(tournament_id).unwrap();
+        assert_eq!(loaded.player_count(), 1);
+        assert_eq!(loaded.version(), 2);
+    }
+
+    #[test]
+    fn damaged_records_in_the_middle_are_reported_as_corruption() {
+        let directory = TempDir::new().unwrap();
+        let tournament = tournament_with_players(1);
+        let tournament_id = tournament.id();
+        let path = {
+            let mut repository = FileTournamentRepository::open(directory.path()).unwrap();
+            repository.save_tournament(tournament).unwrap();
+            let mut loaded = repository.load_tournament(tournament_id).unwrap();
+            _ = loaded.join(Uuid::new_v4(), Nickname::new("Daniel").unwrap());
+            repository.save_tournament(loaded).unwrap();
+            repository.log_path(tournament_id)
+        };
+        let mut data = std::fs::read(&path).unwrap();
+        data[HEADER_SIZE + 2] ^= 0xff;
+        std::fs::write(&path, &data).unwrap();
+
+        let repository = FileTournamentRepository::open(directory.path()).unwrap();
+        assert!(matches!(repository.load_tournament(tournament_id), Err(LoadTournamentError::TournamentCorrupted)));
+        assert!(matches!(repository.load_tournament_events(tournament_id, 0), Err(LoadEventsError::EventsCorrupted)));
+        assert_eq!(std::fs::read(&path).unwrap(), data);
+
+        // A damaged length that seems to run past the end must not be mistaken for a torn write
+        data[HEADER_SIZE + 2] ^= 0xff;
+        data[3] = 0x7f;
+        std::fs::write(&path, &data).unwrap();
+        let repository = FileTournamentRepository::open(directory.path()).unwrap();
+        assert!(matches!(repository.load_tournament(tournament_id), Err(LoadTournamentError::TournamentCorrupted)));
+        assert_eq!(std::fs::read(&path).unwrap(), data);
+    }
+}
@@ -1,3 +1,14 @@
+mod accounts;
+mod event_schema;
+mod file;
 mod repository;
+#[cfg(feature = "sqlite")]
+mod sqlite;
 
+pub use accounts::InMemoryAccountRepository;
+pub use file::FileTournamentRepository;
 pub use repository::InMemoryTournamentRepository;
+#[cfg(feature = "sqlite")]
+pub use sqlite::SqliteAccountRepository;
+#[cfg(feature = "sqlite")]
+pub use sqlite::SqliteTournamentRepository;
@@ -1,10 +1,28 @@
+use crate::domain::EventStream;
+use crate::domain::LoadCheckpoint;
+use crate::domain::LoadCheckpointError;
+use crate::domain::LoadEvents;
+use crate::domain::LoadEventsError;
+use crate::domain::LoadOutbox;
+use crate::domain::LoadOutboxError;
+use crate::domain::LoadTable;
+use crate::domain::LoadTableError;
 use crate::domain::LoadTournament;
 use crate::domain::LoadTournamentError;
+use crate::domain::MarkDelivered;
+use crate::domain::MarkDeliveredError;
+use crate::domain::OutboxEntry;
+use crate::domain::SaveCheckpoint;
+use crate::domain::SaveCheckpointError;
+use crate::domain::SaveTable;
+use crate::domain::SaveTableError;
 use crate::domain::SaveTournament;
 use crate::domain::SaveTournamentError;
-use crate::domain::QueryTournaments;
-use crate::domain::QueryTournamentsError;
+use crate::domain::Table;
+use crate::domain::TableEvent;
 use crate::domain::Tournament;
+use crate::domain::TournamentEvent;
+use crate::domain::TournamentMessage;
 
 use log::debug;
 use uuid::Uuid;
@@ -15,11 +33,32 @@ use std::collections::HashMap;
 #[derive(Debug)]
 pub struct InMemoryTournamentRepository {
     tournaments: HashMap<Uuid, Tournament>,
+    tables: HashMap<(Uuid, usize), Table>,
+    tournament_events: HashMap<Uuid, Vec<TournamentEvent>>,
+    table_events: HashMap<(Uuid, usize), Vec<TableEvent>>,
+    checkpoints: HashMap<EventStream, usize>,
+    outbox: Vec<OutboxEntry>,
+    last_position: u64,
 }
 
 impl InMemoryTournamentRepository {
     pub fn new() -> Self {
-        Self { tournaments: HashMap::new() }
+        Self {
+            tournaments: HashMap::new(),
+            tables: HashMap::new(),
+            tournament_events: HashMap::new(),
+            table_events: HashMap::new(),
+            checkpoints: HashMap::new(),
+            outbox: vec![],
+            last_position: 0,
+        }
+    }
+
+    fn add_to_outbox(&mut self, messages: Vec<TournamentMessage>) {
+        for message in messages {
+            self.last_position += 1;
+            self.outbox.push(OutboxEntry { position: self.last_position, message });
+        }
     }
 }
 
@@ -37,17 +76,115 @@ impl LoadTournament for InMemoryTournamentRepository {
 
 
 impl SaveTournament for InMemoryTournamentRepository {
-    fn save_tournament(&mut self, tournament: Tournament) -> Result<(), SaveTournamentError> {
-        debug!("save tournament {}", tournament.id());
+    fn save_tournament(&mut self, mut tournament: Tournament) -> Result<(), SaveTournamentError> {
+        debug!("save tournament {} at version {}", tournament.id(), tournament.version());
+        let stored_version = self.tournaments.get(&tournament.id()).map_or(0, |stored| stored.version());
+        if stored_version != tournament.version() {
+            return Err(SaveTournamentError::TournamentOutdated);
+        }
+        self.tournament_events.entry(tournament.id()).or_default().extend(tournament.new_events());
+        self.add_to_outbox(tournament.collect_messages());
+        tournament.mark_saved();
         self.tournaments.insert(tournament.id(), tournament);
         Ok(())
     }
 }
 
 
-impl QueryTournaments for InMemoryTournamentRepository {
-    fn query_tournaments(&self) -> Result<Vec<Tournament>, QueryTournamentsError> {
-        debug!("query tournaments");
-        Ok(self.tournaments.values().cloned().collect())
+impl LoadTable for InMemoryTournamentRepository {
+    fn load_table(&self, tournament_id: Uuid, table_number: usize) -> Result<Table, LoadTableError> {
+        debug!("load table {} of tournament {}", table_number, tournament_id);
+        self.tables.get(&(tournament_id, table_number)).cloned().ok_or(LoadTableError::TableNotFound)
+    }
+}
+
+
+impl SaveTable for InMemoryTournamentRepository {
+    fn save_table(&mut self, mut table: Table) -> Result<(), SaveTableError> {
+        let key = (table.tournament_id(), table.table_number());
+        debug!("save table {} of tournament {} at version {}", key.1, key.0, table.version());
+        let stored_version = self.tables.get(&key).map_or(0, |stored| stored.version());
+        if stored_version != table.version() {
+            return Err(SaveTableError::TableOutdated);
+        }
+        self.table_events.entry(key).or_default().extend(table.new_events());
+        self.add_to_outbox(table.collect_tournament_messages());
+        table.mark_saved();
+        self.tables.insert(key, table);
+        Ok(())
+    }
+}
+
+
+impl LoadEvents for InMemoryTournamentRepository {
+    fn load_tournament_ids(&self) -> Result<Vec<Uuid>, LoadEventsError> {
+        Ok(self.tournament_events.keys().copied().collect())
+    }
+
+    fn load_tournament_events(&self, tournament_id: Uuid, from_version: usize) -> Result<Vec<TournamentEvent>, LoadEventsError> {
+        let events = self.tournament_events.get(&tournament_id).map_or(&[][..], Vec::as_slice);
+        Ok(events.iter().skip(from_version).cloned().collect())
+    }
+
+    fn load_table_events(&self, tournament_id: Uuid, table_number: usize, from_version: usize) -> Result<Vec<TableEvent>, LoadEventsError> {
+        let events = self.table_events.get(&(tournament_id, table_number)).map_or(&[][..], Vec::as_slice);
+        Ok(events.iter().skip(from_version).cloned().collect())
+    }
+}
+
+
+impl LoadCheckpoint for InMemoryTournamentRepository {
+    fn load_checkpoint(&self, stream: EventStream) -> Result<usize, LoadCheckpointError> {
+        Ok(self.checkpoints.get(&stream).copied().unwrap_or(0))
+    }
+}
+
+
+impl SaveCheckpoint for InMemoryTournamentRepository {
+    fn save_checkpoint(&mut self, stream: EventStream, checkpoint: usize) -> Result<(), SaveCheckpointError> {
+        self.checkpoints.insert(stream, checkpoint);
+        Ok(())
+    }
+}
+
+
+impl LoadOutbox for InMemoryTournamentRepository {
+    fn load_pending_messages(&self) -> Result<Vec<OutboxEntry>, LoadOutboxError> {
+        Ok(self.outbox.clone())
+    }
+}
+
+
+impl MarkDelivered for InMemoryTournamentRepository {
+    fn mark_delivered(&mut self, position: u64) -> Result<(), MarkDeliveredError> {
+        debug!("mark messages up to position {} delivered", position);
+        self.outbox.retain(|entry| entry.position > position);
+        Ok(())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::Nickname;
+    use crate::domain::TournamentSpecification;
+
+    #[test]
+    fn save_rejects_outdated_tournament() {
+        let mut repository = InMemoryTournamentRepository::new();
+        let spec = TournamentSpecification::new(1, 3).unwrap();
+        let tournament = Tournament::new(&spec);
+        let tournament_id = tournament.id();
+        repository.save_tournament(tournament.clone()).unwrap();
+        assert!(matches!(repository.save_tournament(tournament), Err(SaveTournamentError::TournamentOutdated)));
+
+        let mut first = repository.load_tournament(tournament_id).unwrap();
+        let mut second = repository.load_tournament(tournament_id).unwrap();
+        _ = first.join(Uuid::new_v4(), Nickname::new("James").unwrap());
+        _ = second.join(Uuid::new_v4(), Nickname::new("Patricia").unwrap());
+        repository.save_tournament(first).unwrap();
+        assert!(matches!(repository.save_tournament(second), Err(SaveTournamentError::TournamentOutdated)));
+        assert_eq!(repository.load_tournament(tournament_id).unwrap().version(), 2);
     }
 }
@@ -0,0 +1,1142 @@
+use crate::domain::Account;
+use crate::domain::AccountRole;
+use crate::domain::Ban;
+use crate::domain::BanScope;
+use crate::domain::EventStream;
+use crate::domain::LoadAccount;
+use crate::domain::LoadAccountError;
+use crate::domain::LoadBanError;
+use crate::domain::LoadBans;
+use crate::domain::LoadCheckpoint;
+use crate::domain::LoadCheckpointError;
+use crate::domain::LoadEvents;
+use crate::domain::LoadEventsError;
+use crate::domain::LoadOutbox;
+use crate::domain::LoadOutboxError;
+use crate::domain::LoadRoleChanges;
+use crate::domain::LoadRoleChangesError;
+use crate::domain::LoadTable;
+use crate::domain::LoadTableError;
+use crate::domain::LoadTournament;
+use crate::domain::LoadTournamentError;
+use crate::domain::MarkDelivered;
+use crate::domain::MarkDeliveredError;
+use crate::domain::Nickname;
+use crate::domain::OutboxEntry;
+use crate::domain::RestoreError;
+use crate::domain::RoleChange;
+use crate::domain::SaveAccount;
+use crate::domain::SaveAccountError;
+use crate::domain::SaveBan;
+use crate::domain::SaveBanError;
+use crate::domain::SaveCheckpoint;
+use crate::domain::SaveCheckpointError;
+use crate::domain::SaveTable;
+use crate::domain::SaveTableError;
+use crate::domain::SaveTournament;
+use crate::domain::SaveTournamentError;
+use crate::domain::Table;
+use crate::domain::TableEvent;
+use crate::domain::Tournament;
+use crate::domain::TournamentEvent;
+use crate::domain::TournamentMessage;
+use crate::domain::TournamentSnapshot;
+use crate::domain::Username;
+
+use super::event_schema::EventSchemaError;
+use super::event_schema::decode_event;
+use super::event_schema::decode_message;
+use super::event_schema::decode_snapshot;
+use super::event_schema::decode_table_event;
+use super::event_schema::encode_event;
+use super::event_schema::encode_message;
+use super::event_schema::encode_snapshot;
+use super::event_schema::encode_stream;
+use super::event_schema::encode_table_event;
+
+use log::{debug, error, info, warn};
+use rusqlite::Connection;
+use rusqlite::ErrorCode;
+use rusqlite::OptionalExtension;
+use rusqlite::Transaction;
+use rusqlite::params;
+use thiserror::Error;
+use uuid::Uuid;
+
+use std::path::Path;
+
+
+// Each entry migrates the schema from the version given by its index to the next one
+const MIGRATIONS: &[&str] = &[
+    "
+    CREATE TABLE tournament_events (
+        tournament_id TEXT NOT NULL,
+        sequence INTEGER NOT NULL,
+        payload TEXT NOT NULL,
+        PRIMARY KEY (tournament_id, sequence)
+    );
+    ",
+    "
+    CREATE TABLE tournament_snapshots (
+        tournament_id TEXT PRIMARY KEY,
+        version INTEGER NOT NULL,
+        payload TEXT NOT NULL
+    );
+    ",
+    "
+    CREATE TABLE table_events (
+        tournament_id TEXT NOT NULL,
+        table_number INTEGER NOT NULL,
+        sequence INTEGER NOT NULL,
+        payload TEXT NOT NULL,
+        PRIMARY KEY (tournament_id, table_number, sequence)
+    );
+    ",
+    "
+    CREATE TABLE checkpoints (
+        stream TEXT PRIMARY KEY,
+        checkpoint INTEGER NOT NULL
+    );
+    ",
+    // Autoincrement keeps the positions of delivered and deleted messages from being reused
+    "
+    CREATE TABLE outbox (
+        position INTEGER PRIMARY KEY AUTOINCREMENT,
+        payload TEXT NOT NULL
+    );
+    ",
+    "
+    CREATE TABLE accounts (
+        id TEXT PRIMARY KEY,
+        username TEXT NOT NULL UNIQUE,
+        password_hash TEXT NOT NULL,
+        role TEXT NOT NULL
+    );
+    ",
+    "
+    CREATE TABLE role_changes (
+        position INTEGER PRIMARY KEY AUTOINCREMENT,
+        account_id TEXT NOT NULL,
+        changed_by TEXT,
+        old_role TEXT NOT NULL,
+        new_role TEXT NOT NULL,
+        changed_at INTEGER NOT NULL
+    );
+    CREATE INDEX role_changes_by_account ON role_changes (account_id);
+    CREATE INDEX accounts_by_role ON accounts (role);
+    ",
+    // Platform bans have no tournament id
+    "
+    CREATE TABLE bans (
+        id TEXT PRIMARY KEY,
+        account_id TEXT NOT NULL,
+        tournament_id TEXT,
+        banned_by TEXT NOT NULL,
+        reason TEXT NOT NULL,
+        banned_at INTEGER NOT NULL,
+        expires_at INTEGER,
+        lifted_by TEXT
+    );
+    CREATE INDEX bans_by_account ON bans (account_id);
+    ",
+    // Nicknames are unique regardless of case, through their lowercase key
+    "
+    ALTER TABLE accounts ADD COLUMN nickname TEXT;
+    ALTER TABLE accounts ADD COLUMN nickname_key TEXT;
+    ALTER TABLE accounts ADD COLUMN nickname_changed_at INTEGER;
+    CREATE UNIQUE INDEX accounts_by_nickname ON accounts (nickname_key);
+    ",
+    "
+    CREATE INDEX bans_by_tournament ON bans (tournament_id);
+    ",
+];
+
+// A snapshot is stored whenever a save crosses a multiple of this number of events
+const SNAPSHOT_INTERVAL: usize = 50;
+
+
+#[derive(Debug, Error)]
+enum ReadError {
+    #[error(transparent)]
+    DatabaseError(#[from] rusqlite::Error),
+    #[error(transparent)]
+    RestoreError(#[from] RestoreError),
+}
+
+
+#[derive(Debug)]
+pub struct SqliteTournamentRepository {
+    connection: Connection,
+}
+
+impl SqliteTournamentRepository {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, rusqlite::Error> {
+        let mut connection = Connection::open(path)?;
+        migrate(&mut connection)?;
+        Ok(Self { connection })
+    }
+
+    fn load(&self, tournament_id: Uuid) -> Result<Option<Tournament>, ReadError> {
+        let snapshot = self.load_snapshot(tournament_id)?;
+        let events = self.load_events(tournament_id, snapshot.as_ref().map_or(0, |snapshot| snapshot.version))?;
+        let tournament = match snapshot {
+            Some(snapshot) => Tournament::restore_from_snapshot(snapshot, events)?,
+            None if events.is_empty() => return Ok(None),
+            None => Tournament::restore(events)?,
+        };
+        Ok(Some(tournament))
+    }
+
+    fn load_snapshot(&self, tournament_id: Uuid) -> Result<Option<TournamentSnapshot>, rusqlite::Error> {
+        let payload: Option<String> = self.connection.query_row(
+            "SELECT payload FROM tournament_snapshots WHERE tournament_id = ?1",
+            params![tournament_id.to_string()],
+            |row| row.get(0),
+        ).optional()?;
+        let Some(payload) = payload else {
+            return Ok(None);
+        };
+        let snapshot = serde_json::from_str(&payload).map_err(EventSchemaError::from).and_then(decode_snapshot);
+        match snapshot {
+            Ok(snapshot) => Ok(Some(snapshot)),
+            Err(error) => {
+                warn!("ignoring snapshot of tournament {}: {}", tournament_id, error);
+                Ok(None)
+            }
+        }
+    }
+
+    fn load_events(&self, tournament_id: Uuid, from_sequence: usize) -> Result<Vec<TournamentEvent>, rusqlite::Error> {
+        let mut statement = self.connection.prepare_cached(
+            "SELECT payload FROM tournament_events WHERE tournament_id = ?1 AND sequence >= ?2 ORDER BY sequence"
+        )?;
+        let payloads = statement.query_map(params![tournament_id.to_string(), from_sequence as i64], |row| row.get::<_, String>(0))?;
+        let mut events = vec![];
+        for payload in payloads {
+            let event = serde_json::from_str(&payload?)
+                .map_err(EventSchemaError::from)
+                .and_then(decode_event)
+                .map_err(|error| rusqlite::Error::FromSqlConversionFailure(0, rusqlite::types::Type::Text, Box::new(error)))?;
+            events.push(event);
+        }
+        Ok(events)
+    }
+
+    // Returns false without writing anything if the stored stream is newer than the tournament's version
+    fn append_events(&mut self, tournament: &Tournament, messages: &[TournamentMessage]) -> Result<bool, rusqlite::Error> {
+        let tournament_id = tournament.id().to_string();
+        let transaction = self.connection.transaction()?;
+        let stored_version: i64 = transaction.query_row(
+            "SELECT COUNT(*) FROM tournament_events WHERE tournament_id = ?1",
+            params![tournament_id],
+            |row| row.get(0),
+        )?;
+        if stored_version as usize != tournament.version() {
+            return Ok(false);
+        }
+        let new_events = tournament.new_events();
+        let new_version = tournament.version() + new_events.len();
+        for (sequence, event) in (tournament.version()..).zip(new_events) {
+            let payload = encode_event(&event).to_string();
+            transaction.execute(
+                "INSERT INTO tournament_events (tournament_id, sequence, payload) VALUES (?1, ?2, ?3)",
+                params![tournament_id, sequence as i64, payload],
+            )?;
+        }
+        if tournament.version() / SNAPSHOT_INTERVAL < new_version / SNAPSHOT_INTERVAL {
+            update_snapshot(&transaction, tournament)?;
+        }
+        add_to_outbox(&transaction, messages)?;
+        transaction.commit()?;
+        Ok(true)
+    }
+
+    fn read_tournament_ids(&self) -> Result<Vec<Uuid>, rusqlite::Error> {
+        let mut statement = self.connection.prepare_cached("SELECT DISTINCT tournament_id FROM tournament_events")?;
+        let ids = statement.query_map([], |row| row.get::<_, String>(0))?;
+        let mut tournament_ids = vec![];
+        for id in ids {
+            let tournament_id = Uuid::parse_str(&id?).map_err(|error| {
+                rusqlite::Error::FromSqlConversionFailure(0, rusqlite::types::Type::Text, Box::new(error))
+            })?;
+            tournament_ids.push(tournament_id);
+        }
+        Ok(tournament_ids)
+    }
+
+    fn read_table_events(&self, tournament_id: Uuid, table_number: usize, from_sequence: usize) -> Result<Vec<TableEvent>, rusqlite::Error> {
+        let mut statement = self.connection.prepare_cached(
+            "SELECT payload FROM table_events WHERE tournament_id = ?1 AND table_number = ?2 AND sequence >= ?3 ORDER BY sequence"
+        )?;
+        let payloads = statement.query_map(
+            params![tournament_id.to_string(), table_number as i64, from_sequence as i64],
+            |row| row.get::<_, String>(0),
+        )?;
+        let mut events = vec![];
+        for payload in payloads {
+            let event = serde_json::from_str(&payload?)
+                .map_err(EventSchemaError::from)
+                .and_then(decode_table_event)
+                .map_err(|error| rusqlite::Error::FromSqlConversionFailure(0, rusqlite::types::Type::Text, Box::new(error)))?;
+            events.push(event);
+        }
+        Ok(events)
+    }
+
+    // Returns false without writing anything if the stored stream is newer than the table's version
+    fn append_table_events(&mut self, table: &Table, messages: &[TournamentMessage]) -> Result<bool, rusqlite::Error> {
+        let tournament_id = table.tournament_id().to_string();
+        let table_number = table.table_number() as i64;
+        let transaction = self.connection.transaction()?;
+        let stored_version: i64 = transaction.query_row(
+            "SELECT COUNT(*) FROM table_events WHERE tournament_id = ?1 AND table_number = ?2",
+            params![tournament_id, table_number],
+            |row| row.get(0),
+        )?;
+        if stored_version as usize != table.version() {
+            return Ok(false);
+        }
+        for (sequence, event) in (table.version()..).zip(table.new_events()) {
+            transaction.execute(
+                "INSERT INTO table_events (tournament_id, table_number, sequence, payload) VALUES (?1, ?2, ?3, ?4)",
+                params![tournament_id, table_number, sequence as i64, encode_table_event(&event).to_string()],
+            )?;
+        }
+        add_to_outbox(&transaction, messages)?;
+        transaction.commit()?;
+        Ok(true)
+    }
+
+    fn read_outbox(&self) -> Result<Vec<OutboxEntry>, rusqlite::Error> {
+        let mut statement = self.connection.prepare_cached("SELECT position, payload FROM outbox ORDER BY position")?;
+        let rows = statement.query_map([], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?)))?;
+        let mut entries = vec![];
+        for row in rows {
+            let (position, payload) = row?;
+            let message = serde_json::from_str(&payload)
+                .map_err(EventSchemaError::from)
+                .and_then(decode_message)
+                .map_err(|error| rusqlite::Error::FromSqlConversionFailure(1, rusqlite::types::Type::Text, Box::new(error)))?;
+            entries.push(OutboxEntry { position: position as u64, message });
+        }
+        Ok(entries)
+    }
+}
+
+
+impl LoadTournament for SqliteTournamentRepository {
+    fn load_tournament(&self, tournament_id: Uuid) -> Result<Tournament, LoadTournamentError> {
+        debug!("load tournament {}", tournament_id);
+        let tournament = self.load(tournament_id).map_err(|error| {
+            error!("cannot load tournament {}: {}", tournament_id, error);
+            match error {
+                ReadError::DatabaseError(_) => LoadTournamentError::DatabaseReadingError,
+                ReadError::RestoreError(_) => LoadTournamentError::TournamentCorrupted,
+            }
+        })?;
+        tournament.ok_or(LoadTournamentError::TournamentNotFound)
+    }
+}
+
+
+impl SaveTournament for SqliteTournamentRepository {
+    fn save_tournament(&mut self, mut tournament: Tournament) -> Result<(), SaveTournamentError> {
+        debug!("save tournament {} at version {}", tournament.id(), tournament.version());
+        let messages = tournament.collect_messages();
+        let appended = self.append_events(&tournament, &messages).map_err(|error| {
+            error!("cannot save events of tournament {}: {}", tournament.id(), error);
+            SaveTournamentError::DatabaseWritingError
+        })?;
+        if appended {
+            Ok(())
+        } else {
+            Err(SaveTournamentError::TournamentOutdated)
+        }
+    }
+}
+
+
+impl LoadTable for SqliteTournamentRepository {
+    fn load_table(&self, tournament_id: Uuid, table_number: usize) -> Result<Table, LoadTableError> {
+        debug!("load table {} of tournament {}", table_number, tournament_id);
+        let events = self.read_table_events(tournament_id, table_number, 0).map_err(|error| {
+            error!("cannot load events of table {} of tournament {}: {}", table_number, tournament_id, error);
+            LoadTableError::DatabaseReadingError
+        })?;
+        if events.is_empty() {
+            return Err(LoadTableError::TableNotFound);
+        }
+        Table::restore(events).map_err(|error| {
+            error!("cannot restore table {} of tournament {}: {}", table_number, tournament_id, error);
+            LoadTableError::TableCorrupted
+        })
+    }
+}
+
+
+impl SaveTable for SqliteTournamentRepository {
+    fn save_table(&mut self, mut table: Table) -> Result<(), SaveTableError> {
+        debug!("save table {} of tournament {} at version {}", table.table_number(), table.tournament_id(), table.version());
+        let messages = table.collect_tournament_messages();
+        let appended = self.append_table_events(&table, &messages).map_err(|error| {
+            error!("cannot save events of table {} of tournament {}: {}", table.table_number(), table.tournament_id(), error);
+            SaveTableError::DatabaseWritingError
+        })?;
+        if appended {
+            Ok(())
+        } else {
+            Err(SaveTableError::TableOutdated)
+        }
+    }
+}
+
+
+impl LoadEvents for SqliteTournamentRepository {
+    fn load_tournament_ids(&self) -> Result<Vec<Uuid>, LoadEventsError> {
+        self.read_tournament_ids().map_err(|error| {
+            error!("cannot load tournament ids: {}", error);
+            LoadEventsError::DatabaseReadingError
+        })
+    }
+
+    fn load_tournament_events(&self, tournament_id: Uuid, from_version: usize) -> Result<Vec<TournamentEvent>, LoadEventsError> {
+        self.load_events(tournament_id, from_version).map_err(|error| {
+            error!("cannot load events of tournament {}: {}", tournament_id, error);
+            LoadEventsError::DatabaseReadingError
+        })
+    }
+
+    fn load_table_events(&self, tournament_id: Uuid, table_number: usize, from_version: usize) -> Result<Vec<TableEvent>, LoadEventsError> {
+        self.read_table_events(tournament_id, table_number, from_version).map_err(|error| {
+            error!("cannot load events of table {} of tournament {}: {}", table_number, tournament_id, error);
+            LoadEventsError::DatabaseReadingError
+        })
+    }
+}
+
+
+impl LoadCheckpoint for SqliteTournamentRepository {
+    fn load_checkpoint(&self, stream: EventStream) -> Result<usize, LoadCheckpointError> {
+        let checkpoint: Option<i64> = self.connection.query_row(
+            "SELECT checkpoint FROM checkpoints WHERE stream = ?1",
+            params![encode_stream(stream)],
+            |row| row.get(0),
+        ).optional().map_err(|error| {
+            error!("cannot load checkpoint of {:?}: {}", stream, error);
+            LoadCheckpointError::DatabaseReadingError
+        })?;
+        Ok(checkpoint.map_or(0, |checkpoint| checkpoint as usize))
+    }
+}
+
+
+impl SaveCheckpoint for SqliteTournamentRepository {
+    fn save_checkpoint(&mut self, stream: EventStream, checkpoint: usize) -> Result<(), SaveCheckpointError> {
+        debug!("save checkpoint {} of {:?}", checkpoint, stream);
+        self.connection.execute(
+            "INSERT INTO checkpoints (stream, checkpoint) VALUES (?1, ?2)
+             ON CONFLICT (stream) DO UPDATE SET checkpoint = excluded.checkpoint",
+            params![encode_stream(stream), checkpoint as i64],
+        ).map_err(|error| {
+            error!("cannot save checkpoint of {:?}: {}", stream, error);
+            SaveCheckpointError::DatabaseWritingError
+        })?;
+        Ok(())
+    }
+}
+
+
+impl LoadOutbox for SqliteTournamentRepository {
+    fn load_pending_messages(&self) -> Result<Vec<OutboxEntry>, LoadOutboxError> {
+        self.read_outbox().map_err(|error| {
+            error!("cannot load pending messages: {}", error);
+            LoadOutboxError::DatabaseReadingError
+        })
+    }
+}
+
+
+impl MarkDelivered for SqliteTournamentRepository {
+    fn mark_delivered(&mut self, position: u64) -> Result<(), MarkDeliveredError> {
+        debug!("mark messages up to position {} delivered", position);
+        self.connection.execute("DELETE FROM outbox WHERE position <= ?1", params![position as i64]).map_err(|error| {
+            error!("cannot mark messages up to position {} delivered: {}", position, error);
+            MarkDeliveredError::DatabaseWritingError
+        })?;
+        Ok(())
+    }
+}
+
+
+// Shares the database and its migrations with the tournament repository, over a connection of its own
+#[derive(Debug)]
+pub struct SqliteAccountRepository {
+    connection: Connection,
+}
+
+impl SqliteAccountRepository {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, rusqlite::Error> {
+        let mut connection = Connection::open(path)?;
+        migrate(&mut connection)?;
+        rekey_nicknames(&mut connection)?;
+        Ok(Self { connection })
+    }
+
+    fn read_account(&self, column: &str, value: &str) -> Result<Option<Account>, rusqlite::Error> {
+        let mut statement = self.connection.prepare_cached(
+            &format!("SELECT id, username, password_hash, role, nickname, nickname_changed_at FROM accounts WHERE {} = ?1", column)
+        )?;
+        statement.query_row(params![value], read_account_row).optional()
+    }
+
+    fn read_accounts_with_role(&self, role: AccountRole) -> Result<Vec<Account>, rusqlite::Error> {
+        let mut statement = self.connection.prepare_cached(
+            "SELECT id, username, password_hash, role, nickname, nickname_changed_at FROM accounts WHERE role = ?1 ORDER BY username"
+        )?;
+        let rows = statement.query_map(params![role_name(role)], read_account_row)?;
+        rows.collect()
+    }
+
+    fn read_role_changes(&self, account_id: Uuid) -> Result<Vec<RoleChange>, rusqlite::Error> {
+        let mut statement = self.connection.prepare_cached(
+            "SELECT account_id, changed_by, old_role, new_role, changed_at FROM role_changes WHERE account_id = ?1 ORDER BY position"
+        )?;
+        let rows = statement.query_map(params![account_id.to_string()], |row| {
+            Ok(RoleChange {
+                account_id: parse_uuid(row, 0)?,
+                changed_by: parse_optional_uuid(row, 1)?,
+                old_role: parse_role(row, 2)?,
+                new_role: parse_role(row, 3)?,
+                changed_at: row.get::<_, i64>(4)? as u64,
+            })
+        })?;
+        rows.collect()
+    }
+
+    fn read_bans(&self, column: &str, value: &str) -> Result<Vec<Ban>, rusqlite::Error> {
+        let mut statement = self.connection.prepare_cached(&format!(
+            "SELECT id, account_id, tournament_id, banned_by, reason, banned_at, expires_at, lifted_by FROM bans WHERE {} = ?1 ORDER BY banned_at",
+            column,
+        ))?;
+        let rows = statement.query_map(params![value], |row| {
+            let scope = match parse_optional_uuid(row, 2)? {
+                Some(tournament_id) => BanScope::Tournament { tournament_id },
+                None => BanScope::Platform,
+            };
+            Ok(Ban {
+                id: parse_uuid(row, 0)?,
+                account_id: parse_uuid(row, 1)?,
+                scope,
+                banned_by: parse_uuid(row, 3)?,
+                reason: row.get(4)?,
+                banned_at: row.get::<_, i64>(5)? as u64,
+                expires_at: row.get::<_, Option<i64>>(6)?.map(|expires_at| expires_at as u64),
+                lifted_by: parse_optional_uuid(row, 7)?,
+            })
+        })?;
+        rows.collect()
+    }
+
+    fn write_account(&mut self, account: &Account, role_changes: &[RoleChange]) -> Result<(), rusqlite::Error> {
+        let transaction = self.connection.transaction()?;
+        transaction.execute(
+            "INSERT INTO accounts (id, username, password_hash, role, nickname, nickname_key, nickname_changed_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+             ON CONFLICT (id) DO UPDATE SET
+                username = excluded.username,
+                password_hash = excluded.password_hash,
+                role = excluded.role,
+                nickname = excluded.nickname,
+                nickname_key = excluded.nickname_key,
+                nickname_changed_at = excluded.nickname_changed_at",
+            params![
+                account.id().to_string(),
+                account.username().as_str(),
+                account.password_hash(),
+                role_name(account.role()),
+                account.nickname().map(|nickname| nickname.to_string()),
+                account.nickname().map(|nickname| nickname.key()),
+                account.nickname_changed_at().map(|changed_at| changed_at as i64),
+            ],
+        )?;
+        for change in role_changes {
+            transaction.execute(
+                "INSERT INTO role_changes (account_id, changed_by, old_role, new_role, changed_at) VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![
+                    change.account_id.to_string(),
+                    change.changed_by.map(|id| id.to_string()),
+                    role_name(change.old_role),
+                    role_name(change.new_role),
+                    change.changed_at as i64,
+                ],
+            )?;
+        }
+        transaction.commit()
+    }
+}
+
+
+impl LoadAccount for SqliteAccountRepository {
+    fn load_account(&self, account_id: Uuid) -> Result<Account, LoadAccountError> {
+        debug!("load account {}", account_id);
+        let account = self.read_account("id", &account_id.to_string()).map_err(|error| {
+            error!("cannot load account {}: {}", account_id, error);
+            LoadAccountError::DatabaseReadingError
+        })?;
+        account.ok_or(LoadAccountError::AccountNotFound)
+    }
+
+    fn load_account_by_username(&self, username: &Username) -> Result<Account, LoadAccountError> {
+        debug!("load account of {}", username);
+        let account = self.read_account("username", username.as_str()).map_err(|error| {
+            error!("cannot load account of {}: {}", username, error);
+            LoadAccountError::DatabaseReadingError
+        })?;
+        account.ok_or(LoadAccountError::AccountNotFound)
+    }
+
+    fn load_accounts_with_role(&self, role: AccountRole) -> Result<Vec<Account>, LoadAccountError> {
+        debug!("load accounts with role {:?}", role);
+        self.read_accounts_with_role(role).map_err(|error| {
+            error!("cannot load accounts with role {:?}: {}", role, error);
+            LoadAccountError::DatabaseReadingError
+        })
+    }
+}
+
+
+impl SaveAccount for SqliteAccountRepository {
+    fn save_account(&mut self, mut account: Account) -> Result<(), SaveAccountError> {
+        debug!("save account {}", account.id());
+        let role_changes = account.collect_role_changes();
+        self.write_account(&account, &role_changes).map_err(|error| match error.sqlite_error_code() {
+            Some(ErrorCode::ConstraintViolation) if error.to_string().contains("accounts.nickname_key") => SaveAccountError::NicknameTaken,
+            Some(ErrorCode::ConstraintViolation) => SaveAccountError::UsernameTaken,
+            _ => {
+                error!("cannot save account {}: {}", account.id(), error);
+                SaveAccountError::DatabaseWritingError
+            },
+        })?;
+        Ok(())
+    }
+}
+
+
+impl LoadBans for SqliteAccountRepository {
+    fn load_ban(&self, ban_id: Uuid) -> Result<Ban, LoadBanError> {
+        debug!("load ban {}", ban_id);
+        let bans = self.read_bans("id", &ban_id.to_string()).map_err(|error| {
+            error!("cannot load ban {}: {}", ban_id, error);
+            LoadBanError::DatabaseReadingError
+        })?;
+        bans.into_iter().next().ok_or(LoadBanError::BanNotFound)
+    }
+
+    fn load_bans(&self, account_id: Uuid) -> Result<Vec<Ban>, LoadBanError> {
+        debug!("load bans of account {}", account_id);
+        self.read_bans("account_id", &account_id.to_string()).map_err(|error| {
+            error!("cannot load bans of account {}: {}", account_id, error);
+            LoadBanError::DatabaseReadingError
+        })
+    }
+
+    fn load_tournament_bans(&self, tournament_id: Uuid) -> Result<Vec<Ban>, LoadBanError> {
+        debug!("load bans of tournament {}", tournament_id);
+        self.read_bans("tournament_id", &tournament_id.to_string()).map_err(|error| {
+            error!("cannot load bans of tournament {}: {}", tournament_id, error);
+            LoadBanError::DatabaseReadingError
+        })
+    }
+}
+
+
+impl SaveBan for SqliteAccountRepository {
+    fn save_ban(&mut self, ban: Ban) -> Result<(), SaveBanError> {
+        debug!("save ban {}", ban.id);
+        let tournament_id = match ban.scope {
+            BanScope::Platform => None,
+            BanScope::Tournament { tournament_id } => Some(tournament_id.to_string()),
+        };
+        self.connection.execute(
+            "INSERT INTO bans (id, account_id, tournament_id, banned_by, reason, banned_at, expires_at, lifted_by)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+             ON CONFLICT (id) DO UPDATE SET expires_at = excluded.expires_at, lifted_by = excluded.lifted_by",
+            params![
+                ban.id.to_string(),
+                ban.account_id.to_string(),
+                tournament_id,
+                ban.banned_by.to_string(),
+                ban.reason,
+                ban.banned_at as i64,
+                ban.expires_at.map(|expires_at| expires_at as i64),
+                ban.lifted_by.map(|id| id.to_string()),
+            ],
+        ).map_err(|error| {
+            error!("cannot save ban {}: {}", ban.id, error);
+            SaveBanError::DatabaseWritingError
+        })?;
+        Ok(())
+    }
+}
+
+
+impl LoadRoleChanges for SqliteAccountRepository {
+    fn load_role_changes(&self, account_id: Uuid) -> Result<Vec<RoleChange>, LoadRoleChangesError> {
+        debug!("load role changes of account {}", account_id);
+        self.read_role_changes(account_id).map_err(|error| {
+            error!("cannot load role changes of account {}: {}", account_id, error);
+            LoadRoleChangesError::DatabaseReadingError
+        })
+    }
+}
+
+
+fn migrate(connection: &mut Connection) -> Result<(), rusqlite::Error> {
+    let transaction = connection.transaction()?;
+    let version: i64 = transaction.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+    for (index, migration) in MIGRATIONS.iter().enumerate().skip(version as usize) {
+        info!("migrating tournament database to version {}", index + 1);
+        transaction.execute_batch(migration)?;
+    }
+    transaction.pragma_update(None, "user_version", MIGRATIONS.len() as i64)?;
+    transaction.commit()
+}
+
+
+fn add_to_outbox(transaction: &Transaction, messages: &[TournamentMessage]) -> Result<(), rusqlite::Error> {
+    for message in messages {
+        transaction.execute("INSERT INTO outbox (payload) VALUES (?1)", params![encode_message(message).to_string()])?;
+    }
+    Ok(())
+}
+
+
+// Nickname keys depend on the confusable tables of the Unicode version in use, so they are recomputed
+// on every start. Nicknames whose new key is taken already keep their old one.
+fn rekey_nicknames(connection: &mut Connection) -> Result<(), rusqlite::Error> {
+    let transaction = connection.transaction()?;
+    let nicknames: Vec<(String, String)> = transaction
+        .prepare("SELECT id, nickname FROM accounts WHERE nickname IS NOT NULL")?
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<Result<_, _>>()?;
+    for (account_id, nickname) in nicknames {
+        let Ok(nickname) = Nickname::restore(&nickname) else {
+            warn!("account {} has the invalid nickname {:?}", account_id, nickname);
+            continue;
+        };
+        let changed = transaction.execute(
+            "UPDATE OR IGNORE accounts SET nickname_key = ?1 WHERE id = ?2",
+            params![nickname.key(), account_id],
+        )?;
+        if changed == 0 {
+            warn!("nickname {} of account {} is confusable with another one", nickname, account_id);
+        }
+    }
+    transaction.commit()
+}
+
+
+fn update_snapshot(transaction: &Transaction, tournament: &Tournament) -> Result<(), rusqlite::Error> {
+    let snapshot = tournament.snapshot();
+    transaction.execute(
+        "INSERT INTO tournament_snapshots (tournament_id, version, payload) VALUES (?1, ?2, ?3)
+         ON CONFLICT (tournament_id) DO UPDATE SET version = excluded.version, payload = excluded.payload",
+        params![tournament.id().to_string(), snapshot.version as i64, encode_snapshot(&snapshot).to_string()],
+    )?;
+    Ok(())
+}
+
+
+fn role_name(role: AccountRole) -> &'static str {
+    match role {
+        AccountRole::Member => "member",
+        AccountRole::Moderator => "moderator",
+        AccountRole::Administrator => "administrator",
+    }
+}
+
+
+// Expects the columns id, username, password_hash, role, nickname and nickname_changed_at in this order
+fn read_account_row(row: &rusqlite::Row) -> Result<Account, rusqlite::Error> {
+    let id = parse_uuid(row, 0)?;
+    let username = Username::new(row.get::<_, String>(1)?).map_err(|error| conversion_error(1, Box::new(error)))?;
+    let nickname = row.get::<_, Option<String>>(4)?
+        .map(|nickname| Nickname::restore(nickname).map_err(|error| conversion_error(4, Box::new(error))))
+        .transpose()?;
+    let nickname_changed_at = row.get::<_, Option<i64>>(5)?.map(|changed_at| changed_at as u64);
+    Ok(Account::restore(id, username, row.get(2)?, parse_role(row, 3)?, nickname, nickname_changed_at))
+}
+
+
+fn parse_role(row: &rusqlite::Row, index: usize) -> Result<AccountRole, rusqlite::Error> {
+    match row.get::<_, String>(index)?.as_str() {
+        "member" => Ok(AccountRole::Member),
+        "moderator" => Ok(AccountRole::Moderator),
+        "administrator" => Ok(AccountRole::Administrator),
+        _ => Err(conversion_error(index, "unknown role".into())),
+    }
+}
+
+
+fn parse_uuid(row: &rusqlite::Row, index: usize) -> Result<Uuid, rusqlite::Error> {
+    Uuid::parse_str(&row.get::<_, String>(index)?).map_err(|error| conversion_error(index, Box::new(error)))
+}
+
+
+fn parse_optional_uuid(row: &rusqlite::Row, index: usize) -> Result<Option<Uuid>, rusqlite::Error> {
+    row.get::<_, Option<String>>(index)?
+        .map(|id| Uuid::parse_str(&id).map_err(|error| conversion_error(index, Box::new(error))))
+        .transpose()
+}
+
+
+fn conversion_error(index: usize, error: Box<dyn std::error::Error + Send + Sync>) -> rusqlite::Error {
+    rusqlite::Error::FromSqlConversionFailure(index, rusqlite::types::Type::Text, error)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::HashedPassword;
+    use crate::domain::QueryTournaments;
+    use crate::domain::TableSpecification;
+    use crate::domain::TournamentMessageType;
+    use crate::domain::TournamentProjection;
+    use crate::domain::TournamentQuery;
+    use crate::domain::TournamentSpecification;
+    use crate::domain::unix_time;
+
+    use tempfile::NamedTempFile;
+
+    fn tournament_with_players(table_seat_count: u8, player_count: usize) -> Tournament {
+        let spec = TournamentSpecification::new(1, table_seat_count).unwrap();
+        let mut tournament = Tournament::new(&spec);
+        for _ in 0..player_count {
+            _ = tournament.join(Uuid::new_v4(), Nickname::new("James").unwrap());
+        }
+        tournament
+    }
+
+    #[test]
+    fn load_unknown_tournament() {
+        let file = NamedTempFile::new().unwrap();
+        let repository = SqliteTournamentRepository::open(file.path()).unwrap();
+        let result = repository.load_tournament(Uuid::new_v4());
+        assert!(matches!(result, Err(LoadTournamentError::TournamentNotFound)));
+    }
+
+    #[test]
+    fn save_and_load_across_reopening() {
+        let file = NamedTempFile::new().unwrap();
+        let account_id = Uuid::new_v4();
+        let mut tournament = tournament_with_players(3, 1);
+        {
+            let mut repository = SqliteTournamentRepository::open(file.path()).unwrap();
+            repository.save_tournament(tournament.clone()).unwrap();
+            tournament.mark_saved();
+            _ = tournament.join(account_id, Nickname::new("Daniel").unwrap());
+            repository.save_tournament(tournament.clone()).unwrap();
+        }
+        let repository = SqliteTournamentRepository::open(file.path()).unwrap();
+        let loaded = repository.load_tournament(tournament.id()).unwrap();
+        assert_eq!(loaded.version(), 3);
+        assert_eq!(loaded.players_table_number(account_id), Some(0));
+    }
+
+    #[test]
+    fn save_rejects_outdated_tournament() {
+        let file = NamedTempFile::new().unwrap();
+        let mut repository = SqliteTournamentRepository::open(file.path()).unwrap();
+        let tournament = tournament_with_players(3, 0);
+        let tournament_id = tournament.id();
+        repository.save_tournament(tournament).unwrap();
+        let mut first = repository.load_tournament(tournament_id).unwrap();
+        let mut second = repository.load_tournament(tournament_id).unwrap();
+        _ = first.join(Uuid::new_v4(), Nickname::new("James").unwrap());
+        _ = second.join(Uuid::new_v4(), Nickname::new("Patricia").unwrap());
+        repository.save_tournament(first).unwrap();
+        assert!(matches!(repository.save_tournament(second), Err(SaveTournamentError::TournamentOutdated)));
+        let loaded = repository.load_tournament(tournament_id).unwrap();
+        assert_eq!(loaded.version(), 2);
+        assert_eq!(loaded.player_count(), 1);
+    }
+
+    #[test]
+    fn unversioned_events_are_upcast_on_load() {
+        let file = NamedTempFile::new().unwrap();
+        let repository = SqliteTournamentRepository::open(file.path()).unwrap();
+        let tournament_id = Uuid::new_v4();
+        let payloads = [
+            format!(r#"{{"TournamentCreated":{{"id":"{}","spec":{{"table_count":1,"table_spec":{{"seat_count":3}}}}}}}}"#, tournament_id),
+            format!(r#"{{"PlayerJoined":{{"account_id":"{}","nickname":"Daniel"}}}}"#, Uuid::new_v4()),
+        ];
+        for (sequence, payload) in payloads.iter().enumerate() {
+            repository.connection.execute(
+                "INSERT INTO tournament_events (tournament_id, sequence, payload) VALUES (?1, ?2, ?3)",
+                params![tournament_id.to_string(), sequence as i64, payload],
+            ).unwrap();
+        }
+        let tournament = repository.load_tournament(tournament_id).unwrap();
+        assert_eq!(tournament.player_count(), 1);
+        assert_eq!(tournament.version(), 2);
+    }
+
+    #[test]
+    fn load_starts_from_latest_snapshot() {
+        let file = NamedTempFile::new().unwrap();
+        let mut repository = SqliteTournamentRepository::open(file.path()).unwrap();
+        let spec = TournamentSpecification::new(7, 10).unwrap();
+        let mut tournament = Tournament::new(&spec);
+        let tournament_id = tournament.id();
+        for index in 0..SNAPSHOT_INTERVAL + 5 {
+            _ = tournament.join(Uuid::new_v4(), Nickname::new(format!("Player{}", index)).unwrap());
+            repository.save_tournament(tournament.clone()).unwrap();
+            tournament.mark_saved();
+        }
+        let snapshot_version: i64 = repository.connection.query_row(
+            "SELECT version FROM tournament_snapshots WHERE tournament_id = ?1", params![tournament_id.to_string()], |row| row.get(0)
+        ).unwrap();
+        assert_eq!(snapshot_version as usize, SNAPSHOT_INTERVAL);
+
+        let full_replay = Tournament::restore(repository.load_events(tournament_id, 0).unwrap()).unwrap();
+        let loaded = repository.load_tournament(tournament_id).unwrap();
+        assert_eq!(loaded.snapshot(), full_replay.snapshot());
+
+        // Events covered by the snapshot are not needed anymore
+        repository.connection.execute(
+            "DELETE FROM tournament_events WHERE tournament_id = ?1 AND sequence < ?2", params![tournament_id.to_string(), snapshot_version]
+        ).unwrap();
+        let loaded = repository.load_tournament(tournament_id).unwrap();
+        assert_eq!(loaded.snapshot(), full_replay.snapshot());
+        assert_eq!(loaded.version(), SNAPSHOT_INTERVAL + 6);
+    }
+
+    #[test]
+    fn load_corrupted_tournament() {
+        let file = NamedTempFile::new().unwrap();
+        let repository = SqliteTournamentRepository::open(file.path()).unwrap();
+        let tournament_id = Uuid::new_v4();
+        let payload = encode_event(&TournamentEvent::PlayerJoined { account_id: Uuid::new_v4(), nickname: Nickname::new("Daniel").unwrap() });
+        repository.connection.execute(
+            "INSERT INTO tournament_events (tournament_id, sequence, payload) VALUES (?1, 0, ?2)",
+            params![tournament_id.to_string(), payload.to_string()],
+        ).unwrap();
+        let result = repository.load_tournament(tournament_id);
+        assert!(matches!(result, Err(LoadTournamentError::TournamentCorrupted)));
+    }
+
+    #[test]
+    fn save_and_load_tables() {
+        let file = NamedTempFile::new().unwrap();
+        let mut repository = SqliteTournamentRepository::open(file.path()).unwrap();
+        let tournament_id = Uuid::new_v4();
+        let mut table = Table::new(tournament_id, 2, &TableSpecification::new(3).unwrap());
+        table.sit_down(Uuid::new_v4(), Nickname::new("James").unwrap(), 1500).unwrap();
+        repository.save_table(table).unwrap();
+        let mut first = repository.load_table(tournament_id, 2).unwrap();
+        let mut second = repository.load_table(tournament_id, 2).unwrap();
+        first.sit_down(Uuid::new_v4(), Nickname::new("Daniel").unwrap(), 1500).unwrap();
+        second.sit_down(Uuid::new_v4(), Nickname::new("Patricia").unwrap(), 1500).unwrap();
+        repository.save_table(first).unwrap();
+        assert!(matches!(repository.save_table(second), Err(SaveTableError::TableOutdated)));
+        let table = repository.load_table(tournament_id, 2).unwrap();
+        assert_eq!(table.player_count(), 2);
+        assert_eq!(table.version(), 3);
+        assert!(matches!(repository.load_table(tournament_id, 1), Err(LoadTableError::TableNotFound)));
+    }
+
+    #[test]
+    fn events_and_checkpoints_survive_reopening() {
+        let file = NamedTempFile::new().unwrap();
+        let tournament = tournament_with_players(3, 2);
+        let tournament_id = tournament.id();
+        let stream = EventStream::Tournament { tournament_id };
+        let table_stream = EventStream::Table { tournament_id, table_number: 1 };
+        {
+            let mut repository = SqliteTournamentRepository::open(file.path()).unwrap();
+            repository.save_tournament(tournament).unwrap();
+            repository.save_checkpoint(stream, 1).unwrap();
+            repository.save_checkpoint(stream, 2).unwrap();
+            repository.save_checkpoint(table_stream, 4).unwrap();
+        }
+        let repository = SqliteTournamentRepository::open(file.path()).unwrap();
+        assert_eq!(repository.load_checkpoint(stream).unwrap(), 2);
+        assert_eq!(repository.load_checkpoint(table_stream).unwrap(), 4);
+        assert_eq!(repository.load_checkpoint(EventStream::Table { tournament_id, table_number: 0 }).unwrap(), 0);
+        let events = repository.load_tournament_events(tournament_id, 2).unwrap();
+        assert!(matches!(events[..], [TournamentEvent::PlayerJoined { .. }]));
+        assert_eq!(repository.load_table_events(tournament_id, 1, 0).unwrap(), vec![]);
+    }
+
+    #[test]
+    fn pending_messages_survive_reopening() {
+        let file = NamedTempFile::new().unwrap();
+        let tournament_id = {
+            let mut repository = SqliteTournamentRepository::open(file.path()).unwrap();
+            let tournament = tournament_with_players(4, 3);
+            let tournament_id = tournament.id();
+            repository.save_tournament(tournament).unwrap();
+            let mut table = Table::new(tournament_id, 0, &TableSpecification::new(3).unwrap());
+            table.sit_down(Uuid::new_v4(), Nickname::new("James").unwrap(), 1500).unwrap();
+            repository.save_table(table).unwrap();
+            let entries = repository.load_pending_messages().unwrap();
+            assert_eq!(entries.iter().map(|entry| entry.position).collect::<Vec<_>>(), vec![1, 2, 3, 4]);
+            repository.mark_delivered(4).unwrap();
+            tournament_id
+        };
+        let mut repository = SqliteTournamentRepository::open(file.path()).unwrap();
+        assert_eq!(repository.load_pending_messages().unwrap(), vec![]);
+        let mut tournament = repository.load_tournament(tournament_id).unwrap();
+        _ = tournament.join(Uuid::new_v4(), Nickname::new("Daniel").unwrap());
+        repository.save_tournament(tournament.clone()).unwrap();
+        assert!(matches!(repository.save_tournament(tournament), Err(SaveTournamentError::TournamentOutdated)));
+        let entries = repository.load_pending_messages().unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].position, 5);
+        assert_eq!(entries[0].message.message_type, TournamentMessageType::RegistrationCountChanged { player_count: 4 });
+    }
+
+    #[test]
+    fn accounts_survive_reopening() {
+        let file = NamedTempFile::new().unwrap();
+        let username = Username::new("daniel").unwrap();
+        let account = Account::register(username.clone(), "correct horse").unwrap();
+        let mut repository = SqliteAccountRepository::open(file.path()).unwrap();
+        repository.save_account(account.clone()).unwrap();
+        let other = Account::register(Username::new("Daniel").unwrap(), "correct horse").unwrap();
+        assert!(matches!(repository.save_account(other), Err(SaveAccountError::UsernameTaken)));
+        drop(repository);
+        let mut repository = SqliteAccountRepository::open(file.path()).unwrap();
+        let mut loaded = repository.load_account_by_username(&username).unwrap();
+        assert_eq!(loaded, account);
+        loaded.change_password(&loaded.hashed_password(), HashedPassword::new("battery staple").unwrap()).unwrap();
+        repository.save_account(loaded.clone()).unwrap();
+        assert_eq!(repository.load_account(account.id()).unwrap(), loaded);
+        assert!(matches!(repository.load_account(Uuid::new_v4()), Err(LoadAccountError::AccountNotFound)));
+    }
+
+    #[test]
+    fn nicknames_are_unique_regardless_of_case() {
+        let file = NamedTempFile::new().unwrap();
+        let mut repository = SqliteAccountRepository::open(file.path()).unwrap();
+        let mut account = Account::register(Username::new("daniel").unwrap(), "correct horse").unwrap();
+        account.change_nickname(Nickname::new("Denyo").unwrap(), unix_time()).unwrap();
+        repository.save_account(account.clone()).unwrap();
+        let mut other = Account::register(Username::new("james").unwrap(), "correct horse").unwrap();
+        repository.save_account(other.clone()).unwrap();
+        other.change_nickname(Nickname::new("DENYO").unwrap(), unix_time()).unwrap();
+        assert!(matches!(repository.save_account(other), Err(SaveAccountError::NicknameTaken)));
+        drop(repository);
+        let repository = SqliteAccountRepository::open(file.path()).unwrap();
+        assert_eq!(repository.load_account(account.id()).unwrap(), account);
+    }
+
+    #[test]
+    fn nickname_keys_are_recomputed_on_open() {
+        let file = NamedTempFile::new().unwrap();
+        let mut repository = SqliteAccountRepository::open(file.path()).unwrap();
+        let mut account = Account::register(Username::new("daniel").unwrap(), "correct horse").unwrap();
+        account.change_nickname(Nickname::new("Mike").unwrap(), unix_time()).unwrap();
+        repository.save_account(account).unwrap();
+        repository.connection.execute("UPDATE accounts SET nickname_key = 'mike'", []).unwrap();
+        drop(repository);
+        let mut repository = SqliteAccountRepository::open(file.path()).unwrap();
+        let mut other = Account::register(Username::new("james").unwrap(), "correct horse").unwrap();
+        other.change_nickname(Nickname::new("rnike").unwrap(), unix_time()).unwrap();
+        assert!(matches!(repository.save_account(other), Err(SaveAccountError::NicknameTaken)));
+    }
+
+    #[test]
+    fn nicknames_predating_the_current_rules_are_loaded() {
+        let file = NamedTempFile::new().unwrap();
+        let mut repository = SqliteAccountRepository::open(file.path()).unwrap();
+        let account = Account::register(Username::new("daniel").unwrap(), "correct horse").unwrap();
+        repository.save_account(account.clone()).unwrap();
+        repository.connection.execute("UPDATE accounts SET nickname = 'Bob ', nickname_key = 'bob '", []).unwrap();
+        drop(repository);
+        let repository = SqliteAccountRepository::open(file.path()).unwrap();
+        let loaded = repository.load_account(account.id()).unwrap();
+        assert_eq!(loaded.nickname(), Some(&Nickname::restore("Bob ").unwrap()));
+    }
+
+    #[test]
+    fn role_changes_are_audited() {
+        let file = NamedTempFile::new().unwrap();
+        let administrator_id = Uuid::new_v4();
+        let mut repository = SqliteAccountRepository::open(file.path()).unwrap();
+        let mut account = Account::register(Username::new("daniel").unwrap(), "correct horse").unwrap();
+        repository.save_account(Account::register(Username::new("james").unwrap(), "correct horse").unwrap()).unwrap();
+        account.promote(None, unix_time()).unwrap();
+        account.promote(Some(administrator_id), unix_time()).unwrap();
+        let mut changes = account.clone().collect_role_changes();
+        repository.save_account(account.clone()).unwrap();
+        let administrators = repository.load_accounts_with_role(AccountRole::Administrator).unwrap();
+        assert_eq!(administrators.iter().map(Account::id).collect::<Vec<_>>(), vec![account.id()]);
+        assert_eq!(repository.load_accounts_with_role(AccountRole::Member).unwrap().len(), 1);
+        assert_eq!(repository.load_role_changes(account.id()).unwrap(), changes);
+        let mut loaded = repository.load_account(account.id()).unwrap();
+        loaded.demote(Some(administrator_id), unix_time()).unwrap();
+        changes.extend(loaded.clone().collect_role_changes());
+        repository.save_account(loaded).unwrap();
+        assert_eq!(repository.load_role_changes(account.id()).unwrap(), changes);
+        assert_eq!(repository.load_accounts_with_role(AccountRole::Administrator).unwrap(), vec![]);
+    }
+
+    #[test]
+    fn bans_survive_reopening() {
+        let file = NamedTempFile::new().unwrap();
+        let account = Account::register(Username::new("daniel").unwrap(), "correct horse").unwrap();
+        let moderator_id = Uuid::new_v4();
+        let tournament_id = Uuid::new_v4();
+        let mut ban = Ban::issue(&account, BanScope::Platform, moderator_id, "spam".into(), Some(std::time::Duration::from_secs(60)), unix_time()).unwrap();
+        let exclusion = Ban::issue(&account, BanScope::Tournament { tournament_id }, moderator_id, "collusion".into(), None, unix_time()).unwrap();
+        let mut repository = SqliteAccountRepository::open(file.path()).unwrap();
+        repository.save_ban(ban.clone()).unwrap();
+        repository.save_ban(exclusion.clone()).unwrap();
+        ban.lift(moderator_id, unix_time()).unwrap();
+        repository.save_ban(ban.clone()).unwrap();
+        drop(repository);
+        let repository = SqliteAccountRepository::open(file.path()).unwrap();
+        assert_eq!(repository.load_ban(ban.id).unwrap(), ban);
+        let mut bans = repository.load_bans(account.id()).unwrap();
+        bans.sort_by_key(|ban| ban.scope == BanScope::Platform);
+        assert_eq!(bans, vec![exclusion.clone(), ban]);
+        assert_eq!(repository.load_tournament_bans(tournament_id).unwrap(), vec![exclusion]);
+        assert!(matches!(repository.load_ban(Uuid::new_v4()), Err(LoadBanError::BanNotFound)));
+    }
+
+    #[test]
+    fn migrations_are_applied_once() {
+        let file = NamedTempFile::new().unwrap();
+        drop(SqliteTournamentRepository::open(file.path()).unwrap());
+        let repository = SqliteTournamentRepository::open(file.path()).unwrap();
+        let version: i64 = repository.connection.query_row("PRAGMA user_version", [], |row| row.get(0)).unwrap();
+        assert_eq!(version as usize, MIGRATIONS.len());
+        let table_count: i64 = repository.connection.query_row(
+            "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name IN ('tournaments', 'tournament_players')",
+            [],
+            |row| row.get(0),
+        ).unwrap();
+        assert_eq!(table_count, 0);
+    }
+
+    #[test]
+    fn projection_is_rebuilt_from_stored_events() {
+        let file = NamedTempFile::new().unwrap();
+        let mut repository = SqliteTournamentRepository::open(file.path()).unwrap();
+        let tournaments = vec![tournament_with_players(2, 2), tournament_with_players(6, 3)];
+        for tournament in &tournaments {
+            repository.save_tournament(tournament.clone()).unwrap();
+        }
+        let mut tournament_ids = repository.load_tournament_ids().unwrap();
+        tournament_ids.sort();
+        let mut expected_ids: Vec<_> = tournaments.iter().map(|tournament| tournament.id()).collect();
+        expected_ids.sort();
+        assert_eq!(tournament_ids, expected_ids);
+        let projection = TournamentProjection::rebuild(&repository).unwrap();
+        let page = projection.query_tournaments(&TournamentQuery { with_free_seats: true, ..TournamentQuery::default() });
+        assert_eq!(page.tournaments.len(), 1);
+        assert_eq!(page.tournaments[0].id(), tournaments[1].id());
+        assert_eq!(page.tournaments[0].player_count(), 3);
+    }
+}
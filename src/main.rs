@@ -4,19 +4,90 @@ mod infrastructure;
 
 use std::io::Error;
 
+use application::BootstrapAdministrator;
+use application::BootstrapAdministratorRequest;
 use application::ServiceProvider;
+use infrastructure::FileTournamentRepository;
+use infrastructure::InMemoryAccountRepository;
 use infrastructure::InMemoryTournamentRepository;
 use infrastructure::AxumServer;
+use infrastructure::RateLimits;
+use infrastructure::TokenKey;
 
+use crate::domain::BlockedWords;
 use crate::domain::TableMessageBroadcast;
 
 
 #[tokio::main]
 async fn main() -> Result<(), Error> {
     env_logger::init();
-    let repository = InMemoryTournamentRepository::new();
     let broadcast = TableMessageBroadcast::new();
-    let provider = ServiceProvider::new(repository, broadcast);
-    let server = AxumServer::new(3020);
+    let token_key = match std::env::var("CARDROOM_TOKEN_KEY") {
+        Ok(key) => TokenKey::new(key).map_err(Error::other)?,
+        Err(_) => {
+            log::warn!("CARDROOM_TOKEN_KEY is not set, tokens are signed with a random key and expire on restart");
+            TokenKey::random()
+        },
+    };
+    let server = AxumServer::new(3020, token_key).with_rate_limits(rate_limits()?);
+
+    #[cfg(feature = "sqlite")]
+    if let Ok(path) = std::env::var("CARDROOM_DATABASE") {
+        let repository = infrastructure::SqliteTournamentRepository::open(&path).map_err(Error::other)?;
+        let accounts = infrastructure::SqliteAccountRepository::open(&path).map_err(Error::other)?;
+        let mut provider = ServiceProvider::new(repository, broadcast, accounts).map_err(Error::other)?.with_blocked_words(blocked_words());
+        bootstrap_administrator(&mut provider)?;
+        return server.serve(provider).await;
+    }
+
+    if let Ok(path) = std::env::var("CARDROOM_EVENT_LOG") {
+        let repository = FileTournamentRepository::open(path)?;
+        let mut provider = ServiceProvider::new(repository, broadcast, InMemoryAccountRepository::new()).map_err(Error::other)?.with_blocked_words(blocked_words());
+        bootstrap_administrator(&mut provider)?;
+        return server.serve(provider).await;
+    }
+
+    let repository = InMemoryTournamentRepository::new();
+    let mut provider = ServiceProvider::new(repository, broadcast, InMemoryAccountRepository::new()).map_err(Error::other)?.with_blocked_words(blocked_words());
+    bootstrap_administrator(&mut provider)?;
     server.serve(provider).await
 }
+
+
+// The first administrator is taken from CARDROOM_ADMIN_USERNAME and CARDROOM_ADMIN_PASSWORD. Later ones
+// are promoted by administrators, so the variables are ignored once there is one.
+fn bootstrap_administrator(provider: &mut impl BootstrapAdministrator) -> Result<(), Error> {
+    let (Ok(username), Ok(password)) = (std::env::var("CARDROOM_ADMIN_USERNAME"), std::env::var("CARDROOM_ADMIN_PASSWORD")) else {
+        return Ok(());
+    };
+    let request = BootstrapAdministratorRequest { username, password };
+    match provider.bootstrap_administrator(request).map_err(Error::other)?.account_id {
+        Some(account_id) => log::info!("account {} is the first administrator", account_id),
+        None => log::debug!("administrator exists, not bootstrapping another one"),
+    }
+    Ok(())
+}
+
+
+// Comma separated words in CARDROOM_BLOCKED_WORDS, which must not appear in newly registered nicknames
+fn blocked_words() -> BlockedWords {
+    let words = std::env::var("CARDROOM_BLOCKED_WORDS").unwrap_or_default();
+    BlockedWords::new(words.split(','))
+}
+
+
+// Budgets of the rate limited route groups as <requests>/<seconds>, for example
+// CARDROOM_RATE_LIMIT_ACCOUNTS=10/60. Groups without a variable keep their default budget.
+fn rate_limits() -> Result<RateLimits, Error> {
+    let mut limits = RateLimits::default();
+    for (variable, budget) in [
+        ("CARDROOM_RATE_LIMIT_ACCOUNTS", &mut limits.accounts),
+        ("CARDROOM_RATE_LIMIT_TOURNAMENTS", &mut limits.tournaments),
+        ("CARDROOM_RATE_LIMIT_MODERATION", &mut limits.moderation),
+    ] {
+        if let Ok(value) = std::env::var(variable) {
+            *budget = value.parse().map_err(|error| Error::other(format!("{}: {}", variable, error)))?;
+        }
+    }
+    Ok(limits)
+}